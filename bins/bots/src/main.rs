@@ -0,0 +1,261 @@
+//! Connects simulated players to a server, to see how many it can sustain.
+//!
+//! ```text
+//! bots [--address 127.0.0.1:25565] [--count 8] [--duration 30] [--stagger 100]
+//!      [--chat 5] [--prefix Bot]
+//! ```
+//!
+//! Bots join `--stagger` milliseconds apart, walk in circles around where they
+//! spawned, and chat every `--chat` seconds (or never, if 0). How many are
+//! online is printed every second, with a summary of why bots failed to join
+//! or were disconnected at the end.
+
+use core::time::Duration;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock};
+
+use picocraft_client::{Bot, ClientError, Event};
+use tokio::time::{Instant, MissedTickBehavior};
+
+const TICK: Duration = Duration::from_millis(50);
+/// How far from their spawn point bots walk, in blocks.
+const WALK_RADIUS: f64 = 3.0;
+/// How far round their circle bots walk each tick, in radians.
+const WALK_SPEED: f64 = 0.05;
+
+struct Options {
+    address: String,
+    count: usize,
+    duration: Duration,
+    stagger: Duration,
+    chat: Option<Duration>,
+    prefix: String,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            address: "127.0.0.1:25565".into(),
+            count: 8,
+            duration: Duration::from_secs(30),
+            stagger: Duration::from_millis(100),
+            chat: Some(Duration::from_secs(5)),
+            prefix: "Bot".into(),
+        }
+    }
+}
+
+impl Options {
+    fn from_args() -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = std::env::args().skip(1);
+
+        while let Some(flag) = args.next() {
+            let mut value = || args.next().ok_or(format!("{flag} needs a value"));
+
+            match flag.as_str() {
+                "--address" => options.address = value()?,
+                "--count" => options.count = parse(&flag, &value()?)?,
+                "--duration" => options.duration = Duration::from_secs(parse(&flag, &value()?)?),
+                "--stagger" => options.stagger = Duration::from_millis(parse(&flag, &value()?)?),
+                "--chat" => {
+                    options.chat = Some(Duration::from_secs(parse(&flag, &value()?)?))
+                        .filter(|chat| !chat.is_zero())
+                }
+                "--prefix" => options.prefix = value()?,
+                _ => return Err(format!("unknown option {flag}")),
+            }
+        }
+
+        // Usernames are at most 16 characters, and each bot adds its number.
+        if options.prefix.len() + options.count.to_string().len() > 16 {
+            return Err("--prefix is too long for that many bots".into());
+        }
+
+        Ok(options)
+    }
+
+    /// The host part of `address`, which is sent in the handshake.
+    fn host(&self) -> &str {
+        self.address
+            .rsplit_once(':')
+            .map_or(self.address.as_str(), |(host, _)| host)
+    }
+}
+
+fn parse<T: core::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{flag} expects a number, not {value:?}"))
+}
+
+#[derive(Default)]
+struct Stats {
+    online: AtomicUsize,
+    peak: AtomicUsize,
+    joined: AtomicUsize,
+    chunks: AtomicUsize,
+    /// Why bots couldn't join, and how many times.
+    failed: Mutex<BTreeMap<String, usize>>,
+    /// Why bots were disconnected after joining, and how many times.
+    disconnected: Mutex<BTreeMap<String, usize>>,
+}
+
+static STATS: LazyLock<Stats> = LazyLock::new(Stats::default);
+
+fn count(reasons: &Mutex<BTreeMap<String, usize>>, error: &ClientError) {
+    let mut reasons = reasons.lock().expect("stats lock isn't poisoned");
+    *reasons.entry(error.to_string()).or_default() += 1;
+}
+
+fn total(reasons: &Mutex<BTreeMap<String, usize>>) -> usize {
+    reasons
+        .lock()
+        .expect("stats lock isn't poisoned")
+        .values()
+        .sum()
+}
+
+#[tokio::main(flavor = "multi_thread")]
+async fn main() {
+    let options = match Options::from_args() {
+        Ok(options) => Arc::new(options),
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(2);
+        }
+    };
+
+    println!(
+        "Connecting {} bots to {} for {}s...",
+        options.count,
+        options.address,
+        options.duration.as_secs()
+    );
+
+    let start = Instant::now();
+    let mut bots = tokio::task::JoinSet::new();
+
+    for index in 0..options.count {
+        let options = Arc::clone(&options);
+
+        bots.spawn(async move {
+            tokio::time::sleep(options.stagger * index as u32).await;
+            run_bot(&options, index).await;
+        });
+    }
+
+    let mut report = tokio::time::interval(Duration::from_secs(1));
+    report.tick().await;
+
+    loop {
+        tokio::select! {
+            finished = bots.join_next() => {
+                if finished.is_none() {
+                    break;
+                }
+            }
+            _ = report.tick() => {
+                println!(
+                    "[{:>3}s] online: {}, joined: {}, failed: {}, disconnected: {}, chunks: {}",
+                    start.elapsed().as_secs(),
+                    STATS.online.load(Ordering::Relaxed),
+                    STATS.joined.load(Ordering::Relaxed),
+                    total(&STATS.failed),
+                    total(&STATS.disconnected),
+                    STATS.chunks.load(Ordering::Relaxed),
+                );
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{} of {} bots joined, with at most {} online at once.",
+        STATS.joined.load(Ordering::Relaxed),
+        options.count,
+        STATS.peak.load(Ordering::Relaxed)
+    );
+
+    for (heading, reasons) in [
+        ("Failed to join", &STATS.failed),
+        ("Disconnected", &STATS.disconnected),
+    ] {
+        for (reason, times) in reasons.lock().expect("stats lock isn't poisoned").iter() {
+            println!("{heading} ({times}x): {reason}");
+        }
+    }
+}
+
+async fn run_bot(options: &Options, index: usize) {
+    let username = format!("{}{index}", options.prefix);
+
+    let mut bot = match Bot::join(options.address.as_str(), options.host(), &username).await {
+        Ok(bot) => bot,
+        Err(error) => {
+            count(&STATS.failed, &error);
+            return;
+        }
+    };
+
+    STATS.joined.fetch_add(1, Ordering::Relaxed);
+    let online = STATS.online.fetch_add(1, Ordering::Relaxed) + 1;
+    STATS.peak.fetch_max(online, Ordering::Relaxed);
+
+    if let Err(error) = play(&mut bot, options).await {
+        count(&STATS.disconnected, &error);
+    }
+
+    STATS.online.fetch_sub(1, Ordering::Relaxed);
+}
+
+/// Walks and chats until `options.duration` is up.
+async fn play(bot: &mut Bot, options: &Options) -> Result<(), ClientError> {
+    let leave_at = Instant::now() + options.duration;
+
+    let mut ticker = tokio::time::interval(TICK);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    let mut chat = options.chat.map(tokio::time::interval);
+    let mut spawn = None;
+    let mut angle = 0.0f64;
+
+    loop {
+        tokio::select! {
+            event = bot.next_event() => match event? {
+                Event::Teleported { x, y, z } => spawn = Some((x, y, z)),
+                Event::ChunkLoaded { .. } => {
+                    STATS.chunks.fetch_add(1, Ordering::Relaxed);
+                }
+                _ => {}
+            },
+            _ = ticker.tick() => {
+                // Don't move until the server has said where we are.
+                let Some((x, y, z)) = spawn else {
+                    continue;
+                };
+
+                angle += WALK_SPEED;
+
+                bot.move_to(
+                    x + WALK_RADIUS * angle.cos(),
+                    y,
+                    z + WALK_RADIUS * angle.sin(),
+                )
+                .await?;
+                bot.look(angle.to_degrees() as f32, 0.0).await?;
+            }
+            Some(_) = async {
+                match &mut chat {
+                    Some(chat) => Some(chat.tick().await),
+                    None => None,
+                }
+            } => {
+                bot.chat(&format!("Hello from {}!", bot.username())).await?;
+            }
+            () = tokio::time::sleep_until(leave_at) => return bot.flush().await,
+        }
+    }
+}
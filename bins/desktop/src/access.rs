@@ -0,0 +1,156 @@
+//! Editing the access lists from the command line, while the server isn't
+//! running. Changes take effect the next time the server starts.
+//!
+//! ```text
+//! desktop whitelist add|remove <uuid>
+//! desktop ban <uuid> [reason...]
+//! desktop pardon <uuid>
+//! desktop ban-ip <address> [reason...]
+//! desktop pardon-ip <address>
+//! desktop op|deop <uuid>
+//! desktop lists
+//! ```
+
+use core::net::IpAddr;
+
+use picocraft_core::prelude::UUID;
+use picocraft_server::access::{self, AccessLists};
+use picocraft_server::errors::AccessListError;
+use picocraft_server::storage::file::FileStorage;
+
+/// Runs the command in `args`, returning an error message if it fails.
+pub async fn run(storage: &mut FileStorage, args: &[String]) -> Result<(), String> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    access::load(storage)
+        .await
+        .map_err(|e| format!("Couldn't load the access lists: {e}"))?;
+
+    let message = match args.as_slice() {
+        ["whitelist", "add", uuid] => {
+            let uuid = parse_uuid(uuid)?;
+            if update(storage, |lists| lists.add_to_whitelist(uuid)).await? {
+                "Added to the whitelist."
+            } else {
+                "Already whitelisted."
+            }
+        }
+        ["whitelist", "remove", uuid] => {
+            let uuid = parse_uuid(uuid)?;
+            if update(storage, |lists| Ok(lists.remove_from_whitelist(uuid))).await? {
+                "Removed from the whitelist."
+            } else {
+                "Wasn't whitelisted."
+            }
+        }
+        ["ban", uuid, reason @ ..] => {
+            let uuid = parse_uuid(uuid)?;
+            let reason = reason.join(" ");
+            update(storage, |lists| lists.ban_player(uuid, &reason)).await?;
+            "Banned."
+        }
+        ["pardon", uuid] => {
+            let uuid = parse_uuid(uuid)?;
+            if update(storage, |lists| Ok(lists.pardon_player(uuid))).await? {
+                "Pardoned."
+            } else {
+                "Wasn't banned."
+            }
+        }
+        ["ban-ip", address, reason @ ..] => {
+            let address = parse_address(address)?;
+            let reason = reason.join(" ");
+            update(storage, |lists| lists.ban_ip(address, &reason)).await?;
+            "Banned."
+        }
+        ["pardon-ip", address] => {
+            let address = parse_address(address)?;
+            if update(storage, |lists| Ok(lists.pardon_ip(address))).await? {
+                "Pardoned."
+            } else {
+                "Wasn't banned."
+            }
+        }
+        ["op", uuid] => {
+            let uuid = parse_uuid(uuid)?;
+            if update(storage, |lists| lists.op(uuid)).await? {
+                "Made an operator."
+            } else {
+                "Already an operator."
+            }
+        }
+        ["deop", uuid] => {
+            let uuid = parse_uuid(uuid)?;
+            if update(storage, |lists| Ok(lists.deop(uuid))).await? {
+                "No longer an operator."
+            } else {
+                "Wasn't an operator."
+            }
+        }
+        ["lists"] => {
+            let lists = AccessLists::load(storage)
+                .await
+                .map_err(|e| format!("Couldn't load the access lists: {e}"))?;
+
+            print_lists(&lists);
+            return Ok(());
+        }
+        _ => return Err(USAGE.into()),
+    };
+
+    println!("{message}");
+
+    Ok(())
+}
+
+const USAGE: &str = "\
+usage: desktop whitelist add|remove <uuid>
+       desktop ban <uuid> [reason...]
+       desktop pardon <uuid>
+       desktop ban-ip <address> [reason...]
+       desktop pardon-ip <address>
+       desktop op|deop <uuid>
+       desktop lists";
+
+/// Changes the lists and saves them.
+async fn update<R>(
+    storage: &mut FileStorage,
+    change: impl FnOnce(&mut AccessLists) -> Result<R, AccessListError>,
+) -> Result<R, String> {
+    access::update(storage, change)
+        .await
+        .map_err(|e| format!("Couldn't save the access lists: {e}"))?
+        .map_err(|e| e.to_string())
+}
+
+fn parse_uuid(uuid: &str) -> Result<UUID, String> {
+    UUID::parse_str(uuid).map_err(|_| format!("{uuid:?} isn't a UUID"))
+}
+
+fn parse_address(address: &str) -> Result<IpAddr, String> {
+    address
+        .parse()
+        .map_err(|_| format!("{address:?} isn't an IP address"))
+}
+
+fn print_lists(lists: &AccessLists) {
+    println!("Whitelist:");
+    for uuid in lists.whitelist() {
+        println!("  {uuid}");
+    }
+
+    println!("Banned players:");
+    for ban in lists.banned_players() {
+        println!("  {} {}", ban.uuid, ban.reason);
+    }
+
+    println!("Banned addresses:");
+    for ban in lists.banned_ips() {
+        println!("  {} {}", ban.address.0, ban.reason);
+    }
+
+    println!("Operators:");
+    for uuid in lists.operators() {
+        println!("  {uuid}");
+    }
+}
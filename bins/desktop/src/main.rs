@@ -1,20 +1,45 @@
+mod access;
 mod logger;
 
 use core::cell::RefCell;
 
 use embassy_sync::mutex::Mutex;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use picocraft_core::prelude::*;
+use picocraft_server::journal::Journal;
+use picocraft_server::player_data::PlayerData;
 use picocraft_server::prelude::*;
+use picocraft_server::storage::file::FileStorage;
 use static_cell::StaticCell;
 
 static SYSTEM_RNG: StaticCell<SystemRng> = StaticCell::new();
 static SERVER_CONFIG: StaticCell<ServerConfig> = StaticCell::new();
 
+/// Where the access lists, changed blocks and players' save data are kept.
+const DATA_DIR: &str = "data";
+
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> Result<(), PicocraftError> {
     logger::init_logger_from_env();
 
+    let mut storage = FileStorage::new(DATA_DIR);
+
+    let args: Vec<std::string::String> = std::env::args().skip(1).collect();
+
+    if !args.is_empty() {
+        if let Err(message) = access::run(&mut storage, &args).await {
+            eprintln!("{message}");
+            std::process::exit(2);
+        }
+
+        return Ok(());
+    }
+
+    if let Err(e) = picocraft_server::access::load(&mut storage).await {
+        error!("Couldn't load the access lists from {DATA_DIR}: {e}");
+        return Err(PicocraftError::Unknown);
+    }
+
     let system_rng = SYSTEM_RNG.init_with(|| Mutex::new(RefCell::new(rand::make_rng())));
 
     let config = picocraft_server::config::ServerConfig {
@@ -23,6 +48,14 @@ async fn main() -> Result<(), PicocraftError> {
         port: 25565,
         address: core::net::Ipv4Addr::UNSPECIFIED,
         motd: String::try_from("A Picocraft Server!").expect("String is less than 256 bytes"),
+        favicon: load_favicon("server-icon.png"),
+        compression_threshold: Some(256),
+        accepts_transfers: false,
+        whitelist: false,
+        forwarding: picocraft_server::config::PlayerInfoForwarding::None,
+        trust_client_uuids: false,
+        keep_alive_timeout: core::time::Duration::from_secs(30),
+        plugin_channels: picocraft_server::plugin_channels::PluginChannels::new(),
     };
 
     let listener = tokio::net::TcpListener::bind((config.address, config.port))
@@ -35,6 +68,18 @@ async fn main() -> Result<(), PicocraftError> {
 
     info!("Server listening at: {}:{}", config.address, config.port);
 
+    let journal = match Journal::load(storage.clone(), server.terrain).await {
+        Ok(journal) => journal,
+        Err(e) => {
+            error!("Couldn't load the world's changes from {DATA_DIR}: {e}");
+            return Err(PicocraftError::Unknown);
+        }
+    };
+
+    let terrain = server.terrain;
+    let saving = tokio::spawn(journal.run(terrain));
+    let saving_players = tokio::spawn(PlayerData::new(storage.clone()).run());
+
     let mut world = picocraft_ecs::World::new();
 
     tokio::spawn(async move {
@@ -63,6 +108,10 @@ async fn main() -> Result<(), PicocraftError> {
             }
             Ok(None) => {
                 info!("Server is shutting down.");
+
+                // Saves the last of the world's changes and players.
+                let _ = saving.await;
+                let _ = saving_players.await;
                 break;
             }
             Err(error) => {
@@ -74,3 +123,19 @@ async fn main() -> Result<(), PicocraftError> {
 
     Ok(())
 }
+
+/// Reads the server list icon, which lives for as long as the server does.
+fn load_favicon(path: &str) -> Option<&'static [u8]> {
+    const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    let png = std::fs::read(path).ok()?;
+
+    if !png.starts_with(PNG_SIGNATURE) {
+        warn!("Ignoring {path}, as it isn't a PNG image.");
+        return None;
+    }
+
+    info!("Loaded server icon from {path}.");
+
+    Some(png.leak())
+}
@@ -0,0 +1,564 @@
+use std::boxed::Box;
+use std::collections::HashMap;
+use std::string::{String as StdString, ToString};
+
+use picocraft_core::types::nbt::{Event as NbtEvent, NbtReader, Tag};
+use picocraft_proto::serverbound::{
+    AcknowledgeFinishConfigurationPacket, BlockFace, BrandPacket, ChatMessagePacket, ClickedFace,
+    ClientInformation, ClientInformationPacket, ConfigurationCookieResponsePacket,
+    ConfirmTeleportationPacket, Hand, HandshakePacket, Intent, LoginAcknowledgedPacket,
+    LoginCookieResponsePacket, LoginPluginResponsePacket, LoginStartPacket,
+    MAX_CHAT_MESSAGE_LENGTH, PlayCookieResponsePacket, PlayerActionPacket, PlayerActionStatus,
+    PlayerMovementFlags, ServerboundKeepAlivePacket, SetCreativeModeSlotPacket, SetHeldItemPacket,
+    SetPlayerPositionAndRotationPacket, SetPlayerPositionPacket, SetPlayerRotationPacket,
+    UseItemOnPacket,
+};
+use picocraft_terrain::terrain::chunks::{ChunkAndLightPacket, ChunkData};
+use tokio::net::ToSocketAddrs;
+
+use crate::connection::Connection;
+use crate::prelude::*;
+
+/// Disconnect reasons are read as NBT, and anything this long is cut short.
+const MAX_REASON_SIZE: usize = 4096;
+
+/// Something which happened in play, returned from [`Bot::next_event`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// The server sent Login (play), so the bot is in the world.
+    Joined { entity_id: Int },
+    /// The server moved the bot, and the teleport has been confirmed.
+    Teleported { x: Double, y: Double, z: Double },
+    /// A chunk column arrived, which [`Bot::chunk`] has if chunks are kept.
+    ChunkLoaded { x: Int, z: Int },
+    /// A block was changed to `state`.
+    BlockChanged {
+        x: Int,
+        y: Int,
+        z: Int,
+        state: VarInt,
+    },
+    /// The server has dealt with the bot's block changes up to this sequence
+    /// number.
+    BlockChangesAcknowledged(VarInt),
+    /// A message from the server, as plain text.
+    SystemChat(StdString),
+    /// Any other packet, which the bot ignores.
+    Packet(VarInt),
+}
+
+/// A simulated player.
+///
+/// [`Bot::join`] logs in and finishes configuration, and from then on
+/// [`Bot::next_event`] must be called in a loop to answer keep-alives.
+/// Movement, chat and breaking blocks are queued, and sent on the next call.
+pub struct Bot {
+    connection: Connection,
+    username: String<16>,
+    uuid: UUID,
+    entity_id: Option<Int>,
+    position: (Double, Double, Double),
+    rotation: (Float, Float),
+    keep_chunks: bool,
+    chunks: HashMap<(Int, Int), Box<ChunkData>>,
+    /// Blocks changed since their chunk was kept, which aren't in it.
+    changed_blocks: HashMap<(Int, Int, Int), VarInt>,
+    /// The last sequence number given to a block change.
+    sequence: i32,
+}
+
+impl Bot {
+    /// Connects to `address`, and logs in and configures as `username`.
+    ///
+    /// The UUID is derived from the username as offline-mode servers do, so
+    /// it matches the one the server gives the bot.
+    pub async fn join(
+        address: impl ToSocketAddrs,
+        server_address: &str,
+        username: &str,
+    ) -> Result<Self, ClientError> {
+        let username = String::try_from(username).map_err(|_| EncodeError::TooLong)?;
+        let uuid = offline_uuid(&username);
+
+        let mut bot = Self {
+            connection: Connection::connect(address).await?,
+            username,
+            uuid,
+            entity_id: None,
+            position: (0.0, 0.0, 0.0),
+            rotation: (0.0, 0.0),
+            keep_chunks: false,
+            chunks: HashMap::new(),
+            changed_blocks: HashMap::new(),
+            sequence: 0,
+        };
+
+        bot.login(server_address).await?;
+        bot.configure().await?;
+
+        debug!("{} [{}] has joined.", bot.username, bot.uuid);
+
+        Ok(bot)
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    pub fn uuid(&self) -> UUID {
+        self.uuid
+    }
+
+    /// The bot's entity ID, once [`Event::Joined`] has been returned.
+    pub fn entity_id(&self) -> Option<Int> {
+        self.entity_id
+    }
+
+    pub fn position(&self) -> (Double, Double, Double) {
+        self.position
+    }
+
+    /// Yaw and pitch, in degrees.
+    pub fn rotation(&self) -> (Float, Float) {
+        self.rotation
+    }
+
+    /// Whether to decode and keep the chunks the server sends. Off by
+    /// default, since a load test doesn't need hundreds of chunks per bot.
+    pub fn keep_chunks(&mut self, keep_chunks: bool) {
+        self.keep_chunks = keep_chunks;
+
+        if !keep_chunks {
+            self.chunks.clear();
+            self.changed_blocks.clear();
+        }
+    }
+
+    pub fn chunk(&self, x: Int, z: Int) -> Option<&ChunkData> {
+        self.chunks.get(&(x, z)).map(Box::as_ref)
+    }
+
+    /// The block state ID of the block at `x`, `y`, `z`, if its chunk has
+    /// been kept.
+    pub fn block_state(&self, x: Int, y: u8, z: Int) -> Option<VarInt> {
+        if let Some(state) = self.changed_blocks.get(&(x, Int::from(y), z)) {
+            return Some(*state);
+        }
+
+        self.chunk(x.div_euclid(16), z.div_euclid(16))?.block_state(
+            x.rem_euclid(16) as u8,
+            y,
+            z.rem_euclid(16) as u8,
+        )
+    }
+
+    async fn login(&mut self, server_address: &str) -> Result<(), ClientError> {
+        self.connection
+            .send(&HandshakePacket {
+                protocol_version: CURRENT_PROTOCOL_VERSION,
+                server_address: String::try_from(server_address)
+                    .map_err(|_| EncodeError::TooLong)?,
+                server_port: 25565,
+                intent: Intent::Login,
+            })
+            .await?;
+
+        self.connection.set_state(State::Login);
+
+        self.connection
+            .send(&LoginStartPacket {
+                username: self.username.clone(),
+                uuid: self.uuid,
+            })
+            .await?;
+
+        loop {
+            let packet_id = self.connection.read_packet().await?;
+
+            match packet_id {
+                clientbound::LoginDisconnectPacket::ID => {
+                    // Unlike later states, the reason is JSON.
+                    let mut body = self.connection.body();
+                    let len = VarInt::decode(&mut body).await?;
+                    let reason = body.get(..*len as usize).unwrap_or(body);
+
+                    return Err(ClientError::Disconnected(
+                        StdString::from_utf8_lossy(reason).into_owned(),
+                    ));
+                }
+                clientbound::LoginSuccess::ID => {
+                    self.connection.send(&LoginAcknowledgedPacket).await?;
+                    self.connection.set_state(State::Configuration);
+
+                    return Ok(());
+                }
+                clientbound::SetCompressionPacket::ID => {
+                    let set_compression = self
+                        .connection
+                        .decode::<clientbound::SetCompressionPacket>()
+                        .await?;
+
+                    self.connection
+                        .set_compression(usize::try_from(*set_compression.threshold).ok());
+                }
+                clientbound::LoginPluginRequestPacket::ID => {
+                    // There's nothing to forward, so every request is unanswered.
+                    let request = self
+                        .connection
+                        .decode::<clientbound::LoginPluginRequestPacket>()
+                        .await?;
+
+                    self.connection
+                        .send(&LoginPluginResponsePacket {
+                            message_id: request.message_id,
+                            data: PrefixedOptional(None),
+                        })
+                        .await?;
+                }
+                clientbound::LoginCookieRequestPacket::ID => {
+                    let request = self
+                        .connection
+                        .decode::<clientbound::LoginCookieRequestPacket>()
+                        .await?;
+
+                    self.connection
+                        .send(&LoginCookieResponsePacket(no_cookie(request.0)))
+                        .await?;
+                }
+                id => return Err(ClientError::UnexpectedPacket(*id, State::Login)),
+            }
+        }
+    }
+
+    async fn configure(&mut self) -> Result<(), ClientError> {
+        // Like vanilla, say which client this is before anything else.
+        self.connection
+            .queue(&BrandPacket {
+                brand: String::try_from("picocraft-bots").expect("max 16 bytes"),
+                ..Default::default()
+            })
+            .await?;
+        self.connection
+            .send(&ClientInformationPacket(ClientInformation {
+                locale: String::try_from("en_us").expect("max 16 bytes"),
+                view_distance: 2,
+                chat_colors: true,
+                displayed_skin_parts: 0x7f,
+                main_hand: 1,
+                ..Default::default()
+            }))
+            .await?;
+
+        loop {
+            let packet_id = self.connection.read_packet().await?;
+
+            match packet_id {
+                clientbound::ConfigurationCookieRequestPacket::ID => {
+                    let request = self
+                        .connection
+                        .decode::<clientbound::ConfigurationCookieRequestPacket>()
+                        .await?;
+
+                    self.connection
+                        .send(&ConfigurationCookieResponsePacket(no_cookie(request.0)))
+                        .await?;
+                }
+                clientbound::ConfigurationDisconnectPacket::ID => {
+                    return Err(ClientError::Disconnected(
+                        text_content(self.connection.body()).await,
+                    ));
+                }
+                clientbound::FinishConfigurationPacket::ID => {
+                    self.connection
+                        .send(&AcknowledgeFinishConfigurationPacket)
+                        .await?;
+                    self.connection.set_state(State::Play);
+
+                    return Ok(());
+                }
+                // Brand, known packs and registries don't matter to a bot.
+                _ => {}
+            }
+        }
+    }
+
+    /// Sends anything queued, then waits for the next packet and handles it.
+    ///
+    /// Keep-alives and cookie requests are answered without returning, so
+    /// this should be called in a loop for as long as the bot is connected.
+    /// It is cancel safe.
+    pub async fn next_event(&mut self) -> Result<Event, ClientError> {
+        loop {
+            self.connection.flush().await?;
+
+            let packet_id = self.connection.read_packet().await?;
+
+            match packet_id {
+                clientbound::KeepAlivePacket::ID => {
+                    let keep_alive = self
+                        .connection
+                        .decode::<clientbound::KeepAlivePacket>()
+                        .await?;
+
+                    self.connection
+                        .queue(&ServerboundKeepAlivePacket {
+                            id: keep_alive.id(),
+                        })
+                        .await?;
+                }
+                clientbound::PlayCookieRequestPacket::ID => {
+                    let request = self
+                        .connection
+                        .decode::<clientbound::PlayCookieRequestPacket>()
+                        .await?;
+
+                    self.connection
+                        .queue(&PlayCookieResponsePacket(no_cookie(request.0)))
+                        .await?;
+                }
+                clientbound::PlayDisconnectPacket::ID => {
+                    return Err(ClientError::Disconnected(
+                        text_content(self.connection.body()).await,
+                    ));
+                }
+                ChunkAndLightPacket::ID => {
+                    let mut body = self.connection.body();
+                    let x = Int::decode(&mut body).await?;
+                    let z = Int::decode(&mut body).await?;
+
+                    if self.keep_chunks {
+                        let chunk = self.connection.decode::<ChunkAndLightPacket>().await?;
+                        self.chunks.insert((x, z), Box::new(chunk.chunk_data));
+                        self.changed_blocks.retain(|(block_x, _, block_z), _| {
+                            (block_x.div_euclid(16), block_z.div_euclid(16)) != (x, z)
+                        });
+                    }
+
+                    return Ok(Event::ChunkLoaded { x, z });
+                }
+                clientbound::LoginPlayPacket::ID => {
+                    // Only the entity ID is needed, which comes first.
+                    let entity_id = Int::decode(self.connection.body()).await?;
+                    self.entity_id = Some(entity_id);
+
+                    return Ok(Event::Joined { entity_id });
+                }
+                clientbound::SynchronisePlayerPositionPacket::ID => {
+                    let teleport = self
+                        .connection
+                        .decode::<clientbound::SynchronisePlayerPositionPacket>()
+                        .await?;
+
+                    self.position = teleport.position();
+                    self.rotation = teleport.rotation();
+
+                    let (x, y, z) = self.position;
+                    let (yaw, pitch) = self.rotation;
+
+                    // Vanilla confirms the teleport and then sends its new position.
+                    self.connection
+                        .queue(&ConfirmTeleportationPacket(teleport.teleport_id()))
+                        .await?;
+                    self.connection
+                        .queue(&SetPlayerPositionAndRotationPacket {
+                            x,
+                            feet_y: y,
+                            z,
+                            yaw,
+                            pitch,
+                            flags: PlayerMovementFlags::empty(),
+                        })
+                        .await?;
+
+                    return Ok(Event::Teleported { x, y, z });
+                }
+                clientbound::BlockUpdatePacket::ID => {
+                    let update = self
+                        .connection
+                        .decode::<clientbound::BlockUpdatePacket>()
+                        .await?;
+
+                    let (x, y, z) = (
+                        update.location.x(),
+                        update.location.y(),
+                        update.location.z(),
+                    );
+
+                    if self
+                        .chunks
+                        .contains_key(&(x.div_euclid(16), z.div_euclid(16)))
+                    {
+                        self.changed_blocks.insert((x, y, z), update.block_state);
+                    }
+
+                    return Ok(Event::BlockChanged {
+                        x,
+                        y,
+                        z,
+                        state: update.block_state,
+                    });
+                }
+                clientbound::AcknowledgeBlockChangePacket::ID => {
+                    let acknowledgement = self
+                        .connection
+                        .decode::<clientbound::AcknowledgeBlockChangePacket>()
+                        .await?;
+
+                    return Ok(Event::BlockChangesAcknowledged(acknowledgement.sequence));
+                }
+                clientbound::SystemChatPacket::ID => {
+                    return Ok(Event::SystemChat(
+                        text_content(self.connection.body()).await,
+                    ));
+                }
+                _ => return Ok(Event::Packet(packet_id)),
+            }
+        }
+    }
+
+    /// Queues a move to `x`, `y`, `z`, standing on the ground.
+    pub async fn move_to(&mut self, x: Double, y: Double, z: Double) -> Result<(), ClientError> {
+        self.position = (x, y, z);
+
+        self.connection
+            .queue(&SetPlayerPositionPacket {
+                x,
+                feet_y: y,
+                z,
+                flags: PlayerMovementFlags::TOUCHING_GROUND,
+            })
+            .await
+    }
+
+    /// Queues turning to face `yaw` and `pitch`, in degrees.
+    pub async fn look(&mut self, yaw: Float, pitch: Float) -> Result<(), ClientError> {
+        self.rotation = (yaw, pitch);
+
+        self.connection
+            .queue(&SetPlayerRotationPacket {
+                yaw,
+                pitch,
+                flags: PlayerMovementFlags::TOUCHING_GROUND,
+            })
+            .await
+    }
+
+    /// Queues breaking the block at `x`, `y`, `z`, which is instant in
+    /// creative. Returns the sequence number the server acknowledges it with.
+    pub async fn break_block(&mut self, x: Int, y: Int, z: Int) -> Result<VarInt, ClientError> {
+        self.sequence += 1;
+        let sequence = VarInt(self.sequence);
+
+        self.connection
+            .queue(&PlayerActionPacket {
+                status: PlayerActionStatus::StartedDigging,
+                location: BlockPosition::new(x, z, y),
+                face: BlockFace::Top,
+                sequence,
+            })
+            .await?;
+
+        Ok(sequence)
+    }
+
+    /// Queues taking `item_id` from the creative inventory into hotbar `slot`
+    /// (0 to 8), and holding it.
+    pub async fn hold_item(&mut self, slot: u8, item_id: VarInt) -> Result<(), ClientError> {
+        self.connection
+            .queue(&SetCreativeModeSlotPacket {
+                slot: 36 + Short::from(slot),
+                clicked_item: Slot::new(item_id, 1),
+            })
+            .await?;
+
+        self.connection
+            .queue(&SetHeldItemPacket {
+                slot: Short::from(slot),
+            })
+            .await
+    }
+
+    /// Queues placing the held block against `face` of the block at `x`, `y`,
+    /// `z`. Returns the sequence number the server acknowledges it with.
+    pub async fn place_block(
+        &mut self,
+        x: Int,
+        y: Int,
+        z: Int,
+        face: BlockFace,
+    ) -> Result<VarInt, ClientError> {
+        self.sequence += 1;
+        let sequence = VarInt(self.sequence);
+
+        self.connection
+            .queue(&UseItemOnPacket {
+                hand: Hand::MainHand,
+                location: BlockPosition::new(x, z, y),
+                face: ClickedFace(face),
+                cursor_x: 0.5,
+                cursor_y: 0.5,
+                cursor_z: 0.5,
+                inside_block: false,
+                world_border_hit: false,
+                sequence,
+            })
+            .await?;
+
+        Ok(sequence)
+    }
+
+    /// Queues an unsigned chat message.
+    pub async fn chat(&mut self, message: &str) -> Result<(), ClientError> {
+        let message = String::<MAX_CHAT_MESSAGE_LENGTH>::try_from(message)
+            .map_err(|_| EncodeError::TooLong)?;
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as Long);
+
+        self.connection
+            .queue(&ChatMessagePacket::unsigned(message, timestamp))
+            .await
+    }
+
+    /// Sends anything queued straight away, rather than on the next call to
+    /// [`Bot::next_event`].
+    pub async fn flush(&mut self) -> Result<(), ClientError> {
+        self.connection.flush().await
+    }
+}
+
+/// A bot never has any cookies stored.
+fn no_cookie(request: CookieRequest) -> CookieResponse {
+    CookieResponse {
+        key: request.key,
+        payload: PrefixedOptional(None),
+    }
+}
+
+/// Flattens a text component sent as network NBT into plain text, using
+/// the fallback of translated text since a bot has no translations.
+async fn text_content(body: &[u8]) -> StdString {
+    let mut nbt = NbtReader::<_, 256>::new(body, MAX_REASON_SIZE);
+    let mut text = StdString::new();
+    let mut translate = None;
+
+    while let Ok(Some(event)) = nbt.next().await {
+        match event {
+            NbtEvent::Value {
+                name: "" | "text" | "fallback",
+                tag: Tag::String(value),
+            } => text.push_str(value),
+            NbtEvent::Value {
+                name: "translate",
+                tag: Tag::String(value),
+            } => translate = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    match translate {
+        Some(key) if text.is_empty() => key,
+        _ => text,
+    }
+}
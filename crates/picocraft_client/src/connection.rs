@@ -0,0 +1,242 @@
+use std::vec::Vec;
+
+use embedded_io_async::Write as _;
+use picocraft_core::compression::{Deflater, ZlibEncoder, inflate};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, ToSocketAddrs};
+
+use crate::prelude::*;
+
+/// A connection to a server, which frames, compresses and buffers packets.
+///
+/// Reading and flushing only ever await the socket, with everything else kept
+/// in buffers, so both are cancel safe and can be used in `tokio::select!`.
+pub struct Connection {
+    stream: TcpStream,
+    /// Bytes read from the socket which haven't made up a whole packet yet.
+    inbox: Vec<u8>,
+    /// Framed packets waiting to be written to the socket.
+    outbox: Vec<u8>,
+    /// How much of `outbox` has been written already.
+    written: usize,
+    /// The body of the last packet read, without its ID.
+    body: Vec<u8>,
+    state: State,
+    /// Packets at least this many bytes long are compressed. [`None`] until
+    /// the server sends Set Compression.
+    compression_threshold: Option<usize>,
+    deflater: Deflater,
+}
+
+impl Connection {
+    pub async fn connect(address: impl ToSocketAddrs) -> Result<Self, ClientError> {
+        let stream = TcpStream::connect(address).await?;
+        stream.set_nodelay(true)?;
+
+        Ok(Self {
+            stream,
+            inbox: Vec::with_capacity(4096),
+            outbox: Vec::with_capacity(1024),
+            written: 0,
+            body: Vec::new(),
+            state: State::Handshake,
+            compression_threshold: None,
+            deflater: Deflater::new(),
+        })
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    pub fn set_state(&mut self, state: State) {
+        self.state = state;
+    }
+
+    pub fn compression_threshold(&self) -> Option<usize> {
+        self.compression_threshold
+    }
+
+    /// Switches to the compressed packet format, as the server does straight
+    /// after sending Set Compression.
+    pub fn set_compression(&mut self, threshold: Option<usize>) {
+        self.compression_threshold = threshold;
+    }
+
+    /// Frames `packet` and adds it to the outbox, to be sent by the next
+    /// [`Connection::flush`].
+    pub async fn queue<P: Packet>(&mut self, packet: &P) -> Result<(), ClientError> {
+        trace!("Queueing packet: {packet}");
+
+        let mut data = Vec::new();
+        packet.encode(&mut data).await?;
+
+        if data.len() > *MAX_PACKET_SIZE as usize {
+            return Err(ClientError::PacketTooLarge(data.len()));
+        }
+
+        let data_length = VarInt(data.len() as i32);
+
+        match self.compression_threshold {
+            None => {
+                data_length.encode(&mut self.outbox).await?;
+                self.outbox.extend_from_slice(&data);
+            }
+            Some(threshold) if data.len() < threshold => {
+                VarInt(data.len() as i32 + 1)
+                    .encode(&mut self.outbox)
+                    .await?;
+                VarInt(0).encode(&mut self.outbox).await?;
+                self.outbox.extend_from_slice(&data);
+            }
+            Some(_) => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), &mut self.deflater);
+                let Ok(()) = encoder.write_all(&data).await;
+                let Ok(compressed) = encoder.finish().await;
+
+                VarInt((data_length.encoded_len() + compressed.len()) as i32)
+                    .encode(&mut self.outbox)
+                    .await?;
+                data_length.encode(&mut self.outbox).await?;
+                self.outbox.extend_from_slice(&compressed);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes everything in the outbox to the socket.
+    pub async fn flush(&mut self) -> Result<(), ClientError> {
+        while self.written < self.outbox.len() {
+            let written = self.stream.write(&self.outbox[self.written..]).await?;
+
+            if written == 0 {
+                return Err(ClientError::ConnectionClosed);
+            }
+
+            self.written += written;
+        }
+
+        self.outbox.clear();
+        self.written = 0;
+
+        Ok(())
+    }
+
+    /// Queues `packet` and flushes the outbox.
+    pub async fn send<P: Packet>(&mut self, packet: &P) -> Result<(), ClientError> {
+        self.queue(packet).await?;
+        self.flush().await
+    }
+
+    /// Waits for the next packet, returning its ID. Its body can then be
+    /// decoded with [`Connection::decode`], or read from
+    /// [`Connection::body`].
+    pub async fn read_packet(&mut self) -> Result<VarInt, ClientError> {
+        loop {
+            if let Some(packet_id) = self.next_frame().await? {
+                trace!(
+                    "Packet ID: {:02x?} - Length: {} in {:?} state",
+                    *packet_id,
+                    self.body.len(),
+                    self.state
+                );
+
+                return Ok(packet_id);
+            }
+
+            if self.stream.read_buf(&mut self.inbox).await? == 0 {
+                return Err(ClientError::ConnectionClosed);
+            }
+        }
+    }
+
+    /// The body of the last packet read.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Decodes the body of the last packet read as `P`.
+    pub async fn decode<P: Packet>(&self) -> Result<P, ClientError> {
+        Ok(P::decode(self.body.as_slice()).await?)
+    }
+
+    /// Takes a whole packet off the front of the inbox, if one has arrived,
+    /// leaving its body in `body`. Only ever reads from buffers, so it never
+    /// waits.
+    async fn next_frame(&mut self) -> Result<Option<VarInt>, ClientError> {
+        let Some((packet_length, header_length)) = peek_varint(&self.inbox)? else {
+            return Ok(None);
+        };
+
+        if packet_length > MAX_PACKET_SIZE || *packet_length < 1 {
+            return Err(DecodeError::VarIntTooBig.into());
+        }
+
+        let frame_end = header_length + *packet_length as usize;
+
+        if self.inbox.len() < frame_end {
+            return Ok(None);
+        }
+
+        let mut frame = &self.inbox[header_length..frame_end];
+
+        let data_length = if self.compression_threshold.is_some() {
+            let data_length = VarInt::decode(&mut frame).await?;
+
+            if data_length > MAX_PACKET_SIZE || *data_length < 0 {
+                return Err(DecodeError::DecompressedTooLarge.into());
+            }
+
+            // A data length of 0 means the packet was below the threshold, so
+            // was sent uncompressed.
+            Some(*data_length as usize).filter(|&data_length| data_length > 0)
+        } else {
+            None
+        };
+
+        self.body.clear();
+
+        match data_length {
+            Some(data_length) => {
+                self.body.resize(data_length, 0);
+
+                let compressed_length = frame.len();
+                let length = inflate(&mut frame, compressed_length, &mut self.body).await?;
+
+                if length != data_length {
+                    return Err(DecodeError::InvalidCompressedData.into());
+                }
+            }
+            None => self.body.extend_from_slice(frame),
+        }
+
+        self.inbox.drain(..frame_end);
+
+        let mut body = self.body.as_slice();
+        let packet_id = VarInt::decode(&mut body).await?;
+        self.body.drain(..packet_id.encoded_len());
+
+        Ok(Some(packet_id))
+    }
+}
+
+/// Reads a [`VarInt`] from the start of `bytes`, along with how many bytes it
+/// took up, or [`None`] if not all of it is there yet.
+fn peek_varint(bytes: &[u8]) -> Result<Option<(VarInt, usize)>, DecodeError> {
+    let mut value = 0i32;
+
+    for (i, byte) in bytes.iter().enumerate().take(5) {
+        value |= i32::from(byte & 0x7f) << (7 * i);
+
+        if byte & 0x80 == 0 {
+            return Ok(Some((VarInt(value), i + 1)));
+        }
+    }
+
+    if bytes.len() >= 5 {
+        Err(DecodeError::VarIntTooBig)
+    } else {
+        Ok(None)
+    }
+}
@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+use crate::prelude::*;
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Encode(#[from] EncodeError),
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+    #[error("disconnected by the server: {0}")]
+    Disconnected(std::string::String),
+    #[error("connection closed")]
+    ConnectionClosed,
+    #[error("packet of {0} bytes is too large")]
+    PacketTooLarge(usize),
+    #[error("unexpected packet with ID {0:#04x} in state {1:?}")]
+    UnexpectedPacket(i32, State),
+}
@@ -0,0 +1,35 @@
+//! A headless client for picocraft servers.
+//!
+//! [`Bot`] logs in with the same packet types the server uses, answers
+//! keep-alives and teleports on its own, and can be scripted to walk around
+//! and chat. It is used for load testing and for end-to-end tests which
+//! don't need a vanilla client.
+
+#![deny(clippy::mem_forget)]
+#![warn(
+    clippy::empty_structs_with_brackets,
+    clippy::error_impl_error,
+    clippy::large_include_file,
+    clippy::panic_in_result_fn,
+    clippy::suspicious_xor_used_as_pow,
+    clippy::tests_outside_test_module,
+    clippy::undocumented_unsafe_blocks,
+    clippy::unwrap_used
+)]
+
+pub mod bot;
+pub mod connection;
+pub mod errors;
+
+pub use bot::{Bot, Event};
+pub use connection::Connection;
+pub use errors::ClientError;
+
+#[allow(unused)]
+pub(crate) mod prelude {
+    pub(crate) use log::{debug, error, info, trace, warn};
+    pub(crate) use picocraft_core::prelude::*;
+    pub(crate) use picocraft_proto::prelude::*;
+
+    pub(crate) use crate::errors::ClientError;
+}
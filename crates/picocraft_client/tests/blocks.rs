@@ -0,0 +1,81 @@
+//! Breaks blocks with a bot, and checks everyone sees them broken.
+
+mod common;
+
+use core::time::Duration;
+
+use picocraft_client::{Bot, Event};
+use picocraft_core::prelude::*;
+use picocraft_terrain::terrain::coordinates::Coordinates;
+
+const AIR: VarInt = VarInt(0);
+
+#[tokio::test(flavor = "multi_thread")]
+async fn broken_blocks_stay_broken_for_players_joining_later() {
+    let (address, terrain) = common::start_server().await;
+
+    // The top of the terrain at 0, 0, which the bot stands on to break it.
+    let surface = (0..=u8::MAX)
+        .rev()
+        .find(|y| terrain.block_state_at(Coordinates::new(0, *y, 0)) != Some(AIR))
+        .expect("there is terrain at 0, 0");
+
+    let mut digger = Bot::join(address, "localhost", "Digger")
+        .await
+        .expect("bot can join");
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while !matches!(digger.next_event().await, Ok(Event::Teleported { .. })) {}
+
+        digger
+            .move_to(0.5, f64::from(surface) + 1.0, 0.5)
+            .await
+            .expect("open");
+        let sequence = digger
+            .break_block(0, i32::from(surface), 0)
+            .await
+            .expect("open");
+
+        // Out of reach, so only acknowledged.
+        let too_far = digger.break_block(0, 0, 0).await.expect("open");
+
+        let mut broken = false;
+
+        loop {
+            match digger.next_event().await.expect("bot stays connected") {
+                Event::BlockChanged { x, y, z, state } => {
+                    assert_eq!((x, y, z, state), (0, i32::from(surface), 0, AIR));
+                    broken = true;
+                }
+                Event::BlockChangesAcknowledged(acknowledged) if acknowledged == sequence => {
+                    assert!(broken, "the block update comes before the acknowledgement");
+                }
+                Event::BlockChangesAcknowledged(acknowledged) if acknowledged == too_far => break,
+                _ => {}
+            }
+        }
+    })
+    .await
+    .expect("the block is broken in time");
+
+    assert_eq!(
+        terrain.block_state_at(Coordinates::new(0, surface, 0)),
+        Some(AIR)
+    );
+    assert_ne!(terrain.block_state_at(Coordinates::new(0, 0, 0)), Some(AIR));
+
+    let mut latecomer = Bot::join(address, "localhost", "Latecomer")
+        .await
+        .expect("bot can join");
+    latecomer.keep_chunks(true);
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while latecomer.chunk(0, 0).is_none() {
+            latecomer.next_event().await.expect("bot stays connected");
+        }
+    })
+    .await
+    .expect("the chunk arrives in time");
+
+    assert_eq!(latecomer.block_state(0, surface, 0), Some(AIR));
+}
@@ -0,0 +1,50 @@
+//! A real server for bots to join, shared by the tests.
+
+use core::cell::RefCell;
+use core::net::SocketAddr;
+use core::time::Duration;
+
+use embassy_sync::mutex::Mutex;
+use picocraft_server::prelude::*;
+use picocraft_terrain::Terrain;
+use rand_chacha::ChaCha8Rng;
+use rand_chacha::rand_core::SeedableRng;
+use static_cell::StaticCell;
+
+static SYSTEM_RNG: StaticCell<SystemRng> = StaticCell::new();
+static SERVER_CONFIG: StaticCell<ServerConfig> = StaticCell::new();
+
+/// Starts a server and ticks its world, returning where it's listening and
+/// its terrain. The world and channels are global, so there can only be one
+/// in each test binary.
+pub async fn start_server() -> (SocketAddr, &'static Terrain) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("can bind to a free port");
+    let address = listener.local_addr().expect("listener is bound");
+
+    let system_rng =
+        SYSTEM_RNG.init_with(|| Mutex::new(RefCell::new(ChaCha8Rng::seed_from_u64(0))));
+    let config = SERVER_CONFIG.init_with(ServerConfig::default);
+
+    let mut server = Server::new(config, listener, system_rng);
+    let terrain = server.terrain;
+
+    tokio::spawn(async move {
+        let mut world = picocraft_ecs::World::new();
+        let mut ticker = tokio::time::interval(Duration::from_millis(50));
+
+        loop {
+            ticker.tick().await;
+            picocraft_server::tick::tick(&mut world, terrain);
+        }
+    });
+
+    tokio::spawn(async move {
+        while let Ok(Some(mut client)) = server.next_connection().await {
+            tokio::spawn(async move { client.handle_connection().await });
+        }
+    });
+
+    (address, terrain)
+}
@@ -0,0 +1,54 @@
+//! Joins a real server with a bot, and checks what it sees.
+
+mod common;
+
+use core::time::Duration;
+
+use picocraft_client::{Bot, Event};
+use picocraft_core::prelude::*;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn bot_joins_and_sees_the_terrain() {
+    let (address, terrain) = common::start_server().await;
+
+    let mut bot = Bot::join(address, "localhost", "Tester")
+        .await
+        .expect("bot can join");
+    bot.keep_chunks(true);
+
+    let (mut joined, mut teleported, mut chunks) = (false, false, 0);
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while !(joined && teleported && chunks >= 4) {
+            match bot.next_event().await.expect("bot stays connected") {
+                Event::Joined { .. } => joined = true,
+                Event::Teleported { .. } => {
+                    teleported = true;
+                    bot.chat("Hello!").await.expect("message fits");
+                }
+                Event::ChunkLoaded { x, z } => {
+                    chunks += 1;
+
+                    let expected = terrain.get_chunk(x as i8, z as i8);
+                    let received = bot.chunk(x, z).expect("chunks are kept");
+                    assert!(received.block_state(0, 0, 0).is_some());
+
+                    for y in 0..WORLD_HEIGHT as u8 {
+                        for (x, z) in (0..16).flat_map(|x| (0..16).map(move |z| (x, z))) {
+                            assert_eq!(
+                                received.block_state(x, y, z),
+                                expected.block_state(x, y, z),
+                                "block at {x}, {y}, {z}"
+                            );
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    })
+    .await
+    .expect("bot joins, spawns and receives chunks in time");
+
+    assert!(bot.entity_id().is_some());
+}
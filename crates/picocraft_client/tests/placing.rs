@@ -0,0 +1,99 @@
+//! Places blocks with a bot, and checks everyone sees them placed.
+
+mod common;
+
+use core::time::Duration;
+
+use picocraft_client::{Bot, Event};
+use picocraft_core::prelude::*;
+use picocraft_proto::serverbound::BlockFace;
+use picocraft_terrain::terrain::coordinates::Coordinates;
+
+const AIR: VarInt = VarInt(0);
+const COBBLESTONE_ITEM: VarInt = VarInt(35);
+const COBBLESTONE: VarInt = VarInt(14);
+
+#[tokio::test(flavor = "multi_thread")]
+async fn placed_blocks_stay_placed_for_players_joining_later() {
+    let (address, terrain) = common::start_server().await;
+
+    // The top of the terrain at 0, 0, which the bot stands on to build.
+    let surface = (0..=u8::MAX)
+        .rev()
+        .find(|y| terrain.block_state_at(Coordinates::new(0, *y, 0)) != Some(AIR))
+        .expect("there is terrain at 0, 0");
+    let above_head = surface + 3;
+
+    let mut builder = Bot::join(address, "localhost", "Builder")
+        .await
+        .expect("bot can join");
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while !matches!(builder.next_event().await, Ok(Event::Teleported { .. })) {}
+
+        builder
+            .move_to(0.5, f64::from(surface) + 1.0, 0.5)
+            .await
+            .expect("open");
+        builder.hold_item(0, COBBLESTONE_ITEM).await.expect("open");
+
+        // Where the bot is standing, so only acknowledged.
+        let inside = builder
+            .place_block(0, i32::from(surface), 0, BlockFace::Top)
+            .await
+            .expect("open");
+        let sequence = builder
+            .place_block(0, i32::from(above_head) - 1, 0, BlockFace::Top)
+            .await
+            .expect("open");
+
+        let mut placed = false;
+
+        loop {
+            match builder.next_event().await.expect("bot stays connected") {
+                Event::BlockChanged { x, y, z, state } => {
+                    assert_eq!((x, y, z, state), (0, i32::from(above_head), 0, COBBLESTONE));
+                    placed = true;
+                }
+                Event::BlockChangesAcknowledged(acknowledged) if acknowledged == inside => {
+                    assert!(!placed, "nothing is placed inside the bot");
+                }
+                Event::BlockChangesAcknowledged(acknowledged) if acknowledged == sequence => {
+                    assert!(placed, "the block update comes before the acknowledgement");
+                    break;
+                }
+                _ => {}
+            }
+        }
+    })
+    .await
+    .expect("the block is placed in time");
+
+    assert_eq!(
+        terrain.block_state_at(Coordinates::new(0, above_head, 0)),
+        Some(COBBLESTONE)
+    );
+    assert_eq!(
+        terrain.block_state_at(Coordinates::new(0, surface + 1, 0)),
+        Some(AIR)
+    );
+
+    let mut latecomer = Bot::join(address, "localhost", "Latecomer")
+        .await
+        .expect("bot can join");
+    latecomer.keep_chunks(true);
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while latecomer.chunk(0, 0).is_none() {
+            latecomer.next_event().await.expect("bot stays connected");
+        }
+    })
+    .await
+    .expect("the chunk arrives in time");
+
+    assert_eq!(latecomer.block_state(0, above_head, 0), Some(COBBLESTONE));
+    assert_eq!(
+        latecomer.block_state(0, surface, 0),
+        terrain.block_state_at(Coordinates::new(0, surface, 0))
+    );
+}
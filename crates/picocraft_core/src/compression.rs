@@ -0,0 +1,528 @@
+//! `no_std` and allocation-free zlib compression for the compressed packet
+//! format.
+//!
+//! Compression uses a small LZ77 window and the fixed Huffman codes from the
+//! deflate spec, so everything can live in fixed size buffers. This gives up
+//! some compression ratio compared to dynamic Huffman blocks, but chunk and
+//! light data are very repetitive, so it still shrinks them massively.
+//! Decompression is handled by `miniz_oxide`'s allocation-free inflate core.
+
+use miniz_oxide::inflate::TINFLStatus;
+use miniz_oxide::inflate::core::inflate_flags::{
+    TINFL_FLAG_HAS_MORE_INPUT, TINFL_FLAG_PARSE_ZLIB_HEADER,
+    TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF,
+};
+use miniz_oxide::inflate::core::{DecompressorOxide, decompress};
+
+use crate::prelude::*;
+
+/// How much memory a [`Deflater`] takes, wherever it is kept.
+pub const DEFLATER_SIZE: usize = core::mem::size_of::<Deflater>();
+
+/// The furthest back a match can reference. Must be a power of two.
+const WINDOW_SIZE: usize = 2048;
+/// Holds the window of history plus the same amount again of lookahead.
+const BUFFER_SIZE: usize = WINDOW_SIZE * 2;
+const HASH_BITS: u32 = 11;
+const HASH_SIZE: usize = 1 << HASH_BITS;
+const OUTPUT_SIZE: usize = 256;
+
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+/// CMF and FLG bytes of the zlib header: deflate with a 32K window, no preset
+/// dictionary and the fastest compression level.
+const ZLIB_HEADER: [u8; 2] = [0x78, 0x01];
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// An [`embedded_io_async::Write`] adapter which zlib compresses everything
+/// written to it before passing it on to the inner writer.
+///
+/// [`ZlibEncoder::finish`] must be called once all data has been written, to
+/// flush the final block and the checksum.
+pub struct ZlibEncoder<'a, W: Write> {
+    inner: W,
+    deflater: &'a mut Deflater,
+}
+
+impl<'a, W: Write> ZlibEncoder<'a, W> {
+    /// Starts a new zlib stream, using `deflater` to compress it. Anything it
+    /// was used for before is forgotten.
+    pub fn new(inner: W, deflater: &'a mut Deflater) -> Self {
+        deflater.reset();
+
+        Self { inner, deflater }
+    }
+
+    /// Compresses any remaining input, writes the zlib trailer and returns the
+    /// inner writer.
+    pub async fn finish(mut self) -> Result<W, W::Error> {
+        loop {
+            let done = self.deflater.compress(true);
+            self.write_output().await?;
+
+            if done {
+                break;
+            }
+        }
+
+        self.deflater.finish();
+        self.write_output().await?;
+
+        Ok(self.inner)
+    }
+
+    async fn write_output(&mut self) -> Result<(), W::Error> {
+        self.inner.write_all(self.deflater.output()).await?;
+        self.deflater.clear_output();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for ZlibEncoder<'_, W> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let consumed = self.deflater.fill(buf);
+
+            if consumed > 0 {
+                return Ok(consumed);
+            }
+
+            // the input buffer is full, so compress until there's room again.
+            self.deflater.compress(false);
+            self.write_output().await?;
+            self.deflater.slide();
+        }
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.flush().await
+    }
+}
+
+impl<W: Write> embedded_io::ErrorType for ZlibEncoder<'_, W> {
+    type Error = W::Error;
+}
+
+/// The state of an LZ77 + fixed Huffman deflate stream, which takes
+/// [`DEFLATER_SIZE`] bytes. It is reused for each stream, so it can be kept
+/// somewhere long lived rather than built again for every packet.
+pub struct Deflater {
+    buffer: [u8; BUFFER_SIZE],
+    /// Number of valid bytes in `buffer`.
+    len: usize,
+    /// Position of the next byte in `buffer` to be compressed.
+    pos: usize,
+    /// The most recent position (plus one) in `buffer` of each 3 byte hash, or
+    /// 0 if there isn't one.
+    head: [u16; HASH_SIZE],
+    bits: u32,
+    bit_count: u32,
+    output: Vec<u8, OUTPUT_SIZE>,
+    adler: adler2::Adler32,
+}
+
+impl Default for Deflater {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deflater {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            buffer: [0; BUFFER_SIZE],
+            len: 0,
+            pos: 0,
+            head: [0; HASH_SIZE],
+            bits: 0,
+            bit_count: 0,
+            output: Vec::new(),
+            adler: adler2::Adler32::new(),
+        }
+    }
+
+    /// Forgets any earlier stream and starts a new one.
+    fn reset(&mut self) {
+        self.len = 0;
+        self.pos = 0;
+        self.head.fill(0);
+        self.bits = 0;
+        self.bit_count = 0;
+        self.output.clear();
+        self.adler = adler2::Adler32::new();
+
+        self.output
+            .extend_from_slice(&ZLIB_HEADER)
+            .expect("output buffer is empty");
+
+        // BFINAL = 1, BTYPE = 01 (fixed Huffman codes). All data is written as a
+        // single block, which is ended in `finish`.
+        self.write_bits(0b011, 3);
+    }
+
+    /// Copies as much of `data` as fits into the input buffer, returning how
+    /// many bytes were consumed.
+    fn fill(&mut self, data: &[u8]) -> usize {
+        let count = data.len().min(BUFFER_SIZE - self.len);
+
+        self.buffer[self.len..self.len + count].copy_from_slice(&data[..count]);
+        self.adler.write_slice(&data[..count]);
+        self.len += count;
+
+        count
+    }
+
+    /// Discards history that is further back than the window, making room for
+    /// more input.
+    fn slide(&mut self) {
+        if self.pos <= WINDOW_SIZE {
+            return;
+        }
+
+        let shift = self.pos - WINDOW_SIZE;
+
+        self.buffer.copy_within(shift..self.len, 0);
+        self.len -= shift;
+        self.pos -= shift;
+
+        for head in &mut self.head {
+            *head = head.saturating_sub(shift as u16);
+        }
+    }
+
+    /// Compresses buffered input until either the output buffer is nearly full,
+    /// or there isn't enough lookahead left. If `flush` is true then all
+    /// buffered input is compressed, regardless of lookahead.
+    ///
+    /// Returns true once all buffered input has been compressed.
+    fn compress(&mut self, flush: bool) -> bool {
+        // A single symbol is never more than 31 bits, and the bit accumulator can
+        // hold up to 7 more.
+        while self.output.len() + 8 <= OUTPUT_SIZE {
+            let lookahead = self.len - self.pos;
+
+            if lookahead == 0 || (!flush && lookahead < MAX_MATCH) {
+                break;
+            }
+
+            match self.find_match() {
+                Some((length, distance)) => {
+                    self.write_match(length, distance);
+
+                    for offset in 1..length {
+                        self.insert_hash(self.pos + offset);
+                    }
+
+                    self.pos += length;
+                }
+                None => {
+                    self.write_literal(self.buffer[self.pos]);
+                    self.pos += 1;
+                }
+            }
+        }
+
+        self.pos == self.len
+    }
+
+    /// Writes the end of block symbol, pads to a byte boundary and writes the
+    /// adler32 checksum of the uncompressed data.
+    fn finish(&mut self) {
+        self.write_fixed_code(256);
+
+        if self.bit_count > 0 {
+            self.write_bits(0, 8 - self.bit_count);
+        }
+
+        let checksum = self.adler.checksum();
+
+        self.output
+            .extend_from_slice(&checksum.to_be_bytes())
+            .expect("output buffer was just cleared");
+    }
+
+    fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    fn clear_output(&mut self) {
+        self.output.clear();
+    }
+
+    fn hash(&self, pos: usize) -> usize {
+        let bytes = u32::from(self.buffer[pos])
+            | (u32::from(self.buffer[pos + 1]) << 8)
+            | (u32::from(self.buffer[pos + 2]) << 16);
+
+        (bytes.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+    }
+
+    fn insert_hash(&mut self, pos: usize) {
+        if pos + MIN_MATCH <= self.len {
+            let hash = self.hash(pos);
+            self.head[hash] = pos as u16 + 1;
+        }
+    }
+
+    /// Looks up the last position with the same hash as the current one, and
+    /// returns the `(length, distance)` of the match if it is long enough.
+    fn find_match(&mut self) -> Option<(usize, usize)> {
+        if self.pos + MIN_MATCH > self.len {
+            return None;
+        }
+
+        let hash = self.hash(self.pos);
+        let candidate = self.head[hash];
+        self.head[hash] = self.pos as u16 + 1;
+
+        let candidate = usize::from(candidate.checked_sub(1)?);
+        let distance = self.pos - candidate;
+
+        if distance > WINDOW_SIZE {
+            return None;
+        }
+
+        let max_length = MAX_MATCH.min(self.len - self.pos);
+
+        let length = (0..max_length)
+            .take_while(|&i| self.buffer[candidate + i] == self.buffer[self.pos + i])
+            .count();
+
+        (length >= MIN_MATCH).then_some((length, distance))
+    }
+
+    fn write_literal(&mut self, byte: u8) {
+        self.write_fixed_code(u16::from(byte));
+    }
+
+    fn write_match(&mut self, length: usize, distance: usize) {
+        let length_index = LENGTH_BASE
+            .iter()
+            .rposition(|&base| usize::from(base) <= length)
+            .expect("length is at least MIN_MATCH");
+
+        self.write_fixed_code(257 + length_index as u16);
+        self.write_bits(
+            (length - usize::from(LENGTH_BASE[length_index])) as u32,
+            u32::from(LENGTH_EXTRA[length_index]),
+        );
+
+        let distance_index = DISTANCE_BASE
+            .iter()
+            .rposition(|&base| usize::from(base) <= distance)
+            .expect("distance is at least 1");
+
+        // distance codes are all 5 bits long when using fixed Huffman codes.
+        self.write_huffman(distance_index as u32, 5);
+        self.write_bits(
+            (distance - usize::from(DISTANCE_BASE[distance_index])) as u32,
+            u32::from(DISTANCE_EXTRA[distance_index]),
+        );
+    }
+
+    /// Writes a literal/length symbol using the fixed Huffman code table.
+    fn write_fixed_code(&mut self, symbol: u16) {
+        let symbol = u32::from(symbol);
+
+        match symbol {
+            0..=143 => self.write_huffman(0x30 + symbol, 8),
+            144..=255 => self.write_huffman(0x190 + symbol - 144, 9),
+            256..=279 => self.write_huffman(symbol - 256, 7),
+            _ => self.write_huffman(0xc0 + symbol - 280, 8),
+        }
+    }
+
+    /// Huffman codes are packed starting from their most significant bit.
+    fn write_huffman(&mut self, code: u32, length: u32) {
+        self.write_bits(code.reverse_bits() >> (32 - length), length);
+    }
+
+    fn write_bits(&mut self, value: u32, count: u32) {
+        self.bits |= value << self.bit_count;
+        self.bit_count += count;
+
+        while self.bit_count >= 8 {
+            self.output
+                .push(self.bits as u8)
+                .expect("compress always leaves room for a full symbol");
+            self.bits >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+}
+
+/// Reads `compressed_length` bytes of zlib data from `reader`, and inflates it
+/// into `output`. Returns the number of uncompressed bytes written.
+pub async fn inflate<R: Read>(
+    mut reader: R,
+    compressed_length: usize,
+    output: &mut [u8],
+) -> Result<usize, DecodeError> {
+    let mut decompressor = DecompressorOxide::new();
+
+    let mut input = [0u8; 64];
+    let (mut start, mut end) = (0, 0);
+    let mut remaining = compressed_length;
+    let mut output_position = 0;
+
+    loop {
+        if start == end && remaining > 0 {
+            end = remaining.min(input.len());
+            start = 0;
+
+            reader.read_exact(&mut input[..end]).await?;
+            remaining -= end;
+        }
+
+        let mut flags = TINFL_FLAG_PARSE_ZLIB_HEADER | TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF;
+
+        if remaining > 0 {
+            flags |= TINFL_FLAG_HAS_MORE_INPUT;
+        }
+
+        let (status, consumed, written) = decompress(
+            &mut decompressor,
+            &input[start..end],
+            output,
+            output_position,
+            flags,
+        );
+
+        start += consumed;
+        output_position += written;
+
+        match status {
+            TINFLStatus::Done if remaining == 0 && start == end => return Ok(output_position),
+            TINFLStatus::Done => return Err(DecodeError::InvalidCompressedData),
+            TINFLStatus::NeedsMoreInput if remaining > 0 || start < end => {}
+            TINFLStatus::NeedsMoreInput => return Err(DecodeError::UnexpectedEof),
+            // A full output buffer is reported as needing more space whenever the
+            // input runs out, even if all that's left is the end of the stream, so
+            // only give up once no more input is being consumed.
+            TINFLStatus::HasMoreOutput if consumed > 0 && (remaining > 0 || start < end) => {}
+            TINFLStatus::HasMoreOutput => return Err(DecodeError::DecompressedTooLarge),
+            _ => return Err(DecodeError::InvalidCompressedData),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+
+    async fn compress(data: &[u8]) -> Vec<u8> {
+        compress_with(&mut Deflater::new(), data).await
+    }
+
+    async fn compress_with(deflater: &mut Deflater, data: &[u8]) -> Vec<u8> {
+        let mut compressed = std::vec![0u8; data.len() + 1024];
+
+        let mut encoder = ZlibEncoder::new(compressed.as_mut_slice(), deflater);
+        encoder
+            .write_all(data)
+            .await
+            .expect("buffer is large enough");
+        let remaining = encoder
+            .finish()
+            .await
+            .expect("buffer is large enough")
+            .len();
+
+        let length = compressed.len() - remaining;
+        compressed.truncate(length);
+        compressed
+    }
+
+    async fn roundtrip(data: &[u8]) -> Vec<u8> {
+        let compressed = compress(data).await;
+
+        let mut output = std::vec![0u8; data.len()];
+        let length = inflate(compressed.as_slice(), compressed.len(), &mut output)
+            .await
+            .expect("compressed data should be valid");
+
+        output.truncate(length);
+        output
+    }
+
+    #[test]
+    fn roundtrip_empty() {
+        embassy_futures::block_on(async {
+            assert!(roundtrip(&[]).await.is_empty());
+        });
+    }
+
+    #[test]
+    fn roundtrip_mixed_data() {
+        embassy_futures::block_on(async {
+            let data: Vec<u8> = (0..20_000u32)
+                .map(|i| (i.wrapping_mul(2_654_435_761) >> (i % 13)) as u8 % 7)
+                .collect();
+
+            assert_eq!(roundtrip(&data).await, data);
+        });
+    }
+
+    #[test]
+    fn roundtrip_exactly_sized_output() {
+        embassy_futures::block_on(async {
+            // Some of these fill the output just as an input chunk runs out.
+            for len in 1..600u32 {
+                let data: Vec<u8> = (0..len).map(|i| (i * 31 % 251) as u8).collect();
+
+                assert_eq!(roundtrip(&data).await, data, "{len} bytes");
+            }
+        });
+    }
+
+    #[test]
+    fn light_data_compresses_well() {
+        embassy_futures::block_on(async {
+            let data = [0xffu8; 2048 * 18];
+
+            let compressed = compress(&data).await;
+
+            assert!(compressed.len() < data.len() / 50);
+            assert_eq!(roundtrip(&data).await, data);
+        });
+    }
+
+    #[test]
+    fn deflaters_forget_earlier_streams() {
+        embassy_futures::block_on(async {
+            let mut deflater = Deflater::new();
+            let first: Vec<u8> = (0..5000u32).map(|i| (i * 7 % 13) as u8).collect();
+            let second = [1u8, 2, 3, 1, 2, 3, 1, 2, 3];
+
+            compress_with(&mut deflater, &first).await;
+
+            assert_eq!(
+                compress_with(&mut deflater, &second).await,
+                compress(&second).await
+            );
+        });
+    }
+}
@@ -24,6 +24,10 @@ pub enum EncodeError {
     Unknown,
     #[error("invalid bits per entry value")]
     InvalidBPE,
+    #[error("value is too long to be encoded")]
+    TooLong,
+    #[error("NBT tags were written out of order")]
+    InvalidNbt,
 }
 
 impl<E: embedded_io::Error> From<E> for EncodeError {
@@ -67,8 +71,22 @@ pub enum DecodeError {
     Unimplemented,
     #[error("no bytes should be readable when decoding [`Optional`] ")]
     UnexpectedOptionalRead,
-    #[error("namespace part of Identifier is not 'minecraft'")]
+    #[error("namespace part of Identifier is not 'minecraft', or has invalid characters")]
     InvalidNamespace,
     #[error("invalid EnumSet bits: {0:#04x}")]
     InvalidEnumSetBits(u8),
+    #[error("compressed data is not a valid zlib stream")]
+    InvalidCompressedData,
+    #[error("decompressed data does not fit in the buffer")]
+    DecompressedTooLarge,
+    #[error("compressed packet is smaller than the compression threshold")]
+    CompressedBelowThreshold,
+    #[error("invalid NBT tag type: {0:#04x}")]
+    InvalidNbtTag(u8),
+    #[error("NBT is nested too deeply")]
+    NbtTooDeep,
+    #[error("NBT is too large")]
+    NbtTooLarge,
+    #[error("unsupported bits per entry value: {0}")]
+    InvalidBPE(u8),
 }
@@ -16,6 +16,8 @@ extern crate std;
 
 /// `embedded_io_async` compatible versions of basic `byteorder` traits.
 pub mod byteorder;
+/// `no_std` zlib compression and decompression for compressed packets.
+pub mod compression;
 pub mod errors;
 pub mod packet;
 pub mod state;
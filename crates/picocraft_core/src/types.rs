@@ -6,14 +6,18 @@ mod boolean;
 mod core;
 mod cow;
 mod enum_set;
+mod id_set;
 mod identifier;
+mod json;
 mod lpvec3;
-mod nbt;
+pub mod nbt;
 mod optional;
 mod position;
 mod prefixed_array;
-mod slot;
+pub mod slot;
+mod sound_event;
 mod string;
+pub mod text_component;
 mod uuid;
 mod varint;
 mod varlong;
@@ -29,14 +33,30 @@ pub type Float = f32;
 pub type Double = f64;
 pub type String<const N: usize> = heapless::String<N>;
 
-#[derive(Clone, Debug)]
-#[deprecated(note = "unimplemented")]
-pub struct TextComponent;
-// pub type JsonTextComponent;
+pub use text_component::{ClickEvent, Color, Content, HoverEvent, NamedColor, Style};
+pub use uuid::offline_uuid;
+
+/// Formatted text, as shown in chat, on disconnect screens, as item names etc.
+/// It only borrows its strings and children, so it can be built from
+/// `heapless` strings and arrays without copying them.
+///
+/// Encoded as network NBT, or as JSON when wrapped in [`Json`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextComponent<'a> {
+    pub content: Content<'a>,
+    pub style: Style<'a>,
+    /// Components appended after this one, which inherit its style.
+    pub children: &'a [TextComponent<'a>],
+}
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Identifier<const N: usize>(pub String<N>);
 
+/// An [`Identifier`] which isn't limited to the `minecraft` namespace, stored
+/// as the full `namespace:path` string.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NamespacedIdentifier<const N: usize>(String<N>);
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash, Default)]
 pub struct VarInt(pub i32);
 
@@ -45,22 +65,45 @@ pub struct VarLong(pub i64);
 
 // pub type EntityMetadata;
 
-#[derive(Debug)]
+/// A value sent as a JSON string. It is serialized while being encoded, so the
+/// whole string never has to fit in memory.
+#[derive(Debug, Clone)]
+pub struct Json<T>(pub T);
+
+/// A stack of items, or nothing when `item_count` is zero. See [`slot`] for
+/// its components.
+#[derive(Debug, Clone, Default)]
 pub struct Slot {
     pub item_count: VarInt,
-    pub item_id: Optional<VarInt>,
-    pub number_of_components_to_add: Optional<VarInt>,
-    pub number_of_components_to_remove: Optional<VarInt>,
-    pub components_to_add: Optional<Array<slot::StructuredComponent, 4>>,
+    pub item_id: VarInt,
+    /// Components which the item doesn't have by default, or which differ
+    /// from the defaults.
+    pub components_to_add: heapless::Vec<slot::StructuredComponent, { slot::MAX_COMPONENTS }>,
+    /// Default components of the item which this stack doesn't have.
+    pub components_to_remove: heapless::Vec<VarInt, { slot::MAX_COMPONENTS }>,
+}
+
+/// A stack of items without any components, which is all the server keeps of
+/// items. Sent as a [`Slot`] without components. Item IDs fit in a `u16`, so
+/// are converted to VarInts when serialised.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ItemStack {
+    pub item_id: u16,
+    /// Nothing is in the stack when this is zero.
+    pub count: u8,
 }
 
-// pub type HashedSlot;
+/// A [`Slot`] as clients send it when clicking in a container, with hashes of
+/// its components in place of their data. Only its [`ItemStack`] is kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HashedSlot(pub ItemStack);
 
-/// A placeholder for the NBT data type.
-#[derive(Debug, Clone)]
-pub struct NBT;
+/// Encoded network NBT of up to `N` bytes. See [`nbt`] for reading and
+/// writing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NBT<const N: usize = 256>(heapless::Vec<u8, N>);
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct BlockPosition(i64);
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -75,7 +118,7 @@ pub struct BitSet<const N: usize>(pub PrefixedArray<Long, N>);
 // pub type FixedBitSet;
 pub type Optional<T> = Option<T>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PrefixedOptional<T>(pub Option<T>);
 
 #[derive(Debug, Clone, Default)]
@@ -87,28 +130,32 @@ pub struct PrefixedArray<T, const N: usize>(heapless::Vec<T, N>);
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct EnumSet(u8);
 
+/// Either the ID of a registry entry, or an entry given inline.
 #[derive(Clone, Debug)]
-pub enum IDor<const N: usize, X> {
-    ID(Identifier<N>),
+pub enum IDor<X> {
+    ID(VarInt),
     X(X),
 }
 
+/// A tag of a registry, such as `#minecraft:harmful`, or a list of entries of
+/// that registry.
 #[derive(Clone, Debug)]
-pub struct IDSet<const TAG_LENGTH: usize, const N: usize> {
-    pub r#type: VarInt,
-    pub tag_name: Optional<Identifier<TAG_LENGTH>>,
-    pub ids: Optional<Array<VarInt, N>>,
+pub enum IDSet<const TAG_LENGTH: usize, const N: usize> {
+    Tag(NamespacedIdentifier<TAG_LENGTH>),
+    IDs(heapless::Vec<VarInt, N>),
 }
 
 #[derive(Clone, Debug)]
 pub struct SoundEvent {
-    //TODO check the N value for this Identifier
-    pub sound_name: Identifier<16>,
-    pub has_fixed_range: Boolean,
-    pub fixed_range: Optional<Float>,
+    pub sound_name: NamespacedIdentifier<48>,
+    /// How far away the sound can be heard, if it shouldn't depend on its
+    /// volume.
+    pub fixed_range: PrefixedOptional<Float>,
 }
 
-/// Not fully implemented, only worked for Zeroed values, and will error on any other value. [Minecraft.wiki link to actual implementation](https://minecraft.wiki/w/Java_Edition_protocol/Data_types#LpVec3).
+/// A low precision vector, used for entity velocities in blocks per tick.
+/// Each component is sent to within about 1/16383 of the largest component.
+/// [Minecraft.wiki](https://minecraft.wiki/w/Java_Edition_protocol/Data_types#LpVec3).
 #[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct LpVec3 {
     pub x: f64,
@@ -123,17 +170,14 @@ impl LpVec3 {
         z: 0.0,
     };
 
-    pub fn new(x: f64, y: f64, z: f64) -> Self {
-        let vec = Self { x, y, z };
+    /// Vectors whose components are all smaller than this are sent as zero.
+    pub const MIN_COMPONENT: f64 = 3.051_944_088_384_301e-5;
 
-        assert_eq!(
-            vec,
-            Self::ZERO,
-            "LpVec3 is currently only implemented for zeroed values, got {:?}",
-            vec
-        );
+    /// Larger components are clamped to this.
+    pub const MAX_COMPONENT: f64 = 1.717_986_918_3e10;
 
-        vec
+    pub const fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
     }
 }
 
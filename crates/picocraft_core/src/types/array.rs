@@ -39,3 +39,20 @@ impl<T: Encode, const N: usize> Encode for Array<T, N> {
         Ok(())
     }
 }
+
+/// As there is no length prefix, this reads elements until the buffer is
+/// empty, so it must be the last field of a packet.
+impl<T: Decode, const N: usize> Decode for Array<T, N> {
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        let mut array = Self::new();
+
+        while let Some(element) = Optional::<T>::decode(&mut buffer).await? {
+            array
+                .0
+                .push(element)
+                .map_err(|_| DecodeError::VarIntTooBig)?;
+        }
+
+        Ok(array)
+    }
+}
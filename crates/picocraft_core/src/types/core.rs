@@ -19,6 +19,22 @@ impl Encode for &'static [u8] {
     }
 }
 
+/// Fixed length byte arrays, which aren't length prefixed.
+impl<const N: usize> Encode for [u8; N] {
+    async fn encode<W: Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
+        buffer.write_all(self).await?;
+        Ok(())
+    }
+}
+
+impl<const N: usize> Decode for [u8; N] {
+    async fn decode<R: Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        let mut bytes = [0; N];
+        buffer.read_exact(&mut bytes).await?;
+        Ok(bytes)
+    }
+}
+
 macro_rules! impl_decode_integer {
     ($($ty:ty), *) => {
         $(
@@ -0,0 +1,69 @@
+use crate::prelude::*;
+
+impl<X: Encode> Encode for IDor<X> {
+    /// IDs are sent plus one, so that zero can mean the entry follows inline.
+    async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
+        match self {
+            Self::ID(id) => VarInt(id.0 + 1).encode(&mut buffer).await,
+            Self::X(x) => {
+                VarInt(0).encode(&mut buffer).await?;
+                x.encode(&mut buffer).await
+            }
+        }
+    }
+}
+
+impl<X: Decode> Decode for IDor<X> {
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        match VarInt::decode(&mut buffer).await? {
+            VarInt(0) => Ok(Self::X(X::decode(&mut buffer).await?)),
+            VarInt(id) if id > 0 => Ok(Self::ID(VarInt(id - 1))),
+            id => Err(DecodeError::VarIntTooSmall(id)),
+        }
+    }
+}
+
+impl<const TAG_LENGTH: usize, const N: usize> Encode for IDSet<TAG_LENGTH, N> {
+    /// Prefixed with zero for a tag, or with the number of IDs plus one.
+    async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
+        match self {
+            Self::Tag(tag) => {
+                VarInt(0).encode(&mut buffer).await?;
+                tag.encode(&mut buffer).await
+            }
+            Self::IDs(ids) => {
+                VarInt(ids.len() as i32 + 1).encode(&mut buffer).await?;
+
+                for id in ids {
+                    id.encode(&mut buffer).await?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<const TAG_LENGTH: usize, const N: usize> Decode for IDSet<TAG_LENGTH, N> {
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        let prefix = VarInt::decode(&mut buffer).await?;
+
+        if prefix.0 == 0 {
+            return Ok(Self::Tag(NamespacedIdentifier::decode(&mut buffer).await?));
+        }
+
+        let len = usize::try_from(prefix.0 - 1).map_err(|_| DecodeError::VarIntTooSmall(prefix))?;
+
+        if len > N {
+            return Err(DecodeError::VarIntTooBig);
+        }
+
+        let mut ids = Vec::new();
+
+        for _ in 0..len {
+            let _ = ids.push(VarInt::decode(&mut buffer).await?);
+        }
+
+        Ok(Self::IDs(ids))
+    }
+}
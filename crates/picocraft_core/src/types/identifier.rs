@@ -68,6 +68,83 @@ impl<const N: usize> TryFrom<&str> for Identifier<N> {
     }
 }
 
+impl<const N: usize> NamespacedIdentifier<N> {
+    /// The part before the `:`, which is `minecraft` if there isn't one.
+    pub fn namespace(&self) -> &str {
+        self.0
+            .split_once(':')
+            .map_or(NAMESPACE, |(namespace, _)| namespace)
+    }
+
+    pub fn path(&self) -> &str {
+        self.0.split_once(':').map_or(&self.0, |(_, path)| path)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether this names the same thing as `other`, either of which may
+    /// leave out the `minecraft` namespace.
+    pub fn matches(&self, other: &str) -> bool {
+        let (namespace, path) = other.split_once(':').unwrap_or((NAMESPACE, other));
+
+        self.namespace() == namespace && self.path() == path
+    }
+
+    fn is_valid(identifier: &str) -> bool {
+        let (namespace, path) = identifier
+            .split_once(':')
+            .unwrap_or((NAMESPACE, identifier));
+
+        !path.is_empty()
+            && namespace
+                .bytes()
+                .all(|b| matches!(b, b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-' | b'.'))
+            && path
+                .bytes()
+                .all(|b| matches!(b, b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-' | b'.' | b'/'))
+    }
+}
+
+impl<const N: usize> Encode for NamespacedIdentifier<N> {
+    async fn encode<W: embedded_io_async::Write>(&self, buffer: W) -> Result<(), EncodeError> {
+        self.0.encode(buffer).await
+    }
+}
+
+impl<const N: usize> Decode for NamespacedIdentifier<N> {
+    async fn decode<R: embedded_io_async::Read>(buffer: R) -> Result<Self, DecodeError> {
+        let identifier = String::<N>::decode(buffer).await?;
+
+        if !Self::is_valid(&identifier) {
+            return Err(DecodeError::InvalidNamespace);
+        }
+
+        Ok(Self(identifier))
+    }
+}
+
+impl<const N: usize> TryFrom<&str> for NamespacedIdentifier<N> {
+    type Error = DecodeError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if !Self::is_valid(value) {
+            return Err(DecodeError::InvalidNamespace);
+        }
+
+        Ok(Self(
+            String::try_from(value).map_err(|_| DecodeError::VarIntTooBig)?,
+        ))
+    }
+}
+
+impl<const N: usize> core::fmt::Display for NamespacedIdentifier<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 // trait Identifier: Encode + Decode {
 //     const NAMESPACE: &'static str;
 
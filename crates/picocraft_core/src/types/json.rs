@@ -0,0 +1,38 @@
+use core_json_traits::JsonSerialize;
+
+use crate::prelude::*;
+
+/// How many bytes are written to the buffer at once.
+const CHUNK_SIZE: usize = 64;
+
+impl<T: JsonSerialize> Encode for Json<T> {
+    async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
+        // Serializing is cheap, so do it twice rather than storing it to find the length.
+        let length = self.0.serialize().map(char::len_utf8).sum::<usize>();
+
+        VarInt(i32::try_from(length).map_err(EncodeError::TryFromInt)?)
+            .encode(&mut buffer)
+            .await?;
+
+        let mut chunk = [0u8; CHUNK_SIZE];
+        let mut filled = 0;
+
+        for char in self.0.serialize() {
+            if filled + char.len_utf8() > CHUNK_SIZE {
+                buffer.write_all(&chunk[..filled]).await?;
+                filled = 0;
+            }
+
+            filled += char.encode_utf8(&mut chunk[filled..]).len();
+        }
+
+        Ok(buffer.write_all(&chunk[..filled]).await?)
+    }
+}
+
+impl<T> Decode for Json<T> {
+    #[allow(unused)]
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        Err(DecodeError::Unimplemented)
+    }
+}
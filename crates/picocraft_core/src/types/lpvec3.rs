@@ -1,24 +1,164 @@
 use crate::prelude::*;
 
+/// Bits in each packed component.
+const DATA_BITS: u32 = 15;
+const DATA_MASK: u64 = (1 << DATA_BITS) - 1;
+/// The largest packed component, so that zero is exactly representable.
+const MAX_QUANTISED: f64 = 32766.0;
+
+/// The low bits of the scale, stored in the first byte.
+const SCALE_MASK: u64 = 0b11;
+/// Set when the rest of the scale follows as a [`VarInt`].
+const CONTINUATION: u64 = 0b100;
+
+const X_OFFSET: u32 = 3;
+const Y_OFFSET: u32 = X_OFFSET + DATA_BITS;
+const Z_OFFSET: u32 = Y_OFFSET + DATA_BITS;
+
 impl Encode for LpVec3 {
     async fn encode<W>(&self, mut buffer: W) -> ::core::result::Result<(), EncodeError>
     where
         W: ::embedded_io_async::Write,
     {
-        if self == &LpVec3::ZERO {
-            0u8.encode(&mut buffer).await?;
+        let [x, y, z] = [self.x, self.y, self.z].map(sanitise);
+        let largest = x.abs().max(y.abs()).max(z.abs());
+
+        if largest < LpVec3::MIN_COMPONENT {
+            return 0u8.encode(&mut buffer).await;
+        }
+
+        let scale = ceil(largest);
+        let continues = scale > SCALE_MASK;
+        let header = if continues {
+            scale & SCALE_MASK | CONTINUATION
         } else {
-            return Err(EncodeError::UnsupportedOperation);
+            scale
+        };
+
+        let packed = header
+            | pack(x / scale as f64) << X_OFFSET
+            | pack(y / scale as f64) << Y_OFFSET
+            | pack(z / scale as f64) << Z_OFFSET;
+
+        (packed as u8).encode(&mut buffer).await?;
+        ((packed >> 8) as u8).encode(&mut buffer).await?;
+        ((packed >> 16) as u32).encode(&mut buffer).await?;
+
+        if continues {
+            // The top of the scale is sent as an unsigned 32 bit number.
+            VarInt((scale >> 2) as u32 as i32)
+                .encode(&mut buffer)
+                .await?;
         }
+
         Ok(())
     }
 }
 
 impl Decode for LpVec3 {
-    async fn decode<R>(mut _buffer: R) -> ::core::result::Result<Self, DecodeError>
+    async fn decode<R>(mut buffer: R) -> ::core::result::Result<Self, DecodeError>
     where
         R: ::embedded_io_async::Read,
     {
-        todo!()
+        let header = u8::decode(&mut buffer).await?;
+
+        if header == 0 {
+            return Ok(LpVec3::ZERO);
+        }
+
+        let second = u8::decode(&mut buffer).await?;
+        let rest = u32::decode(&mut buffer).await?;
+        let packed = u64::from(rest) << 16 | u64::from(second) << 8 | u64::from(header);
+        let mut scale = packed & SCALE_MASK;
+
+        if packed & CONTINUATION != 0 {
+            scale |= u64::from(VarInt::decode(&mut buffer).await?.0 as u32) << 2;
+        }
+
+        let scale = scale as f64;
+
+        Ok(LpVec3 {
+            x: unpack(packed >> X_OFFSET) * scale,
+            y: unpack(packed >> Y_OFFSET) * scale,
+            z: unpack(packed >> Z_OFFSET) * scale,
+        })
+    }
+}
+
+fn sanitise(component: f64) -> f64 {
+    if component.is_nan() {
+        0.0
+    } else {
+        component.clamp(-LpVec3::MAX_COMPONENT, LpVec3::MAX_COMPONENT)
+    }
+}
+
+/// Rounds a positive `value` up to the next whole number.
+fn ceil(value: f64) -> u64 {
+    let truncated = value as u64;
+
+    if (truncated as f64) < value {
+        truncated + 1
+    } else {
+        truncated
+    }
+}
+
+/// Quantises a component scaled to between -1 and 1.
+fn pack(component: f64) -> u64 {
+    ((component * 0.5 + 0.5) * MAX_QUANTISED + 0.5) as u64
+}
+
+fn unpack(packed: u64) -> f64 {
+    ((packed & DATA_MASK) as f64).min(MAX_QUANTISED) * 2.0 / MAX_QUANTISED - 1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+
+    async fn round_trip(vec: LpVec3) -> (Vec<u8>, LpVec3) {
+        let mut buffer = std::vec![0u8; 16];
+        let mut remaining = buffer.as_mut_slice();
+        vec.encode(&mut remaining).await.expect("fits in 16 bytes");
+
+        let length = 16 - remaining.len();
+        buffer.truncate(length);
+
+        let mut bytes = buffer.as_slice();
+        let decoded = LpVec3::decode(&mut bytes).await.expect("valid LpVec3");
+        assert!(bytes.is_empty());
+
+        (buffer, decoded)
+    }
+
+    #[test]
+    fn velocities_round_trip() {
+        embassy_futures::block_on(async {
+            let (bytes, decoded) = round_trip(LpVec3::ZERO).await;
+            assert_eq!(bytes, [0]);
+            assert_eq!(decoded, LpVec3::ZERO);
+
+            // Knockback fits in the first byte's scale, a fast arrow doesn't.
+            for (vec, len) in [
+                (LpVec3::new(0.4, 0.36, -0.25), 6),
+                (LpVec3::new(-3.0, 0.0, 1.5), 6),
+                (LpVec3::new(120.0, -48.5, 7.25), 7),
+            ] {
+                let (bytes, decoded) = round_trip(vec).await;
+                let error = 2.0 * ceil(vec.x.abs().max(vec.y.abs()).max(vec.z.abs())) as f64
+                    / MAX_QUANTISED;
+
+                assert_eq!(bytes.len(), len);
+                assert!((decoded.x - vec.x).abs() <= error, "{decoded:?} != {vec:?}");
+                assert!((decoded.y - vec.y).abs() <= error, "{decoded:?} != {vec:?}");
+                assert!((decoded.z - vec.z).abs() <= error, "{decoded:?} != {vec:?}");
+            }
+
+            let (_, decoded) = round_trip(LpVec3::new(f64::NAN, 1e-6, 0.0)).await;
+            assert_eq!(decoded, LpVec3::ZERO);
+        });
     }
 }
@@ -1,19 +1,367 @@
+//! Network NBT, as sent since 1.20.2: the root tag has no name, and since
+//! 1.20.3 it doesn't have to be a compound.
+//!
+//! Nothing is allocated. [`NbtWriter`] streams tags straight into a writer,
+//! and [`NbtReader`] pulls them out one at a time, so large NBT can be looked
+//! through (or skipped) without storing it. [`NBT`] keeps a validated copy of
+//! the encoded bytes in fixed-capacity storage, to be read again later.
+
+mod reader;
+mod writer;
+
+pub use reader::{Event, NbtReader};
+pub use writer::NbtWriter;
+
 use crate::prelude::*;
 
-impl Encode for NBT {
-    async fn encode<W>(&self, _buffer: W) -> ::core::result::Result<(), EncodeError>
-    where
-        W: ::embedded_io_async::Write,
-    {
-        todo!()
+/// How deeply compounds and lists can be nested by default. Vanilla allows
+/// 512, but nothing the server deals with comes close.
+pub const MAX_DEPTH: usize = 16;
+
+/// Vanilla's limit on the size of NBT read from the network.
+pub const MAX_NETWORK_SIZE: usize = 2 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TagType {
+    End = 0,
+    Byte = 1,
+    Short = 2,
+    Int = 3,
+    Long = 4,
+    Float = 5,
+    Double = 6,
+    ByteArray = 7,
+    String = 8,
+    List = 9,
+    Compound = 10,
+    IntArray = 11,
+    LongArray = 12,
+}
+
+impl TryFrom<u8> for TagType {
+    type Error = DecodeError;
+
+    fn try_from(tag: u8) -> Result<Self, Self::Error> {
+        Ok(match tag {
+            0 => Self::End,
+            1 => Self::Byte,
+            2 => Self::Short,
+            3 => Self::Int,
+            4 => Self::Long,
+            5 => Self::Float,
+            6 => Self::Double,
+            7 => Self::ByteArray,
+            8 => Self::String,
+            9 => Self::List,
+            10 => Self::Compound,
+            11 => Self::IntArray,
+            12 => Self::LongArray,
+            tag => return Err(DecodeError::InvalidNbtTag(tag)),
+        })
+    }
+}
+
+/// A tag which doesn't contain other tags.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tag<'a> {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(&'a [u8]),
+    String(&'a str),
+    IntArray(NbtArray<'a, i32>),
+    LongArray(NbtArray<'a, i64>),
+}
+
+impl Tag<'_> {
+    pub const fn tag_type(&self) -> TagType {
+        match self {
+            Self::Byte(_) => TagType::Byte,
+            Self::Short(_) => TagType::Short,
+            Self::Int(_) => TagType::Int,
+            Self::Long(_) => TagType::Long,
+            Self::Float(_) => TagType::Float,
+            Self::Double(_) => TagType::Double,
+            Self::ByteArray(_) => TagType::ByteArray,
+            Self::String(_) => TagType::String,
+            Self::IntArray(_) => TagType::IntArray,
+            Self::LongArray(_) => TagType::LongArray,
+        }
+    }
+}
+
+impl From<bool> for Tag<'_> {
+    /// NBT has no booleans, so they are stored as bytes.
+    fn from(bool: bool) -> Self {
+        Self::Byte(i8::from(bool))
+    }
+}
+
+/// An int or long array, either as numbers to be written or as big endian
+/// bytes which have just been read.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NbtArray<'a, T> {
+    Slice(&'a [T]),
+    BigEndian(&'a [u8]),
+}
+
+impl<'a, T> From<&'a [T]> for NbtArray<'a, T> {
+    fn from(slice: &'a [T]) -> Self {
+        Self::Slice(slice)
+    }
+}
+
+impl<T: ArrayElement> NbtArray<'_, T> {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Slice(slice) => slice.len(),
+            Self::BigEndian(bytes) => bytes.len() / T::SIZE,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        let (slice, bytes) = match *self {
+            Self::Slice(slice) => (slice, [].as_slice()),
+            Self::BigEndian(bytes) => ([].as_slice(), bytes),
+        };
+
+        slice
+            .iter()
+            .copied()
+            .chain(bytes.chunks_exact(T::SIZE).map(T::from_be_slice))
+    }
+}
+
+/// Numbers which can be stored in an [`NbtArray`].
+pub trait ArrayElement: Copy {
+    const SIZE: usize;
+
+    fn from_be_slice(bytes: &[u8]) -> Self;
+
+    fn write_be_slice(self, bytes: &mut [u8]);
+}
+
+impl ArrayElement for i32 {
+    const SIZE: usize = 4;
+
+    fn from_be_slice(bytes: &[u8]) -> Self {
+        Self::from_be_bytes(bytes.try_into().expect("chunks are exactly 4 bytes"))
+    }
+
+    fn write_be_slice(self, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl ArrayElement for i64 {
+    const SIZE: usize = 8;
+
+    fn from_be_slice(bytes: &[u8]) -> Self {
+        Self::from_be_bytes(bytes.try_into().expect("chunks are exactly 8 bytes"))
+    }
+
+    fn write_be_slice(self, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&self.to_be_bytes());
     }
 }
 
-impl Decode for NBT {
-    async fn decode<R>(_buffer: R) -> ::core::result::Result<Self, DecodeError>
-    where
-        R: ::embedded_io_async::Read,
-    {
-        todo!()
+impl<const N: usize> NBT<N> {
+    /// No NBT at all, which is sent as a lone `TAG_End`.
+    pub fn empty() -> Self {
+        Self(Vec::from_array([TagType::End as u8]))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.as_slice() == [TagType::End as u8]
+    }
+
+    /// The encoded NBT, including the root tag's type.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Reads the stored NBT again.
+    pub fn reader<const SCRATCH: usize>(&self) -> NbtReader<&[u8], SCRATCH> {
+        NbtReader::new(self.0.as_slice(), N)
+    }
+}
+
+impl<const N: usize> Default for NBT<N> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<const N: usize> Encode for NBT<N> {
+    async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
+        Ok(buffer.write_all(&self.0).await?)
+    }
+}
+
+impl<const N: usize> Decode for NBT<N> {
+    /// Copies the NBT while checking that it is well formed, without storing
+    /// any of it anywhere else.
+    async fn decode<R: embedded_io_async::Read>(buffer: R) -> Result<Self, DecodeError> {
+        let mut copy = Copying {
+            buffer,
+            copy: Vec::new(),
+        };
+
+        NbtReader::<_, 0>::new(&mut copy, N).skip().await?;
+
+        Ok(Self(copy.copy))
+    }
+}
+
+/// Keeps a copy of everything read through it.
+struct Copying<R, const N: usize> {
+    buffer: R,
+    copy: Vec<u8, N>,
+}
+
+impl<R: embedded_io_async::ErrorType, const N: usize> embedded_io_async::ErrorType
+    for Copying<R, N>
+{
+    type Error = R::Error;
+}
+
+impl<R: embedded_io_async::Read, const N: usize> embedded_io_async::Read for Copying<R, N> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let read = self.buffer.read(buf).await?;
+
+        // The reader stops at N bytes, so this never overflows.
+        let _ = self.copy.extend_from_slice(&buf[..read]);
+
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+
+    const LONGS: [i64; 2] = [i64::MIN, 1];
+
+    async fn player() -> Vec<u8> {
+        let mut buffer = std::vec![0u8; 256];
+        let mut remaining = buffer.as_mut_slice();
+        let mut nbt = NbtWriter::<_>::new(&mut remaining);
+
+        async {
+            nbt.begin_compound("").await?;
+            nbt.write("name", Tag::String("Stéve\0")).await?;
+            nbt.write("flying", Tag::Byte(1)).await?;
+            nbt.write("food", Tag::Short(-2)).await?;
+            nbt.write("score", Tag::Int(3)).await?;
+            nbt.write("seen", Tag::Long(4)).await?;
+            nbt.write("speed", Tag::Float(0.5)).await?;
+            nbt.write("health", Tag::Double(20.0)).await?;
+            nbt.write("data", Tag::ByteArray(&[1, 2])).await?;
+            nbt.write("ints", Tag::IntArray([7].as_slice().into()))
+                .await?;
+            nbt.write("longs", Tag::LongArray(LONGS.as_slice().into()))
+                .await?;
+            nbt.begin_list("pos", TagType::Double, 2).await?;
+            nbt.write("", Tag::Double(0.5)).await?;
+            nbt.write("", Tag::Double(64.0)).await?;
+            nbt.end().await?;
+            nbt.end().await?;
+            Ok::<_, EncodeError>(())
+        }
+        .await
+        .expect("well formed NBT");
+
+        nbt.finish().expect("everything was ended");
+
+        let length = 256 - remaining.len();
+        buffer.truncate(length);
+        buffer
+    }
+
+    #[test]
+    fn every_tag_round_trips() {
+        embassy_futures::block_on(async {
+            let bytes = player().await;
+
+            let mut nbt = NbtReader::<_>::new(bytes.as_slice(), bytes.len());
+            let mut events = Vec::new();
+
+            while let Some(event) = nbt.next().await.expect("valid NBT") {
+                events.push(std::format!("{event:?}"));
+
+                if let Event::Value {
+                    tag: Tag::LongArray(longs),
+                    ..
+                } = event
+                {
+                    assert!(longs.iter().eq(LONGS));
+                }
+            }
+
+            assert_eq!(nbt.bytes_read(), bytes.len());
+            assert_eq!(events.len(), 16);
+            assert_eq!(
+                events[1],
+                "Value { name: \"name\", tag: String(\"Stéve\\0\") }"
+            );
+            assert_eq!(
+                events[11],
+                "BeginList { name: \"pos\", element: Double, len: 2 }"
+            );
+            assert_eq!(events[12], "Value { name: \"\", tag: Double(0.5) }");
+        });
+    }
+
+    #[test]
+    fn skipping_and_decoding_keep_every_byte() {
+        embassy_futures::block_on(async {
+            let bytes = player().await;
+
+            let mut nbt = NbtReader::<_, 8>::new(bytes.as_slice(), bytes.len());
+            assert!(matches!(
+                nbt.next().await,
+                Ok(Some(Event::BeginCompound { name: "" }))
+            ));
+            nbt.skip().await.expect("valid NBT");
+            assert!(matches!(nbt.next().await, Ok(None)));
+            assert_eq!(nbt.bytes_read(), bytes.len());
+
+            let decoded = NBT::<256>::decode(bytes.as_slice())
+                .await
+                .expect("fits in 256 bytes");
+            assert_eq!(decoded.as_bytes(), bytes);
+
+            assert!(matches!(
+                NBT::<16>::decode(bytes.as_slice()).await,
+                Err(DecodeError::NbtTooLarge)
+            ));
+        });
+    }
+
+    #[test]
+    fn nesting_is_bounded() {
+        embassy_futures::block_on(async {
+            // A root list of lists of lists..., which never ends.
+            let mut bytes = std::vec![TagType::List as u8];
+
+            for _ in 0..=MAX_DEPTH {
+                bytes.extend([TagType::List as u8, 0, 0, 0, 1]);
+            }
+
+            assert!(matches!(
+                NBT::<256>::decode(bytes.as_slice()).await,
+                Err(DecodeError::NbtTooDeep)
+            ));
+        });
     }
 }
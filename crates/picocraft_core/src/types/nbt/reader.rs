@@ -0,0 +1,424 @@
+use super::{MAX_DEPTH, NbtArray, Tag, TagType};
+use crate::prelude::*;
+
+/// Skipped data is read in chunks of this size.
+const SKIP_CHUNK_SIZE: usize = 64;
+
+/// One step through some NBT, from [`NbtReader::next`]. Names are empty for
+/// the root tag and list elements.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event<'a> {
+    Value {
+        name: &'a str,
+        tag: Tag<'a>,
+    },
+    BeginCompound {
+        name: &'a str,
+    },
+    BeginList {
+        name: &'a str,
+        element: TagType,
+        len: usize,
+    },
+    /// The end of the innermost compound or list.
+    End,
+}
+
+#[derive(Debug)]
+enum Container {
+    Compound,
+    List { element: TagType, remaining: usize },
+}
+
+/// Reads NBT one tag at a time.
+///
+/// Names, strings and arrays are read into `SCRATCH` bytes of space inside
+/// the reader, and anything which doesn't fit is an error, so use
+/// [`NbtReader::skip`] for anything which isn't needed. Nesting deeper than
+/// `DEPTH`, or reading more than `max_size` bytes in total, is also an error,
+/// so a client can't make the server do unbounded work.
+pub struct NbtReader<R, const SCRATCH: usize = 256, const DEPTH: usize = MAX_DEPTH> {
+    buffer: R,
+    scratch: [u8; SCRATCH],
+    stack: Vec<Container, DEPTH>,
+    read: usize,
+    max_size: usize,
+    started: bool,
+}
+
+impl<R: embedded_io_async::Read, const SCRATCH: usize, const DEPTH: usize>
+    NbtReader<R, SCRATCH, DEPTH>
+{
+    pub fn new(buffer: R, max_size: usize) -> Self {
+        Self {
+            buffer,
+            scratch: [0; SCRATCH],
+            stack: Vec::new(),
+            read: 0,
+            max_size,
+            started: false,
+        }
+    }
+
+    /// How many bytes have been read so far.
+    pub fn bytes_read(&self) -> usize {
+        self.read
+    }
+
+    /// Reads the next tag, or [`None`] once the root tag has been read. A root
+    /// `TAG_End` (meaning no NBT at all) gives [`None`] straight away.
+    pub async fn next(&mut self) -> Result<Option<Event<'_>>, DecodeError> {
+        let Some((tag_type, name_len)) = self.next_header().await? else {
+            return Ok(None);
+        };
+
+        let event = match tag_type {
+            TagType::End => Event::End,
+            TagType::Compound => {
+                self.enter(Container::Compound)?;
+                Event::BeginCompound {
+                    name: self.scratch_str(0, name_len)?,
+                }
+            }
+            TagType::List => {
+                let (element, len) = self.list_header().await?;
+                self.enter(Container::List {
+                    element,
+                    remaining: len,
+                })?;
+
+                Event::BeginList {
+                    name: self.scratch_str(0, name_len)?,
+                    element,
+                    len,
+                }
+            }
+            tag_type => {
+                let value = self.value(tag_type, name_len).await?;
+                let (name, rest) = self.scratch.split_at(name_len);
+
+                Event::Value {
+                    name: core::str::from_utf8(name)?,
+                    tag: value.into_tag(rest)?,
+                }
+            }
+        };
+
+        Ok(Some(event))
+    }
+
+    /// Skips the rest of the innermost compound or list, including its
+    /// `End`, without storing any of it. Before anything has been read, this
+    /// skips the whole root tag.
+    pub async fn skip(&mut self) -> Result<(), DecodeError> {
+        let depth = self.stack.len();
+        let whole_root = !self.started;
+
+        loop {
+            let Some((tag_type, _)) = self.next_header_skipping_name().await? else {
+                return Ok(());
+            };
+
+            match tag_type {
+                TagType::End if self.stack.len() < depth => return Ok(()),
+                TagType::End => {}
+                TagType::Compound => self.enter(Container::Compound)?,
+                TagType::List => {
+                    let (element, len) = self.list_header().await?;
+                    self.enter(Container::List {
+                        element,
+                        remaining: len,
+                    })?;
+                }
+                tag_type => self.skip_value(tag_type).await?,
+            }
+
+            if whole_root && self.stack.is_empty() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Reads the next tag's type and name (into the start of the scratch
+    /// space), popping finished containers.
+    async fn next_header(&mut self) -> Result<Option<(TagType, usize)>, DecodeError> {
+        let Some(tag_type) = self.next_tag_type().await? else {
+            return Ok(None);
+        };
+
+        let name_len =
+            if matches!(self.stack.last(), Some(Container::Compound)) && tag_type != TagType::End {
+                let len = self.string_len().await?;
+                self.read_scratch(0, len).await?;
+                len
+            } else {
+                0
+            };
+
+        Ok(Some((tag_type, name_len)))
+    }
+
+    async fn next_header_skipping_name(&mut self) -> Result<Option<(TagType, usize)>, DecodeError> {
+        let Some(tag_type) = self.next_tag_type().await? else {
+            return Ok(None);
+        };
+
+        if matches!(self.stack.last(), Some(Container::Compound)) && tag_type != TagType::End {
+            let len = self.string_len().await?;
+            self.discard(len).await?;
+        }
+
+        Ok(Some((tag_type, 0)))
+    }
+
+    async fn next_tag_type(&mut self) -> Result<Option<TagType>, DecodeError> {
+        let tag_type = match self.stack.last_mut() {
+            None if self.started => return Ok(None),
+            None => {
+                self.started = true;
+                let tag_type = TagType::try_from(self.read_u8().await?)?;
+
+                if tag_type == TagType::End {
+                    return Ok(None);
+                }
+
+                tag_type
+            }
+            Some(Container::List { remaining: 0, .. }) => {
+                self.stack.pop();
+                TagType::End
+            }
+            Some(Container::List { element, remaining }) => {
+                *remaining -= 1;
+                *element
+            }
+            Some(Container::Compound) => {
+                let tag_type = TagType::try_from(self.read_u8().await?)?;
+
+                if tag_type == TagType::End {
+                    self.stack.pop();
+                }
+
+                tag_type
+            }
+        };
+
+        Ok(Some(tag_type))
+    }
+
+    fn enter(&mut self, container: Container) -> Result<(), DecodeError> {
+        self.stack
+            .push(container)
+            .map_err(|_| DecodeError::NbtTooDeep)
+    }
+
+    async fn list_header(&mut self) -> Result<(TagType, usize), DecodeError> {
+        let element = TagType::try_from(self.read_u8().await?)?;
+        let len = self.array_len().await?;
+
+        if len > 0 && element == TagType::End {
+            return Err(DecodeError::InvalidNbtTag(TagType::End as u8));
+        }
+
+        Ok((element, len))
+    }
+
+    /// Reads a value, storing anything variable length in the scratch space
+    /// after the name.
+    async fn value(&mut self, tag_type: TagType, offset: usize) -> Result<Value, DecodeError> {
+        Ok(match tag_type {
+            TagType::Byte => Value::Byte(self.read_u8().await?.cast_signed()),
+            TagType::Short => Value::Short(i16::from_be_bytes(self.read_array().await?)),
+            TagType::Int => Value::Int(i32::from_be_bytes(self.read_array().await?)),
+            TagType::Long => Value::Long(i64::from_be_bytes(self.read_array().await?)),
+            TagType::Float => Value::Float(f32::from_be_bytes(self.read_array().await?)),
+            TagType::Double => Value::Double(f64::from_be_bytes(self.read_array().await?)),
+            TagType::String => {
+                let len = self.string_len().await?;
+                self.read_scratch(offset, len).await?;
+                let len = decode_modified_utf8(&mut self.scratch[offset..offset + len])?;
+
+                Value::String(len)
+            }
+            TagType::ByteArray | TagType::IntArray | TagType::LongArray => {
+                let len = self.array_size(tag_type).await?;
+                self.read_scratch(offset, len).await?;
+
+                Value::Array(tag_type, len)
+            }
+            TagType::End | TagType::Compound | TagType::List => {
+                unreachable!("containers aren't values")
+            }
+        })
+    }
+
+    async fn skip_value(&mut self, tag_type: TagType) -> Result<(), DecodeError> {
+        let len = match tag_type {
+            TagType::Byte => 1,
+            TagType::Short => 2,
+            TagType::Int | TagType::Float => 4,
+            TagType::Long | TagType::Double => 8,
+            TagType::String => self.string_len().await?,
+            TagType::ByteArray | TagType::IntArray | TagType::LongArray => {
+                self.array_size(tag_type).await?
+            }
+            TagType::End | TagType::Compound | TagType::List => {
+                unreachable!("containers aren't values")
+            }
+        };
+
+        self.discard(len).await
+    }
+
+    fn scratch_str(&self, start: usize, len: usize) -> Result<&str, DecodeError> {
+        Ok(core::str::from_utf8(&self.scratch[start..start + len])?)
+    }
+
+    async fn string_len(&mut self) -> Result<usize, DecodeError> {
+        Ok(u16::from_be_bytes(self.read_array().await?).into())
+    }
+
+    async fn array_len(&mut self) -> Result<usize, DecodeError> {
+        let len = i32::from_be_bytes(self.read_array().await?);
+
+        usize::try_from(len).map_err(|_| DecodeError::VarIntTooSmall(VarInt(len)))
+    }
+
+    /// Reads an array's length and returns its size in bytes.
+    async fn array_size(&mut self, tag_type: TagType) -> Result<usize, DecodeError> {
+        self.array_len()
+            .await?
+            .checked_mul(element_size(tag_type))
+            .ok_or(DecodeError::NbtTooLarge)
+    }
+
+    fn count(&mut self, len: usize) -> Result<(), DecodeError> {
+        self.read = self
+            .read
+            .checked_add(len)
+            .filter(|read| *read <= self.max_size)
+            .ok_or(DecodeError::NbtTooLarge)?;
+
+        Ok(())
+    }
+
+    async fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.read_array::<1>().await?[0])
+    }
+
+    async fn read_array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        self.count(N)?;
+
+        let mut bytes = [0; N];
+        self.buffer.read_exact(&mut bytes).await?;
+
+        Ok(bytes)
+    }
+
+    async fn read_scratch(&mut self, start: usize, len: usize) -> Result<(), DecodeError> {
+        self.count(len)?;
+
+        let scratch = self
+            .scratch
+            .get_mut(start..start + len)
+            .ok_or(DecodeError::NbtTooLarge)?;
+
+        Ok(self.buffer.read_exact(scratch).await?)
+    }
+
+    async fn discard(&mut self, mut len: usize) -> Result<(), DecodeError> {
+        self.count(len)?;
+
+        let mut chunk = [0; SKIP_CHUNK_SIZE];
+
+        while len > 0 {
+            let size = len.min(SKIP_CHUNK_SIZE);
+            self.buffer.read_exact(&mut chunk[..size]).await?;
+            len -= size;
+        }
+
+        Ok(())
+    }
+}
+
+/// A value whose variable length part is in the scratch space, so it can be
+/// borrowed once reading has finished.
+enum Value {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(usize),
+    Array(TagType, usize),
+}
+
+impl Value {
+    fn into_tag(self, scratch: &[u8]) -> Result<Tag<'_>, DecodeError> {
+        Ok(match self {
+            Self::Byte(byte) => Tag::Byte(byte),
+            Self::Short(short) => Tag::Short(short),
+            Self::Int(int) => Tag::Int(int),
+            Self::Long(long) => Tag::Long(long),
+            Self::Float(float) => Tag::Float(float),
+            Self::Double(double) => Tag::Double(double),
+            Self::String(len) => Tag::String(core::str::from_utf8(&scratch[..len])?),
+            Self::Array(TagType::ByteArray, len) => Tag::ByteArray(&scratch[..len]),
+            Self::Array(TagType::IntArray, len) => {
+                Tag::IntArray(NbtArray::BigEndian(&scratch[..len]))
+            }
+            Self::Array(_, len) => Tag::LongArray(NbtArray::BigEndian(&scratch[..len])),
+        })
+    }
+}
+
+const fn element_size(tag_type: TagType) -> usize {
+    match tag_type {
+        TagType::IntArray => 4,
+        TagType::LongArray => 8,
+        _ => 1,
+    }
+}
+
+/// Converts Java's modified UTF-8 to UTF-8 in place, returning the new
+/// length. It only differs for NUL and characters outside the BMP, which are
+/// written as surrogate pairs.
+fn decode_modified_utf8(bytes: &mut [u8]) -> Result<usize, DecodeError> {
+    let mut read = 0;
+    let mut written = 0;
+
+    while read < bytes.len() {
+        match bytes[read..] {
+            [0xc0, 0x80, ..] => {
+                bytes[written] = 0;
+                read += 2;
+                written += 1;
+            }
+            [
+                0xed,
+                high @ 0xa0..=0xaf,
+                high_low,
+                0xed,
+                low @ 0xb0..=0xbf,
+                low_low,
+                ..,
+            ] => {
+                let high = 0xd800 | u32::from(high & 0x0f) << 6 | u32::from(high_low & 0x3f);
+                let low = 0xdc00 | u32::from(low & 0x0f) << 6 | u32::from(low_low & 0x3f);
+                let char = char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))
+                    .ok_or(DecodeError::Custom)?;
+
+                written += char.encode_utf8(&mut bytes[written..]).len();
+                read += 6;
+            }
+            _ => {
+                bytes[written] = bytes[read];
+                read += 1;
+                written += 1;
+            }
+        }
+    }
+
+    Ok(written)
+}
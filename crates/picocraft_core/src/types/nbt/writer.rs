@@ -0,0 +1,215 @@
+use super::{ArrayElement, MAX_DEPTH, NbtArray, Tag, TagType};
+use crate::prelude::*;
+
+#[derive(Debug)]
+enum Container {
+    Compound,
+    List { element: TagType, remaining: usize },
+}
+
+/// Writes NBT tag by tag, straight into `buffer`.
+///
+/// Tags are named inside compounds, but the root tag and list elements
+/// don't have names, so the name passed for those is ignored.
+///
+/// ```ignore
+/// let mut nbt = NbtWriter::new(&mut buffer);
+/// nbt.begin_compound("").await?;
+/// nbt.write("name", Tag::String("Steve")).await?;
+/// nbt.begin_list("pos", TagType::Double, 3).await?;
+/// for value in [0.5, 64.0, 0.5] {
+///     nbt.write("", Tag::Double(value)).await?;
+/// }
+/// nbt.end().await?;
+/// nbt.end().await?;
+/// nbt.finish()?;
+/// ```
+pub struct NbtWriter<W, const DEPTH: usize = MAX_DEPTH> {
+    buffer: W,
+    stack: Vec<Container, DEPTH>,
+    started: bool,
+}
+
+impl<W: embedded_io_async::Write, const DEPTH: usize> NbtWriter<W, DEPTH> {
+    pub fn new(buffer: W) -> Self {
+        Self {
+            buffer,
+            stack: Vec::new(),
+            started: false,
+        }
+    }
+
+    pub async fn write(&mut self, name: &str, tag: Tag<'_>) -> Result<(), EncodeError> {
+        self.header(name, tag.tag_type()).await?;
+
+        let buffer = &mut self.buffer;
+
+        match tag {
+            Tag::Byte(byte) => buffer.write_i8(byte).await?,
+            Tag::Short(short) => buffer.write_all(&short.to_be_bytes()).await?,
+            Tag::Int(int) => buffer.write_all(&int.to_be_bytes()).await?,
+            Tag::Long(long) => buffer.write_all(&long.to_be_bytes()).await?,
+            Tag::Float(float) => buffer.write_all(&float.to_be_bytes()).await?,
+            Tag::Double(double) => buffer.write_all(&double.to_be_bytes()).await?,
+            Tag::ByteArray(bytes) => {
+                write_length(&mut *buffer, bytes.len()).await?;
+                buffer.write_all(bytes).await?;
+            }
+            Tag::String(string) => write_string(buffer, string).await?,
+            Tag::IntArray(array) => write_array(buffer, array).await?,
+            Tag::LongArray(array) => write_array(buffer, array).await?,
+        }
+
+        Ok(())
+    }
+
+    pub async fn begin_compound(&mut self, name: &str) -> Result<(), EncodeError> {
+        self.header(name, TagType::Compound).await?;
+
+        self.stack
+            .push(Container::Compound)
+            .map_err(|_| EncodeError::TooLong)
+    }
+
+    /// Starts a list of exactly `len` elements of type `element`. Empty lists
+    /// can use [`TagType::End`].
+    pub async fn begin_list(
+        &mut self,
+        name: &str,
+        element: TagType,
+        len: usize,
+    ) -> Result<(), EncodeError> {
+        self.header(name, TagType::List).await?;
+
+        self.buffer.write_u8(element as u8).await?;
+        write_length(&mut self.buffer, len).await?;
+
+        self.stack
+            .push(Container::List {
+                element,
+                remaining: len,
+            })
+            .map_err(|_| EncodeError::TooLong)
+    }
+
+    /// Ends the innermost compound or list.
+    pub async fn end(&mut self) -> Result<(), EncodeError> {
+        match self.stack.pop() {
+            Some(Container::Compound) => Ok(self.buffer.write_u8(TagType::End as u8).await?),
+            Some(Container::List { remaining: 0, .. }) => Ok(()),
+            Some(Container::List { .. }) | None => Err(EncodeError::InvalidNbt),
+        }
+    }
+
+    /// Checks the root tag was written and everything has been ended.
+    pub fn finish(self) -> Result<W, EncodeError> {
+        if !self.started || !self.stack.is_empty() {
+            return Err(EncodeError::InvalidNbt);
+        }
+
+        Ok(self.buffer)
+    }
+
+    /// Writes the tag's type and name, where they are needed.
+    async fn header(&mut self, name: &str, tag_type: TagType) -> Result<(), EncodeError> {
+        match self.stack.last_mut() {
+            None if self.started => Err(EncodeError::InvalidNbt),
+            None => {
+                self.started = true;
+                Ok(self.buffer.write_u8(tag_type as u8).await?)
+            }
+            Some(Container::Compound) => {
+                self.buffer.write_u8(tag_type as u8).await?;
+                write_string(&mut self.buffer, name).await
+            }
+            Some(Container::List { element, remaining }) => {
+                if *element != tag_type || *remaining == 0 {
+                    return Err(EncodeError::InvalidNbt);
+                }
+
+                *remaining -= 1;
+                Ok(())
+            }
+        }
+    }
+}
+
+async fn write_length<W: embedded_io_async::Write>(
+    mut buffer: W,
+    len: usize,
+) -> Result<(), EncodeError> {
+    let len = i32::try_from(len).map_err(EncodeError::TryFromInt)?;
+    Ok(buffer.write_all(&len.to_be_bytes()).await?)
+}
+
+async fn write_array<W: embedded_io_async::Write, T: ArrayElement>(
+    mut buffer: W,
+    array: NbtArray<'_, T>,
+) -> Result<(), EncodeError> {
+    write_length(&mut buffer, array.len()).await?;
+
+    match array {
+        NbtArray::BigEndian(bytes) => buffer.write_all(bytes).await?,
+        NbtArray::Slice(slice) => {
+            let mut bytes = [0; 8];
+
+            for element in slice {
+                element.write_be_slice(&mut bytes[..T::SIZE]);
+                buffer.write_all(&bytes[..T::SIZE]).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// NBT strings are prefixed with their length as an unsigned short, and use
+/// Java's modified UTF-8, where NUL and characters outside the BMP are
+/// encoded differently.
+async fn write_string<W: embedded_io_async::Write>(
+    mut buffer: W,
+    string: &str,
+) -> Result<(), EncodeError> {
+    let len = string.chars().map(modified_utf8_len).sum::<usize>();
+    let len = u16::try_from(len).map_err(|_| EncodeError::TooLong)?;
+
+    buffer.write_all(&len.to_be_bytes()).await?;
+
+    if usize::from(len) == string.len() {
+        return Ok(buffer.write_all(string.as_bytes()).await?);
+    }
+
+    for char in string.chars() {
+        match char {
+            '\0' => buffer.write_all(&[0xc0, 0x80]).await?,
+            char if char.len_utf8() == 4 => {
+                let mut surrogates = [0u16; 2];
+
+                for surrogate in char.encode_utf16(&mut surrogates) {
+                    buffer
+                        .write_all(&[
+                            0xe0 | (*surrogate >> 12) as u8,
+                            0x80 | (*surrogate >> 6 & 0x3f) as u8,
+                            0x80 | (*surrogate & 0x3f) as u8,
+                        ])
+                        .await?;
+                }
+            }
+            char => {
+                buffer
+                    .write_all(char.encode_utf8(&mut [0u8; 4]).as_bytes())
+                    .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+const fn modified_utf8_len(char: char) -> usize {
+    match char {
+        '\0' => 2,
+        char if char.len_utf8() == 4 => 6,
+        char => char.len_utf8(),
+    }
+}
@@ -7,6 +7,18 @@ impl BlockPosition {
             | (i64::from(y) & 0x0000_0fff);
         Self(packed)
     }
+
+    pub fn x(&self) -> i32 {
+        (self.0 >> 38) as i32
+    }
+
+    pub fn y(&self) -> i32 {
+        (self.0 << 52 >> 52) as i32
+    }
+
+    pub fn z(&self) -> i32 {
+        (self.0 << 26 >> 38) as i32
+    }
 }
 
 impl Encode for BlockPosition {
@@ -24,3 +36,17 @@ impl Decode for BlockPosition {
         Ok(Self(packed))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negative_coordinates_survive_packing() {
+        let position = BlockPosition::new(-30_000_000, 29_999_999, -64);
+
+        assert_eq!(position.x(), -30_000_000);
+        assert_eq!(position.y(), -64);
+        assert_eq!(position.z(), 29_999_999);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::prelude::*;
 
 impl<T, const N: usize> PrefixedArray<T, N> {
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
         Self(Vec::new())
     }
 
@@ -46,8 +46,12 @@ impl<T: Decode, const N: usize> Decode for PrefixedArray<T, N> {
     async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
         let length = *VarInt::decode(&mut buffer).await?;
 
-        if !length.is_positive() {
-            return Err(DecodeError::VarIntTooSmall(VarInt(0)));
+        if length.is_negative() {
+            return Err(DecodeError::VarIntTooSmall(VarInt(length)));
+        }
+
+        if length as usize > N {
+            return Err(DecodeError::VarIntTooBig);
         }
 
         let mut vec = Self::new();
@@ -1,17 +1,50 @@
+//! Item stacks and their structured data components.
+//!
+//! Only the components a small survival server needs are understood. The
+//! data of other components isn't length prefixed, so a slot can't be read
+//! past one. Slots are always the last field of the packets clients send them
+//! in, so decoding stops at the first unknown component and the rest of the
+//! packet is skipped, keeping the item and whatever was read before it.
+
+use embedded_io_async::Error as _;
+
 use crate::prelude::*;
 
+/// The most components a [`Slot`] can add, and separately remove. Any more
+/// sent by a client are skipped.
+pub const MAX_COMPONENTS: usize = 8;
+
+/// The most NBT a client can send for an item's custom name.
+pub const MAX_TEXT_NBT: usize = 64;
+
+/// Component type IDs, for 1.21.9 and 1.21.10.
+mod id {
+    pub const MAX_STACK_SIZE: i32 = 1;
+    pub const MAX_DAMAGE: i32 = 2;
+    pub const DAMAGE: i32 = 3;
+    pub const UNBREAKABLE: i32 = 4;
+    pub const CUSTOM_NAME: i32 = 5;
+    pub const RARITY: i32 = 9;
+    pub const ENCHANTMENTS: i32 = 10;
+    pub const REPAIR_COST: i32 = 16;
+    pub const INTANGIBLE_PROJECTILE: i32 = 19;
+    pub const FOOD: i32 = 20;
+    pub const CONSUMABLE: i32 = 21;
+}
+
 #[derive(Debug, Clone)]
 pub enum StructuredComponent {
     MaxStackSize(VarInt),
     MaxDamage(VarInt),
     Damage(VarInt),
     Unbreakable,
-    CustomName(TextComponent),
+    CustomName(ItemText),
     Rarity(Rarity),
     Enchantments(PrefixedArray<Enchantment, 8>),
     // AttributeModifiers,
     RepairCost(VarInt),
-    IntangibleProjectile(NBT),
+    /// Sent as an empty NBT compound.
+    IntangibleProjectile,
     Food {
         nutrition: VarInt,
         saturation: Float,
@@ -20,7 +53,7 @@ pub enum StructuredComponent {
     Consumable {
         consume_seconds: Float,
         animation: Animation,
-        sound: IDor<16, SoundEvent>,
+        sound: IDor<SoundEvent>,
         has_consume_particles: Boolean,
         effects: PrefixedArray<ConsumeEffect, 1>,
     },
@@ -31,32 +64,42 @@ pub enum StructuredComponent {
     // }
 }
 
+/// Text in a component. The server's own text can be a [`TextComponent`],
+/// but text from clients is kept as the NBT it arrived in, since there's
+/// nowhere to keep its strings.
+#[derive(Debug, Clone)]
+pub enum ItemText {
+    Component(TextComponent<'static>),
+    Nbt(NBT<MAX_TEXT_NBT>),
+}
+
 #[derive(Debug, Clone)]
 pub struct Enchantment {
     pub type_id: VarInt,
     pub level: VarInt,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Rarity {
-    Common,
-    Uncommon,
-    Rare,
-    Epic,
+    Common = 0,
+    Uncommon = 1,
+    Rare = 2,
+    Epic = 3,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Animation {
-    None,
-    Eat,
-    Drink,
-    Block,
-    Bow,
-    Spear,
-    Crossbow,
-    Spyglass,
-    TootHorn,
-    Brush,
+    None = 0,
+    Eat = 1,
+    Drink = 2,
+    Block = 3,
+    Bow = 4,
+    Spear = 5,
+    Crossbow = 6,
+    Spyglass = 7,
+    TootHorn = 8,
+    Brush = 9,
+    Bundle = 10,
 }
 
 #[derive(Debug, Clone)]
@@ -68,12 +111,14 @@ pub enum ConsumeEffect {
     // what would this be used for?
     RemoveEffects(IDSet<16, 1>),
     ClearAllEffects,
+    /// The diameter of the area the player can be teleported within.
     TeleportRandomly(Float),
-    PlaySound(SoundEvent),
+    PlaySound(IDor<SoundEvent>),
 }
 
 #[derive(Debug, Clone)]
 pub struct PotionEffect {
+    pub type_id: VarInt,
     pub amplifier: VarInt,
     pub duration: VarInt,
     pub ambient: Boolean,
@@ -84,3 +129,646 @@ pub struct PotionEffect {
     pub hidden_effect: bool,
     // hidden_effect: PrefixedOptional<Detail>,
 }
+
+impl Slot {
+    pub const fn empty() -> Self {
+        Self {
+            item_count: VarInt(0),
+            item_id: VarInt(0),
+            components_to_add: Vec::new(),
+            components_to_remove: Vec::new(),
+        }
+    }
+
+    /// `count` of the item with ID `item_id`, with its default components.
+    pub const fn new(item_id: VarInt, count: i32) -> Self {
+        Self {
+            item_count: VarInt(count),
+            item_id,
+            components_to_add: Vec::new(),
+            components_to_remove: Vec::new(),
+        }
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.item_count.0 <= 0
+    }
+
+    /// Adds `component`, replacing any existing component of the same type.
+    /// Returns [`None`] if the slot already has [`MAX_COMPONENTS`] others.
+    pub fn with(mut self, component: StructuredComponent) -> Option<Self> {
+        let id = component.id();
+
+        match self.components_to_add.iter_mut().find(|c| c.id() == id) {
+            Some(existing) => *existing = component,
+            None => self.components_to_add.push(component).ok()?,
+        }
+
+        Some(self)
+    }
+
+    pub fn component(&self, id: VarInt) -> Option<&StructuredComponent> {
+        self.components_to_add.iter().find(|c| c.id() == id)
+    }
+}
+
+impl Encode for Slot {
+    async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
+        if self.is_empty() {
+            return VarInt(0).encode(&mut buffer).await;
+        }
+
+        self.item_count.encode(&mut buffer).await?;
+        self.item_id.encode(&mut buffer).await?;
+        VarInt(self.components_to_add.len() as i32)
+            .encode(&mut buffer)
+            .await?;
+        VarInt(self.components_to_remove.len() as i32)
+            .encode(&mut buffer)
+            .await?;
+
+        for component in &self.components_to_add {
+            component.encode(&mut buffer).await?;
+        }
+
+        for id in &self.components_to_remove {
+            id.encode(&mut buffer).await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Decode for Slot {
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        let item_count = VarInt::decode(&mut buffer).await?;
+
+        if item_count.0 < 0 {
+            return Err(DecodeError::VarIntTooSmall(item_count));
+        }
+
+        if item_count.0 == 0 {
+            return Ok(Self::empty());
+        }
+
+        let mut slot = Self::new(VarInt::decode(&mut buffer).await?, item_count.0);
+
+        let to_add = component_count(&mut buffer).await?;
+        let to_remove = component_count(&mut buffer).await?;
+
+        for _ in 0..to_add {
+            let id = VarInt::decode(&mut buffer).await?;
+
+            let Some(component) = StructuredComponent::decode_data(id, &mut buffer).await? else {
+                skip_rest(&mut buffer).await?;
+                return Ok(slot);
+            };
+
+            let _ = slot.components_to_add.push(component);
+        }
+
+        for _ in 0..to_remove {
+            let _ = slot
+                .components_to_remove
+                .push(VarInt::decode(&mut buffer).await?);
+        }
+
+        Ok(slot)
+    }
+}
+
+impl ItemStack {
+    pub const EMPTY: Self = Self {
+        item_id: 0,
+        count: 0,
+    };
+
+    pub const fn new(item_id: u16, count: u8) -> Self {
+        Self { item_id, count }
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+}
+
+/// Drops the slot's components, and anything which doesn't fit.
+impl From<&Slot> for ItemStack {
+    fn from(slot: &Slot) -> Self {
+        match (
+            u16::try_from(slot.item_id.0),
+            u8::try_from(slot.item_count.0),
+        ) {
+            (Ok(item_id), Ok(count)) if !slot.is_empty() => Self::new(item_id, count),
+            _ => Self::EMPTY,
+        }
+    }
+}
+
+impl Encode for ItemStack {
+    async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
+        if self.is_empty() {
+            return VarInt(0).encode(&mut buffer).await;
+        }
+
+        VarInt(i32::from(self.count)).encode(&mut buffer).await?;
+        VarInt(i32::from(self.item_id)).encode(&mut buffer).await?;
+
+        // No components to add or remove.
+        VarInt(0).encode(&mut buffer).await?;
+        VarInt(0).encode(&mut buffer).await
+    }
+}
+
+/// Only reads slots without components, as written by [`ItemStack`] itself.
+impl Decode for ItemStack {
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        let count = VarInt::decode(&mut buffer).await?;
+
+        if count.0 == 0 {
+            return Ok(Self::EMPTY);
+        }
+
+        let item_id = VarInt::decode(&mut buffer).await?;
+
+        if component_count(&mut buffer).await? != 0 || component_count(&mut buffer).await? != 0 {
+            return Err(DecodeError::Unimplemented);
+        }
+
+        match (u16::try_from(item_id.0), u8::try_from(count.0)) {
+            (Ok(item_id), Ok(count)) => Ok(Self::new(item_id, count)),
+            (Err(_), _) => Err(DecodeError::VarIntTooSmall(item_id)),
+            (_, Err(_)) => Err(DecodeError::VarIntTooSmall(count)),
+        }
+    }
+}
+
+impl Encode for HashedSlot {
+    async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
+        let stack = self.0;
+
+        if stack.is_empty() {
+            return false.encode(&mut buffer).await;
+        }
+
+        true.encode(&mut buffer).await?;
+        VarInt(i32::from(stack.item_id)).encode(&mut buffer).await?;
+        VarInt(i32::from(stack.count)).encode(&mut buffer).await?;
+
+        // No component hashes to add or remove.
+        VarInt(0).encode(&mut buffer).await?;
+        VarInt(0).encode(&mut buffer).await
+    }
+}
+
+impl Decode for HashedSlot {
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        if !bool::decode(&mut buffer).await? {
+            return Ok(Self(ItemStack::EMPTY));
+        }
+
+        let item_id = VarInt::decode(&mut buffer).await?;
+        let count = VarInt::decode(&mut buffer).await?;
+
+        // Each component added is its type and the hash of its data.
+        for _ in 0..component_count(&mut buffer).await? {
+            VarInt::decode(&mut buffer).await?;
+            Int::decode(&mut buffer).await?;
+        }
+
+        for _ in 0..component_count(&mut buffer).await? {
+            VarInt::decode(&mut buffer).await?;
+        }
+
+        let stack = match (u16::try_from(item_id.0), u8::try_from(count.0)) {
+            (Ok(item_id), Ok(count)) => ItemStack::new(item_id, count),
+            _ => ItemStack::EMPTY,
+        };
+
+        Ok(Self(stack))
+    }
+}
+
+async fn component_count<R: embedded_io_async::Read>(buffer: R) -> Result<i32, DecodeError> {
+    let count = VarInt::decode(buffer).await?;
+
+    if count.0 < 0 {
+        return Err(DecodeError::VarIntTooSmall(count));
+    }
+
+    Ok(count.0)
+}
+
+/// Reads (and ignores) everything left in `buffer`.
+async fn skip_rest<R: embedded_io_async::Read>(mut buffer: R) -> Result<(), DecodeError> {
+    let mut chunk = [0; 64];
+
+    while buffer
+        .read(&mut chunk)
+        .await
+        .map_err(|e| DecodeError::Io(e.kind()))?
+        != 0
+    {}
+
+    Ok(())
+}
+
+impl StructuredComponent {
+    /// The component's type ID.
+    pub const fn id(&self) -> VarInt {
+        VarInt(match self {
+            Self::MaxStackSize(_) => id::MAX_STACK_SIZE,
+            Self::MaxDamage(_) => id::MAX_DAMAGE,
+            Self::Damage(_) => id::DAMAGE,
+            Self::Unbreakable => id::UNBREAKABLE,
+            Self::CustomName(_) => id::CUSTOM_NAME,
+            Self::Rarity(_) => id::RARITY,
+            Self::Enchantments(_) => id::ENCHANTMENTS,
+            Self::RepairCost(_) => id::REPAIR_COST,
+            Self::IntangibleProjectile => id::INTANGIBLE_PROJECTILE,
+            Self::Food { .. } => id::FOOD,
+            Self::Consumable { .. } => id::CONSUMABLE,
+        })
+    }
+
+    /// Reads the data of a component of type `id`, or [`None`] if the type
+    /// isn't known.
+    async fn decode_data<R: embedded_io_async::Read>(
+        id: VarInt,
+        mut buffer: R,
+    ) -> Result<Option<Self>, DecodeError> {
+        let component = match id.0 {
+            id::MAX_STACK_SIZE => Self::MaxStackSize(VarInt::decode(&mut buffer).await?),
+            id::MAX_DAMAGE => Self::MaxDamage(VarInt::decode(&mut buffer).await?),
+            id::DAMAGE => Self::Damage(VarInt::decode(&mut buffer).await?),
+            id::UNBREAKABLE => Self::Unbreakable,
+            id::CUSTOM_NAME => Self::CustomName(ItemText::decode(&mut buffer).await?),
+            id::RARITY => Self::Rarity(Rarity::decode(&mut buffer).await?),
+            id::ENCHANTMENTS => Self::Enchantments(PrefixedArray::decode(&mut buffer).await?),
+            id::REPAIR_COST => Self::RepairCost(VarInt::decode(&mut buffer).await?),
+            id::INTANGIBLE_PROJECTILE => {
+                NBT::<2>::decode(&mut buffer).await?;
+                Self::IntangibleProjectile
+            }
+            id::FOOD => Self::Food {
+                nutrition: VarInt::decode(&mut buffer).await?,
+                saturation: Float::decode(&mut buffer).await?,
+                can_always_eat: Boolean::decode(&mut buffer).await?,
+            },
+            id::CONSUMABLE => Self::Consumable {
+                consume_seconds: Float::decode(&mut buffer).await?,
+                animation: Animation::decode(&mut buffer).await?,
+                sound: IDor::decode(&mut buffer).await?,
+                has_consume_particles: Boolean::decode(&mut buffer).await?,
+                effects: PrefixedArray::decode(&mut buffer).await?,
+            },
+            _ => return Ok(None),
+        };
+
+        Ok(Some(component))
+    }
+}
+
+impl Encode for StructuredComponent {
+    async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
+        self.id().encode(&mut buffer).await?;
+
+        match self {
+            Self::MaxStackSize(value)
+            | Self::MaxDamage(value)
+            | Self::Damage(value)
+            | Self::RepairCost(value) => value.encode(&mut buffer).await,
+            Self::Unbreakable => Ok(()),
+            Self::CustomName(name) => name.encode(&mut buffer).await,
+            Self::Rarity(rarity) => rarity.encode(&mut buffer).await,
+            Self::Enchantments(enchantments) => enchantments.encode(&mut buffer).await,
+            Self::IntangibleProjectile => Ok(buffer.write_all(&[0x0a, 0x00]).await?),
+            Self::Food {
+                nutrition,
+                saturation,
+                can_always_eat,
+            } => {
+                nutrition.encode(&mut buffer).await?;
+                saturation.encode(&mut buffer).await?;
+                can_always_eat.encode(&mut buffer).await
+            }
+            Self::Consumable {
+                consume_seconds,
+                animation,
+                sound,
+                has_consume_particles,
+                effects,
+            } => {
+                consume_seconds.encode(&mut buffer).await?;
+                animation.encode(&mut buffer).await?;
+                sound.encode(&mut buffer).await?;
+                has_consume_particles.encode(&mut buffer).await?;
+                effects.encode(&mut buffer).await
+            }
+        }
+    }
+}
+
+impl Decode for StructuredComponent {
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        let id = VarInt::decode(&mut buffer).await?;
+
+        Self::decode_data(id, &mut buffer)
+            .await?
+            .ok_or(DecodeError::InvalidEnumValue)
+    }
+}
+
+impl From<TextComponent<'static>> for ItemText {
+    fn from(text: TextComponent<'static>) -> Self {
+        Self::Component(text)
+    }
+}
+
+impl Encode for ItemText {
+    async fn encode<W: embedded_io_async::Write>(&self, buffer: W) -> Result<(), EncodeError> {
+        match self {
+            Self::Component(text) => text.encode(buffer).await,
+            Self::Nbt(nbt) => nbt.encode(buffer).await,
+        }
+    }
+}
+
+impl Decode for ItemText {
+    async fn decode<R: embedded_io_async::Read>(buffer: R) -> Result<Self, DecodeError> {
+        Ok(Self::Nbt(NBT::decode(buffer).await?))
+    }
+}
+
+impl Encode for Enchantment {
+    async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
+        self.type_id.encode(&mut buffer).await?;
+        self.level.encode(&mut buffer).await
+    }
+}
+
+impl Decode for Enchantment {
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        Ok(Self {
+            type_id: VarInt::decode(&mut buffer).await?,
+            level: VarInt::decode(&mut buffer).await?,
+        })
+    }
+}
+
+impl Encode for Rarity {
+    async fn encode<W: embedded_io_async::Write>(&self, buffer: W) -> Result<(), EncodeError> {
+        VarInt(*self as i32).encode(buffer).await
+    }
+}
+
+impl Decode for Rarity {
+    async fn decode<R: embedded_io_async::Read>(buffer: R) -> Result<Self, DecodeError> {
+        Ok(match VarInt::decode(buffer).await?.0 {
+            0 => Self::Common,
+            1 => Self::Uncommon,
+            2 => Self::Rare,
+            3 => Self::Epic,
+            _ => return Err(DecodeError::InvalidEnumValue),
+        })
+    }
+}
+
+impl Encode for Animation {
+    async fn encode<W: embedded_io_async::Write>(&self, buffer: W) -> Result<(), EncodeError> {
+        VarInt(*self as i32).encode(buffer).await
+    }
+}
+
+impl Decode for Animation {
+    async fn decode<R: embedded_io_async::Read>(buffer: R) -> Result<Self, DecodeError> {
+        Ok(match VarInt::decode(buffer).await?.0 {
+            0 => Self::None,
+            1 => Self::Eat,
+            2 => Self::Drink,
+            3 => Self::Block,
+            4 => Self::Bow,
+            5 => Self::Spear,
+            6 => Self::Crossbow,
+            7 => Self::Spyglass,
+            8 => Self::TootHorn,
+            9 => Self::Brush,
+            10 => Self::Bundle,
+            _ => return Err(DecodeError::InvalidEnumValue),
+        })
+    }
+}
+
+impl Encode for ConsumeEffect {
+    async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
+        match self {
+            Self::ApplyEffects {
+                effects,
+                probability,
+            } => {
+                VarInt(0).encode(&mut buffer).await?;
+                effects.encode(&mut buffer).await?;
+                probability.encode(&mut buffer).await
+            }
+            Self::RemoveEffects(effects) => {
+                VarInt(1).encode(&mut buffer).await?;
+                effects.encode(&mut buffer).await
+            }
+            Self::ClearAllEffects => VarInt(2).encode(&mut buffer).await,
+            Self::TeleportRandomly(diameter) => {
+                VarInt(3).encode(&mut buffer).await?;
+                diameter.encode(&mut buffer).await
+            }
+            Self::PlaySound(sound) => {
+                VarInt(4).encode(&mut buffer).await?;
+                sound.encode(&mut buffer).await
+            }
+        }
+    }
+}
+
+impl Decode for ConsumeEffect {
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        Ok(match VarInt::decode(&mut buffer).await?.0 {
+            0 => Self::ApplyEffects {
+                effects: PrefixedArray::decode(&mut buffer).await?,
+                probability: Float::decode(&mut buffer).await?,
+            },
+            1 => Self::RemoveEffects(IDSet::decode(&mut buffer).await?),
+            2 => Self::ClearAllEffects,
+            3 => Self::TeleportRandomly(Float::decode(&mut buffer).await?),
+            4 => Self::PlaySound(IDor::decode(&mut buffer).await?),
+            _ => return Err(DecodeError::InvalidEnumValue),
+        })
+    }
+}
+
+impl Encode for PotionEffect {
+    /// The hidden effect is never sent.
+    async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
+        self.type_id.encode(&mut buffer).await?;
+        self.amplifier.encode(&mut buffer).await?;
+        self.duration.encode(&mut buffer).await?;
+        self.ambient.encode(&mut buffer).await?;
+        self.show_particles.encode(&mut buffer).await?;
+        self.show_icon.encode(&mut buffer).await?;
+        false.encode(&mut buffer).await
+    }
+}
+
+impl Decode for PotionEffect {
+    /// Hidden effects are skipped, however deeply they are nested.
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        let type_id = VarInt::decode(&mut buffer).await?;
+        let mut effect = None;
+
+        loop {
+            let amplifier = VarInt::decode(&mut buffer).await?;
+            let duration = VarInt::decode(&mut buffer).await?;
+            let ambient = Boolean::decode(&mut buffer).await?;
+            let show_particles = Boolean::decode(&mut buffer).await?;
+            let show_icon = Boolean::decode(&mut buffer).await?;
+            let hidden_effect = Boolean::decode(&mut buffer).await?;
+
+            let effect = effect.get_or_insert(PotionEffect {
+                type_id,
+                amplifier,
+                duration,
+                ambient,
+                show_particles,
+                show_icon,
+                hidden_effect,
+            });
+
+            if !hidden_effect {
+                return Ok(effect.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+
+    async fn encode(slot: &Slot) -> Vec<u8> {
+        let mut buffer = std::vec![0u8; 256];
+        let mut remaining = buffer.as_mut_slice();
+        slot.encode(&mut remaining).await.expect("slot fits");
+
+        let length = 256 - remaining.len();
+        buffer.truncate(length);
+        buffer
+    }
+
+    fn apple() -> Slot {
+        let effect = PotionEffect {
+            type_id: VarInt(10),
+            amplifier: VarInt(1),
+            duration: VarInt(100),
+            ambient: false,
+            show_particles: true,
+            show_icon: true,
+            hidden_effect: false,
+        };
+
+        [
+            StructuredComponent::MaxDamage(VarInt(64)),
+            StructuredComponent::Damage(VarInt(3)),
+            StructuredComponent::Unbreakable,
+            StructuredComponent::CustomName(TextComponent::text("Apple").into()),
+            StructuredComponent::Rarity(Rarity::Epic),
+            StructuredComponent::Enchantments(PrefixedArray::from_array([Enchantment {
+                type_id: VarInt(2),
+                level: VarInt(5),
+            }])),
+            StructuredComponent::Food {
+                nutrition: VarInt(4),
+                saturation: 9.6,
+                can_always_eat: true,
+            },
+            StructuredComponent::Consumable {
+                consume_seconds: 1.6,
+                animation: Animation::Eat,
+                sound: IDor::ID(VarInt(7)),
+                has_consume_particles: true,
+                effects: PrefixedArray::from_array([ConsumeEffect::ApplyEffects {
+                    effects: PrefixedArray::from_array([effect]),
+                    probability: 1.0,
+                }]),
+            },
+        ]
+        .into_iter()
+        .try_fold(Slot::new(VarInt(840), 1), Slot::with)
+        .expect("fewer than MAX_COMPONENTS")
+    }
+
+    #[test]
+    fn slots_round_trip() {
+        embassy_futures::block_on(async {
+            let bytes = encode(&apple()).await;
+
+            let slot = Slot::decode(bytes.as_slice()).await.expect("valid slot");
+
+            assert_eq!(slot.item_id.0, 840);
+            assert_eq!(slot.components_to_add.len(), 8);
+            assert!(matches!(
+                slot.component(VarInt(id::CUSTOM_NAME)),
+                Some(StructuredComponent::CustomName(ItemText::Nbt(_)))
+            ));
+            assert_eq!(encode(&slot).await, bytes);
+
+            let empty = encode(&Slot::empty()).await;
+            assert_eq!(empty, [0]);
+            assert!(
+                Slot::decode(empty.as_slice())
+                    .await
+                    .expect("valid slot")
+                    .is_empty()
+            );
+        });
+    }
+
+    #[test]
+    fn unknown_components_are_skipped() {
+        embassy_futures::block_on(async {
+            // One stone with damage, then an item model, then a removal.
+            let bytes = [1, 1, 2, 1, 3, 7, 7, 5, b's', b't', b'o', b'n', b'e', 3];
+            let mut remaining = bytes.as_slice();
+
+            let slot = Slot::decode(&mut remaining).await.expect("valid slot");
+
+            assert!(remaining.is_empty());
+            assert_eq!(slot.item_count.0, 1);
+            assert_eq!(slot.components_to_add.len(), 1);
+            assert!(matches!(
+                slot.components_to_add[0],
+                StructuredComponent::Damage(VarInt(7))
+            ));
+        });
+    }
+
+    #[test]
+    fn hashed_slots_keep_only_the_item_and_count() {
+        embassy_futures::block_on(async {
+            // 64 of item 35, with a damage component's hash, then a removal.
+            let bytes = [1, 35, 64, 1, 3, 0x12, 0x34, 0x56, 0x78, 1, 5];
+            let mut remaining = bytes.as_slice();
+
+            let slot = HashedSlot::decode(&mut remaining)
+                .await
+                .expect("valid slot");
+
+            assert!(remaining.is_empty());
+            assert_eq!(slot.0, ItemStack::new(35, 64));
+
+            let mut remaining = [0].as_slice();
+            let empty = HashedSlot::decode(&mut remaining)
+                .await
+                .expect("valid slot");
+            assert!(empty.0.is_empty());
+        });
+    }
+}
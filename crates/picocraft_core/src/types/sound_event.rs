@@ -0,0 +1,17 @@
+use crate::prelude::*;
+
+impl Encode for SoundEvent {
+    async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
+        self.sound_name.encode(&mut buffer).await?;
+        self.fixed_range.encode(&mut buffer).await
+    }
+}
+
+impl Decode for SoundEvent {
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        Ok(Self {
+            sound_name: NamespacedIdentifier::decode(&mut buffer).await?,
+            fixed_range: PrefixedOptional::decode(&mut buffer).await?,
+        })
+    }
+}
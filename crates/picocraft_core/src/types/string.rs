@@ -12,8 +12,8 @@ impl<const N: usize> Decode for String<N> {
     async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
         let length = *VarInt::decode(&mut buffer).await?;
 
-        if !length.is_positive() {
-            return Err(DecodeError::VarIntTooSmall(VarInt(0)));
+        if length.is_negative() {
+            return Err(DecodeError::VarIntTooSmall(VarInt(length)));
         }
 
         let mut buf = Vec::<u8, N>::new();
@@ -0,0 +1,825 @@
+use core::fmt::Write as _;
+use core::iter::once;
+
+use core_json_traits::JsonSerialize;
+use heapless::Deque;
+
+use super::nbt::{NbtWriter, Tag, TagType};
+use crate::prelude::*;
+
+/// How deeply components can be nested (through children, translation
+/// arguments or hover text). Anything deeper is sent as empty text.
+pub const MAX_DEPTH: usize = 16;
+
+/// The most tokens a single step can queue up at once, which is all of a
+/// component's style fields and its click event.
+const MAX_PENDING_TOKENS: usize = 16;
+
+/// Each level of nesting opens a couple of compounds or lists, such as a
+/// `hover_event` and its `value`, or `extra` and one of its elements.
+const MAX_NBT_DEPTH: usize = 3 * MAX_DEPTH + 1;
+
+/// What a [`TextComponent`] displays, before its children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Content<'a> {
+    Text(&'a str),
+    /// Text looked up in the client's language file, with `%s` replaced by
+    /// each of `args` in turn. `fallback` is used if the key doesn't exist.
+    Translatable {
+        key: &'a str,
+        fallback: Option<&'a str>,
+        args: &'a [TextComponent<'a>],
+    },
+    /// The key bound to a control, e.g. `key.jump`.
+    Keybind(&'a str),
+}
+
+impl Default for Content<'_> {
+    fn default() -> Self {
+        Self::Text("")
+    }
+}
+
+/// Formatting for a [`TextComponent`]. Anything left as [`None`] is inherited
+/// from the parent component.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style<'a> {
+    pub color: Option<Color>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underlined: Option<bool>,
+    pub strikethrough: Option<bool>,
+    pub obfuscated: Option<bool>,
+    /// Resource location of the font, e.g. `minecraft:uniform`.
+    pub font: Option<&'a str>,
+    /// Text inserted into the chat box when the component is shift-clicked.
+    pub insertion: Option<&'a str>,
+    /// ARGB color of the text's shadow.
+    pub shadow_color: Option<u32>,
+    pub click_event: Option<ClickEvent<'a>>,
+    pub hover_event: Option<HoverEvent<'a>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Named(NamedColor),
+    /// A `0xRRGGBB` color.
+    Rgb(u32),
+}
+
+impl From<NamedColor> for Color {
+    fn from(color: NamedColor) -> Self {
+        Self::Named(color)
+    }
+}
+
+/// The 16 colors which can be used in legacy formatting codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamedColor {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+}
+
+impl NamedColor {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Black => "black",
+            Self::DarkBlue => "dark_blue",
+            Self::DarkGreen => "dark_green",
+            Self::DarkAqua => "dark_aqua",
+            Self::DarkRed => "dark_red",
+            Self::DarkPurple => "dark_purple",
+            Self::Gold => "gold",
+            Self::Gray => "gray",
+            Self::DarkGray => "dark_gray",
+            Self::Blue => "blue",
+            Self::Green => "green",
+            Self::Aqua => "aqua",
+            Self::Red => "red",
+            Self::LightPurple => "light_purple",
+            Self::Yellow => "yellow",
+            Self::White => "white",
+        }
+    }
+}
+
+/// What happens when the component is clicked in chat or a book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClickEvent<'a> {
+    OpenUrl(&'a str),
+    /// Runs a command (or sends a chat message, if it doesn't start with `/`).
+    RunCommand(&'a str),
+    /// Replaces the contents of the chat box.
+    SuggestCommand(&'a str),
+    /// Turns to a page of the open book.
+    ChangePage(i32),
+    CopyToClipboard(&'a str),
+}
+
+/// What is shown when the component is hovered over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HoverEvent<'a> {
+    ShowText(&'a TextComponent<'a>),
+    ShowItem {
+        id: &'a str,
+        count: i32,
+    },
+    ShowEntity {
+        /// The entity type, e.g. `minecraft:pig`.
+        id: &'a str,
+        uuid: UUID,
+        name: Option<&'a TextComponent<'a>>,
+    },
+}
+
+impl<'a> TextComponent<'a> {
+    #[must_use]
+    pub const fn text(text: &'a str) -> Self {
+        Self::new(Content::Text(text))
+    }
+
+    #[must_use]
+    pub const fn translatable(key: &'a str, args: &'a [TextComponent<'a>]) -> Self {
+        Self::new(Content::Translatable {
+            key,
+            fallback: None,
+            args,
+        })
+    }
+
+    #[must_use]
+    pub const fn keybind(key: &'a str) -> Self {
+        Self::new(Content::Keybind(key))
+    }
+
+    #[must_use]
+    pub const fn new(content: Content<'a>) -> Self {
+        Self {
+            content,
+            style: Style {
+                color: None,
+                bold: None,
+                italic: None,
+                underlined: None,
+                strikethrough: None,
+                obfuscated: None,
+                font: None,
+                insertion: None,
+                shadow_color: None,
+                click_event: None,
+                hover_event: None,
+            },
+            children: &[],
+        }
+    }
+
+    /// Text shown instead of a translatable component whose key the client
+    /// doesn't know. Does nothing for other components.
+    #[must_use]
+    pub const fn fallback(mut self, text: &'a str) -> Self {
+        if let Content::Translatable { fallback, .. } = &mut self.content {
+            *fallback = Some(text);
+        }
+
+        self
+    }
+
+    #[must_use]
+    pub fn color(mut self, color: impl Into<Color>) -> Self {
+        self.style.color = Some(color.into());
+        self
+    }
+
+    #[must_use]
+    pub const fn bold(mut self, bold: bool) -> Self {
+        self.style.bold = Some(bold);
+        self
+    }
+
+    #[must_use]
+    pub const fn italic(mut self, italic: bool) -> Self {
+        self.style.italic = Some(italic);
+        self
+    }
+
+    #[must_use]
+    pub const fn underlined(mut self, underlined: bool) -> Self {
+        self.style.underlined = Some(underlined);
+        self
+    }
+
+    #[must_use]
+    pub const fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.style.strikethrough = Some(strikethrough);
+        self
+    }
+
+    #[must_use]
+    pub const fn obfuscated(mut self, obfuscated: bool) -> Self {
+        self.style.obfuscated = Some(obfuscated);
+        self
+    }
+
+    #[must_use]
+    pub const fn font(mut self, font: &'a str) -> Self {
+        self.style.font = Some(font);
+        self
+    }
+
+    #[must_use]
+    pub const fn insertion(mut self, insertion: &'a str) -> Self {
+        self.style.insertion = Some(insertion);
+        self
+    }
+
+    #[must_use]
+    pub const fn shadow_color(mut self, argb: u32) -> Self {
+        self.style.shadow_color = Some(argb);
+        self
+    }
+
+    #[must_use]
+    pub const fn on_click(mut self, event: ClickEvent<'a>) -> Self {
+        self.style.click_event = Some(event);
+        self
+    }
+
+    #[must_use]
+    pub const fn on_hover(mut self, event: HoverEvent<'a>) -> Self {
+        self.style.hover_event = Some(event);
+        self
+    }
+
+    /// Components shown after this one, which inherit its style.
+    #[must_use]
+    pub const fn children(mut self, children: &'a [TextComponent<'a>]) -> Self {
+        self.children = children;
+        self
+    }
+
+    /// Components without any styling or children can be sent as a bare
+    /// string in NBT.
+    fn is_plain_text(&self) -> Option<&'a str> {
+        match self.content {
+            Content::Text(text) if self.children.is_empty() && self.style == Style::default() => {
+                Some(text)
+            }
+            _ => None,
+        }
+    }
+
+    /// Writes the component as the tag `name` of some larger NBT, such as an
+    /// item component or registry entry.
+    pub async fn write_nbt<W: embedded_io_async::Write, const DEPTH: usize>(
+        &self,
+        name: &str,
+        nbt: &mut NbtWriter<W, DEPTH>,
+    ) -> Result<(), EncodeError> {
+        if let Some(text) = self.is_plain_text() {
+            return nbt.write(name, Tag::String(text)).await;
+        }
+
+        for token in Tokens::new(self) {
+            token.nbt(name, nbt).await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl JsonSerialize for TextComponent<'_> {
+    fn serialize(&self) -> impl Iterator<Item = char> {
+        let mut after_value = false;
+
+        Tokens::new(self).flat_map(move |token| {
+            let comma = after_value && !token.closes();
+            after_value = !token.opens();
+
+            token.json(comma)
+        })
+    }
+}
+
+impl Encode for TextComponent<'_> {
+    /// Encodes the component as network NBT, which is how it is sent in the
+    /// configuration and play states.
+    async fn encode<W: embedded_io_async::Write>(&self, buffer: W) -> Result<(), EncodeError> {
+        let mut nbt = NbtWriter::<_, MAX_NBT_DEPTH>::new(buffer);
+        self.write_nbt("", &mut nbt).await?;
+        nbt.finish().map(drop)
+    }
+}
+
+impl Decode for TextComponent<'_> {
+    #[allow(unused)]
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        Err(DecodeError::Unimplemented)
+    }
+}
+
+/// A flattened piece of a component, so it can be serialized without
+/// recursion.
+#[derive(Debug, Clone, Copy)]
+enum Token<'a> {
+    BeginRoot,
+    BeginField(&'static str),
+    BeginElement,
+    End,
+    BeginList(&'static str, usize),
+    EndList,
+    String(&'static str, &'a str),
+    Bool(&'static str, bool),
+    Int(&'static str, i32),
+    Color(Color),
+    Uuid(&'static str, UUID),
+}
+
+impl<'a> Token<'a> {
+    const fn opens(&self) -> bool {
+        matches!(
+            self,
+            Self::BeginRoot | Self::BeginField(_) | Self::BeginElement | Self::BeginList(..)
+        )
+    }
+
+    const fn closes(&self) -> bool {
+        matches!(self, Self::End | Self::EndList)
+    }
+
+    const fn key(&self) -> Option<&'static str> {
+        match *self {
+            Self::BeginField(key)
+            | Self::BeginList(key, _)
+            | Self::String(key, _)
+            | Self::Bool(key, _)
+            | Self::Int(key, _)
+            | Self::Uuid(key, _) => Some(key),
+            Self::Color(_) => Some("color"),
+            Self::BeginRoot | Self::BeginElement | Self::End | Self::EndList => None,
+        }
+    }
+
+    /// Values which never need escaping.
+    fn ascii(&self) -> Ascii {
+        let mut ascii = Ascii::default();
+
+        // These all fit, so writing can't fail.
+        let _ = match *self {
+            Self::Int(_, int) => write!(ascii, "{int}"),
+            Self::Color(Color::Named(color)) => ascii.write_str(color.name()),
+            Self::Color(Color::Rgb(rgb)) => write!(ascii, "#{:06X}", rgb & 0x00ff_ffff),
+            Self::Uuid(_, uuid) => write!(ascii, "{}", uuid.hyphenated()),
+            _ => Ok(()),
+        };
+
+        ascii
+    }
+
+    fn json(self, comma: bool) -> impl Iterator<Item = char> + 'a {
+        let key = self
+            .key()
+            .into_iter()
+            .flat_map(|key| once('"').chain(key.chars()).chain("\":".chars()));
+
+        let (raw, escaped, quoted) = match self {
+            Self::BeginRoot | Self::BeginField(_) | Self::BeginElement => ("{", None, false),
+            Self::End => ("}", None, false),
+            Self::BeginList(..) => ("[", None, false),
+            Self::EndList => ("]", None, false),
+            Self::String(_, string) => ("", Some(string), false),
+            Self::Bool(_, true) => ("true", None, false),
+            Self::Bool(_, false) => ("false", None, false),
+            Self::Int(..) => ("", None, false),
+            Self::Color(_) | Self::Uuid(..) => ("", None, true),
+        };
+
+        let quote = quoted.then_some('"');
+
+        comma
+            .then_some(',')
+            .into_iter()
+            .chain(key)
+            .chain(raw.chars())
+            .chain(escaped.into_iter().flat_map(str::serialize))
+            .chain(quote)
+            .chain(self.ascii())
+            .chain(quote)
+    }
+
+    /// Writes the token, naming the root compound `root`.
+    async fn nbt<W: embedded_io_async::Write, const DEPTH: usize>(
+        self,
+        root: &str,
+        nbt: &mut NbtWriter<W, DEPTH>,
+    ) -> Result<(), EncodeError> {
+        let key = self.key().unwrap_or_default();
+
+        match self {
+            Self::BeginRoot => nbt.begin_compound(root).await,
+            Self::BeginField(_) | Self::BeginElement => nbt.begin_compound(key).await,
+            Self::End | Self::EndList => nbt.end().await,
+            Self::BeginList(_, len) => nbt.begin_list(key, TagType::Compound, len).await,
+            Self::String(_, string) => nbt.write(key, Tag::String(string)).await,
+            Self::Bool(_, bool) => nbt.write(key, bool.into()).await,
+            Self::Int(_, int) => nbt.write(key, Tag::Int(int)).await,
+            Self::Color(_) | Self::Uuid(..) => {
+                nbt.write(key, Tag::String(self.ascii().as_str())).await
+            }
+        }
+    }
+}
+
+/// A short ASCII string, for numbers, colors and UUIDs.
+struct Ascii {
+    bytes: [u8; 36],
+    len: usize,
+    read: usize,
+}
+
+impl Default for Ascii {
+    fn default() -> Self {
+        Self {
+            bytes: [0; 36],
+            len: 0,
+            read: 0,
+        }
+    }
+}
+
+impl Ascii {
+    fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap_or_default()
+    }
+}
+
+impl core::fmt::Write for Ascii {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.len + s.len();
+        self.bytes
+            .get_mut(self.len..end)
+            .ok_or(core::fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+impl Iterator for Ascii {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let byte = self.bytes[..self.len].get(self.read)?;
+        self.read += 1;
+        Some(char::from(*byte))
+    }
+}
+
+/// Where a component is up to in being flattened into [`Token`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Content,
+    Arguments,
+    Style,
+    Hover,
+    HoverEnd,
+    Children,
+    End,
+}
+
+#[derive(Debug)]
+struct Frame<'a> {
+    component: &'a TextComponent<'a>,
+    step: Step,
+    /// The next argument or child to visit.
+    index: usize,
+}
+
+/// Walks a component depth first, without recursing.
+struct Tokens<'a> {
+    stack: Vec<Frame<'a>, MAX_DEPTH>,
+    pending: Deque<Token<'a>, MAX_PENDING_TOKENS>,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(component: &'a TextComponent<'a>) -> Self {
+        let mut tokens = Self {
+            stack: Vec::new(),
+            pending: Deque::new(),
+        };
+
+        tokens.enter(Token::BeginRoot, component);
+
+        tokens
+    }
+
+    fn queue(&mut self, token: Token<'a>) {
+        self.pending
+            .push_back(token)
+            .expect("no step queues more than MAX_PENDING_TOKENS");
+    }
+
+    /// Starts visiting a nested component, or sends empty text instead if it
+    /// is nested too deeply.
+    fn enter(&mut self, begin: Token<'a>, component: &'a TextComponent<'a>) {
+        self.queue(begin);
+
+        let frame = Frame {
+            component,
+            step: Step::Content,
+            index: 0,
+        };
+
+        if self.stack.push(frame).is_err() {
+            self.queue(Token::String("text", ""));
+            self.queue(Token::End);
+        }
+    }
+
+    /// Visits the next item of a list, opening and closing it as needed.
+    /// Returns whether the list is finished.
+    fn list(&mut self, key: &'static str, items: &'a [TextComponent<'a>]) -> bool {
+        let frame = self.stack.last_mut().expect("only called for a frame");
+        let index = frame.index;
+        frame.index += 1;
+
+        if items.is_empty() {
+            return true;
+        }
+
+        if index == 0 {
+            self.queue(Token::BeginList(key, items.len()));
+        }
+
+        match items.get(index) {
+            Some(item) => {
+                self.enter(Token::BeginElement, item);
+                false
+            }
+            None => {
+                self.queue(Token::EndList);
+                true
+            }
+        }
+    }
+
+    fn style(&mut self, style: &Style<'a>) {
+        let tokens = [
+            style.color.map(Token::Color),
+            style.bold.map(|bold| Token::Bool("bold", bold)),
+            style.italic.map(|italic| Token::Bool("italic", italic)),
+            style
+                .underlined
+                .map(|underlined| Token::Bool("underlined", underlined)),
+            style
+                .strikethrough
+                .map(|strikethrough| Token::Bool("strikethrough", strikethrough)),
+            style
+                .obfuscated
+                .map(|obfuscated| Token::Bool("obfuscated", obfuscated)),
+            style.font.map(|font| Token::String("font", font)),
+            style
+                .insertion
+                .map(|insertion| Token::String("insertion", insertion)),
+            style
+                .shadow_color
+                .map(|argb| Token::Int("shadow_color", argb as i32)),
+        ];
+
+        for token in tokens.into_iter().flatten() {
+            self.queue(token);
+        }
+
+        if let Some(event) = style.click_event {
+            let (action, value) = match event {
+                ClickEvent::OpenUrl(url) => ("open_url", Token::String("url", url)),
+                ClickEvent::RunCommand(command) => {
+                    ("run_command", Token::String("command", command))
+                }
+                ClickEvent::SuggestCommand(command) => {
+                    ("suggest_command", Token::String("command", command))
+                }
+                ClickEvent::ChangePage(page) => ("change_page", Token::Int("page", page)),
+                ClickEvent::CopyToClipboard(value) => {
+                    ("copy_to_clipboard", Token::String("value", value))
+                }
+            };
+
+            self.queue(Token::BeginField("click_event"));
+            self.queue(Token::String("action", action));
+            self.queue(value);
+            self.queue(Token::End);
+        }
+    }
+
+    fn hover(&mut self, event: HoverEvent<'a>) {
+        self.queue(Token::BeginField("hover_event"));
+
+        match event {
+            HoverEvent::ShowText(text) => {
+                self.queue(Token::String("action", "show_text"));
+                self.enter(Token::BeginField("value"), text);
+            }
+            HoverEvent::ShowItem { id, count } => {
+                self.queue(Token::String("action", "show_item"));
+                self.queue(Token::String("id", id));
+                self.queue(Token::Int("count", count));
+            }
+            HoverEvent::ShowEntity { id, uuid, name } => {
+                self.queue(Token::String("action", "show_entity"));
+                self.queue(Token::String("id", id));
+                self.queue(Token::Uuid("uuid", uuid));
+
+                if let Some(name) = name {
+                    self.enter(Token::BeginField("name"), name);
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        loop {
+            if let Some(token) = self.pending.pop_front() {
+                return Some(token);
+            }
+
+            let depth = self.stack.len().checked_sub(1)?;
+            let Frame {
+                component, step, ..
+            } = self.stack[depth];
+
+            let next = match step {
+                Step::Content => {
+                    match component.content {
+                        Content::Text(text) => self.queue(Token::String("text", text)),
+                        Content::Translatable { key, fallback, .. } => {
+                            self.queue(Token::String("translate", key));
+
+                            if let Some(fallback) = fallback {
+                                self.queue(Token::String("fallback", fallback));
+                            }
+                        }
+                        Content::Keybind(key) => self.queue(Token::String("keybind", key)),
+                    }
+
+                    Step::Arguments
+                }
+                Step::Arguments => match component.content {
+                    Content::Translatable { args, .. } if !self.list("with", args) => {
+                        Step::Arguments
+                    }
+                    _ => Step::Style,
+                },
+                Step::Style => {
+                    self.style(&component.style);
+                    Step::Hover
+                }
+                Step::Hover => {
+                    if let Some(event) = component.style.hover_event {
+                        self.hover(event);
+                        Step::HoverEnd
+                    } else {
+                        Step::Children
+                    }
+                }
+                Step::HoverEnd => {
+                    self.queue(Token::End);
+                    Step::Children
+                }
+                Step::Children => {
+                    if self.list("extra", component.children) {
+                        Step::End
+                    } else {
+                        Step::Children
+                    }
+                }
+                Step::End => {
+                    self.stack.pop();
+                    return Some(Token::End);
+                }
+            };
+
+            // Entering a nested component pushes a new frame above this one.
+            let frame = &mut self.stack[depth];
+
+            if frame.step != next {
+                frame.index = 0;
+            }
+
+            frame.step = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::string::String;
+    use std::vec::Vec;
+
+    use super::*;
+
+    fn json(component: &TextComponent<'_>) -> String {
+        component.serialize().collect()
+    }
+
+    async fn nbt(component: &TextComponent<'_>) -> Vec<u8> {
+        let mut buffer = std::vec![0u8; 1024];
+        let mut remaining = buffer.as_mut_slice();
+
+        component
+            .encode(&mut remaining)
+            .await
+            .expect("buffer is large enough");
+
+        let length = 1024 - remaining.len();
+        buffer.truncate(length);
+        buffer
+    }
+
+    #[test]
+    fn json_is_escaped_and_nested() {
+        let name = TextComponent::text("Steve \"the\" miner").color(NamedColor::Gold);
+        let hover = TextComponent::text("hi");
+        let args = [name];
+        let children = [TextComponent::text("!").bold(true)];
+
+        let component = TextComponent::translatable("chat.type.announcement", &args)
+            .on_hover(HoverEvent::ShowText(&hover))
+            .on_click(ClickEvent::ChangePage(2))
+            .children(&children);
+
+        assert_eq!(
+            json(&component),
+            "{\"translate\":\"chat.type.announcement\",\"with\":[{\"text\":\"Steve \\\"the\\\" \
+             miner\",\"color\":\"gold\"}],\"click_event\":{\"action\":\"change_page\",\"page\":2},\
+             \"hover_event\":{\"action\":\"show_text\",\"value\":{\"text\":\"hi\"}},\"extra\":[{\
+             \"text\":\"!\",\"bold\":true}]}"
+        );
+    }
+
+    #[test]
+    fn plain_text_is_a_bare_nbt_string() {
+        embassy_futures::block_on(async {
+            assert_eq!(
+                nbt(&TextComponent::text("hi")).await,
+                [TagType::String as u8, 0, 2, b'h', b'i']
+            );
+        });
+    }
+
+    #[test]
+    fn styled_text_is_an_nbt_compound() {
+        embassy_futures::block_on(async {
+            let component = TextComponent::text("\0").color(Color::Rgb(0x00ff_8000));
+
+            let mut expected = std::vec![TagType::Compound as u8];
+            expected.extend([TagType::String as u8, 0, 4]);
+            expected.extend(b"text");
+            // NUL is two bytes in modified UTF-8.
+            expected.extend([0, 2, 0xc0, 0x80]);
+            expected.extend([TagType::String as u8, 0, 5]);
+            expected.extend(b"color");
+            expected.extend([0, 7]);
+            expected.extend(b"#FF8000");
+            expected.push(TagType::End as u8);
+
+            assert_eq!(nbt(&component).await, expected);
+        });
+    }
+
+    #[test]
+    fn overly_nested_components_are_cut_off() {
+        let innermost = [TextComponent::text("deep")];
+        let mut levels = std::vec![innermost];
+
+        for _ in 0..MAX_DEPTH {
+            let children = levels.last().expect("has a level").as_slice();
+            // Leak to get `'static` children for the next level up.
+            let children: &'static [TextComponent<'static>] =
+                std::boxed::Box::leak(children.to_vec().into_boxed_slice());
+            levels.push([TextComponent::text("").children(children)]);
+        }
+
+        let json = json(&levels.last().expect("has a level")[0]);
+
+        assert!(!json.contains("deep"));
+        assert_eq!(json.matches('{').count(), json.matches('}').count());
+    }
+}
@@ -1,5 +1,17 @@
+use md5::{Digest, Md5};
+
 use crate::prelude::*;
 
+/// The UUID an offline-mode server gives `username`, the same way vanilla
+/// does: a version 3 UUID of `OfflinePlayer:<username>`, without a namespace.
+pub fn offline_uuid(username: &str) -> UUID {
+    let mut md5 = Md5::new();
+    md5.update(b"OfflinePlayer:");
+    md5.update(username.as_bytes());
+
+    ::uuid::Builder::from_md5_bytes(md5.finalize().into()).into_uuid()
+}
+
 impl Encode for UUID {
     async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
         self.as_u128().encode(&mut buffer).await
@@ -11,3 +23,17 @@ impl Decode for UUID {
         u128::decode(&mut buffer).await.map(UUID::from_u128)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offline_uuids_match_vanilla() {
+        // From `UUID.nameUUIDFromBytes("OfflinePlayer:Notch".getBytes(UTF_8))`.
+        assert_eq!(
+            offline_uuid("Notch").to_string(),
+            "b50ad385-829d-3141-a216-7e7d7539ba7f"
+        );
+    }
+}
@@ -27,6 +27,19 @@ impl core_json_traits::JsonDeserialize for VarInt {
     }
 }
 
+impl VarInt {
+    /// The number of bytes this [`VarInt`] takes up when encoded.
+    pub const fn encoded_len(&self) -> usize {
+        match self.0 as u32 {
+            0..0x80 => 1,
+            0x80..0x4000 => 2,
+            0x4000..0x20_0000 => 3,
+            0x20_0000..0x1000_0000 => 4,
+            _ => 5,
+        }
+    }
+}
+
 impl core::fmt::Display for VarInt {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         self.0.fmt(f)
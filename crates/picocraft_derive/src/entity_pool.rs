@@ -136,6 +136,46 @@ pub fn derive_entity_pool(item: TokenStream) -> Result<TokenStream> {
                 }
             });
 
+            // Optional fields are prefixed with whether they are present.
+            let encode_save_fields = persistent_fields.iter().map(|f| {
+                let ident = f.ident;
+                if f.is_required {
+                    quote! {
+                        self.#ident.encode(&mut buffer).await?;
+                    }
+                } else {
+                    quote! {
+                        match &self.#ident {
+                            ::core::option::Option::Some(value) => {
+                                true.encode(&mut buffer).await?;
+                                value.encode(&mut buffer).await?;
+                            }
+                            ::core::option::Option::None => false.encode(&mut buffer).await?,
+                        }
+                    }
+                }
+            });
+
+            let decode_save_fields = persistent_fields.iter().map(|f| {
+                let ident = f.ident;
+                let component_ty = f.component_ty;
+                if f.is_required {
+                    quote! {
+                        #ident: <#component_ty as Decode>::decode(&mut buffer).await?
+                    }
+                } else {
+                    quote! {
+                        #ident: if <bool as Decode>::decode(&mut buffer).await? {
+                            ::core::option::Option::Some(
+                                <#component_ty as Decode>::decode(&mut buffer).await?,
+                            )
+                        } else {
+                            ::core::option::Option::None
+                        }
+                    }
+                }
+            });
+
             let restore_bundle_fields = required_fields.iter().map(|f| {
                 let ident = f.ident;
                 quote! { #ident: save.#ident }
@@ -216,6 +256,30 @@ pub fn derive_entity_pool(item: TokenStream) -> Result<TokenStream> {
                     #(#save_data_fields,)*
                 }
 
+                impl ::picocraft_core::packet::Encode for #save_data_name {
+                    async fn encode<W>(&self, mut buffer: W) -> ::core::result::Result<(), ::picocraft_core::errors::EncodeError>
+                    where W: ::embedded_io_async::Write {
+
+                        use ::picocraft_core::packet::Encode;
+
+                        #(#encode_save_fields)*
+
+                        Ok(())
+                    }
+                }
+
+                impl ::picocraft_core::packet::Decode for #save_data_name {
+                    async fn decode<R>(mut buffer: R) -> ::core::result::Result<Self, ::picocraft_core::errors::DecodeError>
+                    where R: ::embedded_io_async::Read {
+
+                        use ::picocraft_core::packet::Decode;
+
+                        Ok(Self {
+                            #(#decode_save_fields,)*
+                        })
+                    }
+                }
+
                 impl #impl_generics #ident #ty_generics #where_clause {
 
                     // Find the first slot not occupied by the canonical component
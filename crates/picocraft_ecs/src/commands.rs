@@ -1,5 +1,10 @@
+use picocraft_proto::plugin_message::PluginChannel;
+use picocraft_proto::serverbound::Hand;
+
 use crate::components::*;
 use crate::entity::EntityId;
+use crate::events::MAX_EVENT_PLUGIN_MESSAGE_SIZE;
+use crate::pools::PlayerSaveData;
 use crate::prelude::*;
 
 /// Commands that can be sent to the world from outside systems, e.g. from
@@ -8,12 +13,22 @@ pub enum WorldCommand {
     /// Doesn't include EntityId because the player hasn't been spawned yet -
     /// the ECS world will assign one when the player is spawned.
     PlayerJoined {
+        login: LoginId,
         username: String<16>,
         uuid: UUID,
     },
     PlayerLeft {
         player_id: EntityId,
     },
+    /// The save data of the player with `uuid` was read from storage for
+    /// their login, or [`None`] if they have never played before.
+    PlayerDataLoaded {
+        uuid: UUID,
+        save: Option<PlayerSaveData>,
+    },
+    /// Hands every player's save data over to be stored, as the server is
+    /// shutting down.
+    SavePlayers,
     PlayerMoved {
         player_id: EntityId,
         position: Position,
@@ -33,15 +48,35 @@ pub enum WorldCommand {
         on_ground: bool,
         against_wall: bool,
     },
-    // BlockBroken {
-    //     player: EntityId,
-    //     pos: BlockPosition,
-    // },
-    // BlockPlaced {
-    //     player: EntityId,
-    //     pos: BlockPosition,
-    //     block: Block,
-    // },
+    /// The player finished digging the block at `position`. `sequence` is
+    /// acknowledged whether or not it was broken.
+    BlockBroken {
+        player_id: EntityId,
+        position: BlockPosition,
+        sequence: VarInt,
+    },
+    /// The player placed the block in their `hand` at `position`, against the
+    /// block at `against`. `sequence` is acknowledged whether or not it was
+    /// placed.
+    BlockPlaced {
+        player_id: EntityId,
+        hand: Hand,
+        against: BlockPosition,
+        position: BlockPosition,
+        sequence: VarInt,
+    },
+    /// The player put `item` in `slot` of their inventory window, or emptied
+    /// it.
+    SetInventorySlot {
+        player_id: EntityId,
+        slot: u8,
+        item: ItemStack,
+    },
+    /// The player selected hotbar slot `slot`, from 0 to 8.
+    SelectHotbarSlot {
+        player_id: EntityId,
+        slot: u8,
+    },
     ChatMessage {
         player_id: EntityId,
         message: String<128>,
@@ -50,4 +85,29 @@ pub enum WorldCommand {
         player_id: EntityId,
         command: String<128>,
     },
+    /// Sends the player to another server, which they connect to with the
+    /// transfer handshake intent.
+    TransferPlayer {
+        player_id: EntityId,
+        host: String<64>,
+        port: u16,
+    },
+    /// The player's client missed events, so needs to be sent every other
+    /// player again.
+    ResyncPlayer {
+        player_id: EntityId,
+    },
+    /// The player's latency (in milliseconds) has been measured from a
+    /// keep-alive round trip.
+    UpdateLatency {
+        player_id: EntityId,
+        latency: i32,
+    },
+    /// Sends a plugin message to one player, or to every player if
+    /// `player_id` is [`None`].
+    SendPluginMessage {
+        player_id: Option<EntityId>,
+        channel: PluginChannel,
+        data: Vec<u8, MAX_EVENT_PLUGIN_MESSAGE_SIZE>,
+    },
 }
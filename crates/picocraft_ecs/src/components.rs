@@ -1,14 +1,16 @@
-use picocraft_proto::serverbound::ProtocolPosition;
+use picocraft_derive::{Decode, Encode};
+use picocraft_proto::clientbound::PLAYER_INVENTORY_SLOTS;
+use picocraft_proto::serverbound::{Hand, ProtocolPosition};
 
 use crate::prelude::*;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Encode, Decode)]
 pub struct Health(pub f32);
 
 #[derive(Debug, Clone, Copy)]
 pub struct OnGround;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Encode, Decode)]
 pub struct Username(pub String<16>);
 
 /// Named "Motion" in Minecraft. Converted to f64 when serialised.
@@ -27,7 +29,7 @@ impl Velocity {
 
 /// Converted to f64 when serialised as minecraft uses f64 for positions, but
 /// f32 is more than enough for internal use and allows FPU use on ESP32s3.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Encode, Decode)]
 pub struct Position {
     pub x: f32,
     pub y: f32,
@@ -74,7 +76,7 @@ impl From<Position> for ProtocolPosition {
     }
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Encode, Decode)]
 pub struct Rotation {
     /// Rotation around the vertical (y) axis, in degrees. From -180 to +180
     /// degrees. Increases when rotating to the right (clockwise), decreases
@@ -106,7 +108,7 @@ impl Rotation {
 #[derive(Debug, Clone, Copy)]
 pub struct FallDistance(pub f32);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
 pub struct Uuid(pub UUID);
 
 /// A marker component for mobs that should not naturally despawn, such as pets
@@ -115,9 +117,94 @@ pub struct Uuid(pub UUID);
 pub struct Persistent;
 
 /// Realistically shouldn't be anything but the Overworld for now
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Encode, Decode)]
+#[protocol(value = VarInt)]
 pub enum Dimension {
-    Overworld,
-    Nether,
-    End,
+    Overworld = 0,
+    Nether = 1,
+    End = 2,
+}
+
+/// A player's items, in the slots of their inventory window.
+#[derive(Debug, Clone)]
+pub struct Inventory {
+    pub slots: [ItemStack; PLAYER_INVENTORY_SLOTS],
+    /// The hotbar slot in the player's main hand, from 0 to 8.
+    pub selected: u8,
+}
+
+impl Inventory {
+    /// The first of the hotbar's slots in the inventory window.
+    pub const FIRST_HOTBAR_SLOT: usize = 36;
+    pub const HOTBAR_SLOTS: u8 = 9;
+    pub const OFF_HAND_SLOT: usize = 45;
+
+    pub const fn new() -> Self {
+        Self {
+            slots: [ItemStack::EMPTY; PLAYER_INVENTORY_SLOTS],
+            selected: 0,
+        }
+    }
+
+    /// The item in the player's `hand`.
+    pub fn held_item(&self, hand: Hand) -> ItemStack {
+        match hand {
+            Hand::MainHand => self.slots[Self::FIRST_HOTBAR_SLOT + usize::from(self.selected)],
+            Hand::OffHand => self.slots[Self::OFF_HAND_SLOT],
+        }
+    }
+
+    /// Puts `item` in `slot`, returning whether there is such a slot.
+    pub fn set(&mut self, slot: usize, item: ItemStack) -> bool {
+        let Some(existing) = self.slots.get_mut(slot) else {
+            return false;
+        };
+
+        *existing = item;
+        true
+    }
+
+    /// Selects hotbar slot `slot`, returning whether there is such a slot.
+    pub fn select(&mut self, slot: u8) -> bool {
+        if slot >= Self::HOTBAR_SLOTS {
+            return false;
+        }
+
+        self.selected = slot;
+        true
+    }
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Encode for Inventory {
+    async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
+        self.selected.encode(&mut buffer).await?;
+
+        for slot in &self.slots {
+            slot.encode(&mut buffer).await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Decode for Inventory {
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        let mut inventory = Self::new();
+
+        if !inventory.select(u8::decode(&mut buffer).await?) {
+            return Err(DecodeError::Custom);
+        }
+
+        for slot in &mut inventory.slots {
+            *slot = ItemStack::decode(&mut buffer).await?;
+        }
+
+        Ok(inventory)
+    }
 }
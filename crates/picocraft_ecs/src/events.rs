@@ -1,9 +1,19 @@
+use picocraft_proto::plugin_message::PluginChannel;
+
 use crate::entity::EntityId;
 use crate::prelude::*;
 
+/// Plugin messages sent through the world are copied to every client's event
+/// queue, so they are kept small. Larger payloads can be sent by the client's
+/// own connection.
+pub const MAX_EVENT_PLUGIN_MESSAGE_SIZE: usize = 128;
+
 #[derive(Debug, Clone)]
 pub enum WorldEvent {
     PlayerJoined {
+        /// Which login this is, so it can tell its own join apart from others
+        /// with the same UUID.
+        login: LoginId,
         player_id: EntityId,
         username: String<16>,
         uuid: UUID,
@@ -50,19 +60,74 @@ pub enum WorldEvent {
     WorldReady {
         recipient: EntityId,
     },
-    // BlockBroken {
-    //     player: EntityId,
-    //     pos: BlockPosition,
-    // },
-    // BlockPlaced {
-    //     player: EntityId,
-    //     pos: BlockPosition,
-    //     block: Block,
-    // },
+    /// Everything in the player's inventory, sent when they join.
+    InventoryContent {
+        recipient: EntityId,
+        inventory: Inventory,
+    },
+    /// Sent to everyone, including whoever broke it, as their client puts the
+    /// block back when acknowledged unless it is told otherwise.
+    BlockBroken {
+        player_id: EntityId,
+        position: BlockPosition,
+    },
+    /// The server has dealt with the recipient's block changes up to
+    /// `sequence`.
+    BlockChangesAcknowledged {
+        recipient: EntityId,
+        sequence: VarInt,
+    },
+    /// Sent to everyone, like [`WorldEvent::BlockBroken`].
+    BlockPlaced {
+        player_id: EntityId,
+        position: BlockPosition,
+        block_state: VarInt,
+    },
+    /// The recipient's change to the block at `position` was refused, so it is
+    /// put back to `block_state` on their client.
+    BlockReverted {
+        recipient: EntityId,
+        position: BlockPosition,
+        block_state: VarInt,
+    },
     ChatMessage {
         player_id: EntityId,
+        username: String<16>,
         message: String<128>,
     },
+    TransferPlayer {
+        recipient: EntityId,
+        host: String<64>,
+        port: u16,
+    },
+    PlayerLatencyUpdated {
+        player_id: EntityId,
+        uuid: UUID,
+        latency: i32,
+    },
+    /// Disconnects `recipient` from the server.
+    PlayerKicked {
+        recipient: EntityId,
+        reason: KickReason,
+    },
+    /// `login` couldn't join the world, and should be disconnected. It hasn't
+    /// been given an entity, so this goes to everyone.
+    JoinRejected {
+        login: LoginId,
+    },
+    /// Sent to `recipient`, or to every player if there isn't one.
+    PluginMessage {
+        recipient: Option<EntityId>,
+        channel: PluginChannel,
+        data: Vec<u8, MAX_EVENT_PLUGIN_MESSAGE_SIZE>,
+    },
+}
+
+/// Why a player was kicked, which decides what their disconnect screen says.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KickReason {
+    /// The same player logged in again on another connection.
+    DuplicateLogin,
 }
 
 pub enum Recipient {
@@ -82,11 +147,22 @@ impl WorldEvent {
             Self::PlayerTeleported { player_id, .. } => Recipient::AllExcept(*player_id),
             Self::PlayerMovedAndRotated { player_id, .. } => Recipient::AllExcept(*player_id),
             Self::WorldReady { recipient } => Recipient::Player(*recipient),
-            // Self::BlockBroken  { player_id, .. }  => Recipient::AllExcept(*player_id),
-            // Self::BlockPlaced  { player_id, .. }  => Recipient::AllExcept(*player_id),
+            Self::InventoryContent { recipient, .. } => Recipient::Player(*recipient),
+            Self::BlockBroken { .. } => Recipient::All,
+            Self::BlockChangesAcknowledged { recipient, .. } => Recipient::Player(*recipient),
+            Self::BlockPlaced { .. } => Recipient::All,
+            Self::BlockReverted { recipient, .. } => Recipient::Player(*recipient),
             // Self::PlayerDamaged { .. } => Recipient::All,
             // Self::PlayerDied   { .. }  => Recipient::All,
             Self::ChatMessage { player_id, .. } => Recipient::AllExcept(*player_id),
+            Self::TransferPlayer { recipient, .. } => Recipient::Player(*recipient),
+            Self::PlayerLatencyUpdated { .. } => Recipient::All,
+            Self::PlayerKicked { recipient, .. } => Recipient::Player(*recipient),
+            Self::JoinRejected { .. } => Recipient::All,
+            Self::PluginMessage { recipient, .. } => match recipient {
+                Some(recipient) => Recipient::Player(*recipient),
+                None => Recipient::All,
+            },
         }
     }
 }
@@ -37,5 +37,5 @@ pub mod prelude {
     pub use crate::errors::*;
     pub use crate::events::{Recipient, WorldEvent};
     pub(crate) use crate::traits::*;
-    pub use crate::world::World;
+    pub use crate::world::{LoginId, World};
 }
@@ -24,6 +24,9 @@ pub struct PlayerPool<const N: usize = 8> {
     pub rotation: SparseSet<Rotation, N>,
     #[persistent]
     pub dimension: SparseSet<Dimension, N>,
+    #[required]
+    #[persistent]
+    pub inventory: SparseSet<Inventory, N>,
     pub on_ground: MarkerSet<OnGround, N>,
     pub fall_distance: SparseSet<FallDistance, N>,
 }
@@ -1,5 +1,42 @@
+use heapless::Vec;
+use picocraft_core::prelude::{String, UUID};
+
 use crate::pools::*;
 
+/// Tells logins apart before they have an entity, as two logins can have the
+/// same UUID. Each connection takes the next one when it joins the world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoginId(pub usize);
+
+/// A login for a UUID which is already in the world. It waits here while the
+/// old session is kicked, and is spawned once that session has left, so its
+/// save data is up to date.
+#[derive(Debug, Clone)]
+pub struct PendingJoin {
+    pub login: LoginId,
+    pub username: String<16>,
+    pub uuid: UUID,
+    /// The tick the login arrived on, so it can be given up on if the old
+    /// session never leaves.
+    pub since: u64,
+    /// Whether the login is waiting for its save data to be loaded from
+    /// storage, rather than for its old session to leave.
+    pub loading: bool,
+}
+
+/// The save data of a player who has left, so they come back where they left
+/// off.
+#[derive(Debug, Clone)]
+pub struct SavedPlayer {
+    pub save: PlayerSaveData,
+    /// The tick the player left on, so the players seen longest ago are the
+    /// first forgotten.
+    pub last_seen: u64,
+    /// Whether `save` has been handed over to be kept in storage, so
+    /// forgetting it loses nothing.
+    pub stored: bool,
+}
+
 pub struct World<
     const MAX_PLAYERS: usize = 8,
     const MAX_SAVED_PLAYERS: usize = 16,
@@ -8,7 +45,8 @@ pub struct World<
 > {
     pub players: PlayerPool<MAX_PLAYERS>,
     // pub mobs: MobPool<MAX_MOBS>,
-    pub player_save_data: [Option<PlayerSaveData>; MAX_SAVED_PLAYERS],
+    pub player_save_data: [Option<SavedPlayer>; MAX_SAVED_PLAYERS],
+    pub pending_joins: Vec<PendingJoin, MAX_PLAYERS>,
     tick_count: u64,
 }
 
@@ -19,6 +57,7 @@ impl<const MAX_PLAYERS: usize, const MAX_SAVED_PLAYERS: usize>
         Self {
             players: PlayerPool::new(),
             player_save_data: [const { None }; MAX_SAVED_PLAYERS],
+            pending_joins: Vec::new(),
             tick_count: 0,
         }
     }
@@ -32,6 +71,43 @@ impl<const MAX_PLAYERS: usize, const MAX_SAVED_PLAYERS: usize>
     pub fn tick_count(&self) -> u64 {
         self.tick_count
     }
+
+    /// The save data of the player with `uuid`, if they have left recently
+    /// enough to be remembered.
+    pub fn saved_player(&self, uuid: UUID) -> Option<&SavedPlayer> {
+        self.player_save_data
+            .iter()
+            .flatten()
+            .find(|saved| saved.save.uuid.0 == uuid)
+    }
+
+    /// Remembers `save` as of now, replacing any older save data of the same
+    /// player. If every slot is in use, the player seen longest ago is
+    /// forgotten to make room, and returned.
+    pub fn remember_player(&mut self, save: PlayerSaveData, stored: bool) -> Option<SavedPlayer> {
+        let saved = SavedPlayer {
+            save,
+            last_seen: self.tick_count,
+            stored,
+        };
+
+        let slots = &mut self.player_save_data;
+
+        let index = slots
+            .iter()
+            .position(|s| s.as_ref().is_some_and(|s| s.save.uuid == saved.save.uuid))
+            .or_else(|| slots.iter().position(Option::is_none));
+
+        if let Some(index) = index {
+            slots[index] = Some(saved);
+            return None;
+        }
+
+        slots
+            .iter_mut()
+            .min_by_key(|s| s.as_ref().map(|s| s.last_seen))?
+            .replace(saved)
+    }
 }
 
 impl Default for World {
@@ -1,4 +1,9 @@
 use crate::prelude::*;
+use crate::registry::{RegistryElement, RegistryEntries, RegistryEntry};
+
+#[derive(Debug, Packet)]
+#[packet(id = 0x00, state = State::Configuration)]
+pub struct ConfigurationCookieRequestPacket(pub CookieRequest);
 
 #[derive(Debug, Packet)]
 #[packet(id = 0x01, state = State::Configuration)]
@@ -23,24 +28,47 @@ impl Default for BrandPacket {
     }
 }
 
+/// The generic form of [`BrandPacket`], for any channel.
+#[derive(Debug, Packet)]
+#[packet(id = 0x01, state = State::Configuration)]
+pub struct ConfigurationPluginMessagePacket(pub PluginMessage);
+
+#[derive(Debug, Packet)]
+#[packet(id = 0x02, state = State::Configuration)]
+pub struct ConfigurationDisconnectPacket<'a> {
+    pub reason: TextComponent<'a>,
+}
+
 #[derive(Debug, Packet)]
 #[packet(id = 0x03, state = State::Configuration)]
 pub struct FinishConfigurationPacket;
 
-// #[derive(Debug, Packet)]
-// #[packet(id = 0x07, state = State::Configuration)]
-// pub struct RegistryDataPacket {
+/// The entries of one registry. See [`registry`](crate::registry) for the
+/// registries and their elements.
+#[derive(Debug, Packet)]
+#[packet(id = 0x07, state = State::Configuration)]
+pub struct RegistryDataPacket<'a, T: RegistryElement> {
+    pub registry: Identifier<32>,
+    pub entries: RegistryEntries<'a, T>,
+}
 
-//     identifier: Identifier<8>,
-//     entries: Vec<RegistryEntry, 1>,
+impl<'a, T: RegistryElement> RegistryDataPacket<'a, T> {
+    /// `registry` is in the `minecraft` namespace, e.g. `"dimension_type"`.
+    pub fn new(registry: &str, entries: &'a [RegistryEntry<'a, T>]) -> Self {
+        Self {
+            registry: Identifier(String::try_from(registry).expect("max 32 bytes")),
+            entries: RegistryEntries(entries),
+        }
+    }
+}
 
-// }
+#[derive(Debug, Packet)]
+#[packet(id = 0x0A, state = State::Configuration)]
+pub struct ConfigurationStoreCookiePacket(pub StoreCookie);
 
-// #[derive(Debug, Clone, Decode, Encode)]
-// pub struct RegistryEntry {
-//     pub entry_id: Identifier<36>,
-//     pub data: PrefixedOptional<NBT>,
-// }
+#[derive(Debug, Packet)]
+#[packet(id = 0x0B, state = State::Configuration)]
+pub struct ConfigurationTransferPacket(pub Transfer);
 
 #[derive(Debug, Packet)]
 #[packet(id = 0x0E, state = State::Configuration)]
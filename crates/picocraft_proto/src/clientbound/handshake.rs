@@ -1,25 +1,98 @@
+use core::fmt::Write as _;
+
 use crate::prelude::*;
 
+/// Response to a [`LegacyPingPacket`](crate::serverbound::LegacyPingPacket),
+/// sent in the `§1` kick string format understood by 1.4 - 1.6 clients (and
+/// most server list scanners), or the older one for
+/// [`unversioned`](Self::unversioned) pings.
 #[derive(Debug)]
-pub struct LegacyKickPacket;
+pub struct LegacyKickPacket {
+    /// Pre-netty clients can never join, so vanilla always sends `127` here to
+    /// mark the server as incompatible, which shows the version name instead.
+    pub protocol_version: i32,
+    pub version_name: String<16>,
+    pub motd: String<128>,
+    pub online_players: i32,
+    pub max_players: i32,
+    /// Answers a [`LegacyPingPacket::Unversioned`](crate::serverbound::LegacyPingPacket::Unversioned),
+    /// whose clients only read the MOTD and player counts.
+    pub unversioned: bool,
+}
+
+impl LegacyKickPacket {
+    pub const INCOMPATIBLE_PROTOCOL: i32 = 127;
+}
 
 impl Packet for LegacyKickPacket {
-    const ID: VarInt = VarInt(0xfe);
+    const ID: VarInt = VarInt(0xff);
 
     const STATE: State = State::Handshake;
 }
 
 impl Encode for LegacyKickPacket {
-    #[allow(unused)]
-    async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
-        todo!("Encode is not yet implemented for LegacyKick")
+    async fn encode<W: embedded_io_async::Write>(&self, buffer: W) -> Result<(), EncodeError> {
+        let mut kick_string: String<256> = String::new();
+
+        if self.unversioned {
+            // Separated by `§` instead, so it is stripped from the MOTD.
+            for char in self.motd.chars().filter(|&char| char != '§') {
+                kick_string.push(char).map_err(|_| EncodeError::Unknown)?;
+            }
+
+            write!(kick_string, "§{}§{}", self.online_players, self.max_players)
+                .map_err(|_| EncodeError::Unknown)?;
+
+            return encode_kick_string(&kick_string, buffer).await;
+        }
+
+        write!(
+            kick_string,
+            "§1\0{}\0{}\0",
+            self.protocol_version, self.version_name
+        )
+        .map_err(|_| EncodeError::Unknown)?;
+
+        // the legacy protocol has no way of escaping the separator, so strip it from
+        // the MOTD.
+        for char in self.motd.chars().filter(|&char| char != '\0') {
+            kick_string.push(char).map_err(|_| EncodeError::Unknown)?;
+        }
+
+        write!(
+            kick_string,
+            "\0{}\0{}",
+            self.online_players, self.max_players
+        )
+        .map_err(|_| EncodeError::Unknown)?;
+
+        encode_kick_string(&kick_string, buffer).await
+    }
+}
+
+async fn encode_kick_string<W: embedded_io_async::Write>(
+    kick_string: &str,
+    mut buffer: W,
+) -> Result<(), EncodeError> {
+    buffer.write_u8(*LegacyKickPacket::ID as u8).await?;
+
+    // The string is prefixed by its length in UTF-16 code units, not bytes.
+    let length =
+        u16::try_from(kick_string.encode_utf16().count()).map_err(EncodeError::TryFromInt)?;
+
+    buffer.write_all(&length.to_be_bytes()).await?;
+
+    for unit in kick_string.encode_utf16() {
+        buffer.write_all(&unit.to_be_bytes()).await?;
     }
+
+    Ok(())
 }
 
 impl Decode for LegacyKickPacket {
     #[allow(unused)]
     async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
-        todo!("Decode is not yet implemented for LegacyKick")
+        Err(DecodeError::Unimplemented)
     }
 }
 
@@ -28,3 +101,72 @@ impl core::fmt::Display for LegacyKickPacket {
         write!(f, "LegacyKickPacket")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+
+    #[test]
+    fn legacy_kicks_are_utf16_kick_strings() {
+        embassy_futures::block_on(async {
+            let packet = LegacyKickPacket {
+                protocol_version: LegacyKickPacket::INCOMPATIBLE_PROTOCOL,
+                version_name: String::try_from("1.21.10").expect("short enough"),
+                motd: String::try_from("A §aPicocraft\0 server").expect("short enough"),
+                online_players: 3,
+                max_players: 20,
+                unversioned: false,
+            };
+
+            let mut buffer = std::vec![0u8; 256];
+            let mut remaining = buffer.as_mut_slice();
+            packet.encode(&mut remaining).await.expect("fits");
+            let length = 256 - remaining.len();
+            buffer.truncate(length);
+
+            // The separator can't be escaped, so it's dropped from the MOTD.
+            let kick_string = "§1\u{0}127\u{0}1.21.10\u{0}A §aPicocraft server\u{0}3\u{0}20";
+            let units: Vec<u16> = kick_string.encode_utf16().collect();
+
+            let mut expected = std::vec![0xff];
+            expected.extend_from_slice(&(units.len() as u16).to_be_bytes());
+            for unit in units {
+                expected.extend_from_slice(&unit.to_be_bytes());
+            }
+
+            assert_eq!(buffer, expected);
+        });
+    }
+
+    #[test]
+    fn unversioned_legacy_kicks_only_have_the_motd_and_player_counts() {
+        embassy_futures::block_on(async {
+            let packet = LegacyKickPacket {
+                protocol_version: LegacyKickPacket::INCOMPATIBLE_PROTOCOL,
+                version_name: String::try_from("1.21.10").expect("short enough"),
+                motd: String::try_from("A §aPicocraft server").expect("short enough"),
+                online_players: 3,
+                max_players: 20,
+                unversioned: true,
+            };
+
+            let mut buffer = std::vec![0u8; 256];
+            let mut remaining = buffer.as_mut_slice();
+            packet.encode(&mut remaining).await.expect("fits");
+            let length = 256 - remaining.len();
+            buffer.truncate(length);
+
+            let units: Vec<u16> = "A aPicocraft server§3§20".encode_utf16().collect();
+
+            let mut expected = std::vec![0xff];
+            expected.extend_from_slice(&(units.len() as u16).to_be_bytes());
+            for unit in units {
+                expected.extend_from_slice(&unit.to_be_bytes());
+            }
+
+            assert_eq!(buffer, expected);
+        });
+    }
+}
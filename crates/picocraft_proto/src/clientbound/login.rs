@@ -1,12 +1,42 @@
 use crate::prelude::*;
 
 #[derive(Debug, Packet)]
-#[packet(id = 0x00)]
-pub struct LoginDisconnectPacket {
-    pub reason: String<256>,
-    // reason: TextComponent
+#[packet(id = 0x00, state = State::Login)]
+pub struct LoginDisconnectPacket<'a> {
+    /// Sent as JSON, unlike text in later states.
+    pub reason: Json<TextComponent<'a>>,
+}
+
+impl<'a> LoginDisconnectPacket<'a> {
+    pub fn new(reason: TextComponent<'a>) -> Self {
+        Self {
+            reason: Json(reason),
+        }
+    }
 }
 
 #[derive(Debug, Packet)]
-#[packet(id = 0x02)]
+#[packet(id = 0x02, state = State::Login)]
 pub struct LoginSuccess(pub GameProfile);
+
+/// Packets with an uncompressed length of at least `threshold` bytes are
+/// compressed from now on. A negative threshold disables compression.
+#[derive(Debug, Packet)]
+#[packet(id = 0x03, state = State::Login)]
+pub struct SetCompressionPacket {
+    pub threshold: VarInt,
+}
+
+/// Custom data sent to the client (or a proxy in front of it) before login
+/// completes. The response has the same `message_id`.
+#[derive(Debug, Packet)]
+#[packet(id = 0x04, state = State::Login)]
+pub struct LoginPluginRequestPacket {
+    pub message_id: VarInt,
+    pub channel: NamespacedIdentifier<32>,
+    pub data: Array<UnsignedByte, 16>,
+}
+
+#[derive(Debug, Packet)]
+#[packet(id = 0x05, state = State::Login)]
+pub struct LoginCookieRequestPacket(pub CookieRequest);
@@ -1,3 +1,7 @@
+mod blocks;
+mod chat;
+mod containers;
+mod cookies;
 pub mod entities;
 mod game_event;
 mod initialise_world_border;
@@ -5,11 +9,16 @@ mod keep_alive_packet;
 mod login_play;
 mod player_info_remove;
 mod player_info_update;
+mod plugin_message;
 pub mod remove_entities;
 mod set_center_chunk;
 pub mod spawn_entity;
 mod syncronise_player_position;
 
+pub use blocks::*;
+pub use chat::*;
+pub use containers::*;
+pub use cookies::*;
 pub use entities::*;
 pub use game_event::*;
 pub use initialise_world_border::*;
@@ -17,6 +26,7 @@ pub use keep_alive_packet::*;
 pub use login_play::*;
 pub use player_info_remove::*;
 pub use player_info_update::*;
+pub use plugin_message::*;
 pub use remove_entities::*;
 pub use set_center_chunk::*;
 pub use spawn_entity::*;
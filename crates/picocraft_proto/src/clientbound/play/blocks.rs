@@ -0,0 +1,17 @@
+use crate::prelude::*;
+
+/// Tells the client the server has dealt with every block change it made up
+/// to `sequence`, so it can stop predicting them. Any it wasn't sent a
+/// [`BlockUpdatePacket`] for are put back how they were.
+#[derive(Debug, Packet)]
+#[packet(id = 0x04)]
+pub struct AcknowledgeBlockChangePacket {
+    pub sequence: VarInt,
+}
+
+#[derive(Debug, Packet)]
+#[packet(id = 0x08)]
+pub struct BlockUpdatePacket {
+    pub location: BlockPosition,
+    pub block_state: VarInt,
+}
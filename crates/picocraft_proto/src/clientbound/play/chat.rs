@@ -0,0 +1,16 @@
+use crate::prelude::*;
+
+#[derive(Debug, Packet)]
+#[packet(id = 0x20)]
+pub struct PlayDisconnectPacket<'a> {
+    pub reason: TextComponent<'a>,
+}
+
+/// A message from the server rather than another player, so it isn't signed.
+#[derive(Debug, Packet)]
+#[packet(id = 0x77)]
+pub struct SystemChatPacket<'a> {
+    pub content: TextComponent<'a>,
+    /// Shows the message above the hotbar instead of in chat.
+    pub overlay: Boolean,
+}
@@ -0,0 +1,26 @@
+use crate::prelude::*;
+
+/// How many slots the player's inventory window has: the crafting output and
+/// grid, armour, the main inventory, the hotbar and the off hand.
+pub const PLAYER_INVENTORY_SLOTS: usize = 46;
+
+/// Every slot of a container window. The player's own inventory is always
+/// open as window 0.
+#[derive(Debug, Packet)]
+#[packet(id = 0x12)]
+pub struct SetContainerContentPacket {
+    pub window_id: VarInt,
+    /// Sent back by the client when it clicks in the window, so it can be
+    /// told if it clicked on slots which had already changed.
+    pub state_id: VarInt,
+    pub slot_data: PrefixedArray<ItemStack, PLAYER_INVENTORY_SLOTS>,
+    /// The item held by the cursor.
+    pub carried_item: ItemStack,
+}
+
+/// Selects the player's hotbar slot, from 0 to 8.
+#[derive(Debug, Packet)]
+#[packet(id = 0x67)]
+pub struct SetHeldItemPacket {
+    pub slot: VarInt,
+}
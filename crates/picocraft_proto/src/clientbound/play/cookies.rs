@@ -0,0 +1,13 @@
+use crate::prelude::*;
+
+#[derive(Debug, Packet)]
+#[packet(id = 0x15)]
+pub struct PlayCookieRequestPacket(pub CookieRequest);
+
+#[derive(Debug, Packet)]
+#[packet(id = 0x76)]
+pub struct PlayStoreCookiePacket(pub StoreCookie);
+
+#[derive(Debug, Packet)]
+#[packet(id = 0x7f)]
+pub struct PlayTransferPacket(pub Transfer);
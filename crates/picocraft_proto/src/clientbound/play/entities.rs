@@ -1,5 +1,23 @@
 use crate::prelude::*;
 
+/// Sets an entity's position outright, for moves too large for
+/// [`UpdateEntityPosPacket`]. Named `entity_position_sync` by Minecraft.
+#[derive(Debug, Packet)]
+#[packet(id = 0x23)]
+pub struct TeleportEntityPacket {
+    pub entity_id: VarInt,
+    pub x: Double,
+    pub y: Double,
+    pub z: Double,
+    pub velocity_x: Double,
+    pub velocity_y: Double,
+    pub velocity_z: Double,
+    /// In degrees, unlike the other entity packets.
+    pub yaw: Float,
+    pub pitch: Float,
+    pub on_ground: Boolean,
+}
+
 #[derive(Debug, Packet)]
 #[packet(id = 0x33)]
 pub struct UpdateEntityPosPacket {
@@ -43,3 +61,11 @@ pub struct SetHeadRotationPacket {
     /// New angle value, not a delta
     pub head_yaw: Angle,
 }
+
+#[derive(Debug, Packet)]
+#[packet(id = 0x63)]
+pub struct SetEntityVelocityPacket {
+    pub entity_id: VarInt,
+    /// In blocks per tick.
+    pub velocity: LpVec3,
+}
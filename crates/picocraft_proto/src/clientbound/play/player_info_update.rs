@@ -25,6 +25,15 @@ impl<const ACTIONS: usize> PlayerInfoUpdatePacket<ACTIONS> {
 
         Self { actions, players }
     }
+
+    /// Updates the latency shown by the connection bars in the tab list.
+    pub fn update_latency(uuid: UUID, latency: VarInt) -> Self {
+        let actions = EnumSet::UPDATE_LATENCY;
+        let player_actions = Array::from_array([PlayerActions::UpdateLatency(latency)]);
+        let players = PrefixedArray::from_array([(uuid, player_actions)]);
+
+        Self { actions, players }
+    }
 }
 
 impl<const ACTIONS: usize> Packet for PlayerInfoUpdatePacket<ACTIONS> {
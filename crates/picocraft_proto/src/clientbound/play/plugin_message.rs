@@ -0,0 +1,5 @@
+use crate::prelude::*;
+
+#[derive(Debug, Packet)]
+#[packet(id = 0x18)]
+pub struct PlayPluginMessagePacket(pub PluginMessage);
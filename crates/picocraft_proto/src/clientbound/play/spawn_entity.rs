@@ -10,6 +10,7 @@ pub struct SpawnEntityPacket {
     pub x: Double,
     pub y: Double,
     pub z: Double,
+    /// In blocks per tick.
     pub velocity: LpVec3,
     pub pitch: Angle,
     pub yaw: Angle,
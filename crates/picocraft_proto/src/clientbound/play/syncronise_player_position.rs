@@ -22,6 +22,22 @@ pub struct SynchronisePlayerPositionPacket {
     flags: TeleportFlags,
 }
 
+impl SynchronisePlayerPositionPacket {
+    /// The ID the client confirms the teleport with.
+    pub fn teleport_id(&self) -> VarInt {
+        self.id
+    }
+
+    pub fn position(&self) -> (Double, Double, Double) {
+        (self.x, self.y, self.z)
+    }
+
+    /// Yaw and pitch, in degrees.
+    pub fn rotation(&self) -> (Float, Float) {
+        (self.yaw, self.pitch)
+    }
+}
+
 /// Bitfield representing which fields are relative in a teleport packet,
 /// however we don't do relative teleportation yet, and so it is always 0.
 #[derive(Debug, Clone, Default, Copy, Encode, Decode)]
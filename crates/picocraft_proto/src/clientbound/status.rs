@@ -1,11 +1,28 @@
+use core::iter::once;
+
+use core_json_traits::JsonSerialize;
+
 use crate::prelude::*;
 
-// use core_json_derive::{JsonDeserialize, JsonSerialize};
+/// The most players listed when hovering over the player count, the same as
+/// vanilla.
+pub const MAX_PLAYER_SAMPLE: usize = 12;
 
-#[derive(Debug, Packet, bon::Builder)]
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Debug, Packet)]
 #[packet(id = 0x00, state = State::Status)]
-pub struct StatusResponsePacket<const N: usize> {
-    json_response: String<N>,
+pub struct StatusResponsePacket<'a> {
+    json_response: Json<JsonStatusResponse<'a>>,
+}
+
+impl<'a> StatusResponsePacket<'a> {
+    pub fn new(json_response: JsonStatusResponse<'a>) -> Self {
+        Self {
+            json_response: Json(json_response),
+        }
+    }
 }
 
 #[derive(Debug, Packet, bon::Builder)]
@@ -40,27 +57,67 @@ pub struct PongResponsePacket {
 /// }
 /// ```
 #[derive(Debug, bon::Builder, Default)]
-// #[derive(JsonDeserialize,JsonSerialize)]
-pub struct JsonStatusResponse {
+pub struct JsonStatusResponse<'a> {
     #[builder(into, default)]
     pub version: Version,
 
-    #[builder(with = |max: i32, online: i32| Players::new(max, online))]
+    #[builder(with = |max: i32, online: i32, sample: Vec<PlayerSample, MAX_PLAYER_SAMPLE>| Players { max, online, sample })]
     pub players: Players,
 
-    ///doesn't need to be the same level as [Players] or [Version], as [Chat]
-    /// already has the `text` field as the description field has.
-    #[builder(into, default)]
-    pub description: Chat,
+    #[builder(default = TextComponent::text("A picocraft server! :D"))]
+    pub description: TextComponent<'a>,
+
+    /// A 64x64 PNG image, which is base64 encoded as it is sent.
+    pub favicon: Option<&'a [u8]>,
 
-    // #[serde(skip_serializing_if = "Option::is_none")]
-    // favicon: Option<String>,
     #[builder(default)]
     pub enforces_secure_chat: bool,
 }
 
+impl JsonSerialize for JsonStatusResponse<'_> {
+    fn serialize(&self) -> impl Iterator<Item = char> {
+        let favicon = self.favicon.into_iter().flat_map(|png| {
+            ",\"favicon\":\"data:image/png;base64,"
+                .chars()
+                .chain(base64(png))
+                .chain(once('"'))
+        });
+
+        "{\"version\":"
+            .chars()
+            .chain(self.version.serialize())
+            .chain(",\"players\":".chars())
+            .chain(self.players.serialize())
+            .chain(",\"description\":".chars())
+            .chain(self.description.serialize())
+            .chain(favicon)
+            .chain(",\"enforcesSecureChat\":".chars())
+            .chain(self.enforces_secure_chat.serialize())
+            .chain(once('}'))
+    }
+}
+
+/// Lazily base64 encodes `bytes`, with padding.
+fn base64(bytes: &[u8]) -> impl Iterator<Item = char> {
+    bytes.chunks(3).flat_map(|chunk| {
+        let triple = chunk.iter().enumerate().fold(0u32, |triple, (i, &byte)| {
+            triple | u32::from(byte) << (16 - 8 * i)
+        });
+
+        // Three bytes make four characters, so a partial chunk is padded with `=`.
+        let characters = chunk.len() + 1;
+
+        (0..4).map(move |i| {
+            if i < characters {
+                char::from(BASE64_ALPHABET[(triple >> (18 - 6 * i) & 0x3f) as usize])
+            } else {
+                '='
+            }
+        })
+    })
+}
+
 #[derive(Debug)]
-// #[derive(JsonDeserialize,JsonSerialize)]
 pub struct Version {
     pub name: String<8>,
     pub protocol: VarInt,
@@ -81,58 +138,100 @@ impl From<ProtocolVersion> for Version {
     }
 }
 
+impl JsonSerialize for Version {
+    fn serialize(&self) -> impl Iterator<Item = char> {
+        "{\"name\":"
+            .chars()
+            .chain(self.name.as_str().serialize())
+            .chain(",\"protocol\":".chars())
+            .chain(self.protocol.serialize())
+            .chain(once('}'))
+    }
+}
+
 #[derive(Debug, Default)]
-// #[derive(JsonDeserialize,JsonSerialize)]
 pub struct Players {
     pub max: i32,
     pub online: i32,
-    //TODO actually send a sample to the client
-    pub sample: Vec<PlayerSample, 0>,
+    pub sample: Vec<PlayerSample, MAX_PLAYER_SAMPLE>,
 }
 
-impl Players {
-    fn new(max: i32, online: i32) -> Self {
-        Self {
-            max,
-            online,
-            sample: Vec::new(),
-        }
+impl JsonSerialize for Players {
+    fn serialize(&self) -> impl Iterator<Item = char> {
+        "{\"max\":"
+            .chars()
+            .chain(self.max.serialize())
+            .chain(",\"online\":".chars())
+            .chain(self.online.serialize())
+            .chain(",\"sample\":".chars())
+            .chain(self.sample.as_slice().serialize())
+            .chain(once('}'))
     }
 }
 
-#[derive(Debug)]
-// #[derive(JsonDeserialize,JsonSerialize)]
-pub struct Chat {
-    pub text: String<128>,
+#[derive(Debug, Clone, Default)]
+pub struct PlayerSample {
+    pub name: String<16>,
+    pub id: UUID,
 }
 
-impl Chat {
-    pub fn new(text: &str) -> Self {
-        Self {
-            text: String::try_from(text)
-                .expect("Chat description must be less than 128 characters long"),
-        }
+impl JsonSerialize for PlayerSample {
+    fn serialize(&self) -> impl Iterator<Item = char> {
+        // Hyphenated UUIDs are always 36 characters long.
+        let mut id = [0u8; 36];
+        self.id.hyphenated().encode_lower(&mut id);
+
+        "{\"name\":"
+            .chars()
+            .chain(self.name.as_str().serialize())
+            .chain(",\"id\":\"".chars())
+            .chain(id.into_iter().map(char::from))
+            .chain("\"}".chars())
     }
 }
 
-impl From<String<128>> for Chat {
-    fn from(text: String<128>) -> Self {
-        Self { text }
+#[cfg(test)]
+mod tests {
+    use std::string::String;
+
+    use super::*;
+
+    #[test]
+    fn status_responses_are_valid_json() {
+        let mut sample = Vec::new();
+        sample
+            .push(PlayerSample {
+                name: "Notch".try_into().expect("short enough"),
+                id: UUID::from_u128(0x069a79f4_44e9_4726_a5be_fca90e38aaf5),
+            })
+            .expect("fits");
+
+        let response = JsonStatusResponse::builder()
+            .players(20, 1, sample)
+            .description(TextComponent::text("Say \"hi\"\\\n"))
+            .favicon(&[0x89, b'P', b'N', b'G'])
+            .build();
+
+        let json: String = response.serialize().collect();
+
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"version":{"name":"1.21.10","protocol":773},"#,
+                r#""players":{"max":20,"online":1,"sample":[{"name":"Notch","id":"069a79f4-44e9-4726-a5be-fca90e38aaf5"}]},"#,
+                r#""description":{"text":"Say \"hi\"\\\u000a"},"#,
+                r#""favicon":"data:image/png;base64,iVBORw==","enforcesSecureChat":false}"#,
+            )
+        );
     }
-}
 
-impl Default for Chat {
-    fn default() -> Self {
-        Self {
-            text: String::try_from("A picocraft server! :D").expect("String is long enough"),
-        }
-    }
-}
+    #[test]
+    fn favicons_are_padded() {
+        let encoded = |bytes: &[u8]| base64(bytes).collect::<String>();
 
-#[allow(unused)]
-#[derive(Debug, Default)]
-// #[derive(JsonDeserialize,JsonSerialize)]
-pub struct PlayerSample {
-    name: String<16>,
-    id: UUID,
+        assert_eq!(encoded(b""), "");
+        assert_eq!(encoded(b"M"), "TQ==");
+        assert_eq!(encoded(b"Ma"), "TWE=");
+        assert_eq!(encoded(b"Man"), "TWFu");
+    }
 }
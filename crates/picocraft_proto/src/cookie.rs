@@ -0,0 +1,203 @@
+//! Packet bodies shared between the login, configuration and play states.
+//! Each state has its own packet ID for these, so the packets themselves are
+//! thin wrappers in the `clientbound` and `serverbound` modules.
+
+use crate::prelude::*;
+
+/// The vanilla client allows cookies of up to 5120 bytes, however the server
+/// keeps each player's cookie responses in memory, so we are much stricter.
+/// Bigger payloads in a [`CookieResponse`] are read as if there were no cookie.
+pub const MAX_COOKIE_SIZE: usize = 512;
+/// Longest `namespace:path` key accepted for a cookie.
+pub const MAX_COOKIE_KEY_LENGTH: usize = 64;
+
+pub type CookieKey = NamespacedIdentifier<MAX_COOKIE_KEY_LENGTH>;
+pub type CookiePayload = PrefixedArray<UnsignedByte, MAX_COOKIE_SIZE>;
+
+/// Asks the client for the cookie stored under `key`.
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct CookieRequest {
+    pub key: CookieKey,
+}
+
+/// Stores `payload` on the client under `key`. Cookies are only kept for
+/// the current session, however they survive a [`Transfer`] to another
+/// server.
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct StoreCookie {
+    pub key: CookieKey,
+    pub payload: CookiePayload,
+}
+
+/// The client's answer to a [`CookieRequest`], with no payload if nothing
+/// is stored under `key`, or if it is bigger than [`MAX_COOKIE_SIZE`].
+#[derive(Debug, Clone, Encode)]
+pub struct CookieResponse {
+    pub key: CookieKey,
+    pub payload: PrefixedOptional<CookiePayload>,
+}
+
+impl Decode for CookieResponse {
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        let key = CookieKey::decode(&mut buffer).await?;
+
+        if !bool::decode(&mut buffer).await? {
+            return Ok(Self {
+                key,
+                payload: PrefixedOptional(None),
+            });
+        }
+
+        let length = *VarInt::decode(&mut buffer).await?;
+
+        if length.is_negative() {
+            return Err(DecodeError::VarIntTooSmall(VarInt(length)));
+        }
+
+        // Vanilla allows payloads we have no room for, which are skipped rather
+        // than treated as a bad packet.
+        if length as usize > MAX_COOKIE_SIZE {
+            for _ in 0..length {
+                let _ = UnsignedByte::decode(&mut buffer).await?;
+            }
+
+            return Ok(Self {
+                key,
+                payload: PrefixedOptional(None),
+            });
+        }
+
+        let mut payload = CookiePayload::new();
+
+        for _ in 0..length {
+            let _ = payload.push(UnsignedByte::decode(&mut buffer).await?);
+        }
+
+        Ok(Self {
+            key,
+            payload: PrefixedOptional(Some(payload)),
+        })
+    }
+}
+
+/// Sends the client to another server, which it connects to with the
+/// [`Intent::Transfer`](crate::serverbound::Intent::Transfer) handshake.
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct Transfer {
+    pub host: String<255>,
+    pub port: VarInt,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::clientbound::{ConfigurationStoreCookiePacket, PlayTransferPacket};
+
+    async fn encode(packet: &impl Encode) -> Vec<u8> {
+        let mut buffer = std::vec![0u8; 64];
+        let mut remaining = buffer.as_mut_slice();
+        packet
+            .encode(&mut remaining)
+            .await
+            .expect("fits in 64 bytes");
+
+        let length = 64 - remaining.len();
+        buffer.truncate(length);
+        buffer
+    }
+
+    #[test]
+    fn stored_cookies_carry_their_key_and_payload() {
+        embassy_futures::block_on(async {
+            let packet = ConfigurationStoreCookiePacket(StoreCookie {
+                key: CookieKey::try_from("picocraft:session").expect("valid key"),
+                payload: PrefixedArray::from_vec(
+                    heapless::Vec::from_slice(&[1, 2, 3]).expect("fits"),
+                ),
+            });
+
+            let mut expected = std::vec![*ConfigurationStoreCookiePacket::ID as u8, 17];
+            expected.extend_from_slice(b"picocraft:session");
+            expected.extend_from_slice(&[3, 1, 2, 3]);
+
+            assert_eq!(encode(&packet).await, expected);
+        });
+    }
+
+    #[test]
+    fn cookie_responses_may_have_no_payload() {
+        embassy_futures::block_on(async {
+            let mut bytes = std::vec![14];
+            bytes.extend_from_slice(b"picocraft:seen");
+            bytes.extend_from_slice(&[1, 2, 9, 8]);
+
+            let mut remaining = bytes.as_slice();
+            let response = CookieResponse::decode(&mut remaining)
+                .await
+                .expect("valid response");
+
+            assert!(remaining.is_empty());
+            assert_eq!(response.key.as_str(), "picocraft:seen");
+            assert_eq!(
+                response
+                    .payload
+                    .0
+                    .as_ref()
+                    .map(|payload| payload.as_slice()),
+                Some([9, 8].as_slice())
+            );
+
+            bytes.truncate(15);
+            bytes.push(0);
+
+            let response = CookieResponse::decode(bytes.as_slice())
+                .await
+                .expect("valid response");
+
+            assert!(response.payload.0.is_none());
+        });
+    }
+
+    #[test]
+    fn oversized_cookie_responses_are_read_without_a_payload() {
+        embassy_futures::block_on(async {
+            let mut bytes = std::vec![14];
+            bytes.extend_from_slice(b"picocraft:seen");
+            // 1000 bytes, bigger than we keep but within vanilla's limit.
+            bytes.extend_from_slice(&[1, 0xe8, 0x07]);
+            bytes.extend_from_slice(&[7; 1000]);
+
+            let mut remaining = bytes.as_slice();
+            let response = CookieResponse::decode(&mut remaining)
+                .await
+                .expect("valid response");
+
+            assert!(remaining.is_empty());
+            assert_eq!(response.key.as_str(), "picocraft:seen");
+            assert!(response.payload.0.is_none());
+        });
+    }
+
+    #[test]
+    fn transfers_round_trip() {
+        embassy_futures::block_on(async {
+            let packet = PlayTransferPacket(Transfer {
+                host: String::try_from("mc.example.com").expect("short enough"),
+                port: VarInt(25566),
+            });
+
+            let bytes = encode(&packet).await;
+
+            let mut expected = std::vec![*PlayTransferPacket::ID as u8, 14];
+            expected.extend_from_slice(b"mc.example.com");
+            expected.extend_from_slice(&[0xde, 0xc7, 0x01]);
+            assert_eq!(bytes, expected);
+
+            let transfer = Transfer::decode(&bytes[1..]).await.expect("valid transfer");
+            assert_eq!(transfer.host, "mc.example.com");
+            assert_eq!(transfer.port, VarInt(25566));
+        });
+    }
+}
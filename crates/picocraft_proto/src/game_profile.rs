@@ -4,7 +4,7 @@ use crate::prelude::*;
 pub struct GameProfile {
     uuid: UUID,
     username: String<16>,
-    properties: Properties,
+    properties: Properties<MAX_PROFILE_PROPERTIES>,
 }
 
 impl GameProfile {
@@ -31,31 +31,48 @@ impl GameProfile {
     pub fn set_uuid(&mut self, uuid: UUID) {
         self.uuid = uuid;
     }
+
+    pub fn properties(&self) -> &Properties<MAX_PROFILE_PROPERTIES> {
+        &self.properties
+    }
+
+    pub fn set_properties(&mut self, properties: Properties<MAX_PROFILE_PROPERTIES>) {
+        self.properties = properties;
+    }
 }
 
-/// The only properties field that currently exists is the output of querying
-/// Mojang's API for a player's skin and cape. The output of this API is the
-/// players UUID and name (which we already know), and then the properties
-/// field, which is a 1 element array with the "name" field as "textures", and a
-/// Base64 encoded string. This string contains a JSON object with the URLs for
-/// the skin and cape textures aswell as another copy of the player's UUID and
-/// name, and a timestamp. Of this we only really need to store the skin and
-/// cape URLs, which is stored in the `Textures` struct. Currently we do not
-/// query Mojang's API for these textures, so the array length is 0 for now.
-#[derive(Debug, Default, Clone, Encode, Decode)]
-pub struct Properties(pub PrefixedArray<Textures, 0>);
+/// A signed property of a player's profile. The only property that currently
+/// exists is `textures`, which is a Base64 encoded JSON object containing the
+/// player's skin and cape URLs, signed by Mojang.
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct Property {
+    pub name: String<16>,
+    pub value: String<MAX_PROPERTY_VALUE_LENGTH>,
+    pub signature: PrefixedOptional<String<MAX_PROPERTY_SIGNATURE_LENGTH>>,
+}
 
-/// An empty struct which contains the skin and optional cape URLs for a player. As the only part of the request to Mojang's API we care about is the 64 bytes of the URL after [http://textures.minecraft.net/texture/](http://textures.minecraft.net/texture/7fd9ba42a7c81eeea22f1524271ae85a8e045ce0af5a6ae16c6406ae917e68b5) (http only is fine here) so we only store those bytes. The rest can just be serialised as needed.
-/// Currently we do not query Mojang's API for these textures, so this struct is
-/// never actually used.
-#[derive(Debug, Default, Clone, Encode, Decode)]
-pub struct Textures {
-    pub skin_url: String<64>,
-    pub cape_url: Optional<String<64>>,
+/// A textures value is usually around 400-600 bytes, depending on the length
+/// of the player's name and whether they have a cape.
+pub const MAX_PROPERTY_VALUE_LENGTH: usize = 768;
+/// Base64 of Mojang's 4096 bit RSA signature.
+pub const MAX_PROPERTY_SIGNATURE_LENGTH: usize = 684;
+/// Properties are large, so only a player's own profile keeps any.
+pub const MAX_PROFILE_PROPERTIES: usize = 1;
+
+/// Properties are only filled in when they are forwarded by a proxy, as we
+/// do not query Mojang's API ourselves. Everywhere else `N` is 0, as each
+/// [`Property`] is around 1.5KB.
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct Properties<const N: usize = 0>(pub PrefixedArray<Property, N>);
+
+impl<const N: usize> Default for Properties<N> {
+    fn default() -> Self {
+        Self(PrefixedArray::new())
+    }
 }
 
-impl core::ops::Deref for Properties {
-    type Target = PrefixedArray<Textures, 0>;
+impl<const N: usize> core::ops::Deref for Properties<N> {
+    type Target = PrefixedArray<Property, N>;
     fn deref(&self) -> &Self::Target {
         &(self.0)
     }
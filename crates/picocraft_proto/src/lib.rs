@@ -13,8 +13,11 @@
 )]
 
 pub mod clientbound;
+pub mod cookie;
 pub mod game_profile;
+pub mod plugin_message;
 pub mod protocol_version;
+pub mod registry;
 pub mod serverbound;
 
 pub use protocol_version::{CURRENT_PROTOCOL_VERSION, CURRENT_VERSION_NAME};
@@ -27,7 +30,9 @@ pub mod prelude {
     pub(crate) use picocraft_core::prelude::*;
     pub(crate) use picocraft_derive::{Decode, Encode, Packet};
 
+    pub use crate::cookie::*;
     pub use crate::game_profile::*;
+    pub use crate::plugin_message::*;
     pub use crate::protocol_version::*;
     pub use crate::{clientbound, serverbound};
 }
@@ -0,0 +1,28 @@
+//! Plugin message bodies, which carry arbitrary data on a named channel
+//! between the server and client mods (or proxies). Like cookies, each state
+//! has its own packet ID for these, so the packets themselves are thin
+//! wrappers in the `clientbound` and `serverbound` modules.
+
+use crate::prelude::*;
+
+/// The vanilla client accepts payloads of up to 1 MiB, and sends up to
+/// 32767 bytes, however a plugin message has to fit in a single receive
+/// buffer, so we are much stricter.
+pub const MAX_PLUGIN_MESSAGE_SIZE: usize = 1024;
+/// Longest `namespace:path` channel name accepted.
+pub const MAX_PLUGIN_CHANNEL_LENGTH: usize = 64;
+
+pub type PluginChannel = NamespacedIdentifier<MAX_PLUGIN_CHANNEL_LENGTH>;
+pub type PluginPayload = Array<UnsignedByte, MAX_PLUGIN_MESSAGE_SIZE>;
+
+/// The channel vanilla uses to tell the other side which software it is,
+/// e.g. `vanilla` or `fabric`. The payload is a single [`String`].
+pub const BRAND_CHANNEL: &str = "minecraft:brand";
+
+/// `data` sent on `channel`. The payload isn't length-prefixed, so it runs
+/// to the end of the packet.
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct PluginMessage {
+    pub channel: PluginChannel,
+    pub data: PluginPayload,
+}
@@ -37,7 +37,22 @@ impl core::ops::DerefMut for ProtocolVersion {
     }
 }
 
+impl From<VarInt> for ProtocolVersion {
+    fn from(value: VarInt) -> Self {
+        Self(value)
+    }
+}
+
 impl ProtocolVersion {
+    pub const CURRENT: Self = Self(CURRENT_PROTOCOL_VERSION);
+
+    /// Whether clients on this version can join. Only 1.21.9 and 1.21.10 can,
+    /// as packet IDs and registries change between versions, and everyone else
+    /// is told which version to use during the handshake.
+    pub fn is_supported(&self) -> bool {
+        *self == Self::CURRENT
+    }
+
     pub fn version_name(&self) -> Option<&'static str> {
         Some(match *self.0 {
             774 => "1.21.11",
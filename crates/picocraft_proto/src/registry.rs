@@ -0,0 +1,90 @@
+//! Typed data for the registries the server sends during configuration.
+//!
+//! Every element type knows how to write itself as network NBT, and is sent
+//! in a [`RegistryDataPacket`](crate::clientbound::RegistryDataPacket) as a
+//! list of [`RegistryEntry`]s. The client looks entries up by their index in
+//! that list, so anything which refers to an entry (such as the biomes in
+//! chunk data) must agree on its order.
+
+mod biome;
+mod damage_type;
+mod dimension_type;
+mod variants;
+
+pub use biome::{Biome, BiomeEffects, MoodSound};
+pub use damage_type::{DamageEffects, DamageScaling, DamageType, DeathMessageType};
+pub use dimension_type::{DimensionType, MonsterSpawnLightLevel};
+use picocraft_core::types::nbt::NbtWriter;
+pub use variants::{MobVariant, PaintingVariant, WolfSoundVariant, WolfVariant};
+
+use crate::prelude::*;
+
+/// Data for an entry in one of the registries.
+#[allow(async_fn_in_trait)]
+pub trait RegistryElement: core::fmt::Debug {
+    /// Writes the element's fields into the compound which has just been
+    /// started.
+    async fn write_nbt<W: embedded_io_async::Write>(
+        &self,
+        nbt: &mut NbtWriter<W>,
+    ) -> Result<(), EncodeError>;
+}
+
+/// A named element of a registry. `id` is in the `minecraft` namespace.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegistryEntry<'a, T> {
+    pub id: &'a str,
+    pub element: T,
+}
+
+impl<'a, T> RegistryEntry<'a, T> {
+    pub const fn new(id: &'a str, element: T) -> Self {
+        Self { id, element }
+    }
+}
+
+impl<T: RegistryElement> Encode for RegistryEntry<'_, T> {
+    async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
+        const NAMESPACE: &str = "minecraft:";
+
+        let len =
+            i32::try_from(NAMESPACE.len() + self.id.len()).map_err(EncodeError::TryFromInt)?;
+        VarInt(len).encode(&mut buffer).await?;
+        buffer.write_all(NAMESPACE.as_bytes()).await?;
+        buffer.write_all(self.id.as_bytes()).await?;
+
+        // The data is always sent, rather than left to the client's copy of
+        // the vanilla data pack.
+        true.encode(&mut buffer).await?;
+
+        let mut nbt = NbtWriter::new(&mut buffer);
+        nbt.begin_compound("").await?;
+        self.element.write_nbt(&mut nbt).await?;
+        nbt.end().await?;
+        nbt.finish().map(drop)
+    }
+}
+
+/// The entries of a registry, prefixed with how many there are.
+#[derive(Debug, Clone, Copy)]
+pub struct RegistryEntries<'a, T>(pub &'a [RegistryEntry<'a, T>]);
+
+impl<T: RegistryElement> Encode for RegistryEntries<'_, T> {
+    async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
+        let len = i32::try_from(self.0.len()).map_err(EncodeError::TryFromInt)?;
+        VarInt(len).encode(&mut buffer).await?;
+
+        for entry in self.0 {
+            entry.encode(&mut buffer).await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> Decode for RegistryEntries<'_, T> {
+    #[allow(unused)]
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        Err(DecodeError::Unimplemented)
+    }
+}
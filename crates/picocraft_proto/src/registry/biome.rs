@@ -0,0 +1,123 @@
+use picocraft_core::types::nbt::{NbtWriter, Tag};
+
+use super::RegistryElement;
+use crate::prelude::*;
+
+/// The parts of a biome the client needs, as an entry of
+/// `minecraft:worldgen/biome`. Everything else about a biome only matters for
+/// generating terrain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Biome {
+    pub has_precipitation: bool,
+    /// Decides whether precipitation is rain or snow, and the default grass
+    /// and foliage colors.
+    pub temperature: f32,
+    /// How wet the biome is, which also tints grass and foliage.
+    pub downfall: f32,
+    pub effects: BiomeEffects,
+}
+
+/// How a biome looks and sounds. Colors are `0xRRGGBB`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BiomeEffects {
+    pub fog_color: u32,
+    pub sky_color: u32,
+    pub water_color: u32,
+    pub water_fog_color: u32,
+    /// Overrides the color worked out from the temperature and downfall.
+    pub foliage_color: Option<u32>,
+    /// Overrides the color worked out from the temperature and downfall.
+    pub grass_color: Option<u32>,
+    pub mood_sound: Option<MoodSound>,
+    pub music_volume: f32,
+}
+
+/// An ambient sound played now and then in dark places.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoodSound {
+    pub sound: &'static str,
+    /// How many ticks the player must spend in the dark first.
+    pub tick_delay: i32,
+    pub block_search_extent: i32,
+    /// How far away the sound is played.
+    pub offset: f64,
+}
+
+impl MoodSound {
+    pub const CAVE: Self = Self {
+        sound: "minecraft:ambient.cave",
+        tick_delay: 6000,
+        block_search_extent: 8,
+        offset: 2.0,
+    };
+}
+
+impl Biome {
+    pub const PLAINS: Self = Self {
+        has_precipitation: true,
+        temperature: 0.8,
+        downfall: 0.4,
+        effects: BiomeEffects {
+            fog_color: 0x00c0_d8ff,
+            sky_color: 0x0078_a7ff,
+            water_color: 0x003f_76e4,
+            water_fog_color: 0x0005_0533,
+            foliage_color: None,
+            grass_color: None,
+            mood_sound: Some(MoodSound::CAVE),
+            music_volume: 1.0,
+        },
+    };
+}
+
+impl RegistryElement for Biome {
+    async fn write_nbt<W: embedded_io_async::Write>(
+        &self,
+        nbt: &mut NbtWriter<W>,
+    ) -> Result<(), EncodeError> {
+        nbt.write("has_precipitation", self.has_precipitation.into())
+            .await?;
+        nbt.write("temperature", Tag::Float(self.temperature))
+            .await?;
+        nbt.write("downfall", Tag::Float(self.downfall)).await?;
+
+        let effects = &self.effects;
+
+        nbt.begin_compound("effects").await?;
+
+        let colors = [
+            ("fog_color", Some(effects.fog_color)),
+            ("sky_color", Some(effects.sky_color)),
+            ("water_color", Some(effects.water_color)),
+            ("water_fog_color", Some(effects.water_fog_color)),
+            ("foliage_color", effects.foliage_color),
+            ("grass_color", effects.grass_color),
+        ];
+
+        for (name, color) in colors {
+            if let Some(color) = color {
+                nbt.write(name, Tag::Int((color & 0x00ff_ffff) as i32))
+                    .await?;
+            }
+        }
+
+        if let Some(mood_sound) = &effects.mood_sound {
+            nbt.begin_compound("mood_sound").await?;
+            nbt.write("sound", Tag::String(mood_sound.sound)).await?;
+            nbt.write("tick_delay", Tag::Int(mood_sound.tick_delay))
+                .await?;
+            nbt.write(
+                "block_search_extent",
+                Tag::Int(mood_sound.block_search_extent),
+            )
+            .await?;
+            nbt.write("offset", Tag::Double(mood_sound.offset)).await?;
+            nbt.end().await?;
+        }
+
+        nbt.write("music_volume", Tag::Float(effects.music_volume))
+            .await?;
+
+        nbt.end().await
+    }
+}
@@ -0,0 +1,246 @@
+use picocraft_core::types::nbt::{NbtWriter, Tag};
+
+use super::{RegistryElement, RegistryEntry};
+use crate::prelude::*;
+
+/// A kind of damage, as an entry of `minecraft:damage_type`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DamageType {
+    /// Used in death messages, as `death.attack.<message_id>`.
+    pub message_id: &'static str,
+    pub scaling: DamageScaling,
+    /// How much hunger exhaustion the damage causes.
+    pub exhaustion: f32,
+    pub effects: DamageEffects,
+    pub death_message_type: DeathMessageType,
+}
+
+/// Whether the damage scales with difficulty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DamageScaling {
+    Never,
+    #[default]
+    WhenCausedByLivingNonPlayer,
+    Always,
+}
+
+/// The sound played when the damage is taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DamageEffects {
+    #[default]
+    Hurt,
+    Thorns,
+    Drowning,
+    Burning,
+    Poking,
+    Freezing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeathMessageType {
+    #[default]
+    Default,
+    FallVariants,
+    IntentionalGameDesign,
+}
+
+impl DamageScaling {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Never => "never",
+            Self::WhenCausedByLivingNonPlayer => "when_caused_by_living_non_player",
+            Self::Always => "always",
+        }
+    }
+}
+
+impl DamageEffects {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Hurt => "hurt",
+            Self::Thorns => "thorns",
+            Self::Drowning => "drowning",
+            Self::Burning => "burning",
+            Self::Poking => "poking",
+            Self::Freezing => "freezing",
+        }
+    }
+}
+
+impl DeathMessageType {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::FallVariants => "fall_variants",
+            Self::IntentionalGameDesign => "intentional_game_design",
+        }
+    }
+}
+
+impl DamageType {
+    /// Every vanilla damage type. The client expects all of them to exist.
+    pub const VANILLA: &[RegistryEntry<'static, Self>] = &[
+        RegistryEntry::new("arrow", DamageType::new("arrow", 0.1)),
+        RegistryEntry::new(
+            "bad_respawn_point",
+            DamageType::new("badRespawnPoint", 0.1)
+                .scaling(DamageScaling::Always)
+                .death_message_type(DeathMessageType::IntentionalGameDesign),
+        ),
+        RegistryEntry::new("cactus", DamageType::new("cactus", 0.1)),
+        RegistryEntry::new(
+            "campfire",
+            DamageType::new("inFire", 0.1).effects(DamageEffects::Burning),
+        ),
+        RegistryEntry::new("cramming", DamageType::new("cramming", 0.0)),
+        RegistryEntry::new("dragon_breath", DamageType::new("dragonBreath", 0.0)),
+        RegistryEntry::new(
+            "drown",
+            DamageType::new("drown", 0.0).effects(DamageEffects::Drowning),
+        ),
+        RegistryEntry::new("dry_out", DamageType::new("dryout", 0.1)),
+        RegistryEntry::new(
+            "ender_pearl",
+            DamageType::new("fall", 0.0).death_message_type(DeathMessageType::FallVariants),
+        ),
+        RegistryEntry::new(
+            "explosion",
+            DamageType::new("explosion", 0.1).scaling(DamageScaling::Always),
+        ),
+        RegistryEntry::new(
+            "fall",
+            DamageType::new("fall", 0.0).death_message_type(DeathMessageType::FallVariants),
+        ),
+        RegistryEntry::new("falling_anvil", DamageType::new("anvil", 0.1)),
+        RegistryEntry::new("falling_block", DamageType::new("fallingBlock", 0.1)),
+        RegistryEntry::new(
+            "falling_stalactite",
+            DamageType::new("fallingStalactite", 0.1),
+        ),
+        RegistryEntry::new(
+            "fireball",
+            DamageType::new("fireball", 0.1).effects(DamageEffects::Burning),
+        ),
+        RegistryEntry::new("fireworks", DamageType::new("fireworks", 0.1)),
+        RegistryEntry::new("fly_into_wall", DamageType::new("flyIntoWall", 0.0)),
+        RegistryEntry::new(
+            "freeze",
+            DamageType::new("freeze", 0.0).effects(DamageEffects::Freezing),
+        ),
+        RegistryEntry::new("generic", DamageType::new("generic", 0.0)),
+        RegistryEntry::new("generic_kill", DamageType::new("genericKill", 0.0)),
+        RegistryEntry::new(
+            "hot_floor",
+            DamageType::new("hotFloor", 0.1).effects(DamageEffects::Burning),
+        ),
+        RegistryEntry::new(
+            "in_fire",
+            DamageType::new("inFire", 0.1).effects(DamageEffects::Burning),
+        ),
+        RegistryEntry::new("in_wall", DamageType::new("inWall", 0.0)),
+        RegistryEntry::new("indirect_magic", DamageType::new("indirectMagic", 0.0)),
+        RegistryEntry::new(
+            "lava",
+            DamageType::new("lava", 0.1).effects(DamageEffects::Burning),
+        ),
+        RegistryEntry::new("lightning_bolt", DamageType::new("lightningBolt", 0.1)),
+        RegistryEntry::new("mace_smash", DamageType::new("mace_smash", 0.1)),
+        RegistryEntry::new("magic", DamageType::new("magic", 0.0)),
+        RegistryEntry::new("mob_attack", DamageType::new("mob", 0.1)),
+        RegistryEntry::new("mob_attack_no_aggro", DamageType::new("mob", 0.1)),
+        RegistryEntry::new("mob_projectile", DamageType::new("mob", 0.1)),
+        RegistryEntry::new(
+            "on_fire",
+            DamageType::new("onFire", 0.0).effects(DamageEffects::Burning),
+        ),
+        RegistryEntry::new("out_of_world", DamageType::new("outOfWorld", 0.0)),
+        RegistryEntry::new("outside_border", DamageType::new("outsideBorder", 0.0)),
+        RegistryEntry::new("player_attack", DamageType::new("player", 0.1)),
+        RegistryEntry::new(
+            "player_explosion",
+            DamageType::new("explosion.player", 0.1).scaling(DamageScaling::Always),
+        ),
+        RegistryEntry::new(
+            "sonic_boom",
+            DamageType::new("sonic_boom", 0.0).scaling(DamageScaling::Always),
+        ),
+        RegistryEntry::new("spit", DamageType::new("mob", 0.1)),
+        RegistryEntry::new("stalagmite", DamageType::new("stalagmite", 0.0)),
+        RegistryEntry::new("starve", DamageType::new("starve", 0.0)),
+        RegistryEntry::new("sting", DamageType::new("sting", 0.1)),
+        RegistryEntry::new(
+            "sweet_berry_bush",
+            DamageType::new("sweetBerryBush", 0.1).effects(DamageEffects::Poking),
+        ),
+        RegistryEntry::new(
+            "thorns",
+            DamageType::new("thorns", 0.1).effects(DamageEffects::Thorns),
+        ),
+        RegistryEntry::new("thrown", DamageType::new("thrown", 0.1)),
+        RegistryEntry::new("trident", DamageType::new("trident", 0.1)),
+        RegistryEntry::new(
+            "unattributed_fireball",
+            DamageType::new("onFire", 0.1).effects(DamageEffects::Burning),
+        ),
+        RegistryEntry::new("wind_charge", DamageType::new("mob", 0.1)),
+        RegistryEntry::new("wither", DamageType::new("wither", 0.0)),
+        RegistryEntry::new("wither_skull", DamageType::new("witherSkull", 0.1)),
+    ];
+
+    #[must_use]
+    pub const fn new(message_id: &'static str, exhaustion: f32) -> Self {
+        Self {
+            message_id,
+            scaling: DamageScaling::WhenCausedByLivingNonPlayer,
+            exhaustion,
+            effects: DamageEffects::Hurt,
+            death_message_type: DeathMessageType::Default,
+        }
+    }
+
+    #[must_use]
+    pub const fn scaling(mut self, scaling: DamageScaling) -> Self {
+        self.scaling = scaling;
+        self
+    }
+
+    #[must_use]
+    pub const fn effects(mut self, effects: DamageEffects) -> Self {
+        self.effects = effects;
+        self
+    }
+
+    #[must_use]
+    pub const fn death_message_type(mut self, death_message_type: DeathMessageType) -> Self {
+        self.death_message_type = death_message_type;
+        self
+    }
+}
+
+impl RegistryElement for DamageType {
+    async fn write_nbt<W: embedded_io_async::Write>(
+        &self,
+        nbt: &mut NbtWriter<W>,
+    ) -> Result<(), EncodeError> {
+        nbt.write("message_id", Tag::String(self.message_id))
+            .await?;
+        nbt.write("scaling", Tag::String(self.scaling.name()))
+            .await?;
+        nbt.write("exhaustion", Tag::Float(self.exhaustion)).await?;
+
+        if self.effects != DamageEffects::Hurt {
+            nbt.write("effects", Tag::String(self.effects.name()))
+                .await?;
+        }
+
+        if self.death_message_type != DeathMessageType::Default {
+            nbt.write(
+                "death_message_type",
+                Tag::String(self.death_message_type.name()),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use picocraft_core::types::nbt::{NbtWriter, Tag};
+
+use super::RegistryElement;
+use crate::prelude::*;
+
+/// How a dimension behaves, as an entry of `minecraft:dimension_type`.
+///
+/// Start from one of the vanilla types and change what's needed:
+///
+/// ```ignore
+/// let bright = DimensionType {
+///     ambient_light: 1.0,
+///     ..DimensionType::OVERWORLD
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DimensionType {
+    /// Stops the daylight cycle at this time of day, if set.
+    pub fixed_time: Option<i64>,
+    pub has_skylight: bool,
+    pub has_ceiling: bool,
+    pub ultrawarm: bool,
+    pub natural: bool,
+    /// How far apart coordinates are compared to other dimensions, for
+    /// nether portals.
+    pub coordinate_scale: f64,
+    pub bed_works: bool,
+    pub respawn_anchor_works: bool,
+    /// The lowest block, which must be a multiple of 16.
+    pub min_y: i32,
+    /// The number of blocks above `min_y`, which must be a multiple of 16.
+    pub height: i32,
+    /// How far up portals and chorus fruit can take players.
+    pub logical_height: i32,
+    /// The block tag of blocks which burn forever, including the `#`.
+    pub infiniburn: &'static str,
+    /// Which sky and fog to render.
+    pub effects: &'static str,
+    /// How bright the darkest light level appears, from 0 to 1.
+    pub ambient_light: f32,
+    /// The height clouds are rendered at, if at all.
+    pub cloud_height: Option<i32>,
+    pub piglin_safe: bool,
+    pub has_raids: bool,
+    pub monster_spawn_light_level: MonsterSpawnLightLevel,
+    pub monster_spawn_block_light_limit: i32,
+}
+
+/// The light level monsters can spawn at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonsterSpawnLightLevel {
+    Constant(i32),
+    /// Chosen uniformly between the two levels, inclusive.
+    Uniform {
+        min: i32,
+        max: i32,
+    },
+}
+
+impl DimensionType {
+    pub const OVERWORLD: Self = Self {
+        fixed_time: None,
+        has_skylight: true,
+        has_ceiling: false,
+        ultrawarm: false,
+        natural: true,
+        coordinate_scale: 1.0,
+        bed_works: true,
+        respawn_anchor_works: false,
+        min_y: 0,
+        height: WORLD_HEIGHT as i32,
+        logical_height: WORLD_HEIGHT as i32,
+        infiniburn: "#minecraft:infiniburn_overworld",
+        effects: "minecraft:overworld",
+        ambient_light: 0.0,
+        cloud_height: Some(192),
+        piglin_safe: false,
+        has_raids: true,
+        monster_spawn_light_level: MonsterSpawnLightLevel::Uniform { min: 0, max: 7 },
+        monster_spawn_block_light_limit: 0,
+    };
+
+    pub const THE_NETHER: Self = Self {
+        fixed_time: Some(18000),
+        has_skylight: false,
+        has_ceiling: true,
+        ultrawarm: true,
+        natural: false,
+        coordinate_scale: 8.0,
+        bed_works: false,
+        respawn_anchor_works: true,
+        logical_height: 128,
+        infiniburn: "#minecraft:infiniburn_nether",
+        effects: "minecraft:the_nether",
+        ambient_light: 0.1,
+        cloud_height: None,
+        piglin_safe: true,
+        has_raids: false,
+        monster_spawn_light_level: MonsterSpawnLightLevel::Constant(7),
+        monster_spawn_block_light_limit: 15,
+        ..Self::OVERWORLD
+    };
+
+    pub const THE_END: Self = Self {
+        fixed_time: Some(6000),
+        has_skylight: false,
+        natural: false,
+        bed_works: false,
+        infiniburn: "#minecraft:infiniburn_end",
+        effects: "minecraft:the_end",
+        cloud_height: None,
+        ..Self::OVERWORLD
+    };
+}
+
+impl RegistryElement for DimensionType {
+    async fn write_nbt<W: embedded_io_async::Write>(
+        &self,
+        nbt: &mut NbtWriter<W>,
+    ) -> Result<(), EncodeError> {
+        if let Some(fixed_time) = self.fixed_time {
+            nbt.write("fixed_time", Tag::Long(fixed_time)).await?;
+        }
+
+        nbt.write("has_skylight", self.has_skylight.into()).await?;
+        nbt.write("has_ceiling", self.has_ceiling.into()).await?;
+        nbt.write("ultrawarm", self.ultrawarm.into()).await?;
+        nbt.write("natural", self.natural.into()).await?;
+        nbt.write("coordinate_scale", Tag::Double(self.coordinate_scale))
+            .await?;
+        nbt.write("bed_works", self.bed_works.into()).await?;
+        nbt.write("respawn_anchor_works", self.respawn_anchor_works.into())
+            .await?;
+        nbt.write("min_y", Tag::Int(self.min_y)).await?;
+        nbt.write("height", Tag::Int(self.height)).await?;
+        nbt.write("logical_height", Tag::Int(self.logical_height))
+            .await?;
+        nbt.write("infiniburn", Tag::String(self.infiniburn))
+            .await?;
+        nbt.write("effects", Tag::String(self.effects)).await?;
+        nbt.write("ambient_light", Tag::Float(self.ambient_light))
+            .await?;
+
+        if let Some(cloud_height) = self.cloud_height {
+            nbt.write("cloud_height", Tag::Int(cloud_height)).await?;
+        }
+
+        nbt.write("piglin_safe", self.piglin_safe.into()).await?;
+        nbt.write("has_raids", self.has_raids.into()).await?;
+
+        match self.monster_spawn_light_level {
+            MonsterSpawnLightLevel::Constant(level) => {
+                nbt.write("monster_spawn_light_level", Tag::Int(level))
+                    .await?;
+            }
+            MonsterSpawnLightLevel::Uniform { min, max } => {
+                nbt.begin_compound("monster_spawn_light_level").await?;
+                nbt.write("type", Tag::String("minecraft:uniform")).await?;
+                nbt.write("min_inclusive", Tag::Int(min)).await?;
+                nbt.write("max_inclusive", Tag::Int(max)).await?;
+                nbt.end().await?;
+            }
+        }
+
+        nbt.write(
+            "monster_spawn_block_light_limit",
+            Tag::Int(self.monster_spawn_block_light_limit),
+        )
+        .await
+    }
+}
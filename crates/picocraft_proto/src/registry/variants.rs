@@ -0,0 +1,129 @@
+use picocraft_core::types::nbt::{NbtWriter, Tag, TagType};
+
+use super::RegistryElement;
+use crate::prelude::*;
+
+/// A texture for a mob, as an entry of one of the `minecraft:*_variant`
+/// registries for cats, chickens, cows, frogs and pigs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MobVariant {
+    /// The texture, relative to `textures/` and without `.png`.
+    pub asset_id: &'static str,
+}
+
+impl MobVariant {
+    pub const fn new(asset_id: &'static str) -> Self {
+        Self { asset_id }
+    }
+}
+
+impl RegistryElement for MobVariant {
+    async fn write_nbt<W: embedded_io_async::Write>(
+        &self,
+        nbt: &mut NbtWriter<W>,
+    ) -> Result<(), EncodeError> {
+        nbt.write("asset_id", Tag::String(self.asset_id)).await?;
+        write_spawn_conditions(nbt).await
+    }
+}
+
+/// The textures for a wolf, as an entry of `minecraft:wolf_variant`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WolfVariant {
+    pub wild: &'static str,
+    pub tame: &'static str,
+    pub angry: &'static str,
+}
+
+impl RegistryElement for WolfVariant {
+    async fn write_nbt<W: embedded_io_async::Write>(
+        &self,
+        nbt: &mut NbtWriter<W>,
+    ) -> Result<(), EncodeError> {
+        nbt.begin_compound("assets").await?;
+        nbt.write("wild", Tag::String(self.wild)).await?;
+        nbt.write("tame", Tag::String(self.tame)).await?;
+        nbt.write("angry", Tag::String(self.angry)).await?;
+        nbt.end().await?;
+
+        write_spawn_conditions(nbt).await
+    }
+}
+
+/// The sounds a wolf makes, as an entry of `minecraft:wolf_sound_variant`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WolfSoundVariant {
+    pub ambient_sound: &'static str,
+    pub death_sound: &'static str,
+    pub growl_sound: &'static str,
+    pub hurt_sound: &'static str,
+    pub pant_sound: &'static str,
+    pub whine_sound: &'static str,
+}
+
+impl RegistryElement for WolfSoundVariant {
+    async fn write_nbt<W: embedded_io_async::Write>(
+        &self,
+        nbt: &mut NbtWriter<W>,
+    ) -> Result<(), EncodeError> {
+        let sounds = [
+            ("ambient_sound", self.ambient_sound),
+            ("death_sound", self.death_sound),
+            ("growl_sound", self.growl_sound),
+            ("hurt_sound", self.hurt_sound),
+            ("pant_sound", self.pant_sound),
+            ("whine_sound", self.whine_sound),
+        ];
+
+        for (name, sound) in sounds {
+            nbt.write(name, Tag::String(sound)).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A painting, as an entry of `minecraft:painting_variant`. The size is in
+/// blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaintingVariant {
+    pub asset_id: &'static str,
+    pub width: i32,
+    pub height: i32,
+    pub title: Option<TextComponent<'static>>,
+    pub author: Option<TextComponent<'static>>,
+}
+
+impl RegistryElement for PaintingVariant {
+    async fn write_nbt<W: embedded_io_async::Write>(
+        &self,
+        nbt: &mut NbtWriter<W>,
+    ) -> Result<(), EncodeError> {
+        nbt.write("asset_id", Tag::String(self.asset_id)).await?;
+        nbt.write("width", Tag::Int(self.width)).await?;
+        nbt.write("height", Tag::Int(self.height)).await?;
+
+        if let Some(title) = &self.title {
+            title.write_nbt("title", nbt).await?;
+        }
+
+        if let Some(author) = &self.author {
+            author.write_nbt("author", nbt).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Variants can be picked based on where a mob spawns. This server doesn't
+/// spawn mobs, so every variant can spawn anywhere.
+async fn write_spawn_conditions<W: embedded_io_async::Write>(
+    nbt: &mut NbtWriter<W>,
+) -> Result<(), EncodeError> {
+    nbt.begin_list("spawn_conditions", TagType::Compound, 1)
+        .await?;
+    nbt.begin_compound("").await?;
+    nbt.write("priority", Tag::Int(0)).await?;
+    nbt.end().await?;
+    nbt.end().await
+}
@@ -9,3 +9,45 @@ pub use handshake::*;
 pub use login::*;
 pub use play::*;
 pub use status::*;
+
+/// Invokes `$callback!` with the path of every serverbound packet, so that
+/// packet dispatch can be generated from this one list. A new serverbound packet
+/// only needs adding here (and a handler on the server) to be received.
+#[macro_export]
+macro_rules! for_each_serverbound_packet {
+    ($callback:ident) => {
+        $callback! {
+            // Handshake
+            $crate::serverbound::HandshakePacket,
+            $crate::serverbound::LegacyPingPacket,
+            // Status
+            $crate::serverbound::StatusRequestPacket,
+            $crate::serverbound::PingRequestPacket,
+            // Login
+            $crate::serverbound::LoginStartPacket,
+            $crate::serverbound::LoginPluginResponsePacket,
+            $crate::serverbound::LoginAcknowledgedPacket,
+            $crate::serverbound::LoginCookieResponsePacket,
+            // Configuration
+            $crate::serverbound::ClientInformationPacket,
+            $crate::serverbound::ConfigurationCookieResponsePacket,
+            $crate::serverbound::ConfigurationPluginMessagePacket,
+            $crate::serverbound::AcknowledgeFinishConfigurationPacket,
+            // Play
+            $crate::serverbound::ConfirmTeleportationPacket,
+            $crate::serverbound::ChatMessagePacket,
+            $crate::serverbound::ClientTickEndPacket,
+            $crate::serverbound::ClickContainerPacket,
+            $crate::serverbound::PlayCookieResponsePacket,
+            $crate::serverbound::PlayPluginMessagePacket,
+            $crate::serverbound::ServerboundKeepAlivePacket,
+            $crate::serverbound::SetPlayerPositionPacket,
+            $crate::serverbound::SetPlayerPositionAndRotationPacket,
+            $crate::serverbound::SetPlayerRotationPacket,
+            $crate::serverbound::PlayerActionPacket,
+            $crate::serverbound::SetHeldItemPacket,
+            $crate::serverbound::SetCreativeModeSlotPacket,
+            $crate::serverbound::UseItemOnPacket,
+        }
+    };
+}
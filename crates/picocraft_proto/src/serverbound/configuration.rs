@@ -4,8 +4,33 @@ pub use client_information::*;
 
 use crate::prelude::*;
 
+#[derive(Debug, Packet)]
+#[packet(id = 0x01, state = State::Configuration)]
+pub struct ConfigurationCookieResponsePacket(pub CookieResponse);
+
+#[derive(Debug, Packet)]
+#[packet(id = 0x02, state = State::Configuration)]
+pub struct ConfigurationPluginMessagePacket(pub PluginMessage);
+
 #[derive(Debug, Packet)]
 #[packet(id = 0x03, state = State::Configuration)]
 pub struct AcknowledgeFinishConfigurationPacket;
 
-pub type BrandPacket = crate::clientbound::configuration::BrandPacket;
+/// The client's brand, on the `minecraft:brand` channel. The server receives
+/// this as a [`ConfigurationPluginMessagePacket`], so it is only used by
+/// clients.
+#[derive(Debug, Packet)]
+#[packet(id = 0x02, state = State::Configuration)]
+pub struct BrandPacket {
+    pub identifier: Identifier<5>,
+    pub brand: String<16>,
+}
+
+impl Default for BrandPacket {
+    fn default() -> Self {
+        Self {
+            identifier: Identifier::try_from("brand").expect("max 5 bytes"),
+            brand: String::try_from("vanilla").expect("max 7 bytes"),
+        }
+    }
+}
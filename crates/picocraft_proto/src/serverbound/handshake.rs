@@ -1,12 +1,17 @@
 use crate::prelude::*;
 
+/// BungeeCord's forwarded address contains the player's skin properties.
+pub const MAX_SERVER_ADDRESS_LENGTH: usize = 1536;
+
 #[derive(Debug, Packet)]
 #[packet(id = 0x00, state = State::Handshake)]
 pub struct HandshakePacket {
     /// See [minecraft.wiki's protocol version numbers page](https://minecraft.wiki/w/Minecraft_Wiki:Projects/wiki.vg_merge/Protocol_version_numbers).
     pub protocol_version: VarInt,
-    /// Hostname or IP which the client used to connect to the server.
-    pub server_address: String<255>,
+    /// Hostname or IP which the client used to connect to the server. When
+    /// behind a BungeeCord proxy this also contains the forwarded player info,
+    /// which is why it is so much longer than a hostname can be.
+    pub server_address: String<MAX_SERVER_ADDRESS_LENGTH>,
     /// Which port the client connected on. Default is 25565.
     pub server_port: UnsignedShort,
     /// What [`State`] the server should switch to next,
@@ -21,8 +26,15 @@ pub enum Intent {
     Transfer = 3,
 }
 
-#[derive(Debug)]
-pub struct LegacyPingPacket;
+/// A server list ping from a client before 1.7, which opens with `0xFE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegacyPingPacket {
+    /// A lone `0xFE`, from clients before 1.4. They only read the MOTD and
+    /// player counts from the reply.
+    Unversioned,
+    /// `0xFE 0x01`, from 1.4 to 1.6, which read the version from the reply too.
+    Versioned,
+}
 
 impl Packet for LegacyPingPacket {
     const ID: VarInt = VarInt(0xfe);
@@ -47,16 +59,18 @@ impl Decode for LegacyPingPacket {
     async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
         //To keep Decode implimentations consistent with other implementations, we have
         // already read the identifying byte (0xFE) before calling this function.
-        let mut buf = [0u8; 4];
-
-        buffer.read_exact(&mut buf).await?;
 
-        // We only check against the first 4 bytes, as the rest is not important and can
-        // just be ignored.
-        if buf == [0x01, 0xfa, 0x00, 0x0b] {
-            Ok(LegacyPingPacket)
-        } else {
-            Err(DecodeError::Custom)
+        // Clients before 1.4 send nothing after it. 1.4 and 1.5 clients only send
+        // `0xFE 0x01`, while 1.6 clients follow it up with a `MC|PingHost` plugin
+        // message, which the connection reads and drops as it is answered the same
+        // way.
+        match buffer.read_u8().await {
+            Ok(0x01) => Ok(LegacyPingPacket::Versioned),
+            Ok(_) => Err(DecodeError::Custom),
+            Err(embedded_io_async::ReadExactError::UnexpectedEof) => {
+                Ok(LegacyPingPacket::Unversioned)
+            }
+            Err(error) => Err(error.into()),
         }
     }
 }
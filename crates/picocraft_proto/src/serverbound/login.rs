@@ -1,12 +1,28 @@
 use crate::prelude::*;
 
 #[derive(Debug, Packet)]
-#[packet(id = 0x00)]
+#[packet(id = 0x00, state = State::Login)]
 pub struct LoginStartPacket {
     pub username: String<16>,
     pub uuid: UUID,
 }
 
 #[derive(Debug, Packet)]
-#[packet(id = 0x03)]
+#[packet(id = 0x03, state = State::Login)]
 pub struct LoginAcknowledgedPacket;
+
+/// Velocity's forwarded player info, with skin properties, is by far the
+/// largest response we expect.
+pub const MAX_LOGIN_PLUGIN_RESPONSE_SIZE: usize = 1792;
+
+/// `data` is [`None`] if the client didn't understand the request.
+#[derive(Debug, Packet)]
+#[packet(id = 0x02, state = State::Login)]
+pub struct LoginPluginResponsePacket {
+    pub message_id: VarInt,
+    pub data: PrefixedOptional<Array<UnsignedByte, MAX_LOGIN_PLUGIN_RESPONSE_SIZE>>,
+}
+
+#[derive(Debug, Packet)]
+#[packet(id = 0x04, state = State::Login)]
+pub struct LoginCookieResponsePacket(pub CookieResponse);
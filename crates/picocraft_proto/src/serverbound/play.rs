@@ -1,6 +1,14 @@
+mod chat;
+mod containers;
 mod player;
+mod player_action;
+mod use_item;
 
+pub use chat::*;
+pub use containers::*;
 pub use player::*;
+pub use player_action::*;
+pub use use_item::*;
 
 use crate::prelude::*;
 
@@ -12,6 +20,14 @@ pub struct ConfirmTeleportationPacket(pub VarInt);
 #[packet(id = 0x0c)]
 pub struct ClientTickEndPacket;
 
+#[derive(Debug, Packet)]
+#[packet(id = 0x14)]
+pub struct PlayCookieResponsePacket(pub CookieResponse);
+
+#[derive(Debug, Packet)]
+#[packet(id = 0x15)]
+pub struct PlayPluginMessagePacket(pub PluginMessage);
+
 #[derive(Debug, Packet)]
 #[packet(id = 0x1b)]
 pub struct ServerboundKeepAlivePacket {
@@ -0,0 +1,35 @@
+use crate::prelude::*;
+
+/// The longest message a player can type.
+pub const MAX_CHAT_MESSAGE_LENGTH: usize = 256;
+
+/// A message typed into chat. Messages are only signed when the client has a
+/// session with Mojang, which they don't on an offline mode server.
+#[derive(Debug, Packet)]
+#[packet(id = 0x08)]
+pub struct ChatMessagePacket {
+    pub message: String<MAX_CHAT_MESSAGE_LENGTH>,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: Long,
+    pub salt: Long,
+    pub signature: PrefixedOptional<[u8; 256]>,
+    pub message_count: VarInt,
+    /// Which of the last 20 messages have been seen, as a fixed bit set.
+    pub acknowledged: [u8; 3],
+    pub checksum: Byte,
+}
+
+impl ChatMessagePacket {
+    /// An unsigned message, which doesn't acknowledge any others.
+    pub fn unsigned(message: String<MAX_CHAT_MESSAGE_LENGTH>, timestamp: Long) -> Self {
+        Self {
+            message,
+            timestamp,
+            salt: 0,
+            signature: PrefixedOptional(None),
+            message_count: VarInt(0),
+            acknowledged: [0; 3],
+            checksum: 0,
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use crate::clientbound::PLAYER_INVENTORY_SLOTS;
+use crate::prelude::*;
+
+/// The player clicked `slot` of a container window, with `changed_slots` and
+/// `carried_item` being what their client predicts the click did.
+#[derive(Debug, Packet)]
+#[packet(id = 0x11)]
+pub struct ClickContainerPacket {
+    pub window_id: VarInt,
+    /// The last state ID the server sent for the window.
+    pub state_id: VarInt,
+    /// The slot clicked, or -999 for outside the window.
+    pub slot: Short,
+    pub button: Byte,
+    /// What kind of click it was, e.g. a shift click or a drag.
+    pub mode: VarInt,
+    pub changed_slots: PrefixedArray<(Short, HashedSlot), PLAYER_INVENTORY_SLOTS>,
+    /// The item left on the cursor.
+    pub carried_item: HashedSlot,
+}
@@ -0,0 +1,54 @@
+use crate::prelude::*;
+
+/// Digging, and a few other things done with the held item.
+#[derive(Debug, Packet)]
+#[packet(id = 0x28)]
+pub struct PlayerActionPacket {
+    pub status: PlayerActionStatus,
+    pub location: BlockPosition,
+    pub face: BlockFace,
+    /// Acknowledged with
+    /// [`AcknowledgeBlockChangePacket`](crate::clientbound::AcknowledgeBlockChangePacket)
+    /// once the server has dealt with the action.
+    pub sequence: VarInt,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+#[protocol(value = VarInt)]
+pub enum PlayerActionStatus {
+    StartedDigging = 0,
+    CancelledDigging = 1,
+    FinishedDigging = 2,
+    DropItemStack = 3,
+    DropItem = 4,
+    /// Also finishing eating, and other uses of the held item.
+    ShootArrow = 5,
+    SwapItemInHand = 6,
+}
+
+/// The side of a block which was clicked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+#[protocol(value = UnsignedByte)]
+pub enum BlockFace {
+    Bottom = 0,
+    Top = 1,
+    North = 2,
+    South = 3,
+    West = 4,
+    East = 5,
+}
+
+impl BlockFace {
+    /// The direction the face points in, as an offset to the neighbouring
+    /// block.
+    pub fn offset(&self) -> (i32, i32, i32) {
+        match self {
+            Self::Bottom => (0, -1, 0),
+            Self::Top => (0, 1, 0),
+            Self::North => (0, 0, -1),
+            Self::South => (0, 0, 1),
+            Self::West => (-1, 0, 0),
+            Self::East => (1, 0, 0),
+        }
+    }
+}
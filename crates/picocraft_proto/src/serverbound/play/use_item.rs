@@ -0,0 +1,73 @@
+use crate::prelude::*;
+use crate::serverbound::BlockFace;
+
+/// The hotbar slot (0 to 8) the player has selected.
+#[derive(Debug, Packet)]
+#[packet(id = 0x34)]
+pub struct SetHeldItemPacket {
+    pub slot: Short,
+}
+
+/// A player in creative mode put `clicked_item` in `slot` of their inventory,
+/// or took it out if the slot is empty. Slots 36 to 44 are the hotbar.
+#[derive(Debug, Packet)]
+#[packet(id = 0x37)]
+pub struct SetCreativeModeSlotPacket {
+    pub slot: Short,
+    pub clicked_item: Slot,
+}
+
+/// The player used the item in `hand` on the `face` of the block at
+/// `location`, which places it if it is a block.
+#[derive(Debug, Packet)]
+#[packet(id = 0x3f)]
+pub struct UseItemOnPacket {
+    pub hand: Hand,
+    pub location: BlockPosition,
+    pub face: ClickedFace,
+    /// Where on the face was clicked, from 0 to 1 along each axis.
+    pub cursor_x: Float,
+    pub cursor_y: Float,
+    pub cursor_z: Float,
+    /// Whether the player's head is inside a block.
+    pub inside_block: Boolean,
+    pub world_border_hit: Boolean,
+    /// Acknowledged with
+    /// [`AcknowledgeBlockChangePacket`](crate::clientbound::AcknowledgeBlockChangePacket)
+    /// once the server has dealt with the placement.
+    pub sequence: VarInt,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+#[protocol(value = VarInt)]
+pub enum Hand {
+    MainHand = 0,
+    OffHand = 1,
+}
+
+/// A [`BlockFace`], which Use Item On sends as a VarInt rather than the byte
+/// Player Action uses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClickedFace(pub BlockFace);
+
+impl Encode for ClickedFace {
+    async fn encode<W: embedded_io_async::Write>(&self, buffer: W) -> Result<(), EncodeError> {
+        VarInt(self.0 as i32).encode(buffer).await
+    }
+}
+
+impl Decode for ClickedFace {
+    async fn decode<R: embedded_io_async::Read>(buffer: R) -> Result<Self, DecodeError> {
+        let face = match VarInt::decode(buffer).await?.0 {
+            0 => BlockFace::Bottom,
+            1 => BlockFace::Top,
+            2 => BlockFace::North,
+            3 => BlockFace::South,
+            4 => BlockFace::West,
+            5 => BlockFace::East,
+            _ => return Err(DecodeError::InvalidEnumValue),
+        };
+
+        Ok(Self(face))
+    }
+}
@@ -0,0 +1,431 @@
+//! Who may join the server: the whitelist, banned players and addresses, and
+//! operators. The lists are kept in memory for the login check, and saved to
+//! a [`Storage`] whenever they are changed with [`update`].
+//!
+//! Each list is stored under its own key, as a version byte followed by the
+//! entries in the protocol's own encoding, so UUIDs take 16 bytes and
+//! addresses 5 or 17.
+
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::mutex::Mutex;
+use picocraft_derive::{Decode, Encode};
+
+use crate::client::buffer::Buffer;
+use crate::prelude::*;
+use crate::storage::Storage;
+
+pub const MAX_WHITELIST: usize = 32;
+pub const MAX_BANNED_PLAYERS: usize = 16;
+pub const MAX_BANNED_IPS: usize = 16;
+pub const MAX_OPERATORS: usize = 8;
+pub const MAX_BAN_REASON_LENGTH: usize = 64;
+
+/// Bumped whenever the stored format of a list changes.
+const FORMAT_VERSION: u8 = 1;
+/// Room for the largest list, which is the banned players.
+const LIST_BUFFER_SIZE: usize = 2048;
+
+const WHITELIST_KEY: &str = "access/whitelist";
+const BANNED_PLAYERS_KEY: &str = "access/banned-players";
+const BANNED_IPS_KEY: &str = "access/banned-ips";
+const OPERATORS_KEY: &str = "access/operators";
+
+static ACCESS_LISTS: Mutex<CriticalSectionRawMutex, AccessLists> = Mutex::new(AccessLists::new());
+
+pub type BanReason = String<MAX_BAN_REASON_LENGTH>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct PlayerBan {
+    pub uuid: UUID,
+    /// Shown to the player when they are turned away. May be empty.
+    pub reason: BanReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct IpBan {
+    pub address: Address,
+    /// Shown to players from the address when they are turned away. May be
+    /// empty.
+    pub reason: BanReason,
+}
+
+/// An IP address, encoded as its version (4 or 6) followed by its octets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Address(pub IpAddr);
+
+impl Encode for Address {
+    async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
+        match self.0 {
+            IpAddr::V4(address) => {
+                4u8.encode(&mut buffer).await?;
+                buffer.write_all(&address.octets()).await?;
+            }
+            IpAddr::V6(address) => {
+                6u8.encode(&mut buffer).await?;
+                buffer.write_all(&address.octets()).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Decode for Address {
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        match u8::decode(&mut buffer).await? {
+            4 => Ok(Self(IpAddr::V4(Ipv4Addr::from(
+                <[u8; 4]>::decode(&mut buffer).await?,
+            )))),
+            6 => Ok(Self(IpAddr::V6(Ipv6Addr::from(
+                <[u8; 16]>::decode(&mut buffer).await?,
+            )))),
+            _ => Err(DecodeError::InvalidEnumValue),
+        }
+    }
+}
+
+/// Why a player may not join.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Denial {
+    Banned(BanReason),
+    IpBanned(BanReason),
+    NotWhitelisted,
+}
+
+impl core::fmt::Display for Denial {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Banned(reason) => write!(f, "banned ({reason})"),
+            Self::IpBanned(reason) => write!(f, "address banned ({reason})"),
+            Self::NotWhitelisted => f.write_str("not whitelisted"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AccessLists {
+    whitelist: PrefixedArray<UUID, MAX_WHITELIST>,
+    banned_players: PrefixedArray<PlayerBan, MAX_BANNED_PLAYERS>,
+    banned_ips: PrefixedArray<IpBan, MAX_BANNED_IPS>,
+    operators: PrefixedArray<UUID, MAX_OPERATORS>,
+}
+
+impl AccessLists {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            whitelist: PrefixedArray::new(),
+            banned_players: PrefixedArray::new(),
+            banned_ips: PrefixedArray::new(),
+            operators: PrefixedArray::new(),
+        }
+    }
+
+    /// Checks a player against the lists in the same order as vanilla: bans,
+    /// then the whitelist (if `whitelist` is set), then address bans.
+    /// Operators don't need to be whitelisted.
+    pub fn check(&self, uuid: UUID, address: IpAddr, whitelist: bool) -> Result<(), Denial> {
+        if let Some(ban) = self.banned_players.iter().find(|ban| ban.uuid == uuid) {
+            return Err(Denial::Banned(ban.reason.clone()));
+        }
+
+        if whitelist && !self.is_whitelisted(uuid) && !self.is_operator(uuid) {
+            return Err(Denial::NotWhitelisted);
+        }
+
+        if let Some(ban) = self.banned_ips.iter().find(|ban| ban.address.0 == address) {
+            return Err(Denial::IpBanned(ban.reason.clone()));
+        }
+
+        Ok(())
+    }
+
+    pub fn whitelist(&self) -> &[UUID] {
+        &self.whitelist
+    }
+
+    pub fn is_whitelisted(&self, uuid: UUID) -> bool {
+        self.whitelist.contains(&uuid)
+    }
+
+    /// Returns whether the player wasn't already whitelisted.
+    pub fn add_to_whitelist(&mut self, uuid: UUID) -> Result<bool, AccessListError> {
+        insert(&mut self.whitelist, uuid)
+    }
+
+    /// Returns whether the player was whitelisted.
+    pub fn remove_from_whitelist(&mut self, uuid: UUID) -> bool {
+        remove(&mut self.whitelist, |entry| *entry == uuid)
+    }
+
+    pub fn banned_players(&self) -> &[PlayerBan] {
+        &self.banned_players
+    }
+
+    pub fn is_banned(&self, uuid: UUID) -> bool {
+        self.banned_players.iter().any(|ban| ban.uuid == uuid)
+    }
+
+    /// Bans a player, or changes the reason if they are already banned.
+    pub fn ban_player(&mut self, uuid: UUID, reason: &str) -> Result<(), AccessListError> {
+        let reason = BanReason::try_from(reason).map_err(|_| AccessListError::ReasonTooLong)?;
+
+        remove(&mut self.banned_players, |ban| ban.uuid == uuid);
+        insert(&mut self.banned_players, PlayerBan { uuid, reason })?;
+
+        Ok(())
+    }
+
+    /// Returns whether the player was banned.
+    pub fn pardon_player(&mut self, uuid: UUID) -> bool {
+        remove(&mut self.banned_players, |ban| ban.uuid == uuid)
+    }
+
+    pub fn banned_ips(&self) -> &[IpBan] {
+        &self.banned_ips
+    }
+
+    /// Bans an address, or changes the reason if it is already banned.
+    pub fn ban_ip(&mut self, address: IpAddr, reason: &str) -> Result<(), AccessListError> {
+        let reason = BanReason::try_from(reason).map_err(|_| AccessListError::ReasonTooLong)?;
+
+        remove(&mut self.banned_ips, |ban| ban.address.0 == address);
+        insert(
+            &mut self.banned_ips,
+            IpBan {
+                address: Address(address),
+                reason,
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns whether the address was banned.
+    pub fn pardon_ip(&mut self, address: IpAddr) -> bool {
+        remove(&mut self.banned_ips, |ban| ban.address.0 == address)
+    }
+
+    pub fn operators(&self) -> &[UUID] {
+        &self.operators
+    }
+
+    pub fn is_operator(&self, uuid: UUID) -> bool {
+        self.operators.contains(&uuid)
+    }
+
+    /// Returns whether the player wasn't already an operator.
+    pub fn op(&mut self, uuid: UUID) -> Result<bool, AccessListError> {
+        insert(&mut self.operators, uuid)
+    }
+
+    /// Returns whether the player was an operator.
+    pub fn deop(&mut self, uuid: UUID) -> bool {
+        remove(&mut self.operators, |entry| *entry == uuid)
+    }
+
+    /// Reads every list from `storage`. Lists which have never been saved are
+    /// empty.
+    pub async fn load<S: Storage>(storage: &mut S) -> Result<Self, StorageError> {
+        let mut lists = Self::new();
+
+        if let Some(whitelist) = load_list(storage, WHITELIST_KEY).await? {
+            lists.whitelist = whitelist;
+        }
+        if let Some(banned_players) = load_list(storage, BANNED_PLAYERS_KEY).await? {
+            lists.banned_players = banned_players;
+        }
+        if let Some(banned_ips) = load_list(storage, BANNED_IPS_KEY).await? {
+            lists.banned_ips = banned_ips;
+        }
+        if let Some(operators) = load_list(storage, OPERATORS_KEY).await? {
+            lists.operators = operators;
+        }
+
+        Ok(lists)
+    }
+
+    pub async fn save<S: Storage>(&self, storage: &mut S) -> Result<(), StorageError> {
+        save_list(storage, WHITELIST_KEY, &self.whitelist).await?;
+        save_list(storage, BANNED_PLAYERS_KEY, &self.banned_players).await?;
+        save_list(storage, BANNED_IPS_KEY, &self.banned_ips).await?;
+        save_list(storage, OPERATORS_KEY, &self.operators).await
+    }
+}
+
+impl Default for AccessLists {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Adds `entry` unless it is already there, returning whether it was added.
+fn insert<T: Encode + PartialEq, const N: usize>(
+    list: &mut PrefixedArray<T, N>,
+    entry: T,
+) -> Result<bool, AccessListError> {
+    if list.contains(&entry) {
+        return Ok(false);
+    }
+
+    list.push(entry).map_err(|_| AccessListError::Full)?;
+
+    Ok(true)
+}
+
+/// Removes the entries matching `f`, returning whether there were any.
+fn remove<T: Encode, const N: usize>(
+    list: &mut PrefixedArray<T, N>,
+    mut f: impl FnMut(&T) -> bool,
+) -> bool {
+    let len = list.len();
+
+    list.retain(|entry| !f(entry));
+
+    list.len() != len
+}
+
+async fn load_list<S: Storage, T: Decode>(
+    storage: &mut S,
+    key: &str,
+) -> Result<Option<T>, StorageError> {
+    let mut buf = [0u8; LIST_BUFFER_SIZE];
+
+    let Some(len) = storage.read(key, &mut buf).await? else {
+        return Ok(None);
+    };
+
+    let mut value = &buf[..len];
+
+    if u8::decode(&mut value).await? != FORMAT_VERSION {
+        return Err(StorageError::Corrupt);
+    }
+
+    Ok(Some(T::decode(&mut value).await?))
+}
+
+async fn save_list<S: Storage, T: Encode>(
+    storage: &mut S,
+    key: &str,
+    list: &T,
+) -> Result<(), StorageError> {
+    let mut buf = Buffer::<LIST_BUFFER_SIZE>::new();
+
+    FORMAT_VERSION.encode(&mut buf).await?;
+    list.encode(&mut buf).await?;
+
+    storage.write(key, &buf).await
+}
+
+/// Replaces the lists in memory with those in `storage`, which should be
+/// done once before accepting connections.
+pub async fn load<S: Storage>(storage: &mut S) -> Result<(), StorageError> {
+    let lists = AccessLists::load(storage).await?;
+
+    *ACCESS_LISTS.lock().await = lists;
+
+    Ok(())
+}
+
+/// Changes the lists in memory with `change`, then saves them to `storage`.
+///
+/// ```ignore
+/// access::update(&mut storage, |lists| lists.ban_player(uuid, "Griefing")).await??;
+/// ```
+pub async fn update<S: Storage, R>(
+    storage: &mut S,
+    change: impl FnOnce(&mut AccessLists) -> R,
+) -> Result<R, StorageError> {
+    let mut lists = ACCESS_LISTS.lock().await;
+
+    let result = change(&mut lists);
+    lists.save(storage).await?;
+
+    Ok(result)
+}
+
+/// Checks a joining player against the lists in memory. See
+/// [`AccessLists::check`].
+pub async fn check(uuid: UUID, address: IpAddr, whitelist: bool) -> Result<(), Denial> {
+    ACCESS_LISTS.lock().await.check(uuid, address, whitelist)
+}
+
+pub async fn is_operator(uuid: UUID) -> bool {
+    ACCESS_LISTS.lock().await.is_operator(uuid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::memory::MemoryStorage;
+
+    const LOCALHOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    #[test]
+    fn lists_survive_a_round_trip_through_storage() {
+        let alice = UUID::from_u128(1);
+        let bob = UUID::from_u128(2);
+        let carol = UUID::from_u128(3);
+
+        let mut lists = AccessLists::new();
+        assert!(lists.add_to_whitelist(alice).expect("room"));
+        assert!(!lists.add_to_whitelist(alice).expect("room"));
+        lists.ban_player(bob, "Griefing").expect("room");
+        lists
+            .ban_ip(IpAddr::V6(Ipv6Addr::LOCALHOST), "")
+            .expect("room");
+        lists.ban_ip(LOCALHOST, "Spam").expect("room");
+        assert!(lists.op(carol).expect("room"));
+
+        let mut storage = MemoryStorage::default();
+
+        let loaded = embassy_futures::block_on(async {
+            lists.save(&mut storage).await.expect("saves");
+            AccessLists::load(&mut storage).await.expect("loads")
+        });
+
+        assert_eq!(loaded.whitelist(), &[alice]);
+        assert_eq!(loaded.banned_players(), lists.banned_players());
+        assert_eq!(loaded.banned_ips(), lists.banned_ips());
+        assert_eq!(loaded.operators(), &[carol]);
+    }
+
+    #[test]
+    fn bans_come_before_the_whitelist() {
+        let alice = UUID::from_u128(1);
+        let operator = UUID::from_u128(2);
+        let stranger = UUID::from_u128(3);
+        let elsewhere = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+
+        let mut lists = AccessLists::new();
+        lists.add_to_whitelist(alice).expect("room");
+        lists.op(operator).expect("room");
+        lists.ban_ip(LOCALHOST, "Spam").expect("room");
+
+        assert_eq!(lists.check(alice, elsewhere, true), Ok(()));
+        assert_eq!(lists.check(operator, elsewhere, true), Ok(()));
+        assert_eq!(
+            lists.check(stranger, elsewhere, true),
+            Err(Denial::NotWhitelisted)
+        );
+        assert_eq!(lists.check(stranger, elsewhere, false), Ok(()));
+        assert_eq!(
+            lists.check(alice, LOCALHOST, true),
+            Err(Denial::IpBanned(
+                BanReason::try_from("Spam").expect("short")
+            ))
+        );
+
+        lists.ban_player(alice, "").expect("room");
+        assert_eq!(
+            lists.check(alice, elsewhere, true),
+            Err(Denial::Banned(BanReason::new()))
+        );
+
+        assert!(lists.pardon_player(alice));
+        assert!(!lists.pardon_player(alice));
+        assert_eq!(lists.check(alice, elsewhere, true), Ok(()));
+    }
+}
@@ -1,12 +1,16 @@
 use core::cell::RefCell;
+use core::sync::atomic::AtomicUsize;
 
+use embassy_sync::blocking_mutex;
 use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
 use embassy_sync::channel::{Channel, Receiver, Sender};
 use embassy_sync::mutex::Mutex;
 use embassy_sync::pubsub::{PubSubChannel, Publisher, Subscriber};
+use heapless::Vec;
 use picocraft_core::consts::MAX_PLAYERS;
 use picocraft_ecs::commands::WorldCommand;
 use picocraft_ecs::events::WorldEvent;
+use picocraft_proto::clientbound::{MAX_PLAYER_SAMPLE, PlayerSample};
 use rand_chacha::ChaCha8Rng;
 
 // pub const MAX_PLAYERS: usize = 8;
@@ -21,6 +25,21 @@ pub static COMMANDS: Channel<CriticalSectionRawMutex, WorldCommand, MAX_COMMANDS
 pub static EVENTS: PubSubChannel<CriticalSectionRawMutex, WorldEvent, MAX_EVENTS, MAX_PLAYERS, 1> =
     PubSubChannel::new();
 
+/// Number of players currently in the world. Updated by the tick loop, so it can
+/// be read by connections which haven't joined the world (e.g. status pings).
+pub static ONLINE_PLAYERS: AtomicUsize = AtomicUsize::new(0);
+
+/// The [`LoginId`](picocraft_ecs::world::LoginId) the next connection to join
+/// the world takes.
+pub static NEXT_LOGIN_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Players listed in the server list, kept up to date alongside
+/// [`ONLINE_PLAYERS`].
+pub static PLAYER_SAMPLE: blocking_mutex::Mutex<
+    CriticalSectionRawMutex,
+    RefCell<Vec<PlayerSample, MAX_PLAYER_SAMPLE>>,
+> = blocking_mutex::Mutex::new(RefCell::new(Vec::new()));
+
 pub type EventsSubscriber =
     Subscriber<'static, CriticalSectionRawMutex, WorldEvent, MAX_EVENTS, MAX_PLAYERS, 1>;
 
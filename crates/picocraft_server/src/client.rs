@@ -1,33 +1,71 @@
 pub mod buffer;
 pub mod connection;
-pub mod packet_socket;
+pub mod outbound;
 pub mod player;
 
 use connection::Connection;
 use embassy_futures::select::{Either3, select3};
 use embassy_sync::pubsub::WaitResult;
+use outbound::OutboundQueue;
 use picocraft_ecs::commands::WorldCommand;
 use picocraft_ecs::entity::EntityId;
-use picocraft_ecs::events::{Recipient, WorldEvent};
+use picocraft_ecs::events::{KickReason, Recipient, WorldEvent};
 use player::Player;
 
 use crate::channels::{COMMANDS, EventsSubscriber};
+use crate::handlers;
 use crate::prelude::*;
 
-pub struct Client {
-    pub connection: Connection,
+/// How often keep-alives are sent to clients in play, and how often they are
+/// checked for timeouts.
+pub const KEEP_ALIVE_INTERVAL: embassy_time::Duration = embassy_time::Duration::from_secs(10);
+
+/// Shown to clients which stop answering keep-alives, or answer them wrongly.
+pub const TIMED_OUT: TextComponent<'static> =
+    TextComponent::translatable("disconnect.timeout", &[]).fallback("Timed out");
+
+/// Shown to a player who logged in again somewhere else.
+pub const DUPLICATE_LOGIN: TextComponent<'static> =
+    TextComponent::translatable("multiplayer.disconnect.duplicate_login", &[])
+        .fallback("You logged in from another location");
+
+/// How long a kicked player is given to take their disconnect packet before
+/// their connection is closed anyway. Well under
+/// [`DUPLICATE_LOGIN_TIMEOUT_TICKS`](crate::systems::DUPLICATE_LOGIN_TIMEOUT_TICKS),
+/// so the login which kicked them isn't turned away.
+pub const KICK_TIMEOUT: embassy_time::Duration = embassy_time::Duration::from_secs(2);
+
+/// What a kicked player's disconnect screen says.
+fn kick_message(reason: KickReason) -> TextComponent<'static> {
+    match reason {
+        KickReason::DuplicateLogin => DUPLICATE_LOGIN,
+    }
+}
+
+pub struct Client<T> {
+    pub connection: Connection<T>,
     pub player: Player,
     system_rng: &'static SystemRng,
     pub server_config: &'static ServerConfig,
     pub terrain: &'static picocraft_terrain::Terrain,
     pub events: Option<EventsSubscriber>,
+    /// Events taken off `events` which haven't been sent to the client yet.
+    pub outbound: OutboundQueue,
     pub entity_id: Option<EntityId>,
+    /// The message ID of the login plugin request sent for Velocity forwarding,
+    /// while we wait for the proxy's response.
+    pub login_plugin_message_id: Option<VarInt>,
+    /// Whether Login Success has been sent, so the client may acknowledge it.
+    pub login_succeeded: bool,
+    /// The ID and send time of the last keep-alive, until the client answers
+    /// it.
+    pub pending_keep_alive: Option<(Long, embassy_time::Instant)>,
 }
 
 #[allow(unused)]
-impl Client {
+impl<T: Transport> Client<T> {
     pub fn new(
-        socket: tokio::net::TcpStream,
+        socket: T,
         system_rng: &'static SystemRng,
         server_config: &'static ServerConfig,
         terrain: &'static picocraft_terrain::Terrain,
@@ -40,15 +78,19 @@ impl Client {
             server_config,
             terrain,
             events: None,
+            outbound: OutboundQueue::new(),
             entity_id: None,
+            login_plugin_message_id: None,
+            login_succeeded: false,
+            pending_keep_alive: None,
         }
     }
 
-    pub async fn system_random<T>(&self) -> T
+    pub async fn system_random<R>(&self) -> R
     where
-        rand::distr::StandardUniform: rand::distr::Distribution<T>,
+        rand::distr::StandardUniform: rand::distr::Distribution<R>,
     {
-        self.system_rng.lock().await.borrow_mut().random::<T>()
+        self.system_rng.lock().await.borrow_mut().random::<R>()
     }
 
     pub fn server_config(&self) -> &'_ ServerConfig {
@@ -82,20 +124,15 @@ impl Client {
                 uuid,
                 position,
                 rotation,
+                ..
             } => {
-                // assert!(
-                //     self.uuid() != uuid,
-                //     "Client should not receive PlayerJoined event for itself."
-                // );
-
-                //TODO this occasionally happens when leaving and rejoining very quickly
+                // A second login for our UUID waits for us to be kicked and
+                // leave before it joins, so we'd only see this if we kept going
+                // after being kicked.
                 if self.uuid() == uuid {
-                    error!(
-                        "Received PlayerJoined event for self. Player: {} [{}]",
-                        username, uuid
-                    );
+                    debug!("Ignoring PlayerJoined event for self. Player: {username} [{uuid}]");
 
-                    return Err(PacketError::Unknown);
+                    return Ok(());
                 }
 
                 let player_info_update =
@@ -193,6 +230,34 @@ impl Client {
 
                 self.encode_packet(&head_rotation).await?;
             }
+            WorldEvent::PlayerTeleported {
+                player_id,
+                position,
+                rotation,
+                on_ground,
+            } => {
+                let teleport_entity = clientbound::TeleportEntityPacket {
+                    entity_id: player_id.protocol_id(),
+                    x: position.protocol_x(),
+                    y: position.protocol_y(),
+                    z: position.protocol_z(),
+                    velocity_x: 0.0,
+                    velocity_y: 0.0,
+                    velocity_z: 0.0,
+                    yaw: rotation.yaw,
+                    pitch: rotation.pitch,
+                    on_ground,
+                };
+
+                self.encode_packet(&teleport_entity).await?;
+
+                let head_rotation = clientbound::SetHeadRotationPacket {
+                    entity_id: player_id.protocol_id(),
+                    head_yaw: rotation.protocol_yaw(),
+                };
+
+                self.encode_packet(&head_rotation).await?;
+            }
             WorldEvent::ExistingPlayer {
                 recipient,
                 player_id,
@@ -218,7 +283,102 @@ impl Client {
 
                 self.encode_packet(&spawn_entity).await?;
             }
-            _ => todo!(),
+            WorldEvent::TransferPlayer { host, port, .. } => {
+                self.transfer(&host, port).await?;
+            }
+            WorldEvent::PlayerLatencyUpdated { uuid, latency, .. } => {
+                let player_info_update =
+                    clientbound::PlayerInfoUpdatePacket::<1>::update_latency(uuid, VarInt(latency));
+
+                self.encode_packet(&player_info_update).await?;
+            }
+            WorldEvent::PluginMessage { channel, data, .. } => {
+                self.send_plugin_message(channel, &data).await?;
+            }
+            WorldEvent::BlockBroken { position, .. } => {
+                let block_update = clientbound::BlockUpdatePacket {
+                    location: position,
+                    block_state: VarInt(picocraft_terrain::terrain::blocks::Block::Air as i32),
+                };
+
+                self.encode_packet(&block_update).await?;
+            }
+            WorldEvent::BlockPlaced {
+                position,
+                block_state,
+                ..
+            } => {
+                let block_update = clientbound::BlockUpdatePacket {
+                    location: position,
+                    block_state,
+                };
+
+                self.encode_packet(&block_update).await?;
+            }
+            WorldEvent::BlockReverted {
+                position,
+                block_state,
+                ..
+            } => {
+                let block_update = clientbound::BlockUpdatePacket {
+                    location: position,
+                    block_state,
+                };
+
+                self.encode_packet(&block_update).await?;
+            }
+            WorldEvent::BlockChangesAcknowledged { sequence, .. } => {
+                self.encode_packet(&clientbound::AcknowledgeBlockChangePacket { sequence })
+                    .await?;
+            }
+            WorldEvent::InventoryContent { inventory, .. } => {
+                self.encode_packet(&clientbound::SetContainerContentPacket {
+                    window_id: VarInt(0),
+                    state_id: VarInt(0),
+                    slot_data: PrefixedArray::from_vec(Vec::from_array(inventory.slots)),
+                    carried_item: ItemStack::EMPTY,
+                })
+                .await?;
+
+                self.encode_packet(&clientbound::SetHeldItemPacket {
+                    slot: VarInt(i32::from(inventory.selected)),
+                })
+                .await?;
+            }
+            WorldEvent::PlayerKicked { reason, .. } => {
+                info!(
+                    "Kicking player {} [{}]: {reason:?}",
+                    self.username(),
+                    self.uuid()
+                );
+
+                // The old session may be on a dead connection which won't take
+                // the disconnect packet, so it is closed either way, letting a
+                // new login waiting on it join.
+                let _ =
+                    embassy_time::with_timeout(KICK_TIMEOUT, self.disconnect(kick_message(reason)))
+                        .await;
+
+                return Err(PacketError::ConnectionClosed);
+            }
+            // Only a login still waiting to join can be rejected, and that's
+            // handled while it waits.
+            WorldEvent::JoinRejected { .. } => {}
+            WorldEvent::ChatMessage {
+                username, message, ..
+            } => {
+                let args = [
+                    TextComponent::text(&username),
+                    TextComponent::text(&message),
+                ];
+                let chat = clientbound::SystemChatPacket {
+                    content: TextComponent::translatable("chat.type.text", &args),
+                    overlay: false,
+                };
+
+                self.encode_packet(&chat).await?;
+            }
+            event => debug!("Ignoring {event:?}"),
         };
 
         Ok(())
@@ -230,46 +390,32 @@ impl Client {
             self.connection.remote_endpoint()
         );
 
-        //TODO We need a generic timer implemation that works with either tokio or
-        // embassy
-        let mut ticker = tokio::time::interval(core::time::Duration::from_secs(10));
-        let _ = ticker.tick().await; // advance to next tick
-        // let mut ticker = Ticker::every(embassy_time::Duration::from_secs(15));
+        let mut ticker = embassy_time::Ticker::every(KEEP_ALIVE_INTERVAL);
 
         loop {
-            // This method may lose packets if both a packet is received and 15 seconds have
-            // elapsed since the last keep-alive, but thats a good enough tradeoff for now.
+            // This method may lose packets if both a packet is received and a keep-alive is
+            // due, but thats a good enough tradeoff for now.
             let res = match select3(
                 self.connection.read_packet(),
                 Self::next_event(&mut self.events),
-                ticker.tick(),
+                ticker.next(),
             )
             .await
             {
+                // A client sending lots of packets would never get to the events branch, so
+                // catch up on events after each packet too.
                 Either3::First(Ok((packet_length, packet_id))) => {
-                    self.process_packet(packet_length, packet_id).await
+                    match self.process_packet(packet_length, packet_id).await {
+                        Ok(()) => self.sync_events().await,
+                        Err(e) => Err(e),
+                    }
                 }
                 Either3::First(Err(e)) => Err(e),
 
-                Either3::Second(WaitResult::Message(event)) => {
-                    self.handle_event(event).await?;
-
-                    Ok(())
-                }
-                Either3::Second(WaitResult::Lagged(skipped)) => {
-                    error!(
-                        "Client {} [{}] has fallen behind and skipped {} events.",
-                        self.username(),
-                        self.uuid(),
-                        skipped
-                    );
-                    Err(PacketError::ConnectionClosed)
-                }
+                Either3::Second(result) => self.receive_event(result).await,
                 Either3::Third(_) => {
                     if self.state() == State::Play {
-                        let keep_alive =
-                            clientbound::KeepAlivePacket::new(self.system_random().await);
-                        self.encode_packet(&keep_alive).await
+                        self.check_keep_alive().await
                     } else {
                         warn!(
                             "Client timed out in state {:?}: {} [{}]",
@@ -320,6 +466,13 @@ impl Client {
 
                     return Ok(());
                 }
+                Err(PacketError::InvalidState(state)) => {
+                    warn!(
+                        "Tried to send a packet in state {state:?} to player: {} [{}]",
+                        self.username(),
+                        self.uuid()
+                    );
+                }
                 Err(PacketError::Unknown) => {
                     warn!(
                         "Unknown error processing packet for player: {} [{}]",
@@ -384,123 +537,153 @@ impl Client {
         packet_length: VarInt,
         packet_id: VarInt,
     ) -> Result<(), PacketError> {
-        use serverbound::*;
+        if packet_id == connection::SKIPPED_PACKET_ID {
+            return Ok(());
+        }
 
-        match self.state() {
-            State::Handshake => match packet_id {
-                HandshakePacket::ID => {
-                    let packet =
-                        HandshakePacket::decode(&mut self.connection.rx_buf.as_slice()).await?;
+        if !handlers::dispatch(self, packet_id).await? {
+            // Play has far more packets than we handle, so anything we don't know about
+            // is skipped. The body has already been read, so nothing is left behind.
+            if self.state() != State::Play {
+                return Err(PacketError::InvalidPacket(*packet_id, self.state()));
+            }
 
-                    HandshakePacket::handle(packet, self).await?;
-                }
-                _ => {
-                    return Err(PacketError::InvalidPacket(*packet_id, self.state()));
-                }
-            },
-            State::Status => match packet_id {
-                StatusRequestPacket::ID => {
-                    let packet =
-                        StatusRequestPacket::decode(&mut self.connection.rx_buf.as_slice()).await?;
+            debug!(
+                "Skipping unhandled packet {:#04x} ({packet_length} bytes) from {} [{}]",
+                *packet_id,
+                self.username(),
+                self.uuid()
+            );
+        }
 
-                    StatusRequestPacket::handle(packet, self).await?;
-                }
-                PingRequestPacket::ID => {
-                    let packet =
-                        PingRequestPacket::decode(&mut self.connection.rx_buf.as_slice()).await?;
+        // should maybe be self.connection.flush()
+        self.connection.socket.flush().await?;
 
-                    PingRequestPacket::handle(packet, self).await?;
-                }
-                _ => {
-                    return Err(PacketError::InvalidPacket(*packet_id, self.state()));
-                }
-            },
-            State::Login => match packet_id {
-                LoginStartPacket::ID => {
-                    let packet =
-                        LoginStartPacket::decode(&mut self.connection.rx_buf.as_slice()).await?;
+        Ok(())
+    }
 
-                    LoginStartPacket::handle(packet, self).await?;
-                }
-                LoginAcknowledgedPacket::ID => {
-                    let packet =
-                        LoginAcknowledgedPacket::decode(&mut self.connection.rx_buf.as_slice())
-                            .await?;
+    /// Handles an event received from the world, along with any others that are
+    /// already waiting.
+    async fn receive_event(&mut self, result: WaitResult<WorldEvent>) -> Result<(), PacketError> {
+        match result {
+            WaitResult::Message(event) => self.queue_event(event)?,
+            WaitResult::Lagged(skipped) => self.resync(skipped).await,
+        }
 
-                    LoginAcknowledgedPacket::handle(packet, self).await?;
-                }
-                _ => {
-                    return Err(PacketError::InvalidPacket(*packet_id, self.state()));
-                }
-            },
-            State::Configuration => match packet_id {
-                ClientInformationPacket::ID => {
-                    let packet =
-                        ClientInformationPacket::decode(&mut self.connection.rx_buf.as_slice())
-                            .await?;
-
-                    ClientInformationPacket::handle(packet, self).await?;
-                }
-                AcknowledgeFinishConfigurationPacket::ID => {
-                    let packet = AcknowledgeFinishConfigurationPacket::decode(
-                        &mut self.connection.rx_buf.as_slice(),
-                    )
-                    .await?;
+        self.sync_events().await
+    }
 
-                    AcknowledgeFinishConfigurationPacket::handle(packet, self).await?;
-                }
-                _ => {
-                    return Err(PacketError::InvalidPacket(*packet_id, self.state()));
-                }
-            },
-            State::Play => match packet_id {
-                ConfirmTeleportationPacket::ID => {
-                    let packet =
-                        ConfirmTeleportationPacket::decode(&mut self.connection.rx_buf.as_slice())
-                            .await?;
-
-                    ConfirmTeleportationPacket::handle(packet, self).await?;
-                }
-                ClientTickEndPacket::ID => {
-                    let packet =
-                        ClientTickEndPacket::decode(&mut self.connection.rx_buf.as_slice()).await?;
+    /// Takes every waiting event off the world's channel and sends them to the
+    /// client.
+    async fn sync_events(&mut self) -> Result<(), PacketError> {
+        self.pump_events().await?;
+        self.flush_outbound().await
+    }
 
-                    ClientTickEndPacket::handle(packet, self).await?;
-                }
-                SetPlayerPositionPacket::ID => {
-                    let packet =
-                        SetPlayerPositionPacket::decode(&mut self.connection.rx_buf.as_slice())
-                            .await?;
-                    SetPlayerPositionPacket::handle(packet, self).await?;
-                }
-                SetPlayerRotationPacket::ID => {
-                    let packet =
-                        SetPlayerRotationPacket::decode(&mut self.connection.rx_buf.as_slice())
-                            .await?;
-                    SetPlayerRotationPacket::handle(packet, self).await?;
-                }
-                SetPlayerPositionAndRotationPacket::ID => {
-                    let packet = SetPlayerPositionAndRotationPacket::decode(
-                        &mut self.connection.rx_buf.as_slice(),
-                    )
-                    .await?;
-                    SetPlayerPositionAndRotationPacket::handle(packet, self).await?;
-                }
-                ServerboundKeepAlivePacket::ID => {
-                    let packet =
-                        ServerboundKeepAlivePacket::decode(&mut self.connection.rx_buf.as_slice())
-                            .await?;
+    /// Moves every event waiting on the world's channel into the outbound
+    /// queue, without blocking. Call this during long runs of packets (e.g.
+    /// chunks) so the client doesn't fall behind the channel.
+    pub(crate) async fn pump_events(&mut self) -> Result<(), PacketError> {
+        while let Some(result) = self
+            .events
+            .as_mut()
+            .and_then(|events| events.try_next_message())
+        {
+            match result {
+                WaitResult::Message(event) => self.queue_event(event)?,
+                WaitResult::Lagged(skipped) => self.resync(skipped).await,
+            }
 
-                    ServerboundKeepAlivePacket::handle(packet, self).await?;
-                }
-                _ => {
-                    return Err(PacketError::InvalidPacket(*packet_id, self.state()));
-                }
-            },
+            // The channel can hold more events than the queue, so send what's
+            // queued rather than overflowing it.
+            if self.outbound.is_full() {
+                self.flush_outbound().await?;
+            }
         }
 
-        // should maybe be self.connection.flush()
-        self.connection.socket.flush().await?;
+        Ok(())
+    }
+
+    /// Sends every queued event to the client.
+    pub(crate) async fn flush_outbound(&mut self) -> Result<(), PacketError> {
+        while let Some(event) = self.outbound.pop() {
+            self.handle_event(event).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Queues an event to be sent to the client, if it is meant for it.
+    pub(crate) fn queue_event(&mut self, event: WorldEvent) -> Result<(), PacketError> {
+        let should_receive_event = match event.recipient() {
+            Recipient::All => true,
+            Recipient::Player(id) => Some(id) == self.entity_id,
+            Recipient::AllExcept(id) => Some(id) != self.entity_id,
+        };
+
+        if !should_receive_event {
+            return Ok(());
+        }
+
+        if self.outbound.push(event).is_err() {
+            // The queue is sent as soon as it fills up, so this only happens if
+            // something queued events without sending them.
+            warn!(
+                "Client {} [{}] has too many events waiting to be sent.",
+                self.username(),
+                self.uuid()
+            );
+
+            return Err(PacketError::ConnectionClosed);
+        }
+
+        Ok(())
+    }
+
+    /// Events were missed, so the client's view of other players may be out of
+    /// date. Ask the world to send everyone's positions again.
+    async fn resync(&mut self, skipped: u64) {
+        warn!(
+            "Client {} [{}] has fallen behind and skipped {} events.",
+            self.username(),
+            self.uuid(),
+            skipped
+        );
+
+        if let Some(player_id) = self.entity_id {
+            COMMANDS
+                .send(WorldCommand::ResyncPlayer { player_id })
+                .await;
+        }
+    }
+
+    /// Sends a keep-alive, or disconnects the client if it didn't answer the
+    /// last one in time.
+    async fn check_keep_alive(&mut self) -> Result<(), PacketError> {
+        match self.pending_keep_alive {
+            Some((_, sent))
+                if u128::from(sent.elapsed().as_micros())
+                    >= self.server_config.keep_alive_timeout.as_micros() =>
+            {
+                warn!(
+                    "Client didn't answer keep-alive in time: {} [{}]",
+                    self.username(),
+                    self.uuid()
+                );
+                self.disconnect(TIMED_OUT).await
+            }
+            // Still waiting on the last one.
+            Some(_) => Ok(()),
+            None => self.send_keep_alive().await,
+        }
+    }
+
+    async fn send_keep_alive(&mut self) -> Result<(), PacketError> {
+        let keep_alive = clientbound::KeepAlivePacket::new(self.system_random().await);
+
+        self.encode_packet(&keep_alive).await?;
+
+        self.pending_keep_alive = Some((keep_alive.id(), embassy_time::Instant::now()));
 
         Ok(())
     }
@@ -509,6 +692,135 @@ impl Client {
         self.connection.encode_packet(packet).await
     }
 
+    /// Shows `reason` on the client's disconnect screen and closes the
+    /// connection, so this always returns
+    /// [`PacketError::ConnectionClosed`] unless sending fails.
+    pub async fn disconnect(&mut self, reason: TextComponent<'_>) -> Result<(), PacketError> {
+        match self.state() {
+            State::Login => {
+                self.encode_packet(&clientbound::LoginDisconnectPacket::new(reason))
+                    .await?;
+            }
+            State::Configuration => {
+                self.encode_packet(&clientbound::ConfigurationDisconnectPacket { reason })
+                    .await?;
+            }
+            State::Play => {
+                self.encode_packet(&clientbound::PlayDisconnectPacket { reason })
+                    .await?;
+            }
+            // There's no way to tell the client why before logging in.
+            State::Handshake | State::Status => {}
+        }
+
+        Err(PacketError::ConnectionClosed)
+    }
+
+    /// Sends the player to another server. The client disconnects straight
+    /// away, and reconnects to `host:port` with the transfer intent.
+    pub async fn transfer(&mut self, host: &str, port: u16) -> Result<(), PacketError> {
+        let transfer = Transfer {
+            host: String::try_from(host).map_err(|_| EncodeError::TooLong)?,
+            port: VarInt(port.into()),
+        };
+
+        info!(
+            "Transferring player {} [{}] to {host}:{port}",
+            self.username(),
+            self.uuid()
+        );
+
+        match self.state() {
+            State::Configuration => {
+                self.encode_packet(&clientbound::ConfigurationTransferPacket(transfer))
+                    .await
+            }
+            State::Play => {
+                self.encode_packet(&clientbound::PlayTransferPacket(transfer))
+                    .await
+            }
+            state => Err(PacketError::InvalidState(state)),
+        }
+    }
+
+    /// Stores a cookie on the client, which is kept when it is transferred to
+    /// another server.
+    pub async fn store_cookie(
+        &mut self,
+        key: CookieKey,
+        payload: &[u8],
+    ) -> Result<(), PacketError> {
+        let store_cookie = StoreCookie {
+            key,
+            payload: PrefixedArray::from_vec(
+                Vec::from_slice(payload).map_err(|_| EncodeError::TooLong)?,
+            ),
+        };
+
+        match self.state() {
+            State::Configuration => {
+                self.encode_packet(&clientbound::ConfigurationStoreCookiePacket(store_cookie))
+                    .await
+            }
+            State::Play => {
+                self.encode_packet(&clientbound::PlayStoreCookiePacket(store_cookie))
+                    .await
+            }
+            state => Err(PacketError::InvalidState(state)),
+        }
+    }
+
+    /// Sends `data` to the client on a plugin channel. Only clients with a mod
+    /// listening on `channel` will see it.
+    pub async fn send_plugin_message(
+        &mut self,
+        channel: PluginChannel,
+        data: &[u8],
+    ) -> Result<(), PacketError> {
+        let plugin_message = PluginMessage {
+            channel,
+            data: Array::from_vec(Vec::from_slice(data).map_err(|_| EncodeError::TooLong)?),
+        };
+
+        match self.state() {
+            State::Configuration => {
+                self.encode_packet(&clientbound::ConfigurationPluginMessagePacket(
+                    plugin_message,
+                ))
+                .await
+            }
+            State::Play => {
+                self.encode_packet(&clientbound::PlayPluginMessagePacket(plugin_message))
+                    .await
+            }
+            state => Err(PacketError::InvalidState(state)),
+        }
+    }
+
+    /// Asks the client for a cookie. The response arrives later, and can then
+    /// be read with [`Player::cookie`].
+    pub async fn request_cookie(&mut self, key: CookieKey) -> Result<(), PacketError> {
+        let cookie_request = CookieRequest { key };
+
+        match self.state() {
+            State::Login => {
+                self.encode_packet(&clientbound::LoginCookieRequestPacket(cookie_request))
+                    .await
+            }
+            State::Configuration => {
+                self.encode_packet(&clientbound::ConfigurationCookieRequestPacket(
+                    cookie_request,
+                ))
+                .await
+            }
+            State::Play => {
+                self.encode_packet(&clientbound::PlayCookieRequestPacket(cookie_request))
+                    .await
+            }
+            state => Err(PacketError::InvalidState(state)),
+        }
+    }
+
     pub(crate) fn username(&self) -> &heapless::String<16> {
         self.player.username()
     }
@@ -525,3 +837,225 @@ impl Client {
         self.connection.set_state(state);
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use core::cell::RefCell;
+
+    use embassy_sync::mutex::Mutex;
+    use picocraft_ecs::components::{Position, Rotation};
+    use picocraft_terrain::TerrainBuilder;
+    use rand_chacha::ChaCha8Rng;
+    use rand_chacha::rand_core::SeedableRng;
+
+    use super::*;
+    use crate::channels::EVENTS;
+    use crate::client::outbound::MAX_OUTBOUND_EVENTS;
+    use crate::transport::memory::{MemoryListener, MemoryPipe, MemoryTransport};
+
+    /// Connects a client over its own in-memory pipe, returning the server's
+    /// client and the other end of the connection.
+    pub(crate) async fn connect(
+        config: ServerConfig,
+    ) -> (Client<MemoryTransport<'static>>, MemoryTransport<'static>) {
+        let listener: &'static MemoryListener = Box::leak(Box::new(MemoryListener::new()));
+        let pipe = Box::leak(Box::new(MemoryPipe::new()));
+        let config = Box::leak(Box::new(config));
+        let system_rng = Box::leak(Box::new(Mutex::new(RefCell::new(
+            ChaCha8Rng::seed_from_u64(0),
+        ))));
+        let terrain = Box::leak(Box::new(TerrainBuilder::new(config.seed).build()));
+
+        let remote = listener.connect(pipe).await;
+        let socket = (&*listener).accept().await.expect("listener is open");
+
+        (Client::new(socket, system_rng, config, terrain), remote)
+    }
+
+    /// Reads the next packet sent to `remote`, returning its ID and body.
+    pub(crate) async fn next_packet(
+        remote: &mut MemoryTransport<'static>,
+    ) -> (VarInt, std::vec::Vec<u8>) {
+        let length = VarInt::decode(&mut *remote).await.expect("a packet");
+        let packet_id = VarInt::decode(&mut *remote).await.expect("a packet");
+
+        let mut body = std::vec![0; *length as usize - packet_id.encoded_len()];
+        remote.read_exact(&mut body).await.expect("a whole packet");
+
+        (packet_id, body)
+    }
+
+    /// Reads the ID of the next packet sent to `remote`, skipping its body.
+    pub(crate) async fn next_packet_id(remote: &mut MemoryTransport<'static>) -> VarInt {
+        next_packet(remote).await.0
+    }
+
+    #[tokio::test]
+    async fn unanswered_keep_alives_time_out() {
+        let (mut client, mut remote) = connect(ServerConfig {
+            keep_alive_timeout: core::time::Duration::ZERO,
+            ..ServerConfig::default()
+        })
+        .await;
+        client.set_state(State::Play);
+
+        client
+            .check_keep_alive()
+            .await
+            .expect("a keep-alive is sent");
+        assert_eq!(
+            next_packet_id(&mut remote).await,
+            clientbound::KeepAlivePacket::ID
+        );
+
+        assert!(matches!(
+            client.check_keep_alive().await,
+            Err(PacketError::ConnectionClosed)
+        ));
+        assert_eq!(
+            next_packet_id(&mut remote).await,
+            clientbound::PlayDisconnectPacket::ID
+        );
+    }
+
+    #[tokio::test]
+    async fn keep_alives_are_given_time_to_be_answered() {
+        let (mut client, mut remote) = connect(ServerConfig::default()).await;
+        client.set_state(State::Play);
+
+        client
+            .check_keep_alive()
+            .await
+            .expect("a keep-alive is sent");
+        assert_eq!(
+            next_packet_id(&mut remote).await,
+            clientbound::KeepAlivePacket::ID
+        );
+
+        client.check_keep_alive().await.expect("still waiting");
+        assert!(client.pending_keep_alive.is_some());
+    }
+
+    #[tokio::test]
+    async fn mismatched_keep_alives_disconnect() {
+        let (mut client, mut remote) = connect(ServerConfig::default()).await;
+        client.set_state(State::Play);
+
+        client
+            .check_keep_alive()
+            .await
+            .expect("a keep-alive is sent");
+        assert_eq!(
+            next_packet_id(&mut remote).await,
+            clientbound::KeepAlivePacket::ID
+        );
+
+        let (id, _) = client.pending_keep_alive.expect("waiting for an answer");
+        let answer = serverbound::ServerboundKeepAlivePacket {
+            id: id.wrapping_add(1),
+        };
+
+        assert!(matches!(
+            answer.handle(&mut client).await,
+            Err(PacketError::ConnectionClosed)
+        ));
+        assert_eq!(
+            next_packet_id(&mut remote).await,
+            clientbound::PlayDisconnectPacket::ID
+        );
+    }
+
+    #[tokio::test]
+    async fn kicked_players_are_disconnected_even_if_they_stop_reading() {
+        let (mut client, _remote) = connect(ServerConfig::default()).await;
+        client.set_state(State::Play);
+        let player_id = EntityId::player(0);
+        client.entity_id = Some(player_id);
+
+        // Nothing is reading the other end, so fill the pipe up.
+        let mut full = false;
+        while !full {
+            full = embassy_time::with_timeout(
+                embassy_time::Duration::from_millis(10),
+                client.connection.socket.write_all(&[0; 256]),
+            )
+            .await
+            .is_err();
+        }
+
+        let kicked = client
+            .handle_event(WorldEvent::PlayerKicked {
+                recipient: player_id,
+                reason: KickReason::DuplicateLogin,
+            })
+            .await;
+
+        assert!(matches!(kicked, Err(PacketError::ConnectionClosed)));
+    }
+
+    #[tokio::test]
+    async fn events_are_sent_rather_than_overflowing_the_queue() {
+        let (mut client, mut remote) = connect(ServerConfig::default()).await;
+        client.set_state(State::Play);
+        client.events = Some(EVENTS.subscriber().expect("a subscriber is free"));
+
+        // As if the world kept going while chunks were being sent.
+        let publisher = EVENTS.immediate_publisher();
+        for index in 0..MAX_OUTBOUND_EVENTS + MAX_OUTBOUND_EVENTS / 2 {
+            publisher.publish_immediate(WorldEvent::PlayerLeft {
+                player_id: EntityId::player(index as u8),
+                uuid: UUID::from_u128(index as u128),
+            });
+        }
+
+        client.pump_events().await.expect("events are sent");
+        assert!(!client.outbound.is_full());
+
+        assert_eq!(
+            next_packet_id(&mut remote).await,
+            clientbound::PlayerInfoRemovePacket::ID
+        );
+        assert_eq!(
+            next_packet_id(&mut remote).await,
+            clientbound::RemoveEntitiesPacket::<1>::ID
+        );
+    }
+
+    #[tokio::test]
+    async fn far_moves_are_sent_as_teleports() {
+        let (mut client, mut remote) = connect(ServerConfig::default()).await;
+        client.set_state(State::Play);
+
+        client
+            .handle_event(WorldEvent::PlayerTeleported {
+                player_id: EntityId::player(1),
+                position: Position::new(100.0, 64.0, -100.0),
+                rotation: Rotation::new(90.0, 0.0),
+                on_ground: true,
+            })
+            .await
+            .expect("the teleport is sent");
+
+        assert_eq!(
+            next_packet_id(&mut remote).await,
+            clientbound::TeleportEntityPacket::ID
+        );
+        assert_eq!(
+            next_packet_id(&mut remote).await,
+            clientbound::SetHeadRotationPacket::ID
+        );
+    }
+
+    #[tokio::test]
+    async fn unhandled_events_are_ignored() {
+        let (mut client, _remote) = connect(ServerConfig::default()).await;
+        client.set_state(State::Play);
+
+        client
+            .handle_event(WorldEvent::WorldReady {
+                recipient: EntityId::player(1),
+            })
+            .await
+            .expect("the event is ignored");
+    }
+}
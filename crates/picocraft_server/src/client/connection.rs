@@ -1,27 +1,60 @@
-use core::net::SocketAddr;
+use core::net::{IpAddr, SocketAddr};
+
+use picocraft_core::compression::{DEFLATER_SIZE, Deflater, ZlibEncoder, inflate};
 
 use super::buffer::Buffer;
-use super::packet_socket::PacketSocket;
 use crate::prelude::*;
 
-pub struct Connection {
-    pub socket: PacketSocket,
+/// Returned by [`Connection::read_packet`] in place of a packet ID when a packet
+/// was too big to read, and has been skipped instead. No real packet uses it.
+pub const SKIPPED_PACKET_ID: VarInt = VarInt(-1);
+
+/// Compressed packets are held here so they only have to be compressed once.
+/// Chunks compress to well under this, so only unusually large packets don't
+/// fit.
+const COMPRESSED_BUFFER_SIZE: usize = 4096;
+
+/// Packets read are held here, decompressed. Anything bigger is skipped in
+/// play, and closes the connection otherwise.
+const RECEIVE_BUFFER_SIZE: usize = 2048;
+
+/// Roughly how much memory each [`Connection`] takes: both buffers, and the
+/// [`Deflater`] it compresses packets with, which is kept for the whole
+/// connection rather than made for each packet. About 14.3 KiB, on top of
+/// which `miniz_oxide`'s inflate state takes about 10 KiB while a compressed
+/// packet is being read.
+pub const CONNECTION_MEMORY: usize = RECEIVE_BUFFER_SIZE + COMPRESSED_BUFFER_SIZE + DEFLATER_SIZE;
+
+/// How long to wait for more of a legacy ping after each part. Clients before
+/// 1.4 send `0xFE` on its own, and 1.4 and 1.5 stop after `0xFE 0x01`, so there
+/// may be nothing more coming.
+const LEGACY_PING_TIMEOUT: embassy_time::Duration = embassy_time::Duration::from_millis(250);
+
+pub struct Connection<T> {
+    pub socket: T,
     remote_endpoint: SocketAddr,
-    pub rx_buf: Buffer<1024>,
+    pub rx_buf: Buffer<RECEIVE_BUFFER_SIZE>,
+    /// Holds a packet once compressed, until its length has been sent.
+    tx_buf: Buffer<COMPRESSED_BUFFER_SIZE>,
+    deflater: Deflater,
     state: State,
+    /// Packets at least this many bytes long are compressed. [`None`] until
+    /// Set Compression has been sent.
+    compression_threshold: Option<usize>,
 }
 
-impl Connection {
-    pub fn new(socket: tokio::net::TcpStream) -> Self {
-        let remote_endpoint = socket
-            .peer_addr()
-            .expect("should be able to get peer address of socket as it is already connected");
+impl<T: Transport> Connection<T> {
+    pub fn new(socket: T) -> Self {
+        let remote_endpoint = socket.remote_endpoint();
 
         Self {
-            socket: PacketSocket::new(socket),
+            socket,
             rx_buf: Buffer::new(),
+            tx_buf: Buffer::new(),
+            deflater: Deflater::new(),
             remote_endpoint,
             state: State::default(),
+            compression_threshold: None,
         }
     }
 
@@ -29,6 +62,12 @@ impl Connection {
         self.remote_endpoint
     }
 
+    /// Replaces the address the client appears to connect from, keeping the
+    /// port. Used when a proxy forwards the player's real address.
+    pub fn set_remote_address(&mut self, address: IpAddr) {
+        self.remote_endpoint.set_ip(address);
+    }
+
     pub fn state(&self) -> State {
         self.state
     }
@@ -37,6 +76,16 @@ impl Connection {
         self.state = state;
     }
 
+    pub fn compression_threshold(&self) -> Option<usize> {
+        self.compression_threshold
+    }
+
+    /// Switches to the compressed packet format. This must only be called
+    /// straight after Set Compression has been sent to the client.
+    pub fn set_compression(&mut self, threshold: Option<usize>) {
+        self.compression_threshold = threshold;
+    }
+
     //TODO This isn't a very elegant way to do this - having a "raw packet" type or
     // similar would be better.
     pub async fn read_packet(&mut self) -> Result<(VarInt, VarInt), PacketError> {
@@ -48,71 +97,249 @@ impl Connection {
             &self.state
         );
 
-        // if self.state != State::Play && self.is_legacy_ping().await? {
-        //     LegacyPingPacket::handle(LegacyPingPacket, self).await?;
-        //     self.socket.shutdown().await?;
-        //     return Ok(());
-        // }
+        // Legacy pings can only be the very first thing sent on a connection, which is
+        // also the only time peeking at the socket won't skip over buffered data.
+        if self.state == State::Handshake && self.is_legacy_ping().await? {
+            return Ok((VarInt(0), serverbound::LegacyPingPacket::ID));
+        }
 
         let packet_length = self.read_packet_length().await?;
 
-        let packet_id = VarInt::decode(&mut self.socket).await?;
+        // Packets too big for `rx_buf` can't be handled, but play has plenty of
+        // packets we don't care about anyway, so they are skipped rather than
+        // closing the connection.
+        if self.state == State::Play && *packet_length > self.rx_buf.capacity() as i32 {
+            debug!(
+                "Skipping oversized packet ({packet_length} bytes) from {}",
+                self.remote_endpoint()
+            );
+
+            self.discard(*packet_length as usize).await?;
+
+            return Ok((VarInt(0), SKIPPED_PACKET_ID));
+        }
+
+        let (data_length, packet_id) = if self.compression_threshold.is_some() {
+            self.read_compressed_packet(packet_length).await?
+        } else {
+            let packet_id = VarInt::decode(&mut self.socket).await?;
+
+            self.read_packet_body(*packet_length as usize, packet_id)
+                .await?;
+
+            (packet_length, packet_id)
+        };
 
         trace!(
-            "Packet Length: {packet_length} - Packet ID: {:02x?}",
+            "Packet Length: {data_length} - Packet ID: {:02x?}",
             *packet_id
         );
 
-        self.read_packet_body(packet_length).await?;
-
-        Ok((packet_length, packet_id))
+        Ok((data_length, packet_id))
     }
+
     async fn read_packet_length(&mut self) -> Result<VarInt, PacketError> {
         let packet_length = VarInt::decode(&mut self.socket).await?;
 
-        if packet_length > MAX_PACKET_SIZE || *packet_length > self.rx_buf.capacity() as i32 {
+        if packet_length > MAX_PACKET_SIZE
+            || (*packet_length > self.rx_buf.capacity() as i32 && self.state != State::Play)
+            || *packet_length < 1
+        {
             return Err(PacketError::Decode(DecodeError::VarIntTooBig));
         }
 
         Ok(packet_length)
     }
 
-    async fn read_packet_body(&mut self, length: VarInt) -> Result<(), PacketError> {
-        //SAFETY: length has already been validated in read_packet_length
+    /// Reads the rest of a packet in the compressed format, returning the
+    /// uncompressed length and the packet ID. The packet body is left in
+    /// `rx_buf`.
+    async fn read_compressed_packet(
+        &mut self,
+        packet_length: VarInt,
+    ) -> Result<(VarInt, VarInt), PacketError> {
+        let data_length = VarInt::decode(&mut self.socket).await?;
+
+        let Some(remaining) = (*packet_length as usize).checked_sub(data_length.encoded_len())
+        else {
+            return Err(PacketError::Decode(DecodeError::VarIntTooSmall(
+                packet_length,
+            )));
+        };
+
+        // A data length of 0 means the packet was below the threshold, so was sent
+        // uncompressed.
+        if *data_length == 0 {
+            let packet_id = VarInt::decode(&mut self.socket).await?;
+
+            self.read_packet_body(remaining, packet_id).await?;
+
+            return Ok((VarInt(remaining as i32), packet_id));
+        }
+
+        // Like vanilla, packets which should have been sent uncompressed are
+        // refused.
+        if self
+            .compression_threshold
+            .is_some_and(|threshold| (*data_length as usize) < threshold)
+        {
+            return Err(PacketError::Decode(DecodeError::CompressedBelowThreshold));
+        }
+
+        if self.state == State::Play && *data_length > self.rx_buf.capacity() as i32 {
+            debug!(
+                "Skipping oversized packet ({data_length} bytes uncompressed) from {}",
+                self.remote_endpoint()
+            );
+
+            self.discard(remaining).await?;
+
+            return Ok((VarInt(0), SKIPPED_PACKET_ID));
+        }
+
+        if data_length > MAX_PACKET_SIZE
+            || *data_length > self.rx_buf.capacity() as i32
+            || *data_length < 1
+        {
+            return Err(PacketError::Decode(DecodeError::DecompressedTooLarge));
+        }
+
+        self.rx_buf
+            .resize_default(*data_length as usize)
+            .expect("data length has already been validated");
+
+        let length = inflate(&mut self.socket, remaining, &mut self.rx_buf).await?;
+
+        if length != *data_length as usize {
+            return Err(PacketError::Decode(DecodeError::InvalidCompressedData));
+        }
+
+        let packet_id = VarInt::decode(&mut self.rx_buf.as_slice()).await?;
+
+        // Shift the body to the front of the buffer so it matches the uncompressed
+        // format.
+        self.rx_buf.copy_within(packet_id.encoded_len().., 0);
+        self.rx_buf.truncate(length - packet_id.encoded_len());
+
+        Ok((data_length, packet_id))
+    }
+
+    /// Reads the packet body into `rx_buf`, where `length` includes the
+    /// already read packet ID.
+    async fn read_packet_body(
+        &mut self,
+        length: usize,
+        packet_id: VarInt,
+    ) -> Result<(), PacketError> {
+        let Some(body_length) = length.checked_sub(packet_id.encoded_len()) else {
+            return Err(PacketError::Decode(DecodeError::VarIntTooSmall(VarInt(
+                length as i32,
+            ))));
+        };
+
         self.rx_buf
-            .resize_default(*length as usize - 1)
+            .resize_default(body_length)
             .expect("length has already been validated in fn read_packet_length()");
 
-        if *length as u32 - 1 > 0 {
-            self.socket.read(&mut self.rx_buf).await?;
+        self.socket.read_exact(&mut self.rx_buf).await?;
+
+        Ok(())
+    }
+
+    /// Reads and throws away `length` bytes from the socket.
+    async fn discard(&mut self, mut length: usize) -> Result<(), PacketError> {
+        while length > 0 {
+            let chunk = length.min(self.rx_buf.capacity());
+
+            self.rx_buf
+                .resize_default(chunk)
+                .expect("chunk is at most the buffer's capacity");
+
+            self.socket.read_exact(&mut self.rx_buf).await?;
+
+            length -= chunk;
         }
 
+        self.rx_buf.clear();
+
         Ok(())
     }
 
     pub(crate) async fn encode_packet<P: Packet>(&mut self, packet: &P) -> Result<(), PacketError> {
         trace!("Encoding packet: {packet}");
 
+        self.encode_frame(packet).await?;
+
+        trace!("Packet sent: {packet}");
+
+        Ok(())
+    }
+
+    /// Writes the length prefix (and compression header, if enabled) followed
+    /// by the packet itself.
+    async fn encode_frame<E: Encode>(&mut self, packet: &E) -> Result<(), PacketError> {
         let mut counting_writer = ByteCountWriter::new();
 
         packet.encode(&mut counting_writer).await?;
 
         let len = counting_writer.count;
 
-        VarInt(len as i32).encode(&mut self.socket).await?;
-
-        packet.encode(&mut self.socket).await?;
+        match self.compression_threshold {
+            None => {
+                VarInt(len as i32).encode(&mut self.socket).await?;
+
+                packet.encode(&mut self.socket).await?;
+            }
+            Some(threshold) if len < threshold => {
+                VarInt(len as i32 + 1).encode(&mut self.socket).await?;
+                VarInt(0).encode(&mut self.socket).await?;
+
+                packet.encode(&mut self.socket).await?;
+            }
+            Some(_) => {
+                let data_length = VarInt(len as i32);
+
+                self.tx_buf.clear();
+
+                let mut encoder = ZlibEncoder::new(&mut self.tx_buf, &mut self.deflater);
+                let compressed =
+                    packet.encode(&mut encoder).await.is_ok() && encoder.finish().await.is_ok();
+
+                if compressed {
+                    VarInt((data_length.encoded_len() + self.tx_buf.len()) as i32)
+                        .encode(&mut self.socket)
+                        .await?;
+                    data_length.encode(&mut self.socket).await?;
+
+                    self.socket.write_all(&self.tx_buf).await?;
+                } else {
+                    // Too big to hold once compressed, so it is compressed twice
+                    // instead: once to find its length, which has to be sent
+                    // first, and again to send it.
+                    let mut encoder = ZlibEncoder::new(ByteCountWriter::new(), &mut self.deflater);
+                    packet.encode(&mut encoder).await?;
+                    let Ok(counting_writer) = encoder.finish().await;
+
+                    VarInt((data_length.encoded_len() + counting_writer.count) as i32)
+                        .encode(&mut self.socket)
+                        .await?;
+                    data_length.encode(&mut self.socket).await?;
+
+                    let mut encoder = ZlibEncoder::new(&mut self.socket, &mut self.deflater);
+                    packet.encode(&mut encoder).await?;
+                    encoder.finish().await?;
+                }
+            }
+        }
 
         self.socket.flush().await?;
 
-        trace!("Packet sent: {packet}");
-
         Ok(())
     }
 
-    /// Handle legacy ping packets (0xFE) sent by old Minecraft clients (pre
+    /// Detect legacy ping packets (0xFE) sent by old Minecraft clients (pre
     /// 1.7, before the netty rewrite). Returns true if a legacy ping was
-    /// handled, false otherwise.
+    /// read, false otherwise.
     pub async fn is_legacy_ping(&mut self) -> Result<bool, PacketError> {
         use picocraft_core::byteorder::ReadBytesExt;
 
@@ -131,10 +358,179 @@ impl Connection {
         // packets.
         let _ = self.socket.read_u8().await?;
 
-        let _ = serverbound::LegacyPingPacket::decode(&mut self.socket)
-            .await
-            .inspect_err(|e| warn!("couldn't decode legacy ping packet: {e:?}"))?;
+        // Clients before 1.4 send nothing more, which leaves `rx_buf` empty for
+        // LegacyPingPacket::decode.
+        let Ok(payload) =
+            embassy_time::with_timeout(LEGACY_PING_TIMEOUT, self.socket.read_u8()).await
+        else {
+            return Ok(true);
+        };
+        let payload = payload?;
+
+        // 1.6 clients follow up with an `MC|PingHost` plugin message. Closing the
+        // connection with it unread would reset it, losing the kick.
+        if payload == 0x01
+            && let Ok(id) =
+                embassy_time::with_timeout(LEGACY_PING_TIMEOUT, self.socket.read_u8()).await
+            && id? == 0xfa
+        {
+            self.discard_ping_host().await?;
+        }
+
+        // The payload byte is left in `rx_buf`, ready for LegacyPingPacket::decode.
+        let _ = self.rx_buf.push(payload);
 
         Ok(true)
     }
+
+    /// Reads the rest of a 1.6 `MC|PingHost` plugin message, after its ID.
+    async fn discard_ping_host(&mut self) -> Result<(), PacketError> {
+        let mut length = [0u8; 2];
+
+        // The channel name's length is in UTF-16 code units.
+        self.socket.read_exact(&mut length).await?;
+        self.discard(usize::from(u16::from_be_bytes(length)) * 2)
+            .await?;
+
+        self.socket.read_exact(&mut length).await?;
+        self.discard(usize::from(u16::from_be_bytes(length))).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use picocraft_proto::plugin_message::{MAX_PLUGIN_MESSAGE_SIZE, PluginChannel, PluginMessage};
+
+    use super::*;
+    use crate::transport::memory::{MemoryListener, MemoryPipe};
+
+    #[test]
+    fn compressed_packets_are_read_back_whole() {
+        static LISTENER: MemoryListener<'static> = MemoryListener::new();
+        static PIPE: MemoryPipe = MemoryPipe::new();
+
+        embassy_futures::block_on(async {
+            let sender = LISTENER.connect(&PIPE).await;
+            let receiver = (&LISTENER).accept().await.expect("a client is waiting");
+
+            let mut sender = Connection::new(sender);
+            let mut receiver = Connection::new(receiver);
+
+            for connection in [&mut sender, &mut receiver] {
+                connection.set_state(State::Play);
+                connection.set_compression(Some(256));
+            }
+
+            let data: Vec<u8, MAX_PLUGIN_MESSAGE_SIZE> = (0..1000).map(|i| (i % 7) as u8).collect();
+            let packet = clientbound::PlayPluginMessagePacket(PluginMessage {
+                channel: PluginChannel::try_from("picocraft:test").expect("valid channel"),
+                data: Array::from_vec(data.clone()),
+            });
+
+            sender.encode_packet(&packet).await.expect("pipe is open");
+
+            let (_, packet_id) = receiver.read_packet().await.expect("valid frame");
+            assert_eq!(packet_id, clientbound::PlayPluginMessagePacket::ID);
+
+            let message = PluginMessage::decode(receiver.rx_buf.as_slice())
+                .await
+                .expect("valid plugin message");
+            assert_eq!(message.data.as_slice(), data.as_slice());
+        });
+    }
+
+    #[test]
+    fn compressed_packets_below_the_threshold_are_refused() {
+        static LISTENER: MemoryListener<'static> = MemoryListener::new();
+        static PIPE: MemoryPipe = MemoryPipe::new();
+
+        embassy_futures::block_on(async {
+            let mut sender = LISTENER.connect(&PIPE).await;
+            let receiver = (&LISTENER).accept().await.expect("a client is waiting");
+
+            let mut receiver = Connection::new(receiver);
+            receiver.set_state(State::Play);
+            receiver.set_compression(Some(256));
+
+            // A packet claiming to be 10 bytes once inflated.
+            sender
+                .write_all(&[3, 10, 0x78, 0x01])
+                .await
+                .expect("pipe is open");
+
+            assert!(matches!(
+                receiver.read_packet().await,
+                Err(PacketError::Decode(DecodeError::CompressedBelowThreshold))
+            ));
+        });
+    }
+
+    #[test]
+    fn lone_legacy_pings_are_answered() {
+        static LISTENER: MemoryListener<'static> = MemoryListener::new();
+        static PIPE: MemoryPipe = MemoryPipe::new();
+
+        embassy_futures::block_on(async {
+            let mut sender = LISTENER.connect(&PIPE).await;
+            let receiver = (&LISTENER).accept().await.expect("a client is waiting");
+
+            let mut receiver = Connection::new(receiver);
+
+            // Clients before 1.4 send nothing more, and wait for the reply.
+            sender.write_all(&[0xfe]).await.expect("pipe is open");
+
+            let (_, packet_id) = receiver.read_packet().await.expect("a legacy ping");
+            assert_eq!(packet_id, serverbound::LegacyPingPacket::ID);
+
+            let ping = serverbound::LegacyPingPacket::decode(receiver.rx_buf.as_slice())
+                .await
+                .expect("valid legacy ping");
+            assert_eq!(ping, serverbound::LegacyPingPacket::Unversioned);
+        });
+    }
+
+    #[test]
+    fn legacy_ping_hosts_are_read_before_answering() {
+        static LISTENER: MemoryListener<'static> = MemoryListener::new();
+        static PIPE: MemoryPipe = MemoryPipe::new();
+
+        embassy_futures::block_on(async {
+            let mut sender = LISTENER.connect(&PIPE).await;
+            let receiver = (&LISTENER).accept().await.expect("a client is waiting");
+
+            let mut receiver = Connection::new(receiver);
+
+            // As sent by 1.6: the ping, then `MC|PingHost` with the protocol version,
+            // host and port.
+            let mut ping = std::vec![0xfe, 0x01, 0xfa];
+            let channel: std::vec::Vec<u16> = "MC|PingHost".encode_utf16().collect();
+            ping.extend_from_slice(&(channel.len() as u16).to_be_bytes());
+            ping.extend(channel.iter().flat_map(|unit| unit.to_be_bytes()));
+            let host: std::vec::Vec<u16> = "localhost".encode_utf16().collect();
+            ping.extend_from_slice(&(7 + host.len() as u16 * 2).to_be_bytes());
+            ping.push(78);
+            ping.extend_from_slice(&(host.len() as u16).to_be_bytes());
+            ping.extend(host.iter().flat_map(|unit| unit.to_be_bytes()));
+            ping.extend_from_slice(&25565i32.to_be_bytes());
+            // Anything after the ping, to check that none of it was left unread.
+            ping.push(0x42);
+            sender.write_all(&ping).await.expect("pipe is open");
+
+            let (_, packet_id) = receiver.read_packet().await.expect("a legacy ping");
+            assert_eq!(packet_id, serverbound::LegacyPingPacket::ID);
+
+            let ping = serverbound::LegacyPingPacket::decode(receiver.rx_buf.as_slice())
+                .await
+                .expect("valid legacy ping");
+            assert_eq!(ping, serverbound::LegacyPingPacket::Versioned);
+
+            let mut next = [0u8; 1];
+            receiver
+                .socket
+                .read_exact(&mut next)
+                .await
+                .expect("pipe is open");
+            assert_eq!(next, [0x42]);
+        });
+    }
 }
@@ -0,0 +1,326 @@
+use heapless::Deque;
+use picocraft_ecs::components::{Position, Rotation};
+use picocraft_ecs::entity::EntityId;
+use picocraft_ecs::events::WorldEvent;
+
+use crate::prelude::*;
+
+/// How many events can be waiting to be sent to a single client. Movement is
+/// coalesced, and the queue is sent whenever it fills up, so this only bounds
+/// how many events are taken off the channel between sends.
+pub const MAX_OUTBOUND_EVENTS: usize = 32;
+
+/// Events waiting to be sent to a client. Taking events off the shared
+/// [`EVENTS`](crate::channels::EVENTS) channel as soon as possible stops a
+/// client which is busy (e.g. receiving chunks) from lagging behind it, while
+/// only the latest movement of each player is kept.
+#[derive(Default)]
+pub struct OutboundQueue<const N: usize = MAX_OUTBOUND_EVENTS> {
+    events: Deque<WorldEvent, N>,
+}
+
+impl<const N: usize> OutboundQueue<N> {
+    pub fn new() -> Self {
+        Self {
+            events: Deque::new(),
+        }
+    }
+
+    /// Queues an event, merging it into the last queued event for the same
+    /// player if they are both movement. Gives the event back if the queue is
+    /// full.
+    #[allow(clippy::result_large_err)]
+    pub fn push(&mut self, event: WorldEvent) -> Result<(), WorldEvent> {
+        let event = match subject(&event) {
+            Some(player_id) => match self
+                .events
+                .iter_mut()
+                .rev()
+                .find(|queued| subject(queued) == Some(player_id))
+            {
+                Some(queued) => match coalesce(queued, event) {
+                    Ok(()) => return Ok(()),
+                    Err(event) => event,
+                },
+                None => event,
+            },
+            None => event,
+        };
+
+        self.events.push_back(event)
+    }
+
+    pub fn pop(&mut self) -> Option<WorldEvent> {
+        self.events.pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.events.is_full()
+    }
+}
+
+/// The player an event is about, for events whose order matters relative to
+/// other events about the same player.
+fn subject(event: &WorldEvent) -> Option<EntityId> {
+    match event {
+        WorldEvent::PlayerJoined { player_id, .. }
+        | WorldEvent::PlayerLeft { player_id, .. }
+        | WorldEvent::ExistingPlayer { player_id, .. }
+        | WorldEvent::PlayerMoved { player_id, .. }
+        | WorldEvent::PlayerRotated { player_id, .. }
+        | WorldEvent::PlayerMovedAndRotated { player_id, .. }
+        | WorldEvent::PlayerTeleported { player_id, .. } => Some(*player_id),
+        _ => None,
+    }
+}
+
+/// A relative movement, taken apart so it can be merged with another.
+struct Movement {
+    delta_position: Option<DeltaPosition>,
+    rotation: Option<Rotation>,
+    on_ground: bool,
+    against_wall: bool,
+}
+
+impl Movement {
+    fn from_event(event: &WorldEvent) -> Option<Self> {
+        match *event {
+            WorldEvent::PlayerMoved {
+                delta_position,
+                on_ground,
+                against_wall,
+                ..
+            } => Some(Self {
+                delta_position: Some(delta_position),
+                rotation: None,
+                on_ground,
+                against_wall,
+            }),
+            WorldEvent::PlayerRotated {
+                rotation,
+                on_ground,
+                against_wall,
+                ..
+            } => Some(Self {
+                delta_position: None,
+                rotation: Some(rotation),
+                on_ground,
+                against_wall,
+            }),
+            WorldEvent::PlayerMovedAndRotated {
+                delta_position,
+                rotation,
+                on_ground,
+                against_wall,
+                ..
+            } => Some(Self {
+                delta_position: Some(delta_position),
+                rotation: Some(rotation),
+                on_ground,
+                against_wall,
+            }),
+            _ => None,
+        }
+    }
+
+    fn into_event(self, player_id: EntityId) -> WorldEvent {
+        let Self {
+            delta_position,
+            rotation,
+            on_ground,
+            against_wall,
+        } = self;
+
+        match (delta_position, rotation) {
+            (Some(delta_position), Some(rotation)) => WorldEvent::PlayerMovedAndRotated {
+                player_id,
+                delta_position,
+                rotation,
+                on_ground,
+                against_wall,
+            },
+            (Some(delta_position), None) => WorldEvent::PlayerMoved {
+                player_id,
+                delta_position,
+                on_ground,
+                against_wall,
+            },
+            (None, rotation) => WorldEvent::PlayerRotated {
+                player_id,
+                rotation: rotation.unwrap_or_default(),
+                on_ground,
+                against_wall,
+            },
+        }
+    }
+}
+
+/// Merges `newer` into `older`, which must be about the same player. Gives
+/// `newer` back if they can't be merged.
+#[allow(clippy::result_large_err)]
+fn coalesce(older: &mut WorldEvent, newer: WorldEvent) -> Result<(), WorldEvent> {
+    // A teleport is absolute, so it replaces any movement before it.
+    if let WorldEvent::PlayerTeleported { .. } = newer
+        && (Movement::from_event(older).is_some()
+            || matches!(older, WorldEvent::PlayerTeleported { .. }))
+    {
+        *older = newer;
+        return Ok(());
+    }
+
+    let Some(newer_movement) = Movement::from_event(&newer) else {
+        return Err(newer);
+    };
+
+    if let WorldEvent::PlayerTeleported {
+        position,
+        rotation,
+        on_ground,
+        ..
+    } = older
+    {
+        if let Some(delta) = newer_movement.delta_position {
+            *position = Position {
+                x: position.x + f32::from(delta.dx) / 4096.0,
+                y: position.y + f32::from(delta.dy) / 4096.0,
+                z: position.z + f32::from(delta.dz) / 4096.0,
+            };
+        }
+
+        if let Some(new_rotation) = newer_movement.rotation {
+            *rotation = new_rotation;
+        }
+
+        *on_ground = newer_movement.on_ground;
+
+        return Ok(());
+    }
+
+    let Some(older_movement) = Movement::from_event(older) else {
+        return Err(newer);
+    };
+
+    let delta_position = match (older_movement.delta_position, newer_movement.delta_position) {
+        (Some(a), Some(b)) => {
+            // Deltas are limited to 8 blocks, past that they have to be sent separately.
+            let (Some(dx), Some(dy), Some(dz)) = (
+                a.dx.checked_add(b.dx),
+                a.dy.checked_add(b.dy),
+                a.dz.checked_add(b.dz),
+            ) else {
+                return Err(newer);
+            };
+
+            Some(DeltaPosition { dx, dy, dz })
+        }
+        (a, b) => a.or(b),
+    };
+
+    let player_id = subject(older).expect("movement events always have a subject");
+
+    *older = Movement {
+        delta_position,
+        rotation: newer_movement.rotation.or(older_movement.rotation),
+        on_ground: newer_movement.on_ground,
+        against_wall: newer_movement.against_wall,
+    }
+    .into_event(player_id);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moved(player_id: EntityId, dx: i16) -> WorldEvent {
+        WorldEvent::PlayerMoved {
+            player_id,
+            delta_position: DeltaPosition { dx, dy: 0, dz: 0 },
+            on_ground: true,
+            against_wall: false,
+        }
+    }
+
+    #[test]
+    fn movement_is_coalesced_per_player() {
+        let mut queue = OutboundQueue::<4>::new();
+        let a = EntityId::player(0);
+        let b = EntityId::player(1);
+
+        queue.push(moved(a, 100)).expect("queue has room");
+        queue.push(moved(b, 5)).expect("queue has room");
+        queue.push(moved(a, 200)).expect("queue has room");
+        queue
+            .push(WorldEvent::PlayerRotated {
+                player_id: a,
+                rotation: Rotation::default(),
+                on_ground: false,
+                against_wall: false,
+            })
+            .expect("queue has room");
+
+        assert_eq!(queue.len(), 2);
+
+        let Some(WorldEvent::PlayerMovedAndRotated {
+            player_id,
+            delta_position,
+            on_ground,
+            ..
+        }) = queue.pop()
+        else {
+            panic!("expected a merged movement");
+        };
+
+        assert_eq!(player_id, a);
+        assert_eq!(delta_position.dx, 300);
+        assert!(!on_ground);
+    }
+
+    #[test]
+    fn overflowing_deltas_are_kept_separate() {
+        let mut queue = OutboundQueue::<4>::new();
+        let a = EntityId::player(0);
+
+        queue.push(moved(a, i16::MAX)).expect("queue has room");
+        queue.push(moved(a, 1)).expect("queue has room");
+
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn movement_is_not_merged_across_a_respawn() {
+        let mut queue = OutboundQueue::<4>::new();
+        let a = EntityId::player(0);
+
+        queue.push(moved(a, 1)).expect("queue has room");
+        queue
+            .push(WorldEvent::PlayerLeft {
+                player_id: a,
+                uuid: UUID::nil(),
+            })
+            .expect("queue has room");
+        queue.push(moved(a, 1)).expect("queue has room");
+
+        assert_eq!(queue.len(), 3);
+    }
+
+    #[test]
+    fn full_queue_gives_the_event_back() {
+        let mut queue = OutboundQueue::<1>::new();
+
+        queue
+            .push(moved(EntityId::player(0), 1))
+            .expect("queue has room");
+
+        assert!(queue.push(moved(EntityId::player(1), 1)).is_err());
+    }
+}
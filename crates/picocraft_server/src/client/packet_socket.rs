@@ -1,69 +0,0 @@
-use crate::prelude::*;
-
-#[derive(Debug)]
-pub struct PacketSocket {
-    // pub socket: tokio::io::BufWriter<tokio::net::TcpStream>,
-    pub inner: tokio::io::BufStream<tokio::net::TcpStream>,
-}
-
-impl PacketSocket {
-    pub fn new(socket: tokio::net::TcpStream) -> Self {
-        Self {
-            inner: tokio::io::BufStream::with_capacity(1024, 1024, socket),
-        }
-    }
-
-    pub async fn peek(&mut self, buf: &mut [u8]) -> Result<usize, SocketError> {
-        Ok(self
-            .inner
-            .get_mut()
-            .peek(buf)
-            .await
-            .inspect_err(|e| warn!("failed to peek at upcoming data: {e}"))?)
-    }
-
-    pub async fn shutdown(&mut self) -> Result<(), SocketError> {
-        use tokio::io::AsyncWriteExt;
-
-        Ok(self
-            .inner
-            .shutdown()
-            .await
-            .inspect_err(|e| warn!("failed to send shutdown command: {e}"))?)
-    }
-
-    pub async fn readable(&self) -> Result<(), SocketError> {
-        self.inner
-            .get_ref()
-            .readable()
-            .await
-            .inspect_err(|e| warn!("socket not readable: {e}"))
-            .map_err(|_| SocketError::NotReadable)
-    }
-}
-
-impl embedded_io_async::Read for PacketSocket {
-    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
-        use tokio::io::AsyncReadExt;
-
-        Ok(self.inner.read(buf).await?)
-    }
-}
-
-impl embedded_io_async::Write for PacketSocket {
-    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
-        use tokio::io::AsyncWriteExt;
-
-        Ok(self.inner.write(buf).await?)
-    }
-
-    async fn flush(&mut self) -> Result<(), Self::Error> {
-        use tokio::io::AsyncWriteExt;
-
-        Ok(self.inner.flush().await?)
-    }
-}
-
-impl embedded_io::ErrorType for PacketSocket {
-    type Error = SocketError;
-}
@@ -1,24 +1,29 @@
 use picocraft_proto::serverbound::ClientInformation;
 
+use crate::plugin_channels::MAX_BRAND_LENGTH;
 use crate::prelude::*;
 
+/// How many cookie responses are kept per player. Cookies are large, so only
+/// keep a couple around.
+const MAX_COOKIES: usize = 2;
+
 #[derive(Debug, Default, Clone)]
 pub struct Player {
     profile: GameProfile,
-    protocol_version: VarInt,
     client_info: ClientInformation,
+    /// Whether the player arrived via a Transfer packet from another server.
+    transferred: bool,
+    /// Whether a proxy has already forwarded this player's details.
+    forwarded: bool,
+    /// Smoothed keep-alive round trip time in milliseconds, once measured.
+    latency: Option<i32>,
+    cookies: Vec<CookieResponse, MAX_COOKIES>,
+    /// The client's mod loader or `vanilla`, if it has said.
+    brand: Option<String<MAX_BRAND_LENGTH>>,
 }
 
 #[allow(unused)]
 impl Player {
-    pub(crate) fn protocol_version(&self) -> VarInt {
-        self.protocol_version
-    }
-
-    pub(crate) fn set_protocol_version(&mut self, protocol_version: VarInt) {
-        self.protocol_version = protocol_version;
-    }
-
     pub(crate) fn set_username(&mut self, username: heapless::String<16>) {
         self.profile.set_username(username);
     }
@@ -27,6 +32,10 @@ impl Player {
         self.profile.set_uuid(uuid);
     }
 
+    pub(crate) fn set_properties(&mut self, properties: Properties<MAX_PROFILE_PROPERTIES>) {
+        self.profile.set_properties(properties);
+    }
+
     pub(crate) fn username(&self) -> &heapless::String<16> {
         self.profile.username()
     }
@@ -35,6 +44,14 @@ impl Player {
         self.profile.uuid()
     }
 
+    pub(crate) fn profile(&self) -> &GameProfile {
+        &self.profile
+    }
+
+    pub(crate) fn set_profile(&mut self, profile: GameProfile) {
+        self.profile = profile;
+    }
+
     pub(crate) fn client_info(&self) -> &ClientInformation {
         &self.client_info
     }
@@ -42,4 +59,69 @@ impl Player {
     pub(crate) fn set_client_info(&mut self, client_info: ClientInformation) {
         self.client_info = client_info;
     }
+
+    pub fn transferred(&self) -> bool {
+        self.transferred
+    }
+
+    pub(crate) fn set_transferred(&mut self, transferred: bool) {
+        self.transferred = transferred;
+    }
+
+    pub fn forwarded(&self) -> bool {
+        self.forwarded
+    }
+
+    pub(crate) fn set_forwarded(&mut self, forwarded: bool) {
+        self.forwarded = forwarded;
+    }
+
+    pub fn latency(&self) -> Option<i32> {
+        self.latency
+    }
+
+    /// Folds a new round trip measurement into the latency, weighting it the
+    /// same way as vanilla so a single slow keep-alive doesn't spike it.
+    pub(crate) fn update_latency(&mut self, round_trip: i32) -> i32 {
+        let latency = match self.latency {
+            Some(latency) => (latency * 3 + round_trip) / 4,
+            None => round_trip,
+        };
+
+        self.latency = Some(latency);
+
+        latency
+    }
+
+    /// The most recent response to a cookie request for `key`. The outer
+    /// [`None`] means no response has arrived (yet), while the inner one means
+    /// the client had nothing stored under `key`.
+    pub fn cookie(&self, key: &CookieKey) -> Option<Option<&[u8]>> {
+        self.cookies
+            .iter()
+            .find(|cookie| &cookie.key == key)
+            .map(|cookie| cookie.payload.0.as_ref().map(|payload| payload.as_slice()))
+    }
+
+    /// Records a cookie response, replacing any older response for the same
+    /// key. If there's no room left, the oldest response is forgotten.
+    pub(crate) fn insert_cookie(&mut self, cookie: CookieResponse) {
+        self.cookies.retain(|existing| existing.key != cookie.key);
+
+        if self.cookies.is_full() {
+            self.cookies.remove(0);
+        }
+
+        let _ = self.cookies.push(cookie);
+    }
+
+    /// Which client the player is using, e.g. `vanilla` or `fabric`, as sent
+    /// on the `minecraft:brand` plugin channel.
+    pub fn brand(&self) -> Option<&str> {
+        self.brand.as_deref()
+    }
+
+    pub(crate) fn set_brand(&mut self, brand: String<MAX_BRAND_LENGTH>) {
+        self.brand = Some(brand);
+    }
 }
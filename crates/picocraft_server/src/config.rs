@@ -1,6 +1,8 @@
 use core::net::Ipv4Addr;
 use core::str::FromStr;
+use core::time::Duration;
 
+use crate::plugin_channels::PluginChannels;
 use crate::prelude::*;
 
 #[derive(Debug)]
@@ -9,6 +11,56 @@ pub struct ServerConfig {
     pub address: Ipv4Addr,
     pub port: u16,
     pub motd: String<128>,
+    /// A 64x64 PNG shown next to the server in the server list.
+    pub favicon: Option<&'static [u8]>,
+    /// Packets at least this many bytes long are compressed. [`None`] disables
+    /// compression entirely.
+    pub compression_threshold: Option<u16>,
+    /// Whether clients sent here by another server's Transfer packet are
+    /// allowed to log in. Off by default, as in vanilla.
+    pub accepts_transfers: bool,
+    /// Whether only whitelisted players and operators may join.
+    pub whitelist: bool,
+    /// How player info is forwarded by a proxy in front of the server, if
+    /// there is one.
+    pub forwarding: PlayerInfoForwarding,
+    /// Whether the UUIDs a proxy forwards are used as they are, for proxies in
+    /// online mode. Otherwise every player gets the UUID vanilla's offline
+    /// mode derives from their name, so nobody can claim someone else's. This
+    /// is ignored without [`forwarding`](Self::forwarding), as the UUID would
+    /// come straight from the client.
+    pub trust_client_uuids: bool,
+    /// How long a client in play has to answer a keep-alive before it is
+    /// disconnected. Keep-alives are only checked every
+    /// [`KEEP_ALIVE_INTERVAL`](crate::client::KEEP_ALIVE_INTERVAL), so timeouts
+    /// are only that precise.
+    pub keep_alive_timeout: Duration,
+    /// Handlers for plugin messages sent by client mods.
+    pub plugin_channels: PluginChannels,
+}
+
+impl ServerConfig {
+    /// Whether players keep the UUID they log in with, rather than getting
+    /// their offline-mode one.
+    pub fn trusts_client_uuids(&self) -> bool {
+        self.trust_client_uuids && self.forwarding != PlayerInfoForwarding::None
+    }
+}
+
+/// The ways a proxy can forward a player's real address, UUID and skin to the
+/// server.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum PlayerInfoForwarding {
+    /// Players connect directly, or the proxy doesn't forward anything.
+    #[default]
+    None,
+    /// BungeeCord's legacy forwarding through the handshake's server address.
+    /// It is unauthenticated, so the server must only be reachable through the
+    /// proxy.
+    BungeeCord,
+    /// Velocity's modern forwarding, signed with a secret shared with the
+    /// proxy.
+    Velocity { secret: String<128> },
 }
 
 impl Default for ServerConfig {
@@ -19,6 +71,14 @@ impl Default for ServerConfig {
             port: 25565,
             motd: heapless::String::from_str("A Picocraft Server!")
                 .expect("String is less than 256 bytes"),
+            favicon: None,
+            compression_threshold: Some(256),
+            accepts_transfers: false,
+            whitelist: false,
+            forwarding: PlayerInfoForwarding::None,
+            trust_client_uuids: false,
+            keep_alive_timeout: Duration::from_secs(30),
+            plugin_channels: PluginChannels::new(),
         }
     }
 }
@@ -6,6 +6,8 @@ use crate::prelude::*;
 pub enum PacketError {
     #[error("Invalid packet with ID {0:x?} in state {1:?}")]
     InvalidPacket(i32, State),
+    #[error("Packet can't be sent in state {0:?}")]
+    InvalidState(State),
     #[error("Connection closed")]
     ConnectionClosed,
     #[error("Unknown error")]
@@ -18,6 +20,74 @@ pub enum PacketError {
     Decode(#[from] DecodeError),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum ForwardingError {
+    #[error("forwarded player info is malformed")]
+    Malformed,
+    #[error("forwarded player info has an invalid signature")]
+    InvalidSignature,
+    #[error("unsupported forwarding version {0}")]
+    UnsupportedVersion(i32),
+}
+
+impl From<DecodeError> for ForwardingError {
+    fn from(_: DecodeError) -> Self {
+        Self::Malformed
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum PluginChannelError {
+    #[error("plugin channels must be `namespace:path` identifiers")]
+    InvalidChannel,
+    #[error("no room to register another plugin channel")]
+    TooManyChannels,
+    #[error("plugin message payload of {0} bytes is too large")]
+    PayloadTooLarge(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum AccessListError {
+    #[error("the list is full")]
+    Full,
+    #[error(
+        "ban reasons can be at most {} bytes",
+        crate::access::MAX_BAN_REASON_LENGTH
+    )]
+    ReasonTooLong,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum StorageError {
+    #[error(transparent)]
+    Io(#[from] embedded_io::ErrorKind),
+    #[error("storage keys must be `/`-separated lowercase names")]
+    InvalidKey,
+    #[error("value is too large to store or read")]
+    TooLarge,
+    #[error("stored value is corrupt")]
+    Corrupt,
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for StorageError {
+    fn from(e: std::io::Error) -> Self {
+        StorageError::Io(e.kind().into())
+    }
+}
+
+impl From<DecodeError> for StorageError {
+    fn from(_: DecodeError) -> Self {
+        Self::Corrupt
+    }
+}
+
+impl From<EncodeError> for StorageError {
+    fn from(_: EncodeError) -> Self {
+        Self::TooLarge
+    }
+}
+
 #[derive(Debug, Error, Clone, Copy)]
 pub enum SocketError {
     #[error(transparent)]
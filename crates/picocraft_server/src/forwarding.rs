@@ -0,0 +1,415 @@
+//! Player info forwarding from a proxy in front of the server. Without it,
+//! every player appears to connect from the proxy's address, and is trusted to
+//! send its own UUID.
+
+use core::net::IpAddr;
+use core::str::FromStr;
+
+use core_json::{ConstStack, Deserializer};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::prelude::*;
+
+/// Login plugin channel used by Velocity's modern forwarding.
+pub const VELOCITY_CHANNEL: &str = "velocity:player_info";
+/// The oldest (and simplest) version of Velocity's forwarding format. Newer
+/// versions only add chat signing keys, which we don't use.
+pub const VELOCITY_FORWARDING_VERSION: u8 = 1;
+
+const VELOCITY_SIGNATURE_LENGTH: usize = 32;
+
+/// The profile property holding the player's skin and cape.
+const TEXTURES_PROPERTY: &str = "textures";
+
+/// The player's details as seen by the proxy.
+#[derive(Debug)]
+pub struct ForwardedPlayer {
+    pub address: IpAddr,
+    /// BungeeCord doesn't forward the username, so it is left empty, and has to
+    /// come from Login Start instead.
+    pub profile: GameProfile,
+}
+
+/// Verifies and parses the response to a Velocity `player_info` login plugin
+/// request, which is an HMAC-SHA256 signature followed by the signed data.
+pub async fn parse_velocity(
+    secret: &[u8],
+    data: &[u8],
+) -> Result<ForwardedPlayer, ForwardingError> {
+    if data.len() < VELOCITY_SIGNATURE_LENGTH {
+        return Err(ForwardingError::Malformed);
+    }
+
+    let (signature, mut data) = data.split_at(VELOCITY_SIGNATURE_LENGTH);
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.verify_slice(signature)
+        .map_err(|_| ForwardingError::InvalidSignature)?;
+
+    let version = VarInt::decode(&mut data).await?;
+
+    if *version < 1 {
+        return Err(ForwardingError::UnsupportedVersion(*version));
+    }
+
+    let address = String::<64>::decode(&mut data).await?;
+    let address = IpAddr::from_str(&address).map_err(|_| ForwardingError::Malformed)?;
+
+    // The rest is laid out like a game profile, though it may have more
+    // properties than we keep. Anything after it is only sent for newer
+    // forwarding versions, which we never ask for.
+    let uuid = UUID::decode(&mut data).await?;
+    let username = String::<16>::decode(&mut data).await?;
+    let count = VarInt::decode(&mut data).await?;
+    let count = u32::try_from(*count).map_err(|_| ForwardingError::Malformed)?;
+
+    let mut properties = PrefixedArray::new();
+
+    for _ in 0..count {
+        keep_property(&mut properties, Property::decode(&mut data).await?);
+    }
+
+    let mut profile = GameProfile::new(username, uuid);
+    profile.set_properties(Properties(properties));
+
+    Ok(ForwardedPlayer { address, profile })
+}
+
+/// Parses BungeeCord's legacy forwarding, where the proxy replaces the
+/// handshake's server address with `host\0address\0uuid\0properties`.
+///
+/// This is unauthenticated, so anyone who can connect to the server directly
+/// can pretend to be anyone. Only use it if the server is firewalled off from
+/// everything but the proxy.
+pub fn parse_bungeecord(server_address: &str) -> Result<ForwardedPlayer, ForwardingError> {
+    let mut parts = server_address.split('\0');
+
+    let (Some(_host), Some(address), Some(uuid)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(ForwardingError::Malformed);
+    };
+
+    let address = IpAddr::from_str(address).map_err(|_| ForwardingError::Malformed)?;
+    let uuid = UUID::try_parse(uuid).map_err(|_| ForwardingError::Malformed)?;
+
+    let mut profile = GameProfile::new(String::new(), uuid);
+
+    if let Some(properties) = parts.next() {
+        profile.set_properties(parse_bungeecord_properties(properties)?);
+    }
+
+    Ok(ForwardedPlayer { address, profile })
+}
+
+/// BungeeCord sends properties as a JSON array of
+/// `{"name": .., "value": .., "signature": ..}` objects.
+fn parse_bungeecord_properties(
+    json: &str,
+) -> Result<Properties<MAX_PROFILE_PROPERTIES>, ForwardingError> {
+    let mut deserializer = Deserializer::<&[u8], ConstStack<1>>::new(json.as_bytes())
+        .map_err(|_| ForwardingError::Malformed)?;
+
+    let mut properties = PrefixedArray::new();
+
+    let mut array = deserializer
+        .value()
+        .and_then(|value| value.iterate())
+        .map_err(|_| ForwardingError::Malformed)?;
+
+    while let Some(value) = array.next() {
+        let mut fields = value
+            .and_then(|value| value.fields())
+            .map_err(|_| ForwardingError::Malformed)?;
+
+        let mut name = None;
+        let mut value = None;
+        let mut signature = None;
+
+        while let Some(field) = fields.next() {
+            let mut field = field.map_err(|_| ForwardingError::Malformed)?;
+
+            let key = collect_string::<16, _>(field.key())?;
+
+            let string = field
+                .value()
+                .to_str()
+                .map_err(|_| ForwardingError::Malformed);
+
+            match key.as_str() {
+                "name" => name = Some(collect_string(string?)?),
+                "value" => value = Some(collect_string(string?)?),
+                "signature" => signature = Some(collect_string(string?)?),
+                _ => {}
+            }
+        }
+
+        let (Some(name), Some(value)) = (name, value) else {
+            return Err(ForwardingError::Malformed);
+        };
+
+        keep_property(
+            &mut properties,
+            Property {
+                name,
+                value,
+                signature: PrefixedOptional(signature),
+            },
+        );
+    }
+
+    Ok(Properties(properties))
+}
+
+/// Keeps `property` if there is room for it. Only a few properties are kept,
+/// so once full, `textures` still replaces any other, as skins are all clients
+/// use them for. Anything else is ignored rather than turning the player away.
+fn keep_property(
+    properties: &mut PrefixedArray<Property, MAX_PROFILE_PROPERTIES>,
+    property: Property,
+) {
+    if let Err(property) = properties.push(property)
+        && property.name == TEXTURES_PROPERTY
+        && let Some(last) = properties.last_mut()
+        && last.name != TEXTURES_PROPERTY
+    {
+        *last = property;
+    }
+}
+
+fn collect_string<const N: usize, E>(
+    chars: impl Iterator<Item = Result<char, E>>,
+) -> Result<String<N>, ForwardingError> {
+    let mut string = String::new();
+
+    for char in chars {
+        string
+            .push(char.map_err(|_| ForwardingError::Malformed)?)
+            .map_err(|_| ForwardingError::Malformed)?;
+    }
+
+    Ok(string)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::client::buffer::Buffer;
+
+    const SECRET: &[u8] = b"it's a secret to everybody";
+
+    /// A real-looking skin and cape, the same size as Mojang's.
+    const TEXTURES: &str = concat!(
+        "ewogICJ0aW1lc3RhbXAiIDogMTcyOTI1MDAwMDAwMCwKICAicHJvZmlsZUlkIiA6ICIwNjlh",
+        "NzlmNDQ0ZTk0NzI2YTViZWZjYTkwZTM4YWFmNSIsCiAgInByb2ZpbGVOYW1lIiA6ICJOb3Rj",
+        "aCIsCiAgInNpZ25hdHVyZVJlcXVpcmVkIiA6IHRydWUsCiAgInRleHR1cmVzIiA6IHsKICAg",
+        "ICJTS0lOIiA6IHsKICAgICAgInVybCIgOiAiaHR0cDovL3RleHR1cmVzLm1pbmVjcmFmdC5u",
+        "ZXQvdGV4dHVyZS8yOTIwMDlhNDkyNWI1OGYwMmM3N2RhZGMzZWNlZjA3ZWE0Yzc0NzJmNjRl",
+        "MGZkYzMyY2U1NTIyNDg5MzYyNjgwIgogICAgfSwKICAgICJDQVBFIiA6IHsKICAgICAgInVy",
+        "bCIgOiAiaHR0cDovL3RleHR1cmVzLm1pbmVjcmFmdC5uZXQvdGV4dHVyZS8yMzQwYzBlMDNk",
+        "ZDI0YTExYjE1YThiMzNjMmE3ZTllMzJhYmIyMDUxYjI0ODFkMGJhN2RlZmQ2MzVjYTdhOTMz",
+        "IgogICAgfQogIH0KfQ==",
+    );
+
+    /// 4096 bits, like Mojang's signatures, though not a real one.
+    const TEXTURES_SIGNATURE: &str = concat!(
+        "pU3KGCUwux1tEyze1iN7LtkeP3IfyxlxF0SU1kk8nVw0YL4xIB5p/tqg7ui5mX9cfCmZ/a/l",
+        "kyU81lSvTfrXFCegrrP+6SMvivIhH57kkcWxC+y1Vjv8Hm+TQn7LyP4pVeXNjkbcjtS3wnZN",
+        "KlpNdncG+F2GkAJK1r2jQBvpyMvMyTX2zR9hImrhUziuGjQATTO6DSRqwEyBsbryPjv57vX3",
+        "nytJNK+H9VILablLDZguhbtVtnKocmN6zXRm/LYODo/xhGOw5LK6KXA0dPBkrGj3APWwKz3G",
+        "ZvRb3qosyu3NK1FXQQ5N7krys09DCgc0R95jbA6AbJV7poTWQx+16tdCTQnhXQJMWEjyPR+m",
+        "9zYdf2GNFTLnDiDipmaN5/R+hGflRtU+yOKhJXvbJWybPk+7SYFG73Awy/lTclLczq3XZLaj",
+        "L7sJrerhCcSplyA5dTUrh4sUXIpC2ITPTP2nLY4dXdkliQgthSpxIoc+6AWt1YlCFno4UoYZ",
+        "XGefnGmU5FuKsQmAEgcJYfN95Dbd/cmdbnWvZUfPsRtCBySC3FMcK8OQfJYX615QieQBhrqo",
+        "pX0Rnm+2XQCrwyrzjmZ/Ai6HLUnMFckLmZt3K0/Hpv1MkUoW20cIdSsPFUS4NcDnGQl9+ocB",
+        "6SMvIfKBJod4aXbr/MMn9ZMXZSdLqYKbRAY=",
+    );
+
+    /// Velocity's response to a `player_info` request, signed with `secret`.
+    async fn velocity_payload(secret: &[u8], properties: &[(&str, &str)]) -> Vec<u8> {
+        let mut data = Buffer::<4096>::new();
+
+        async {
+            VarInt(1).encode(&mut data).await?;
+            String::<64>::try_from("203.0.113.7")
+                .expect("short enough")
+                .encode(&mut data)
+                .await?;
+            UUID::from_u128(0x069a79f4_44e9_4726_a5be_fca90e38aaf5)
+                .encode(&mut data)
+                .await?;
+            String::<16>::try_from("Notch")
+                .expect("short enough")
+                .encode(&mut data)
+                .await?;
+            VarInt(properties.len() as i32).encode(&mut data).await?;
+
+            for (name, value) in properties {
+                Property {
+                    name: String::try_from(*name).expect("short enough"),
+                    value: String::try_from(*value).expect("short enough"),
+                    signature: PrefixedOptional(Some(
+                        String::try_from(TEXTURES_SIGNATURE).expect("short enough"),
+                    )),
+                }
+                .encode(&mut data)
+                .await?;
+            }
+
+            Ok::<_, EncodeError>(())
+        }
+        .await
+        .expect("fits in the buffer");
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("any length works");
+        mac.update(&data);
+
+        let mut payload = mac.finalize().into_bytes().to_vec();
+        payload.extend_from_slice(&data);
+        payload
+    }
+
+    #[test]
+    fn velocity_forwarding_with_a_good_signature_is_accepted() {
+        embassy_futures::block_on(async {
+            let payload = velocity_payload(SECRET, &[("textures", TEXTURES)]).await;
+
+            let forwarded = parse_velocity(SECRET, &payload)
+                .await
+                .expect("valid forwarding");
+
+            assert_eq!(forwarded.address, IpAddr::from([203, 0, 113, 7]));
+            assert_eq!(forwarded.profile.username(), "Notch");
+            assert_eq!(
+                forwarded.profile.uuid(),
+                UUID::from_u128(0x069a79f4_44e9_4726_a5be_fca90e38aaf5)
+            );
+            assert_eq!(forwarded.profile.properties()[0].value, TEXTURES);
+        });
+    }
+
+    #[test]
+    fn velocity_forwarding_with_a_bad_signature_is_refused() {
+        embassy_futures::block_on(async {
+            let payload = velocity_payload(b"not the secret", &[]).await;
+
+            assert!(matches!(
+                parse_velocity(SECRET, &payload).await,
+                Err(ForwardingError::InvalidSignature)
+            ));
+
+            let mut payload = velocity_payload(SECRET, &[]).await;
+            *payload.last_mut().expect("not empty") ^= 1;
+
+            assert!(matches!(
+                parse_velocity(SECRET, &payload).await,
+                Err(ForwardingError::InvalidSignature)
+            ));
+        });
+    }
+
+    #[test]
+    fn truncated_velocity_forwarding_is_refused() {
+        embassy_futures::block_on(async {
+            let payload = velocity_payload(SECRET, &[("textures", TEXTURES)]).await;
+
+            assert!(matches!(
+                parse_velocity(SECRET, &payload[..VELOCITY_SIGNATURE_LENGTH - 1]).await,
+                Err(ForwardingError::Malformed)
+            ));
+
+            // Cut off after signing, so the signature no longer matches.
+            assert!(matches!(
+                parse_velocity(SECRET, &payload[..payload.len() - 10]).await,
+                Err(ForwardingError::InvalidSignature)
+            ));
+
+            // Signed as it is, so only the data itself is wrong.
+            let data = &payload[VELOCITY_SIGNATURE_LENGTH..payload.len() - 10];
+            let mut mac = Hmac::<Sha256>::new_from_slice(SECRET).expect("any length works");
+            mac.update(data);
+
+            let mut truncated = mac.finalize().into_bytes().to_vec();
+            truncated.extend_from_slice(data);
+
+            assert!(matches!(
+                parse_velocity(SECRET, &truncated).await,
+                Err(ForwardingError::Malformed)
+            ));
+        });
+    }
+
+    #[test]
+    fn extra_velocity_properties_are_ignored() {
+        embassy_futures::block_on(async {
+            let payload =
+                velocity_payload(SECRET, &[("picocraft", "extra"), ("textures", TEXTURES)]).await;
+
+            let forwarded = parse_velocity(SECRET, &payload)
+                .await
+                .expect("valid forwarding");
+
+            let properties = forwarded.profile.properties();
+            assert_eq!(properties.len(), 1);
+            assert_eq!(properties[0].name, "textures");
+        });
+    }
+
+    fn bungeecord_address(properties: &str) -> std::string::String {
+        std::format!(
+            "mc.example.com\x00203.0.113.7\x00069a79f444e94726a5befca90e38aaf5\0{properties}"
+        )
+    }
+
+    #[test]
+    fn bungeecord_forwarding_carries_textures() {
+        let address = bungeecord_address(&std::format!(
+            r#"[{{"name":"textures","value":"{TEXTURES}","signature":"{TEXTURES_SIGNATURE}"}}]"#
+        ));
+
+        // It has to fit in the handshake.
+        assert!(address.len() <= serverbound::MAX_SERVER_ADDRESS_LENGTH);
+
+        let forwarded = parse_bungeecord(&address).expect("valid forwarding");
+
+        assert_eq!(forwarded.address, IpAddr::from([203, 0, 113, 7]));
+        assert_eq!(
+            forwarded.profile.uuid(),
+            UUID::from_u128(0x069a79f4_44e9_4726_a5be_fca90e38aaf5)
+        );
+
+        let textures = &forwarded.profile.properties()[0];
+        assert_eq!(textures.name, "textures");
+        assert_eq!(textures.value, TEXTURES);
+        assert_eq!(textures.signature.0.as_deref(), Some(TEXTURES_SIGNATURE));
+    }
+
+    #[test]
+    fn extra_bungeecord_properties_are_ignored() {
+        let address = bungeecord_address(&std::format!(
+            r#"[{{"name":"picocraft","value":"extra"}},{{"name":"textures","value":"{TEXTURES}"}}]"#
+        ));
+
+        let forwarded = parse_bungeecord(&address).expect("valid forwarding");
+
+        let properties = forwarded.profile.properties();
+        assert_eq!(properties.len(), 1);
+        assert_eq!(properties[0].value, TEXTURES);
+    }
+
+    #[test]
+    fn bungeecord_forwarding_without_properties_is_accepted() {
+        let forwarded =
+            parse_bungeecord("mc.example.com\x00203.0.113.7\x00069a79f444e94726a5befca90e38aaf5")
+                .expect("valid forwarding");
+
+        assert!(forwarded.profile.properties().is_empty());
+        assert!(matches!(
+            parse_bungeecord("mc.example.com\x00203.0.113.7"),
+            Err(ForwardingError::Malformed)
+        ));
+    }
+}
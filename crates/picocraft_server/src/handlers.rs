@@ -1,9 +1,11 @@
 use crate::prelude::*;
 
 mod configuration;
+mod cookie;
 mod handshake;
 mod login;
 mod play;
+mod plugin_message;
 mod status;
 
 pub use crate::client::Client;
@@ -11,5 +13,37 @@ pub use crate::client::Client;
 #[allow(async_fn_in_trait)]
 pub(crate) trait HandlePacket {
     //use "ctx" in future.
-    async fn handle(self, client: &mut Client) -> Result<(), PacketError>;
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError>;
 }
+
+/// Generates [`dispatch`] from a list of packets, checking each packet's
+/// [`Packet::STATE`] and [`Packet::ID`] in turn.
+macro_rules! dispatch {
+    ($($packet:path),* $(,)?) => {
+        /// Decodes the packet in `client`'s receive buffer and passes it to its
+        /// [`HandlePacket`] impl. Returns `false` if there is no serverbound packet
+        /// with this ID in the current state.
+        pub(crate) async fn dispatch<T: Transport>(
+            client: &mut Client<T>,
+            packet_id: VarInt,
+        ) -> Result<bool, PacketError> {
+            let state = client.state();
+
+            $(
+                if state == <$packet as Packet>::STATE && packet_id == <$packet as Packet>::ID {
+                    let packet =
+                        <$packet as Decode>::decode(&mut client.connection.rx_buf.as_slice())
+                            .await?;
+
+                    <$packet as HandlePacket>::handle(packet, client).await?;
+
+                    return Ok(true);
+                }
+            )*
+
+            Ok(false)
+        }
+    };
+}
+
+picocraft_proto::for_each_serverbound_packet!(dispatch);
@@ -1,16 +1,19 @@
+use core::sync::atomic::Ordering;
+
 use embassy_sync::pubsub::WaitResult;
 use picocraft_ecs::commands::WorldCommand;
 use picocraft_ecs::events::WorldEvent;
+use picocraft_ecs::world::LoginId;
 use picocraft_proto::serverbound::configuration::AcknowledgeFinishConfigurationPacket;
 use picocraft_terrain::terrain::chunks::empty_chunk::EmptyChunkAndLightPacket;
 use picocraft_terrain::terrain::coordinates::ChunkColumnCoordinates;
 use picocraft_terrain::terrain::spiral_iterator::{BorderedSpiralIterator, ChunkKind};
 
-use crate::channels::COMMANDS;
+use crate::channels::{COMMANDS, NEXT_LOGIN_ID};
 use crate::prelude::*;
 
 impl HandlePacket for AcknowledgeFinishConfigurationPacket {
-    async fn handle(self, client: &mut Client) -> Result<(), PacketError> {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
         trace!("Packet received: {:?}", &self);
 
         info!(
@@ -21,8 +24,13 @@ impl HandlePacket for AcknowledgeFinishConfigurationPacket {
 
         client.set_state(State::Play);
 
+        // Another login with our UUID may be waiting to join too, so our events are
+        // told apart by this rather than by UUID.
+        let login = LoginId(NEXT_LOGIN_ID.fetch_add(1, Ordering::Relaxed));
+
         COMMANDS
             .send(WorldCommand::PlayerJoined {
+                login,
                 username: client.username().clone(),
                 uuid: client.uuid(),
             })
@@ -31,8 +39,6 @@ impl HandlePacket for AcknowledgeFinishConfigurationPacket {
         let mut opt_position = None;
         let mut opt_rotation = None;
 
-        let mut buffered_events: heapless::Vec<WorldEvent, MAX_PLAYERS> = heapless::Vec::new();
-
         let (entity_id, position, rotation) = loop {
             match client
                 .events
@@ -42,12 +48,12 @@ impl HandlePacket for AcknowledgeFinishConfigurationPacket {
                 .await
             {
                 WaitResult::Message(WorldEvent::PlayerJoined {
+                    login: joined,
                     player_id,
-                    uuid,
                     position,
                     rotation,
                     ..
-                }) if uuid == client.uuid() => {
+                }) if joined == login => {
                     client.entity_id = Some(player_id);
                     opt_position.replace(position);
                     opt_rotation.replace(rotation);
@@ -61,20 +67,34 @@ impl HandlePacket for AcknowledgeFinishConfigurationPacket {
                         opt_rotation.expect("we set this"),
                     );
                 }
-                WaitResult::Message(event @ WorldEvent::ExistingPlayer { .. }) => {
-                    // ignore other messages until we get the PlayerJoined event and any
-                    // ExistingPlayer events for this client since we need to
-                    // know our entity id before doing anything else.
-
-                    let _ = buffered_events.push(event);
+                // Our old session didn't leave in time, or too many logins were
+                // waiting for theirs to.
+                WaitResult::Message(WorldEvent::JoinRejected { login: rejected })
+                    if rejected == login =>
+                {
+                    return client
+                        .disconnect(TextComponent::text(
+                            "You are still logged in from another location, try again in a moment",
+                        ))
+                        .await;
+                }
+                // Anything from before our PlayerJoined event is already reflected in the
+                // ExistingPlayer events which follow it, so only queue events after it.
+                WaitResult::Message(event) if client.entity_id.is_some() => {
+                    client.queue_event(event)?;
                 }
                 WaitResult::Message(_) => {}
-                WaitResult::Lagged(skipped) => {
-                    // in theory this should be unreachable or very close to impossible
-                    warn!(
+                // Events from before we joined get thrown away anyway, so falling behind
+                // while configuring is harmless.
+                WaitResult::Lagged(skipped) if client.entity_id.is_none() => {
+                    debug!(
                         "Lagged while waiting for PlayerJoined event, skipped {skipped} messages."
                     );
                 }
+                WaitResult::Lagged(skipped) => {
+                    // in theory this should be unreachable or very close to impossible
+                    warn!("Lagged while waiting for WorldReady event, skipped {skipped} messages.");
+                }
             }
         };
 
@@ -153,6 +173,10 @@ impl HandlePacket for AcknowledgeFinishConfigurationPacket {
                     client.encode_packet(&empty).await?;
                 }
             }
+
+            // Sending every chunk takes a while, so keep up with the world in the
+            // meantime.
+            client.pump_events().await?;
         }
 
         trace!("Packets sent: ChunkAndLightPacket");
@@ -163,9 +187,7 @@ impl HandlePacket for AcknowledgeFinishConfigurationPacket {
             client.player.uuid()
         );
 
-        for event in buffered_events {
-            client.handle_event(event).await?;
-        }
+        client.flush_outbound().await?;
 
         Ok(())
     }
@@ -0,0 +1,35 @@
+use picocraft_proto::serverbound::{
+    ConfigurationCookieResponsePacket, LoginCookieResponsePacket, PlayCookieResponsePacket,
+};
+
+use crate::prelude::*;
+
+impl HandlePacket for LoginCookieResponsePacket {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
+        handle_cookie_response(self.0, client);
+
+        Ok(())
+    }
+}
+
+impl HandlePacket for ConfigurationCookieResponsePacket {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
+        handle_cookie_response(self.0, client);
+
+        Ok(())
+    }
+}
+
+impl HandlePacket for PlayCookieResponsePacket {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
+        handle_cookie_response(self.0, client);
+
+        Ok(())
+    }
+}
+
+fn handle_cookie_response<T: Transport>(cookie: CookieResponse, client: &mut Client<T>) {
+    trace!("Cookie received: {:?}", &cookie);
+
+    client.player.insert_cookie(cookie);
+}
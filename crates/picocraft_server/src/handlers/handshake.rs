@@ -1,29 +1,175 @@
+use core::sync::atomic::Ordering;
+
 use picocraft_proto::serverbound::handshake::*;
 
+use crate::channels::ONLINE_PLAYERS;
+use crate::config::PlayerInfoForwarding;
+use crate::forwarding;
 use crate::prelude::*;
 
 impl HandlePacket for HandshakePacket {
-    async fn handle(self, client: &mut Client) -> Result<(), PacketError> {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
         trace!("Packet received: {:?}", &self);
 
-        client.player.set_protocol_version(self.protocol_version);
+        let protocol_version = ProtocolVersion::from(self.protocol_version);
 
         client.set_state(match self.intent {
             Intent::Status => State::Status,
-            Intent::Login => State::Login,
-            Intent::Transfer => {
-                panic!("Transfer intent not supported yet");
-            }
+            // A transfer is just a login, except the client may have cookies from the
+            // server it came from.
+            Intent::Login | Intent::Transfer => State::Login,
         });
 
+        if self.intent != Intent::Status && !protocol_version.is_supported() {
+            return disconnect_outdated(client, protocol_version).await;
+        }
+
+        client
+            .player
+            .set_transferred(self.intent == Intent::Transfer);
+
+        if client.server_config.forwarding == PlayerInfoForwarding::BungeeCord
+            && self.intent != Intent::Status
+        {
+            // If this fails, the player is left unforwarded and gets disconnected
+            // once they send Login Start.
+            match forwarding::parse_bungeecord(&self.server_address) {
+                Ok(forwarded) => {
+                    client.connection.set_remote_address(forwarded.address);
+                    client.player.set_uuid(forwarded.profile.uuid());
+                    client
+                        .player
+                        .set_properties(forwarded.profile.properties().clone());
+                    client.player.set_forwarded(true);
+                }
+                Err(e) => debug!(
+                    "Couldn't parse BungeeCord forwarding from {}: {e}",
+                    client.connection.remote_endpoint()
+                ),
+            }
+        }
+
         Ok(())
     }
 }
 
+/// Tells a client on an unsupported version which version to use instead, the
+/// same way vanilla does.
+async fn disconnect_outdated<T: Transport>(
+    client: &mut Client<T>,
+    protocol_version: ProtocolVersion,
+) -> Result<(), PacketError> {
+    debug!(
+        "Client {} tried to log in with unsupported protocol version {}",
+        client.connection.remote_endpoint(),
+        *protocol_version
+    );
+
+    if *protocol_version < CURRENT_PROTOCOL_VERSION {
+        client
+            .disconnect(
+                TextComponent::translatable(
+                    "multiplayer.disconnect.outdated_client",
+                    &[TextComponent::text(CURRENT_VERSION_NAME)],
+                )
+                .fallback("Outdated client! Please use %s"),
+            )
+            .await
+    } else {
+        client
+            .disconnect(
+                TextComponent::translatable(
+                    "multiplayer.disconnect.outdated_server",
+                    &[TextComponent::text(CURRENT_VERSION_NAME)],
+                )
+                .fallback("Outdated server! I'm still on %s"),
+            )
+            .await
+    }
+}
+
 impl HandlePacket for LegacyPingPacket {
-    async fn handle(self, _client: &mut Client) -> Result<(), PacketError> {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
         trace!("Packet received: {:?}", &self);
 
-        todo!("can't handle legacy ping packets yet")
+        let legacy_kick = clientbound::LegacyKickPacket {
+            protocol_version: clientbound::LegacyKickPacket::INCOMPATIBLE_PROTOCOL,
+            version_name: String::try_from(CURRENT_VERSION_NAME)
+                .expect("version names are never longer than 16 characters"),
+            //TODO the clone here ideally shouldn't occur
+            motd: client.server_config.motd.clone(),
+            online_players: ONLINE_PLAYERS.load(Ordering::Relaxed) as i32,
+            max_players: MAX_PLAYERS as i32,
+            unversioned: self == LegacyPingPacket::Unversioned,
+        };
+
+        trace!("Packet constructed: {:?}", &legacy_kick);
+
+        // Legacy packets aren't length prefixed, so this skips the usual framing.
+        legacy_kick.encode(&mut client.connection.socket).await?;
+        client.connection.socket.flush().await?;
+
+        debug!(
+            "Handled legacy ping for client: {}",
+            client.connection.remote_endpoint()
+        );
+
+        Err(PacketError::ConnectionClosed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::client::tests::{connect, next_packet};
+
+    /// Logs in with `protocol_version`, returning the body of the disconnect
+    /// packet.
+    async fn log_in_with(protocol_version: i32) -> Vec<u8> {
+        let (mut client, mut remote) = connect(ServerConfig::default()).await;
+
+        let handshake = HandshakePacket {
+            protocol_version: VarInt(protocol_version),
+            server_address: String::try_from("localhost").expect("address is short"),
+            server_port: 25565,
+            intent: Intent::Login,
+        };
+
+        assert!(matches!(
+            handshake.handle(&mut client).await,
+            Err(PacketError::ConnectionClosed)
+        ));
+
+        let (packet_id, body) = next_packet(&mut remote).await;
+        assert_eq!(packet_id, clientbound::LoginDisconnectPacket::ID);
+
+        body
+    }
+
+    fn contains(body: &[u8], text: &str) -> bool {
+        body.windows(text.len())
+            .any(|window| window == text.as_bytes())
+    }
+
+    #[tokio::test]
+    async fn older_clients_are_told_to_update() {
+        // Other 1.21.x versions aren't supported until their packet IDs and
+        // registries are.
+        for protocol_version in [767, 772, 47] {
+            let body = log_in_with(protocol_version).await;
+
+            assert!(contains(&body, "multiplayer.disconnect.outdated_client"));
+            assert!(contains(&body, CURRENT_VERSION_NAME));
+        }
+    }
+
+    #[tokio::test]
+    async fn newer_clients_are_told_the_server_is_outdated() {
+        let body = log_in_with(774).await;
+
+        assert!(contains(&body, "multiplayer.disconnect.outdated_server"));
+        assert!(contains(&body, CURRENT_VERSION_NAME));
     }
 }
@@ -1,55 +1,241 @@
 use picocraft_proto::serverbound::login::*;
 
+use crate::access::{self, Denial};
 use crate::channels::EVENTS;
+use crate::config::PlayerInfoForwarding;
+use crate::forwarding;
 use crate::prelude::*;
-
-const CAT_VARIANT: &[u8] = include_bytes!("login/cat_variant.bin");
-const COW_VARIANT: &[u8] = include_bytes!("login/cow_variant.bin");
-const CHICKEN_VARIANT: &[u8] = include_bytes!("login/chicken_variant.bin");
-const DAMAGE_TYPES: &[u8] = include_bytes!("login/damage_types.bin");
-const DIMENSION_TYPES: &[u8] = include_bytes!("login/dimension_types.bin");
-const FROG_VARIANT: &[u8] = include_bytes!("login/frog_variant.bin");
-const PAINTING_VARIANT: &[u8] = include_bytes!("login/painting_variant.bin");
-const PIG_VARIANT: &[u8] = include_bytes!("login/pig_variant.bin");
-const WOLF_SOUND_VARIANT: &[u8] = include_bytes!("login/wolf_sound_variant.bin");
-const WOLF_VARIANT: &[u8] = include_bytes!("login/wolf_variant.bin");
-const WORLDGEN_BIOME: &[u8] = include_bytes!("login/worldgen_biome.bin");
+use crate::registries;
 
 impl HandlePacket for LoginStartPacket {
-    async fn handle(self, client: &mut Client) -> Result<(), PacketError> {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
         trace!("Packet received: {:?}", &self);
 
-        let login_disconnect = clientbound::LoginDisconnectPacket {
-            reason: heapless::format!("{{ text: \"Server is full.\" }}")
-                .expect("should be less than 64 chars"),
-        };
+        if client.player.transferred() && !client.server_config.accepts_transfers {
+            return client
+                .disconnect(
+                    TextComponent::translatable("multiplayer.disconnect.transfers_disabled", &[])
+                        .fallback("Transfers are disabled."),
+                )
+                .await;
+        }
 
         let Ok(subscriber) = EVENTS.subscriber() else {
-            client.encode_packet(&login_disconnect).await?;
-
-            return Err(PacketError::ConnectionClosed);
+            return client
+                .disconnect(
+                    TextComponent::translatable("multiplayer.disconnect.server_full", &[])
+                        .fallback("Server is full."),
+                )
+                .await;
         };
 
         client.events = Some(subscriber);
 
-        let login_success = clientbound::LoginSuccess(GameProfile::new(
-            client.player.username().clone(),
-            client.player.uuid(),
-        ));
+        match &client.server_config.forwarding {
+            PlayerInfoForwarding::None => {
+                client.player.set_username(self.username);
+                client.player.set_uuid(self.uuid);
+            }
+            PlayerInfoForwarding::BungeeCord => {
+                if !client.player.forwarded() {
+                    return client
+                        .disconnect(TextComponent::text(
+                            "If you wish to use IP forwarding, please enable it in your \
+                             BungeeCord config as well!",
+                        ))
+                        .await;
+                }
 
-        trace!("Packet constructed: {login_success:?}");
+                // BungeeCord forwards everything but the username.
+                client.player.set_username(self.username);
+            }
+            PlayerInfoForwarding::Velocity { .. } => {
+                let message_id = VarInt(client.system_random::<u16>().await.into());
 
-        client.player.set_username(self.username.clone());
-        client.player.set_uuid(self.uuid);
+                let login_plugin_request = clientbound::LoginPluginRequestPacket {
+                    message_id,
+                    channel: NamespacedIdentifier::try_from(forwarding::VELOCITY_CHANNEL)
+                        .expect("channel is a valid identifier"),
+                    data: Array::from_array([forwarding::VELOCITY_FORWARDING_VERSION]),
+                };
 
-        client.encode_packet(&login_success).await?;
+                trace!("Packet constructed: {login_plugin_request:?}");
 
-        Ok(())
+                client.encode_packet(&login_plugin_request).await?;
+
+                // Login continues once the proxy responds with the player's details.
+                client.login_plugin_message_id = Some(message_id);
+
+                return Ok(());
+            }
+        }
+
+        finish_login(client).await
+    }
+}
+
+impl HandlePacket for LoginPluginResponsePacket {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
+        trace!("Packet received: {:?}", &self);
+
+        // We only ever send login plugin requests for Velocity forwarding.
+        let PlayerInfoForwarding::Velocity { secret } = &client.server_config.forwarding else {
+            return Err(PacketError::InvalidPacket(
+                *LoginPluginResponsePacket::ID,
+                client.state(),
+            ));
+        };
+
+        if client.login_plugin_message_id.take() != Some(self.message_id) {
+            return Err(PacketError::InvalidPacket(
+                *LoginPluginResponsePacket::ID,
+                client.state(),
+            ));
+        }
+
+        let Some(data) = self.data.0 else {
+            return client
+                .disconnect(TextComponent::text(
+                    "This server requires you to connect with Velocity.",
+                ))
+                .await;
+        };
+
+        let forwarded = match forwarding::parse_velocity(secret.as_bytes(), &data).await {
+            Ok(forwarded) => forwarded,
+            Err(e) => {
+                warn!(
+                    "Couldn't verify Velocity forwarding from {}: {e}",
+                    client.connection.remote_endpoint()
+                );
+
+                return client
+                    .disconnect(TextComponent::text("Unable to verify player details."))
+                    .await;
+            }
+        };
+
+        client.connection.set_remote_address(forwarded.address);
+        client.player.set_profile(forwarded.profile);
+        client.player.set_forwarded(true);
+
+        finish_login(client).await
     }
 }
 
+/// Enables compression and sends Login Success, once the player's profile is
+/// known.
+async fn finish_login<T: Transport>(client: &mut Client<T>) -> Result<(), PacketError> {
+    if !is_valid_username(client.username()) {
+        info!(
+            "Login from {} had an invalid username {:?}",
+            client.connection.remote_endpoint(),
+            client.username()
+        );
+
+        return client
+            .disconnect(TextComponent::text("Invalid characters in username"))
+            .await;
+    }
+
+    if !client.server_config.trusts_client_uuids() {
+        let uuid = offline_uuid(client.username());
+        client.player.set_uuid(uuid);
+    }
+
+    // Only checked now, as a proxy may have forwarded a different UUID and
+    // address.
+    if let Err(denial) = access::check(
+        client.uuid(),
+        client.connection.remote_endpoint().ip(),
+        client.server_config.whitelist,
+    )
+    .await
+    {
+        return turn_away(client, denial).await;
+    }
+
+    if let Some(threshold) = client.server_config.compression_threshold {
+        client
+            .encode_packet(&clientbound::SetCompressionPacket {
+                threshold: VarInt(threshold.into()),
+            })
+            .await?;
+
+        client.connection.set_compression(Some(threshold.into()));
+    }
+
+    let login_success = clientbound::LoginSuccess(client.player.profile().clone());
+
+    trace!("Packet constructed: {login_success:?}");
+
+    client.encode_packet(&login_success).await?;
+    client.login_succeeded = true;
+
+    Ok(())
+}
+
+/// Whether `username` could be a player's name. Like vanilla, this allows any
+/// printable ASCII without spaces, so offline names needn't be valid Mojang
+/// account names.
+fn is_valid_username(username: &str) -> bool {
+    (1..=16).contains(&username.len()) && username.bytes().all(|c| (b'!'..=b'~').contains(&c))
+}
+
+/// Disconnects a player who isn't allowed to join, telling them why.
+async fn turn_away<T: Transport>(
+    client: &mut Client<T>,
+    denial: Denial,
+) -> Result<(), PacketError> {
+    info!(
+        "{} [{}] from {} was turned away: {denial}",
+        client.username(),
+        client.uuid(),
+        client.connection.remote_endpoint()
+    );
+
+    let args;
+
+    let reason = match &denial {
+        Denial::Banned(reason) if reason.is_empty() => {
+            TextComponent::translatable("multiplayer.disconnect.banned", &[])
+                .fallback("You are banned from this server.")
+        }
+        Denial::Banned(reason) => {
+            args = [TextComponent::text(reason)];
+
+            TextComponent::translatable("multiplayer.disconnect.banned.reason", &args)
+                .fallback("You are banned from this server.\nReason: %s")
+        }
+        Denial::IpBanned(reason) => {
+            args = [TextComponent::text(if reason.is_empty() {
+                "Banned by an operator."
+            } else {
+                reason
+            })];
+
+            TextComponent::translatable("multiplayer.disconnect.banned_ip.reason", &args)
+                .fallback("Your IP address is banned from this server.\nReason: %s")
+        }
+        Denial::NotWhitelisted => {
+            TextComponent::translatable("multiplayer.disconnect.not_whitelisted", &[])
+                .fallback("You are not white-listed on this server!")
+        }
+    };
+
+    client.disconnect(reason).await
+}
+
 impl HandlePacket for LoginAcknowledgedPacket {
-    async fn handle(self, client: &mut Client) -> Result<(), PacketError> {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
+        // Skipping Login Start would skip forwarding and access checks.
+        if !client.login_succeeded {
+            return Err(PacketError::InvalidPacket(
+                *LoginAcknowledgedPacket::ID,
+                client.state(),
+            ));
+        }
+
         debug!("{} [{}] has logged in.", &client.username(), &client.uuid());
 
         client.set_state(State::Configuration);
@@ -62,17 +248,7 @@ impl HandlePacket for LoginAcknowledgedPacket {
             .encode_packet(&clientbound::KnownPacksPacket::new())
             .await?;
 
-        encode_registry_data(CAT_VARIANT, client).await?;
-        encode_registry_data(COW_VARIANT, client).await?;
-        encode_registry_data(CHICKEN_VARIANT, client).await?;
-        encode_registry_data(DAMAGE_TYPES, client).await?;
-        encode_registry_data(DIMENSION_TYPES, client).await?;
-        encode_registry_data(FROG_VARIANT, client).await?;
-        encode_registry_data(PAINTING_VARIANT, client).await?;
-        encode_registry_data(PIG_VARIANT, client).await?;
-        encode_registry_data(WOLF_SOUND_VARIANT, client).await?;
-        encode_registry_data(WOLF_VARIANT, client).await?;
-        encode_registry_data(WORLDGEN_BIOME, client).await?;
+        registries::encode_registry_data(client).await?;
 
         client
             .encode_packet(&clientbound::FinishConfigurationPacket)
@@ -82,15 +258,20 @@ impl HandlePacket for LoginAcknowledgedPacket {
     }
 }
 
-async fn encode_registry_data(
-    bytes: &'static [u8],
-    client: &mut Client,
-) -> Result<(), PacketError> {
-    VarInt(bytes.len() as i32)
-        .encode(&mut client.connection.socket)
-        .await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::tests::connect;
 
-    bytes.encode(&mut client.connection.socket).await?;
+    #[tokio::test]
+    async fn login_acknowledged_without_logging_in_is_refused() {
+        let (mut client, _remote) = connect(ServerConfig::default()).await;
+        client.set_state(State::Login);
 
-    Ok(())
+        assert!(matches!(
+            LoginAcknowledgedPacket.handle(&mut client).await,
+            Err(PacketError::InvalidPacket(_, State::Login))
+        ));
+        assert_eq!(client.state(), State::Login);
+    }
 }
@@ -1,17 +1,75 @@
 mod confirm_teleportation;
+mod containers;
 mod player;
+mod player_action;
+mod use_item;
 
-use picocraft_proto::serverbound::{ClientTickEndPacket, ServerboundKeepAlivePacket};
+use picocraft_ecs::commands::WorldCommand;
+use picocraft_proto::serverbound::{
+    ChatMessagePacket, ClientTickEndPacket, ServerboundKeepAlivePacket,
+};
 
+use crate::channels::COMMANDS;
+use crate::client::TIMED_OUT;
 use crate::prelude::*;
+
+impl HandlePacket for ChatMessagePacket {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
+        // Chat isn't shown to other players yet.
+        info!("<{}> {}", client.username(), self.message);
+
+        Ok(())
+    }
+}
+
 impl HandlePacket for ClientTickEndPacket {
-    async fn handle(self, _client: &mut Client) -> Result<(), PacketError> {
+    async fn handle<T: Transport>(self, _client: &mut Client<T>) -> Result<(), PacketError> {
         Ok(())
     }
 }
 
 impl HandlePacket for ServerboundKeepAlivePacket {
-    async fn handle(self, _client: &mut Client) -> Result<(), PacketError> {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
+        trace!("Packet received: {:?}", &self);
+
+        // Vanilla disconnects clients which answer keep-alives it didn't send, so we
+        // do too.
+        let Some((id, sent)) = client.pending_keep_alive.take() else {
+            warn!(
+                "Unexpected keep-alive from {} [{}]",
+                client.username(),
+                client.uuid()
+            );
+
+            return client.disconnect(TIMED_OUT).await;
+        };
+
+        if id != self.id {
+            warn!(
+                "Keep-alive ID mismatch from {} [{}]: expected {id}, got {}",
+                client.username(),
+                client.uuid(),
+                self.id
+            );
+
+            return client.disconnect(TIMED_OUT).await;
+        }
+
+        let round_trip = i32::try_from(sent.elapsed().as_millis()).unwrap_or(i32::MAX);
+        let latency = client.player.update_latency(round_trip);
+
+        trace!(
+            "Latency for {} [{}]: {latency}ms",
+            client.username(),
+            client.uuid()
+        );
+
+        if let Some(player_id) = client.entity_id {
+            COMMANDS
+                .send(WorldCommand::UpdateLatency { player_id, latency })
+                .await;
+        }
+
         Ok(())
     }
 }
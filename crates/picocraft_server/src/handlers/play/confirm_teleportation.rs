@@ -3,7 +3,7 @@ use picocraft_proto::serverbound::play::ConfirmTeleportationPacket;
 use crate::prelude::*;
 
 impl HandlePacket for ConfirmTeleportationPacket {
-    async fn handle(self, _client: &mut Client) -> Result<(), PacketError> {
+    async fn handle<T: Transport>(self, _client: &mut Client<T>) -> Result<(), PacketError> {
         if *self.0 == 0 {
             Ok(())
         } else {
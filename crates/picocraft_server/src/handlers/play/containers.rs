@@ -0,0 +1,40 @@
+use picocraft_ecs::commands::WorldCommand;
+use picocraft_proto::serverbound::ClickContainerPacket;
+
+use crate::channels::COMMANDS;
+use crate::prelude::*;
+
+/// The window ID of the player's own inventory, which is always open.
+const PLAYER_INVENTORY_WINDOW: VarInt = VarInt(0);
+
+impl HandlePacket for ClickContainerPacket {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
+        trace!("Packet received: {:?}", &self);
+
+        let Some(player_id) = client.entity_id else {
+            return Ok(());
+        };
+
+        // No other containers can be opened yet.
+        if self.window_id != PLAYER_INVENTORY_WINDOW {
+            return Ok(());
+        }
+
+        // The client's prediction of the click is taken as it is.
+        for (slot, item) in self.changed_slots.iter() {
+            let Ok(slot) = u8::try_from(*slot) else {
+                continue;
+            };
+
+            COMMANDS
+                .send(WorldCommand::SetInventorySlot {
+                    player_id,
+                    slot,
+                    item: item.0,
+                })
+                .await;
+        }
+
+        Ok(())
+    }
+}
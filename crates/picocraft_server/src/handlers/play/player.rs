@@ -8,7 +8,7 @@ use crate::channels::COMMANDS;
 use crate::prelude::*;
 
 impl HandlePacket for SetPlayerPositionPacket {
-    async fn handle(self, client: &mut Client) -> Result<(), PacketError> {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
         COMMANDS
             .send(WorldCommand::PlayerMoved {
                 player_id: client
@@ -25,7 +25,7 @@ impl HandlePacket for SetPlayerPositionPacket {
 }
 
 impl HandlePacket for SetPlayerRotationPacket {
-    async fn handle(self, client: &mut Client) -> Result<(), PacketError> {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
         COMMANDS
             .send(WorldCommand::PlayerRotated {
                 player_id: client
@@ -42,7 +42,7 @@ impl HandlePacket for SetPlayerRotationPacket {
 }
 
 impl HandlePacket for SetPlayerPositionAndRotationPacket {
-    async fn handle(self, client: &mut Client) -> Result<(), PacketError> {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
         COMMANDS
             .send(WorldCommand::PlayerMovedAndRotated {
                 player_id: client
@@ -0,0 +1,40 @@
+use picocraft_ecs::commands::WorldCommand;
+use picocraft_proto::serverbound::{PlayerActionPacket, PlayerActionStatus};
+
+use crate::channels::COMMANDS;
+use crate::prelude::*;
+
+impl HandlePacket for PlayerActionPacket {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
+        trace!("Packet received: {:?}", &self);
+
+        let Some(player_id) = client.entity_id else {
+            return Ok(());
+        };
+
+        match self.status {
+            // Players are always in creative, where blocks break as soon as
+            // digging starts, and survival only says it has finished.
+            PlayerActionStatus::StartedDigging | PlayerActionStatus::FinishedDigging => {
+                COMMANDS
+                    .send(WorldCommand::BlockBroken {
+                        player_id,
+                        position: self.location,
+                        sequence: self.sequence,
+                    })
+                    .await;
+            }
+            PlayerActionStatus::CancelledDigging => {
+                client
+                    .encode_packet(&clientbound::AcknowledgeBlockChangePacket {
+                        sequence: self.sequence,
+                    })
+                    .await?;
+            }
+            // There are no items to drop or use yet.
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
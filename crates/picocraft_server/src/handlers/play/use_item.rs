@@ -0,0 +1,75 @@
+use picocraft_ecs::commands::WorldCommand;
+use picocraft_proto::serverbound::{SetCreativeModeSlotPacket, SetHeldItemPacket, UseItemOnPacket};
+
+use crate::channels::COMMANDS;
+use crate::prelude::*;
+
+impl HandlePacket for SetHeldItemPacket {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
+        trace!("Packet received: {:?}", &self);
+
+        let Some(player_id) = client.entity_id else {
+            return Ok(());
+        };
+
+        if let Ok(slot) = u8::try_from(self.slot) {
+            COMMANDS
+                .send(WorldCommand::SelectHotbarSlot { player_id, slot })
+                .await;
+        }
+
+        Ok(())
+    }
+}
+
+impl HandlePacket for SetCreativeModeSlotPacket {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
+        trace!("Packet received: {:?}", &self);
+
+        let Some(player_id) = client.entity_id else {
+            return Ok(());
+        };
+
+        // -1 drops the item out of the inventory, which isn't kept.
+        if let Ok(slot) = u8::try_from(self.slot) {
+            COMMANDS
+                .send(WorldCommand::SetInventorySlot {
+                    player_id,
+                    slot,
+                    item: ItemStack::from(&self.clicked_item),
+                })
+                .await;
+        }
+
+        Ok(())
+    }
+}
+
+impl HandlePacket for UseItemOnPacket {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
+        trace!("Packet received: {:?}", &self);
+
+        let Some(player_id) = client.entity_id else {
+            return Ok(());
+        };
+
+        let (dx, dy, dz) = self.face.0.offset();
+        let position = BlockPosition::new(
+            self.location.x() + dx,
+            self.location.z() + dz,
+            self.location.y() + dy,
+        );
+
+        COMMANDS
+            .send(WorldCommand::BlockPlaced {
+                player_id,
+                hand: self.hand,
+                against: self.location,
+                position,
+                sequence: self.sequence,
+            })
+            .await;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use picocraft_proto::serverbound::{ConfigurationPluginMessagePacket, PlayPluginMessagePacket};
+
+use crate::plugin_channels::{MAX_BRAND_LENGTH, PluginMessageContext};
+use crate::prelude::*;
+
+impl HandlePacket for ConfigurationPluginMessagePacket {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
+        handle_plugin_message(self.0, client).await
+    }
+}
+
+impl HandlePacket for PlayPluginMessagePacket {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
+        handle_plugin_message(self.0, client).await
+    }
+}
+
+async fn handle_plugin_message<T: Transport>(
+    message: PluginMessage,
+    client: &mut Client<T>,
+) -> Result<(), PacketError> {
+    trace!(
+        "Plugin message on {} ({} bytes) from {} [{}]",
+        message.channel,
+        message.data.len(),
+        client.username(),
+        client.uuid()
+    );
+
+    if message.channel.matches(BRAND_CHANNEL) {
+        match String::<MAX_BRAND_LENGTH>::decode(message.data.as_slice()).await {
+            Ok(brand) => {
+                debug!(
+                    "{} [{}] is using the {brand} client",
+                    client.username(),
+                    client.uuid()
+                );
+
+                client.player.set_brand(brand);
+            }
+            Err(e) => debug!(
+                "Unreadable brand from {} [{}]: {e:?}",
+                client.username(),
+                client.uuid()
+            ),
+        }
+    }
+
+    let Some(handler) = client
+        .server_config
+        .plugin_channels
+        .handler(&message.channel)
+    else {
+        return Ok(());
+    };
+
+    let mut context = PluginMessageContext::new(&client.player, client.entity_id, client.state());
+    handler(&mut context, &message.data);
+
+    if let Some(reply) = context.take_reply() {
+        client.send_plugin_message(message.channel, &reply).await?;
+    }
+
+    Ok(())
+}
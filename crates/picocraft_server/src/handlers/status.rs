@@ -1,38 +1,26 @@
-use heapless::format;
+use core::sync::atomic::Ordering;
+
 use picocraft_proto::serverbound::status::*;
 
+use crate::channels::{ONLINE_PLAYERS, PLAYER_SAMPLE};
 use crate::prelude::*;
 
 impl HandlePacket for StatusRequestPacket {
-    async fn handle(self, client: &mut Client) -> Result<(), PacketError> {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
         trace!("Packet received: {:?}", &self);
 
-        // let server_config = client.server_config().lock().await.borrow();
+        let server_config = client.server_config;
+        let online = ONLINE_PLAYERS.load(Ordering::Relaxed) as i32;
+        let sample = PLAYER_SAMPLE.lock(|sample| sample.borrow().clone());
 
-        // This should really be built with values from the server config for player
-        // count
         let json = clientbound::JsonStatusResponse::builder()
-            .players(MAX_PLAYERS as i32, 0)
-            //TODO the clone here ideally shouldn't occur
-            .description(client.server_config.motd.clone())
+            .version(clientbound::Version::default())
+            .players(MAX_PLAYERS as i32, online, sample)
+            .description(TextComponent::text(&server_config.motd))
+            .maybe_favicon(server_config.favicon)
             .build();
 
-        let status_response = clientbound::StatusResponsePacket::<256>::builder()
-            .json_response(
-                format!(
-                    "{{\"version\":{{\"name\":\"{}\",\"protocol\":{}}},\"players\":{{\"max\":{},\"\
-                     online\":{},\"sample\":[]}},\"description\":{{\"text\":\"{}\"}},\"\
-                     enforcesSecureChat\":{}}}",
-                    json.version.name,
-                    json.version.protocol,
-                    json.players.max,
-                    json.players.online,
-                    json.description.text,
-                    json.enforces_secure_chat
-                )
-                .expect("this string should be less than or equal to 256 bytes"),
-            )
-            .build();
+        let status_response = clientbound::StatusResponsePacket::new(json);
 
         trace!("Packet constructed: {:?}", &status_response);
 
@@ -43,7 +31,7 @@ impl HandlePacket for StatusRequestPacket {
 }
 
 impl HandlePacket for PingRequestPacket {
-    async fn handle(self, client: &mut Client) -> Result<(), PacketError> {
+    async fn handle<T: Transport>(self, client: &mut Client<T>) -> Result<(), PacketError> {
         trace!("Packet received: {:?}", &self);
 
         let pong_response = clientbound::PongResponsePacket::builder()
@@ -0,0 +1,429 @@
+//! Saving the blocks players change, so they are still changed after a
+//! restart.
+//!
+//! Changes are appended to a journal in batches as they are saved, and each
+//! batch ends with a checksum, so one cut short by a power cut is noticed and
+//! dropped. Once the journal grows large, every change is written as a
+//! snapshot, which replaces it. Both group changes by chunk section:
+//!
+//! ```text
+//! batch:   version: u8 | generation: u32 | length: u16 | sections | crc32
+//! section: x: i8 | y: u8 | z: i8 | count: u16 | (index: u16 | state: u16) * count
+//! ```
+//!
+//! The snapshot is a single batch. Each snapshot has a new generation, and
+//! only journal batches of the same generation are replayed on top of it, so
+//! a journal left behind by a power cut while compacting is ignored.
+
+use core::cell::Cell;
+
+use embassy_sync::blocking_mutex::Mutex as BlockingMutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::Channel;
+use picocraft_terrain::Terrain;
+use picocraft_terrain::terrain::coordinates::{ChunkCoordinates, Coordinates};
+use picocraft_terrain::terrain::modifications::MAX_BLOCK_CHANGES;
+
+use crate::prelude::*;
+use crate::storage::Storage;
+
+/// How often the desktop server saves changed blocks.
+pub const SAVE_INTERVAL: embassy_time::Duration = embassy_time::Duration::from_secs(5);
+
+/// Bumped whenever the stored format changes.
+const FORMAT_VERSION: u8 = 1;
+
+const SNAPSHOT_KEY: &str = "world/snapshot";
+const JOURNAL_KEY: &str = "world/journal";
+
+/// Most changes which can wait to be saved. Any more, and the next save
+/// writes a snapshot instead.
+const MAX_UNSAVED_CHANGES: usize = 64;
+
+const BATCH_HEADER_SIZE: usize = 7;
+const BATCH_OVERHEAD: usize = BATCH_HEADER_SIZE + 4;
+const SECTION_HEADER_SIZE: usize = 5;
+const CHANGE_SIZE: usize = 4;
+
+/// Room for a batch of every unsaved change, each in its own section.
+const MAX_BATCH_SIZE: usize =
+    BATCH_OVERHEAD + MAX_UNSAVED_CHANGES * (SECTION_HEADER_SIZE + CHANGE_SIZE);
+/// Room for a snapshot of every change the terrain can hold, each in its own
+/// section.
+const MAX_SNAPSHOT_SIZE: usize =
+    BATCH_OVERHEAD + MAX_BLOCK_CHANGES * (SECTION_HEADER_SIZE + CHANGE_SIZE);
+/// The journal is compacted before it grows past this.
+const MAX_JOURNAL_SIZE: usize = 4096;
+
+const CRC: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+
+static UNSAVED_CHANGES: Channel<
+    CriticalSectionRawMutex,
+    (Coordinates, VarInt),
+    MAX_UNSAVED_CHANGES,
+> = Channel::new();
+
+/// Whether a change didn't fit in [`UNSAVED_CHANGES`], so was lost.
+static CHANGES_LOST: BlockingMutex<CriticalSectionRawMutex, Cell<bool>> =
+    BlockingMutex::new(Cell::new(false));
+
+/// Notes that the block at `coordinates` was changed to `state`, so it is
+/// saved next time.
+pub fn record(coordinates: Coordinates, state: VarInt) {
+    if UNSAVED_CHANGES.try_send((coordinates, state)).is_err() {
+        CHANGES_LOST.lock(|lost| lost.set(true));
+    }
+}
+
+/// The changed blocks kept in a [`Storage`].
+pub struct Journal<S> {
+    storage: S,
+    generation: u32,
+    /// How much has been appended to the journal since it was compacted.
+    journal_len: usize,
+}
+
+impl<S: Storage> Journal<S> {
+    /// Changes the blocks in `terrain`, which should have just been built, to
+    /// how they were saved in `storage`.
+    pub async fn load(mut storage: S, terrain: &Terrain) -> Result<Self, StorageError> {
+        let mut buf = [0u8; MAX_SNAPSHOT_SIZE];
+        let mut generation = 0;
+
+        if let Some(len) = storage.read(SNAPSHOT_KEY, &mut buf).await? {
+            let (batch, _) = Batch::decode(&buf[..len]).ok_or(StorageError::Corrupt)?;
+
+            generation = batch.generation;
+            batch.apply(terrain)?;
+        }
+
+        let mut journal = Self {
+            storage,
+            generation,
+            journal_len: 0,
+        };
+
+        let Some(len) = journal.storage.read(JOURNAL_KEY, &mut buf).await? else {
+            return Ok(journal);
+        };
+
+        let mut rest = &buf[..len];
+
+        while !rest.is_empty() {
+            let Some((batch, after)) = Batch::decode(rest) else {
+                // Anything appended after the damage would be lost with it.
+                warn!("The end of the world journal is damaged, so compacting it");
+                journal.compact(terrain).await?;
+
+                return Ok(journal);
+            };
+
+            if batch.generation == generation {
+                batch.apply(terrain)?;
+            }
+
+            rest = after;
+        }
+
+        journal.journal_len = len;
+
+        Ok(journal)
+    }
+
+    /// Saves the blocks changed since the last save, compacting the journal
+    /// instead if it has grown too large or changes were lost.
+    pub async fn save(&mut self, terrain: &Terrain) -> Result<(), StorageError> {
+        let mut changes = Vec::<(Coordinates, VarInt), MAX_UNSAVED_CHANGES>::new();
+
+        while let Ok((coordinates, state)) = UNSAVED_CHANGES.try_receive() {
+            // Only the latest state of each block matters.
+            changes.retain(|(changed, _)| *changed != coordinates);
+            let _ = changes.push((coordinates, state));
+        }
+
+        let lost = CHANGES_LOST.lock(|lost| lost.replace(false));
+
+        if lost || self.journal_len + MAX_BATCH_SIZE > MAX_JOURNAL_SIZE {
+            return self.compact(terrain).await;
+        }
+
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let mut buf = Vec::<u8, MAX_BATCH_SIZE>::new();
+        encode_batch(
+            &mut buf,
+            self.generation,
+            changes.iter().map(|(coordinates, state)| {
+                (coordinates.chunk(), coordinates.section_index(), *state)
+            }),
+        )?;
+
+        if let Err(e) = self.storage.append(JOURNAL_KEY, &buf).await {
+            // Whatever was appended can't be trusted, and the changes weren't
+            // saved.
+            CHANGES_LOST.lock(|lost| lost.set(true));
+            return Err(e);
+        }
+
+        self.journal_len += buf.len();
+
+        Ok(())
+    }
+
+    /// Saves every changed block as a new snapshot, and empties the journal.
+    pub async fn compact(&mut self, terrain: &Terrain) -> Result<(), StorageError> {
+        // Everything waiting to be saved is in the snapshot.
+        while UNSAVED_CHANGES.try_receive().is_ok() {}
+
+        let generation = self.generation.wrapping_add(1);
+        let mut buf = Vec::<u8, MAX_SNAPSHOT_SIZE>::new();
+
+        let encoded = terrain.with_modifications(|modifications| {
+            encode_batch(
+                &mut buf,
+                generation,
+                modifications
+                    .iter()
+                    .map(|change| (change.section(), change.index(), change.state)),
+            )
+        });
+
+        let saved = match encoded {
+            Ok(()) => self.storage.write(SNAPSHOT_KEY, &buf).await,
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = saved {
+            CHANGES_LOST.lock(|lost| lost.set(true));
+            return Err(e);
+        }
+
+        self.generation = generation;
+        self.journal_len = 0;
+
+        debug!(
+            "Saved a snapshot of the world's changes, taking {} bytes",
+            buf.len()
+        );
+
+        // The old journal is ignored now the generation has changed, so this
+        // failing only wastes space until the next compaction.
+        self.storage.remove(JOURNAL_KEY).await
+    }
+
+    /// Saves changes every [`SAVE_INTERVAL`] until the server shuts down, then
+    /// once more.
+    pub async fn run(mut self, terrain: &Terrain) {
+        use embassy_futures::select::{Either, select};
+
+        // Made once, so a signal arriving while saving isn't missed.
+        let mut shutdown = core::pin::pin!(crate::shutdown::shutdown_signal());
+
+        loop {
+            let shutting_down = matches!(
+                select(embassy_time::Timer::after(SAVE_INTERVAL), &mut shutdown).await,
+                Either::Second(())
+            );
+
+            if let Err(e) = self.save(terrain).await {
+                error!("Couldn't save the world's changes: {e}");
+            }
+
+            if shutting_down {
+                break;
+            }
+        }
+    }
+}
+
+/// A batch of changes, decoded from storage.
+struct Batch<'a> {
+    generation: u32,
+    sections: &'a [u8],
+}
+
+impl<'a> Batch<'a> {
+    /// Decodes the batch at the start of `bytes`, returning it and what comes
+    /// after it, or [`None`] if it is cut short or damaged.
+    fn decode(bytes: &'a [u8]) -> Option<(Self, &'a [u8])> {
+        let header = bytes.get(..BATCH_HEADER_SIZE)?;
+
+        if header[0] != FORMAT_VERSION {
+            return None;
+        }
+
+        let generation = u32::from_be_bytes([header[1], header[2], header[3], header[4]]);
+        let len = usize::from(u16::from_be_bytes([header[5], header[6]]));
+
+        let end = BATCH_HEADER_SIZE + len;
+        let crc = bytes.get(end..end + 4)?;
+
+        if CRC.checksum(&bytes[..end]).to_be_bytes() != crc {
+            return None;
+        }
+
+        let batch = Self {
+            generation,
+            sections: &bytes[BATCH_HEADER_SIZE..end],
+        };
+
+        Some((batch, &bytes[end + 4..]))
+    }
+
+    /// Changes each block in the batch in `terrain`.
+    fn apply(&self, terrain: &Terrain) -> Result<(), StorageError> {
+        let mut rest = self.sections;
+
+        while !rest.is_empty() {
+            let header = rest
+                .get(..SECTION_HEADER_SIZE)
+                .ok_or(StorageError::Corrupt)?;
+
+            if header[1] >= 16 {
+                return Err(StorageError::Corrupt);
+            }
+
+            let section = ChunkCoordinates::new(header[0] as i8, header[1], header[2] as i8);
+            let count = usize::from(u16::from_be_bytes([header[3], header[4]]));
+
+            let changes = rest
+                .get(SECTION_HEADER_SIZE..SECTION_HEADER_SIZE + count * CHANGE_SIZE)
+                .ok_or(StorageError::Corrupt)?;
+
+            for change in changes.chunks_exact(CHANGE_SIZE) {
+                let index = u16::from_be_bytes([change[0], change[1]]);
+                let state = VarInt(i32::from(u16::from_be_bytes([change[2], change[3]])));
+
+                if index >= 4096 {
+                    return Err(StorageError::Corrupt);
+                }
+
+                let coordinates = section.block(index);
+
+                if let Err(e) = terrain.set_block_state(coordinates, state) {
+                    warn!("Couldn't restore the block at {coordinates:?}: {e:?}");
+                }
+            }
+
+            rest = &rest[SECTION_HEADER_SIZE + changes.len()..];
+        }
+
+        Ok(())
+    }
+}
+
+/// Encodes `changes` into `buf` as a batch, with each run of changes in the
+/// same section under one section header.
+fn encode_batch<const N: usize>(
+    buf: &mut Vec<u8, N>,
+    generation: u32,
+    changes: impl Iterator<Item = (ChunkCoordinates, u16, VarInt)>,
+) -> Result<(), StorageError> {
+    put(buf, &[FORMAT_VERSION])?;
+    put(buf, &generation.to_be_bytes())?;
+    put(buf, &[0, 0])?;
+
+    // The current section, and where its count is.
+    let mut current: Option<(ChunkCoordinates, usize)> = None;
+
+    for (section, index, state) in changes {
+        let Ok(state) = u16::try_from(*state) else {
+            warn!("Can't save block state {}, as it is too large", *state);
+            continue;
+        };
+
+        let count_at = match current {
+            Some((current, count_at)) if current == section => count_at,
+            _ => {
+                put(buf, &[section.x as u8, section.y, section.z as u8])?;
+                let count_at = buf.len();
+                put(buf, &[0, 0])?;
+
+                current = Some((section, count_at));
+                count_at
+            }
+        };
+
+        let count = u16::from_be_bytes([buf[count_at], buf[count_at + 1]]) + 1;
+        buf[count_at..count_at + 2].copy_from_slice(&count.to_be_bytes());
+
+        put(buf, &index.to_be_bytes())?;
+        put(buf, &state.to_be_bytes())?;
+    }
+
+    let len = u16::try_from(buf.len() - BATCH_HEADER_SIZE).map_err(|_| StorageError::TooLarge)?;
+    buf[BATCH_HEADER_SIZE - 2..BATCH_HEADER_SIZE].copy_from_slice(&len.to_be_bytes());
+
+    let crc = CRC.checksum(buf);
+    put(buf, &crc.to_be_bytes())
+}
+
+fn put<const N: usize>(buf: &mut Vec<u8, N>, bytes: &[u8]) -> Result<(), StorageError> {
+    buf.extend_from_slice(bytes)
+        .map_err(|_| StorageError::TooLarge)
+}
+
+#[cfg(test)]
+mod tests {
+    use picocraft_terrain::TerrainBuilder;
+    use picocraft_terrain::terrain::blocks::Block;
+
+    use super::*;
+    use crate::storage::memory::MemoryStorage;
+
+    const STONE: VarInt = VarInt(Block::Stone as i32);
+
+    fn change(terrain: &Terrain, coordinates: Coordinates, state: VarInt) {
+        terrain
+            .set_block_state(coordinates, state)
+            .expect("room for the change");
+        record(coordinates, state);
+    }
+
+    #[tokio::test]
+    async fn changes_are_replayed_from_the_snapshot_and_journal() {
+        let a = Coordinates::new(0, 200, 0);
+        let b = Coordinates::new(-17, 201, 33);
+        let c = Coordinates::new(5, 202, -5);
+
+        let terrain = TerrainBuilder::new(0).build();
+        let mut journal = Journal::load(MemoryStorage::default(), &terrain)
+            .await
+            .expect("storage works");
+
+        change(&terrain, a, STONE);
+        journal.compact(&terrain).await.expect("storage works");
+
+        change(&terrain, b, STONE);
+        change(&terrain, c, STONE);
+        change(&terrain, c, VarInt(Block::Dirt as i32));
+        journal.save(&terrain).await.expect("storage works");
+
+        let mut storage = journal.storage;
+        assert!(storage.0.contains_key(SNAPSHOT_KEY));
+
+        // A batch cut short by a power cut, which is dropped.
+        let journal_len = storage.0[JOURNAL_KEY].len();
+        storage
+            .0
+            .get_mut(JOURNAL_KEY)
+            .expect("there is a journal")
+            .extend_from_slice(&[FORMAT_VERSION, 0, 0]);
+
+        let terrain = TerrainBuilder::new(0).build();
+        let journal = Journal::load(storage, &terrain)
+            .await
+            .expect("storage works");
+
+        assert_eq!(terrain.block_state_at(a), Some(STONE));
+        assert_eq!(terrain.block_state_at(b), Some(STONE));
+        assert_eq!(terrain.block_state_at(c), Some(VarInt(Block::Dirt as i32)));
+
+        // The damaged journal was compacted away.
+        assert!(journal_len > 0);
+        assert!(!journal.storage.0.contains_key(JOURNAL_KEY));
+        assert_eq!(journal.generation, 2);
+    }
+}
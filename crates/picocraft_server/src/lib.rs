@@ -15,15 +15,23 @@
 #[cfg(feature = "std")]
 extern crate std;
 
+pub mod access;
 pub mod channels;
 pub mod client;
 pub mod config;
 pub mod errors;
+pub mod forwarding;
 pub mod handlers;
+pub mod journal;
+pub mod player_data;
+pub mod plugin_channels;
+pub mod registries;
 pub mod server;
 pub mod shutdown;
+pub mod storage;
 pub mod systems;
 pub mod tick;
+pub mod transport;
 
 pub use client::Client;
 pub use server::Server;
@@ -44,6 +52,8 @@ pub mod prelude {
     pub(crate) use crate::errors::*;
     pub(crate) use crate::handlers::HandlePacket;
     pub use crate::server::Server;
+    pub use crate::storage::Storage;
+    pub use crate::transport::{Listener, Transport};
 }
 
 use core::cell::RefCell;
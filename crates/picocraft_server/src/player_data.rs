@@ -0,0 +1,237 @@
+//! Keeping players' save data in storage, so they come back where they left
+//! off after a restart.
+//!
+//! The world is ticked without waiting on anything, so a [`PlayerData`] task
+//! which owns the storage reads and writes save data for it, as asked through
+//! [`save`] and [`load`]. The world still remembers the players who left most
+//! recently, so they can rejoin without waiting on storage.
+
+use core::cell::Cell;
+use core::fmt::Write as _;
+
+use embassy_sync::blocking_mutex::Mutex as BlockingMutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::Channel;
+use picocraft_ecs::commands::WorldCommand;
+use picocraft_ecs::pools::PlayerSaveData;
+
+use crate::channels::COMMANDS;
+use crate::client::buffer::Buffer;
+use crate::prelude::*;
+use crate::storage::Storage;
+
+/// How often every player in the world is saved, a minute at 20 ticks a
+/// second.
+pub const AUTOSAVE_INTERVAL_TICKS: u64 = 1200;
+
+/// Bumped whenever the stored format changes.
+const FORMAT_VERSION: u8 = 2;
+
+/// Enough for a full inventory, which takes up most of it.
+const SAVE_BUFFER_SIZE: usize = 512;
+
+/// Most requests which can wait for the storage at once.
+const MAX_REQUESTS: usize = 32;
+
+// Nothing can be boxed without an allocator, and saves are most of what's sent.
+#[allow(clippy::large_enum_variant)]
+enum Request {
+    Save(PlayerSaveData),
+    Load(UUID),
+    /// Everything asked for before this has been done.
+    Flush,
+}
+
+static REQUESTS: Channel<CriticalSectionRawMutex, Request, MAX_REQUESTS> = Channel::new();
+
+/// Whether a [`PlayerData`] task is running, so save data is kept in storage.
+static STORED: BlockingMutex<CriticalSectionRawMutex, Cell<bool>> =
+    BlockingMutex::new(Cell::new(false));
+
+/// Whether save data is kept in storage, rather than only by the world.
+pub fn is_stored() -> bool {
+    STORED.lock(Cell::get)
+}
+
+/// Asks for `save` to be written to storage, returning whether it will be.
+pub fn save(save: PlayerSaveData) -> bool {
+    is_stored() && REQUESTS.try_send(Request::Save(save)).is_ok()
+}
+
+/// Asks for the save data of the player with `uuid` to be read from storage,
+/// returning whether it will be. Once read, it is sent back to the world with
+/// [`WorldCommand::PlayerDataLoaded`].
+pub fn load(uuid: UUID) -> bool {
+    is_stored() && REQUESTS.try_send(Request::Load(uuid)).is_ok()
+}
+
+/// Notes that everything asked for so far should be finished before the
+/// [`PlayerData`] task stops.
+pub fn flush() -> bool {
+    is_stored() && REQUESTS.try_send(Request::Flush).is_ok()
+}
+
+/// Players' save data in a [`Storage`], each under their UUID.
+pub struct PlayerData<S> {
+    storage: S,
+}
+
+impl<S: Storage> PlayerData<S> {
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Writes `save` to storage, replacing what was there for the player.
+    pub async fn write(&mut self, save: &PlayerSaveData) -> Result<(), StorageError> {
+        let mut buf = Buffer::<SAVE_BUFFER_SIZE>::new();
+
+        FORMAT_VERSION.encode(&mut buf).await?;
+        save.encode(&mut buf).await?;
+
+        self.storage.write(&key(save.uuid.0), &buf).await
+    }
+
+    /// Reads the save data of the player with `uuid`, or [`None`] if they
+    /// have none.
+    pub async fn read(&mut self, uuid: UUID) -> Result<Option<PlayerSaveData>, StorageError> {
+        let mut buf = [0u8; SAVE_BUFFER_SIZE];
+
+        let Some(len) = self.storage.read(&key(uuid), &mut buf).await? else {
+            return Ok(None);
+        };
+
+        let mut value = &buf[..len];
+
+        if u8::decode(&mut value).await? != FORMAT_VERSION {
+            return Err(StorageError::Corrupt);
+        }
+
+        Ok(Some(PlayerSaveData::decode(&mut value).await?))
+    }
+
+    /// Does what `request` asks, returning whether it was a flush.
+    async fn handle(&mut self, request: Request) -> bool {
+        match request {
+            Request::Save(save) => {
+                if let Err(e) = self.write(&save).await {
+                    error!("Couldn't save {} [{}]: {e}", save.username.0, save.uuid.0);
+                }
+            }
+            Request::Load(uuid) => {
+                let save = match self.read(uuid).await {
+                    Ok(save) => save,
+                    // It can't be used, so they start again rather than never
+                    // being let in.
+                    Err(StorageError::Corrupt) => {
+                        warn!("The save data of {uuid} is corrupt, so they're starting again.");
+                        None
+                    }
+                    // The login is turned away once it has waited too long.
+                    Err(e) => {
+                        error!("Couldn't load the save data of {uuid}: {e}");
+                        return false;
+                    }
+                };
+
+                COMMANDS
+                    .send(WorldCommand::PlayerDataLoaded { uuid, save })
+                    .await;
+            }
+            Request::Flush => return true,
+        }
+
+        false
+    }
+
+    /// Reads and writes save data for the world until the server shuts down,
+    /// then saves every player once more.
+    pub async fn run(mut self) {
+        use embassy_futures::select::{Either, select};
+
+        /// How long the world has to hand over its players once shutting down.
+        const FLUSH_TIMEOUT: embassy_time::Duration = embassy_time::Duration::from_secs(5);
+
+        STORED.lock(|stored| stored.set(true));
+
+        // Made once, so a signal arriving while saving isn't missed.
+        let mut shutdown = core::pin::pin!(crate::shutdown::shutdown_signal());
+
+        while let Either::First(request) = select(REQUESTS.receive(), &mut shutdown).await {
+            self.handle(request).await;
+        }
+
+        COMMANDS.send(WorldCommand::SavePlayers).await;
+
+        let flushed = embassy_time::with_timeout(FLUSH_TIMEOUT, async {
+            while !self.handle(REQUESTS.receive().await).await {}
+        })
+        .await;
+
+        if flushed.is_err() {
+            error!("The world didn't hand over its players in time, so they weren't saved.");
+        }
+    }
+}
+
+/// Where the save data of the player with `uuid` is kept.
+fn key(uuid: UUID) -> String<40> {
+    let mut key = String::new();
+    let _ = write!(key, "players/{:032x}", uuid.as_u128());
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use picocraft_ecs::components::*;
+    use picocraft_proto::serverbound::Hand;
+
+    use super::*;
+    use crate::storage::memory::MemoryStorage;
+
+    #[tokio::test]
+    async fn save_data_survives_a_round_trip_through_storage() {
+        let mut player_data = PlayerData::new(MemoryStorage::default());
+        let uuid = UUID::from_u128(0x1234);
+
+        assert!(
+            player_data
+                .read(uuid)
+                .await
+                .expect("storage works")
+                .is_none()
+        );
+
+        let save = PlayerSaveData {
+            uuid: Uuid(uuid),
+            username: Username(String::try_from("Steve").expect("short enough")),
+            health: Health(17.5),
+            position: Position::new(10.0, 70.5, -4.25),
+            rotation: Rotation::new(90.0, -12.0),
+            dimension: Some(Dimension::Nether),
+            inventory: {
+                let mut inventory = Inventory::new();
+                inventory.set(Inventory::FIRST_HOTBAR_SLOT + 2, ItemStack::new(35, 64));
+                inventory.select(2);
+                inventory
+            },
+        };
+
+        player_data.write(&save).await.expect("storage works");
+
+        let read = player_data
+            .read(uuid)
+            .await
+            .expect("storage works")
+            .expect("it was written");
+
+        assert_eq!(read.username.0, save.username.0);
+        assert_eq!(read.health.0, save.health.0);
+        assert_eq!(read.position.z, save.position.z);
+        assert_eq!(read.rotation.pitch, save.rotation.pitch);
+        assert!(matches!(read.dimension, Some(Dimension::Nether)));
+        assert_eq!(
+            read.inventory.held_item(Hand::MainHand),
+            ItemStack::new(35, 64)
+        );
+    }
+}
@@ -0,0 +1,236 @@
+//! Plugin messages let client mods (and proxies) exchange arbitrary data with
+//! the server on named channels. Handlers are registered for a channel in
+//! [`ServerConfig::plugin_channels`], and payloads received on it are routed to
+//! them. Anything on a channel nobody registered is ignored, as vanilla does.
+//!
+//! ```ignore
+//! fn companion(context: &mut PluginMessageContext<'_>, data: &[u8]) {
+//!     let _ = context.reply(data);
+//! }
+//!
+//! config.plugin_channels.register("picocraft:companion", companion)?;
+//! ```
+
+use picocraft_ecs::commands::WorldCommand;
+use picocraft_ecs::entity::EntityId;
+
+use crate::channels::COMMANDS;
+use crate::client::player::Player;
+use crate::prelude::*;
+
+/// Most channels which can have a handler at once.
+pub const MAX_PLUGIN_CHANNELS: usize = 8;
+
+/// Longest client brand which is recorded. Vanilla's is `vanilla`, and mod
+/// loaders use similarly short names.
+pub const MAX_BRAND_LENGTH: usize = 64;
+
+/// Handles a payload received on a registered channel. It runs on the
+/// sending player's connection, so must not block.
+pub type PluginMessageHandler = fn(&mut PluginMessageContext<'_>, &[u8]);
+
+/// What a [`PluginMessageHandler`] knows about the player who sent the
+/// message, and how it can answer them.
+pub struct PluginMessageContext<'a> {
+    player: &'a Player,
+    player_id: Option<EntityId>,
+    state: State,
+    reply: Option<Vec<u8, MAX_PLUGIN_MESSAGE_SIZE>>,
+}
+
+impl<'a> PluginMessageContext<'a> {
+    pub(crate) fn new(player: &'a Player, player_id: Option<EntityId>, state: State) -> Self {
+        Self {
+            player,
+            player_id,
+            state,
+            reply: None,
+        }
+    }
+
+    pub fn player(&self) -> &Player {
+        self.player
+    }
+
+    /// The player's entity, which only exists once they have joined the
+    /// world, so is [`None`] during configuration.
+    pub fn player_id(&self) -> Option<EntityId> {
+        self.player_id
+    }
+
+    /// Either [`State::Configuration`] or [`State::Play`].
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Sends `data` back to the player on the same channel, once the handler
+    /// returns. Replying again replaces the earlier reply.
+    pub fn reply(&mut self, data: &[u8]) -> Result<(), PluginChannelError> {
+        let data =
+            Vec::from_slice(data).map_err(|_| PluginChannelError::PayloadTooLarge(data.len()))?;
+
+        self.reply = Some(data);
+
+        Ok(())
+    }
+
+    pub(crate) fn take_reply(&mut self) -> Option<Vec<u8, MAX_PLUGIN_MESSAGE_SIZE>> {
+        self.reply.take()
+    }
+}
+
+/// The handler for each registered channel.
+#[derive(Debug, Default)]
+pub struct PluginChannels {
+    handlers: Vec<(PluginChannel, PluginMessageHandler), MAX_PLUGIN_CHANNELS>,
+}
+
+impl PluginChannels {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Routes messages on `channel` to `handler`, replacing any handler
+    /// already registered for it.
+    pub fn register(
+        &mut self,
+        channel: &str,
+        handler: PluginMessageHandler,
+    ) -> Result<(), PluginChannelError> {
+        let channel =
+            PluginChannel::try_from(channel).map_err(|_| PluginChannelError::InvalidChannel)?;
+
+        if let Some(registered) = self
+            .handlers
+            .iter_mut()
+            .find(|(registered, _)| registered.matches(channel.as_str()))
+        {
+            registered.1 = handler;
+            return Ok(());
+        }
+
+        self.handlers
+            .push((channel, handler))
+            .map_err(|_| PluginChannelError::TooManyChannels)
+    }
+
+    /// Stops routing messages on `channel`, returning whether it had a
+    /// handler.
+    pub fn unregister(&mut self, channel: &str) -> bool {
+        let count = self.handlers.len();
+
+        self.handlers
+            .retain(|(registered, _)| !registered.matches(channel));
+
+        self.handlers.len() != count
+    }
+
+    pub fn handler(&self, channel: &PluginChannel) -> Option<PluginMessageHandler> {
+        self.handlers
+            .iter()
+            .find(|(registered, _)| registered.matches(channel.as_str()))
+            .map(|(_, handler)| *handler)
+    }
+}
+
+/// Sends a plugin message to a player in the world. This goes through the
+/// world, so the payload is limited to
+/// [`MAX_EVENT_PLUGIN_MESSAGE_SIZE`](picocraft_ecs::events::MAX_EVENT_PLUGIN_MESSAGE_SIZE)
+/// bytes; use [`Client::send_plugin_message`] on the player's own connection
+/// for more.
+pub async fn send_to_player(
+    player_id: EntityId,
+    channel: &str,
+    data: &[u8],
+) -> Result<(), PluginChannelError> {
+    send(Some(player_id), channel, data).await
+}
+
+/// Sends a plugin message to every player in the world, with the same limits
+/// as [`send_to_player`].
+pub async fn send_to_all(channel: &str, data: &[u8]) -> Result<(), PluginChannelError> {
+    send(None, channel, data).await
+}
+
+async fn send(
+    player_id: Option<EntityId>,
+    channel: &str,
+    data: &[u8],
+) -> Result<(), PluginChannelError> {
+    let channel =
+        PluginChannel::try_from(channel).map_err(|_| PluginChannelError::InvalidChannel)?;
+    let data =
+        Vec::from_slice(data).map_err(|_| PluginChannelError::PayloadTooLarge(data.len()))?;
+
+    COMMANDS
+        .send(WorldCommand::SendPluginMessage {
+            player_id,
+            channel,
+            data,
+        })
+        .await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first(context: &mut PluginMessageContext<'_>, _data: &[u8]) {
+        context.reply(b"first").expect("reply fits");
+    }
+
+    fn second(context: &mut PluginMessageContext<'_>, _data: &[u8]) {
+        context.reply(b"second").expect("reply fits");
+    }
+
+    /// Runs the handler for `channel`, returning its reply.
+    fn reply(channels: &PluginChannels, channel: &str) -> Option<Vec<u8, MAX_PLUGIN_MESSAGE_SIZE>> {
+        let channel = PluginChannel::try_from(channel).expect("valid channel");
+        let handler = channels.handler(&channel)?;
+
+        let player = Player::default();
+        let mut context = PluginMessageContext::new(&player, None, State::Configuration);
+        handler(&mut context, &[]);
+
+        context.take_reply()
+    }
+
+    #[test]
+    fn handlers_are_found_with_or_without_the_namespace() {
+        let mut channels = PluginChannels::new();
+
+        channels
+            .register("brand", first)
+            .expect("room for a channel");
+        channels
+            .register("picocraft:companion", first)
+            .expect("room for a channel");
+        channels
+            .register("picocraft:companion", second)
+            .expect("replacing doesn't take more room");
+
+        assert_eq!(
+            reply(&channels, "minecraft:brand").as_deref(),
+            Some(&b"first"[..])
+        );
+        assert_eq!(
+            reply(&channels, "picocraft:companion").as_deref(),
+            Some(&b"second"[..])
+        );
+        assert_eq!(reply(&channels, "picocraft:other"), None);
+
+        assert!(channels.unregister("minecraft:brand"));
+        assert!(!channels.unregister("minecraft:brand"));
+        assert_eq!(reply(&channels, "brand"), None);
+
+        assert!(matches!(
+            channels.register("Not A Channel", first),
+            Err(PluginChannelError::InvalidChannel)
+        ));
+    }
+}
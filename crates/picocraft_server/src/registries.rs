@@ -0,0 +1,334 @@
+//! The registries sent to every client during configuration.
+//!
+//! Biomes come from [`Biome`], so the registry always matches the biomes the
+//! terrain refers to in chunk data. Everything else is vanilla, trimmed down
+//! to what the client needs: one variant of each mob and painting is enough,
+//! but every damage type must exist.
+
+use picocraft_proto::registry::{
+    DamageType, DimensionType, MobVariant, PaintingVariant, RegistryElement, RegistryEntry,
+    WolfSoundVariant, WolfVariant,
+};
+use picocraft_terrain::terrain::biomes::Biome;
+
+use crate::prelude::*;
+
+/// Dimension types, where the first is used for the world.
+pub const DIMENSION_TYPES: &[RegistryEntry<'static, DimensionType>] = &[
+    RegistryEntry::new("overworld", DimensionType::OVERWORLD),
+    RegistryEntry::new("the_nether", DimensionType::THE_NETHER),
+    RegistryEntry::new("the_end", DimensionType::THE_END),
+];
+
+pub const CAT_VARIANTS: &[RegistryEntry<'static, MobVariant>] = &[RegistryEntry::new(
+    "red",
+    MobVariant::new("minecraft:entity/cat/red"),
+)];
+
+pub const CHICKEN_VARIANTS: &[RegistryEntry<'static, MobVariant>] = &[RegistryEntry::new(
+    "temperate",
+    MobVariant::new("minecraft:entity/chicken/temperate_chicken"),
+)];
+
+pub const COW_VARIANTS: &[RegistryEntry<'static, MobVariant>] = &[RegistryEntry::new(
+    "temperate",
+    MobVariant::new("minecraft:entity/cow/temperate_cow"),
+)];
+
+pub const FROG_VARIANTS: &[RegistryEntry<'static, MobVariant>] = &[RegistryEntry::new(
+    "temperate",
+    MobVariant::new("minecraft:entity/frog/temperate_frog"),
+)];
+
+pub const PIG_VARIANTS: &[RegistryEntry<'static, MobVariant>] = &[RegistryEntry::new(
+    "temperate",
+    MobVariant::new("minecraft:entity/pig/temperate_pig"),
+)];
+
+pub const WOLF_VARIANTS: &[RegistryEntry<'static, WolfVariant>] = &[RegistryEntry::new(
+    "pale",
+    WolfVariant {
+        wild: "minecraft:entity/wolf/wolf",
+        tame: "minecraft:entity/wolf/wolf_tame",
+        angry: "minecraft:entity/wolf/wolf_angry",
+    },
+)];
+
+pub const WOLF_SOUND_VARIANTS: &[RegistryEntry<'static, WolfSoundVariant>] = &[RegistryEntry::new(
+    "classic",
+    WolfSoundVariant {
+        ambient_sound: "minecraft:entity.wolf.ambient",
+        death_sound: "minecraft:entity.wolf.death",
+        growl_sound: "minecraft:entity.wolf.growl",
+        hurt_sound: "minecraft:entity.wolf.hurt",
+        pant_sound: "minecraft:entity.wolf.pant",
+        whine_sound: "minecraft:entity.wolf.whine",
+    },
+)];
+
+pub const PAINTING_VARIANTS: &[RegistryEntry<'static, PaintingVariant>] = &[RegistryEntry::new(
+    "fire",
+    PaintingVariant {
+        asset_id: "minecraft:fire",
+        width: 2,
+        height: 2,
+        title: Some({
+            let mut title = TextComponent::translatable("painting.minecraft.fire.title", &[]);
+            title.style.color = Some(Color::Named(NamedColor::Yellow));
+            title
+        }),
+        author: None,
+    },
+)];
+
+/// Sends a Registry Data packet for every registry.
+pub async fn encode_registry_data<T: Transport>(client: &mut Client<T>) -> Result<(), PacketError> {
+    encode(client, "dimension_type", DIMENSION_TYPES).await?;
+    encode(client, "worldgen/biome", &Biome::registry_entries()).await?;
+    encode(client, "damage_type", DamageType::VANILLA).await?;
+    encode(client, "cat_variant", CAT_VARIANTS).await?;
+    encode(client, "chicken_variant", CHICKEN_VARIANTS).await?;
+    encode(client, "cow_variant", COW_VARIANTS).await?;
+    encode(client, "frog_variant", FROG_VARIANTS).await?;
+    encode(client, "pig_variant", PIG_VARIANTS).await?;
+    encode(client, "wolf_variant", WOLF_VARIANTS).await?;
+    encode(client, "wolf_sound_variant", WOLF_SOUND_VARIANTS).await?;
+    encode(client, "painting_variant", PAINTING_VARIANTS).await
+}
+
+async fn encode<T: Transport, E: RegistryElement>(
+    client: &mut Client<T>,
+    registry: &str,
+    entries: &[RegistryEntry<'_, E>],
+) -> Result<(), PacketError> {
+    client
+        .encode_packet(&clientbound::RegistryDataPacket::new(registry, entries))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::ToOwned;
+    use std::format;
+    use std::string::String;
+    use std::vec::Vec;
+
+    use picocraft_core::types::nbt::{Event, NbtReader, Tag};
+
+    use super::*;
+    use crate::client::buffer::Buffer;
+
+    /// An entry's ID, and its NBT as sorted `path = value` lines so the order
+    /// fields are written in doesn't matter.
+    type Entry = (String, Vec<String>);
+
+    /// Vanilla writes numbers with the smallest tag that holds them, and the
+    /// client reads any numeric tag as whatever number it expects, so only
+    /// the value is compared.
+    fn number(tag: Tag<'_>) -> String {
+        match tag {
+            Tag::Byte(n) => format!("{n}"),
+            Tag::Short(n) => format!("{n}"),
+            Tag::Int(n) => format!("{n}"),
+            Tag::Long(n) => format!("{n}"),
+            Tag::Float(n) => format!("{}", f64::from(n)),
+            Tag::Double(n) => format!("{n}"),
+            tag => format!("{tag:?}"),
+        }
+    }
+
+    /// Takes apart a Registry Data packet, including its packet ID.
+    async fn entries(mut packet: &[u8]) -> (String, Vec<Entry>) {
+        let packet_id = VarInt::decode(&mut packet).await.expect("a packet ID");
+        assert_eq!(packet_id, clientbound::RegistryDataPacket::<DamageType>::ID);
+
+        let registry = heapless::String::<64>::decode(&mut packet)
+            .await
+            .expect("a registry");
+        let len = VarInt::decode(&mut packet).await.expect("an entry count");
+
+        let mut entries = Vec::new();
+
+        for _ in 0..*len {
+            let id = heapless::String::<64>::decode(&mut packet)
+                .await
+                .expect("an entry ID");
+            assert!(
+                bool::decode(&mut packet).await.expect("a flag"),
+                "{id} has data"
+            );
+
+            let mut lines = Vec::new();
+            let mut path = Vec::new();
+            let mut nbt = NbtReader::<_, 1024>::new(&mut packet, usize::MAX);
+
+            while let Some(event) = nbt.next().await.expect("valid NBT") {
+                match event {
+                    Event::Value { name, tag } => {
+                        lines.push(format!("{}/{name} = {}", path.join("/"), number(tag)));
+                    }
+                    Event::BeginCompound { name } => path.push(name.to_owned()),
+                    Event::BeginList { name, element, len } => {
+                        lines.push(format!("{}/{name} = {len} x {element:?}", path.join("/")));
+                        path.push(name.to_owned());
+                    }
+                    Event::End => drop(path.pop()),
+                }
+            }
+
+            lines.sort();
+            entries.push((id.as_str().to_owned(), lines));
+        }
+
+        assert!(packet.is_empty(), "{registry} has trailing data");
+
+        (registry.as_str().to_owned(), entries)
+    }
+
+    async fn encoded<E: RegistryElement>(
+        registry: &str,
+        registry_entries: &[RegistryEntry<'_, E>],
+    ) -> (String, Vec<Entry>) {
+        let mut packet = Buffer::<8192>::new();
+        clientbound::RegistryDataPacket::new(registry, registry_entries)
+            .encode(&mut packet)
+            .await
+            .expect("packet fits");
+
+        entries(&packet).await
+    }
+
+    /// Checks every entry we send is the same as the vanilla entry with the
+    /// same ID in `vanilla`, a Registry Data packet captured from a vanilla
+    /// server.
+    async fn assert_matches_vanilla<E: RegistryElement>(
+        vanilla: &[u8],
+        registry: &str,
+        registry_entries: &[RegistryEntry<'_, E>],
+    ) -> usize {
+        let (vanilla_registry, vanilla_entries) = entries(vanilla).await;
+        let (ours_registry, ours) = encoded(registry, registry_entries).await;
+
+        assert_eq!(ours_registry.trim_start_matches("minecraft:"), registry);
+        assert_eq!(vanilla_registry, format!("minecraft:{registry}"));
+
+        let mut matched = 0;
+
+        for (id, lines) in &ours {
+            if let Some((_, vanilla_lines)) = vanilla_entries
+                .iter()
+                .find(|(vanilla_id, _)| vanilla_id == id)
+            {
+                assert_eq!(lines, vanilla_lines, "{registry} {id} differs from vanilla");
+                matched += 1;
+            }
+        }
+
+        matched
+    }
+
+    #[test]
+    fn damage_types_match_vanilla() {
+        embassy_futures::block_on(async {
+            let vanilla = include_bytes!("registries/damage_types.bin");
+
+            let matched = assert_matches_vanilla(vanilla, "damage_type", DamageType::VANILLA).await;
+            let (_, vanilla_entries) = entries(vanilla).await;
+
+            assert_eq!(matched, vanilla_entries.len(), "a damage type is missing");
+            assert_eq!(matched, DamageType::VANILLA.len());
+        });
+    }
+
+    #[test]
+    fn dimension_types_match_vanilla() {
+        embassy_futures::block_on(async {
+            let vanilla = include_bytes!("registries/dimension_types.bin");
+
+            let matched = assert_matches_vanilla(vanilla, "dimension_type", DIMENSION_TYPES).await;
+            assert_eq!(matched, DIMENSION_TYPES.len());
+        });
+    }
+
+    #[test]
+    fn variants_match_vanilla() {
+        embassy_futures::block_on(async {
+            for matched in [
+                assert_matches_vanilla(
+                    include_bytes!("registries/cat_variant.bin"),
+                    "cat_variant",
+                    CAT_VARIANTS,
+                )
+                .await,
+                assert_matches_vanilla(
+                    include_bytes!("registries/chicken_variant.bin"),
+                    "chicken_variant",
+                    CHICKEN_VARIANTS,
+                )
+                .await,
+                assert_matches_vanilla(
+                    include_bytes!("registries/cow_variant.bin"),
+                    "cow_variant",
+                    COW_VARIANTS,
+                )
+                .await,
+                assert_matches_vanilla(
+                    include_bytes!("registries/frog_variant.bin"),
+                    "frog_variant",
+                    FROG_VARIANTS,
+                )
+                .await,
+                assert_matches_vanilla(
+                    include_bytes!("registries/pig_variant.bin"),
+                    "pig_variant",
+                    PIG_VARIANTS,
+                )
+                .await,
+                assert_matches_vanilla(
+                    include_bytes!("registries/wolf_variant.bin"),
+                    "wolf_variant",
+                    WOLF_VARIANTS,
+                )
+                .await,
+                assert_matches_vanilla(
+                    include_bytes!("registries/wolf_sound_variant.bin"),
+                    "wolf_sound_variant",
+                    WOLF_SOUND_VARIANTS,
+                )
+                .await,
+                assert_matches_vanilla(
+                    include_bytes!("registries/painting_variant.bin"),
+                    "painting_variant",
+                    PAINTING_VARIANTS,
+                )
+                .await,
+            ] {
+                assert_eq!(matched, 1);
+            }
+        });
+    }
+
+    #[test]
+    fn biomes_match_vanilla() {
+        embassy_futures::block_on(async {
+            let (_, vanilla) = entries(include_bytes!("registries/worldgen_biome.bin")).await;
+            let (_, ours) = encoded("worldgen/biome", &Biome::registry_entries()).await;
+
+            // Vanilla also sends what only the server needs to generate terrain,
+            // so just check what we send agrees with it.
+            for (id, vanilla_lines) in &vanilla {
+                let (_, lines) = ours
+                    .iter()
+                    .find(|(ours_id, _)| ours_id == id)
+                    .expect("vanilla biome is sent");
+
+                for line in lines {
+                    assert!(
+                        vanilla_lines.contains(line),
+                        "{id} {line} differs from vanilla"
+                    );
+                }
+            }
+        });
+    }
+}
@@ -1,7 +1,6 @@
 use embassy_futures::select::{Either, select};
 use picocraft_terrain::{Terrain, TerrainBuilder};
 use static_cell::StaticCell;
-use tokio::net::TcpListener;
 
 use crate::prelude::*;
 use crate::shutdown::shutdown_signal;
@@ -9,19 +8,15 @@ use crate::shutdown::shutdown_signal;
 static TERRAIN: StaticCell<Terrain> = StaticCell::new();
 
 #[allow(unused)]
-pub struct Server {
+pub struct Server<L> {
     pub config: &'static ServerConfig,
-    listener: TcpListener,
+    listener: L,
     pub terrain: &'static Terrain,
     pub system_rng: &'static SystemRng,
 }
 
-impl Server {
-    pub fn new(
-        config: &'static ServerConfig,
-        listener: TcpListener,
-        system_rng: &'static SystemRng,
-    ) -> Self {
+impl<L: Listener> Server<L> {
+    pub fn new(config: &'static ServerConfig, listener: L, system_rng: &'static SystemRng) -> Self {
         let terrain = TERRAIN.init_with(|| TerrainBuilder::new(config.seed).build());
 
         Server {
@@ -32,10 +27,12 @@ impl Server {
         }
     }
 
-    pub async fn next_connection(&mut self) -> Result<Option<Client>, PicocraftError> {
+    pub async fn next_connection(
+        &mut self,
+    ) -> Result<Option<Client<L::Transport>>, PicocraftError> {
         match select(self.listener.accept(), shutdown_signal()).await {
-            Either::First(Ok((socket, addr))) => {
-                info!("New connection from: {}", &addr);
+            Either::First(Ok(socket)) => {
+                info!("New connection from: {}", socket.remote_endpoint());
 
                 let client = Client::new(socket, self.system_rng, self.config, self.terrain);
 
@@ -55,8 +52,96 @@ impl Server {
 
 #[cfg(feature = "embassy")]
 #[embassy_executor::task]
-pub async fn handle_connection_task(mut client: Client) -> ! {
-    let _ = client.handle_connection().await;
+pub async fn handle_connection_task(
+    mut client: Client<crate::transport::embassy::EmbassyTransport<'static>>,
+) {
+    // Returning frees the task for the next connection.
+    match client.handle_connection().await {
+        Ok(()) => debug!(
+            "Connection with {:?} finished successfully.",
+            client.connection.remote_endpoint()
+        ),
+        Err(_) => error!(
+            "Connection with {:?} ended with an error.",
+            client.connection.remote_endpoint()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+
+    use embassy_futures::join::join;
+    use embassy_sync::mutex::Mutex;
+    use picocraft_proto::clientbound::PongResponsePacket;
+    use picocraft_proto::serverbound::PingRequestPacket;
+    use picocraft_proto::serverbound::handshake::{HandshakePacket, Intent};
+    use rand_chacha::ChaCha8Rng;
+    use rand_chacha::rand_core::SeedableRng;
+
+    use super::*;
+    use crate::client::buffer::Buffer;
+    use crate::transport::memory::{MemoryListener, MemoryPipe, MemoryTransport};
+
+    static LISTENER: MemoryListener = MemoryListener::new();
+    static PIPE: MemoryPipe = MemoryPipe::new();
+    static CONFIG: StaticCell<ServerConfig> = StaticCell::new();
+    static SYSTEM_RNG: StaticCell<SystemRng> = StaticCell::new();
+
+    async fn send<P: Packet>(transport: &mut MemoryTransport<'_>, packet: &P) {
+        let mut data = Buffer::<256>::new();
+        packet.encode(&mut data).await.expect("packet fits");
+
+        VarInt(data.len() as i32)
+            .encode(&mut *transport)
+            .await
+            .expect("pipe is open");
+        transport.write_all(&data).await.expect("pipe is open");
+    }
 
-    loop {}
+    #[tokio::test]
+    async fn answers_a_ping_over_an_in_memory_connection() {
+        let config = CONFIG.init_with(ServerConfig::default);
+        let system_rng =
+            SYSTEM_RNG.init_with(|| Mutex::new(RefCell::new(ChaCha8Rng::seed_from_u64(0))));
+
+        let mut server = Server::new(config, &LISTENER, system_rng);
+
+        let serve = async {
+            let mut client = server
+                .next_connection()
+                .await
+                .expect("listener is open")
+                .expect("not shutting down");
+
+            let _ = client.handle_connection().await;
+        };
+
+        let ping = async {
+            let mut transport = LISTENER.connect(&PIPE).await;
+
+            send(
+                &mut transport,
+                &HandshakePacket {
+                    protocol_version: CURRENT_PROTOCOL_VERSION,
+                    server_address: String::try_from("localhost").expect("address is short"),
+                    server_port: 25565,
+                    intent: Intent::Status,
+                },
+            )
+            .await;
+            send(&mut transport, &PingRequestPacket { timestamp: 1234 }).await;
+
+            let length = VarInt::decode(&mut transport).await.expect("a response");
+            let packet_id = VarInt::decode(&mut transport).await.expect("a response");
+            let timestamp = Long::decode(&mut transport).await.expect("a pong");
+
+            assert_eq!(*length, 9);
+            assert_eq!(packet_id, PongResponsePacket::ID);
+            assert_eq!(timestamp, 1234);
+        };
+
+        join(serve, ping).await;
+    }
 }
@@ -21,3 +21,10 @@ pub async fn shutdown_signal() {
         let _ = signal::ctrl_c().await;
     }
 }
+
+/// There's no signal to wait for without an OS, so the server runs until it
+/// loses power.
+#[cfg(not(feature = "std"))]
+pub async fn shutdown_signal() {
+    core::future::pending().await
+}
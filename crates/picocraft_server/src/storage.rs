@@ -0,0 +1,43 @@
+//! Somewhere to keep data between restarts, as small values stored under
+//! names. On the desktop these are files, while a board can keep them in its
+//! flash with [`flash::FlashStorage`].
+
+#[cfg(feature = "std")]
+pub mod file;
+pub mod flash;
+#[cfg(test)]
+pub(crate) mod memory;
+
+use crate::prelude::*;
+
+/// A store of values under keys, which are short `/`-separated paths made of
+/// lowercase letters, digits, `-` and `_`, like `access/whitelist`.
+#[allow(async_fn_in_trait)]
+pub trait Storage {
+    /// Reads the value stored under `key` into `buf`, returning its length,
+    /// or [`None`] if nothing is stored under it.
+    async fn read(&mut self, key: &str, buf: &mut [u8]) -> Result<Option<usize>, StorageError>;
+
+    /// Stores `value` under `key`, replacing what was there. If this fails,
+    /// the old value is left as it was.
+    async fn write(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError>;
+
+    /// Adds `value` to the end of the value stored under `key`, or stores it
+    /// if there is none. A power cut part way through can leave only some of
+    /// `value` added, so anything appended to should be able to tell.
+    async fn append(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError>;
+
+    /// Removes the value stored under `key`, if there is one.
+    async fn remove(&mut self, key: &str) -> Result<(), StorageError>;
+}
+
+/// Whether `key` is made of the characters allowed in keys, with no empty
+/// parts.
+pub fn is_valid_key(key: &str) -> bool {
+    key.split('/').all(|part| {
+        !part.is_empty()
+            && part
+                .bytes()
+                .all(|b| matches!(b, b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_'))
+    })
+}
@@ -0,0 +1,100 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use super::{Storage, is_valid_key};
+use crate::prelude::*;
+
+/// Keeps each value in its own file under a directory.
+#[derive(Debug, Clone)]
+pub struct FileStorage {
+    root: PathBuf,
+}
+
+impl FileStorage {
+    /// Stores values under `root`, which is created when something is first
+    /// written.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, StorageError> {
+        if !is_valid_key(key) {
+            return Err(StorageError::InvalidKey);
+        }
+
+        Ok(key
+            .split('/')
+            .fold(self.root.clone(), |path, part| path.join(part)))
+    }
+}
+
+impl Storage for FileStorage {
+    async fn read(&mut self, key: &str, buf: &mut [u8]) -> Result<Option<usize>, StorageError> {
+        let value = match tokio::fs::read(self.path(key)?).await {
+            Ok(value) => value,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let Some(buf) = buf.get_mut(..value.len()) else {
+            return Err(StorageError::TooLarge);
+        };
+
+        buf.copy_from_slice(&value);
+
+        Ok(Some(value.len()))
+    }
+
+    async fn write(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        use tokio::io::AsyncWriteExt;
+
+        let path = self.path(key)?;
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        // Written alongside and renamed over the old value, so a crash part way
+        // through can't leave half a value behind.
+        let partial = path.with_extension("partial");
+
+        let mut file = tokio::fs::File::create(&partial).await?;
+        file.write_all(value).await?;
+        // Otherwise the rename can reach the disk before the data does, leaving
+        // an empty or partial value after a power cut.
+        file.sync_data().await?;
+        drop(file);
+
+        tokio::fs::rename(&partial, &path).await?;
+
+        Ok(())
+    }
+
+    async fn append(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        use tokio::io::AsyncWriteExt;
+
+        let path = self.path(key)?;
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+
+        file.write_all(value).await?;
+        file.sync_data().await?;
+
+        Ok(())
+    }
+
+    async fn remove(&mut self, key: &str) -> Result<(), StorageError> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,659 @@
+//! Values kept in NOR flash, for boards without a filesystem.
+//!
+//! The flash is split into two banks, and changes are written as records to
+//! the end of the active one, so nothing is ever written over. Each record
+//! has a checksum, so one cut short by a power cut is ignored. Once the
+//! active bank is full, the records still in use are copied to the other
+//! bank, which only takes over once its header is written after them.
+//!
+//! ```text
+//! bank:   "PCFS" | generation: u32 | records...
+//! record: 0xa5 | kind: u8 | key length: u8 | 0 | value length: u32 | crc32
+//!         | key | value | 0xff padding to a whole write
+//! ```
+
+use embedded_storage_async::nor_flash::{NorFlash, NorFlashError};
+
+use super::{Storage, is_valid_key};
+use crate::prelude::*;
+
+/// The longest key which can be stored.
+pub const MAX_KEY_LENGTH: usize = 64;
+
+const BANK_MAGIC: [u8; 4] = *b"PCFS";
+const BANK_HEADER_SIZE: usize = 8;
+const RECORD_MAGIC: u8 = 0xa5;
+const RECORD_HEADER_SIZE: usize = 12;
+/// Records are written and copied through a buffer this size, so the flash's
+/// write size has to divide it.
+const CHUNK_SIZE: usize = 256;
+
+const CRC: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum Kind {
+    Write = 1,
+    Append = 2,
+    Remove = 3,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Header {
+    kind: Kind,
+    key_len: u8,
+    value_len: u32,
+    crc: u32,
+}
+
+impl Header {
+    fn new(kind: Kind, key: &[u8], value: &[u8]) -> Self {
+        let mut header = Self {
+            kind,
+            key_len: key.len() as u8,
+            value_len: value.len() as u32,
+            crc: 0,
+        };
+
+        let mut digest = CRC.digest();
+        digest.update(&header.encode()[..8]);
+        digest.update(key);
+        digest.update(value);
+        header.crc = digest.finalize();
+
+        header
+    }
+
+    fn encode(&self) -> [u8; RECORD_HEADER_SIZE] {
+        let mut bytes = [0; RECORD_HEADER_SIZE];
+
+        bytes[0] = RECORD_MAGIC;
+        bytes[1] = self.kind as u8;
+        bytes[2] = self.key_len;
+        bytes[4..8].copy_from_slice(&self.value_len.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.crc.to_le_bytes());
+
+        bytes
+    }
+
+    fn decode(bytes: &[u8; RECORD_HEADER_SIZE]) -> Option<Self> {
+        let kind = match bytes[1] {
+            1 => Kind::Write,
+            2 => Kind::Append,
+            3 => Kind::Remove,
+            _ => return None,
+        };
+
+        (bytes[0] == RECORD_MAGIC).then(|| Self {
+            kind,
+            key_len: bytes[2],
+            value_len: u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+            crc: u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]),
+        })
+    }
+
+    /// Where the value starts, from the start of the record.
+    fn value_offset(&self) -> u32 {
+        (RECORD_HEADER_SIZE + usize::from(self.key_len)) as u32
+    }
+}
+
+/// A [`Storage`] over NOR flash, such as the part of a board's program flash
+/// after the firmware. Only one value is read or written at a time, so reads
+/// scan every record and are slow, but values are only read at startup.
+pub struct FlashStorage<F> {
+    flash: F,
+    bank_size: u32,
+    /// Which bank is in use, and its generation, once the flash is mounted.
+    active: Option<(u32, u32)>,
+    /// Where the records in the active bank end.
+    end: u32,
+    /// Whether the active bank has a damaged record at `end`, so has to be
+    /// compacted before anything is added.
+    damaged: bool,
+}
+
+impl<F: NorFlash> FlashStorage<F> {
+    /// Keeps values in all of `flash`, which is mounted when first used. If it
+    /// holds neither bank, it is erased.
+    ///
+    /// # Panics
+    /// Panics if `flash` can't be read a byte at a time, or has room for less
+    /// than two erasable blocks.
+    pub fn new(flash: F) -> Self {
+        const {
+            assert!(F::READ_SIZE == 1, "flash must be readable a byte at a time");
+            assert!(CHUNK_SIZE.is_multiple_of(F::WRITE_SIZE));
+        };
+
+        let bank_size = (flash.capacity() / 2 / F::ERASE_SIZE * F::ERASE_SIZE) as u32;
+        assert!(bank_size > 0, "flash must have room for two banks");
+
+        Self {
+            flash,
+            bank_size,
+            active: None,
+            end: 0,
+            damaged: false,
+        }
+    }
+
+    /// Gives back the flash.
+    pub fn into_inner(self) -> F {
+        self.flash
+    }
+
+    fn data_start() -> u32 {
+        BANK_HEADER_SIZE.next_multiple_of(F::WRITE_SIZE) as u32
+    }
+
+    fn record_size(header: &Header) -> u32 {
+        (header.value_offset() as usize + header.value_len as usize).next_multiple_of(F::WRITE_SIZE)
+            as u32
+    }
+
+    fn bank(&self) -> u32 {
+        self.active.map_or(0, |(bank, _)| bank) * self.bank_size
+    }
+
+    /// Finds the active bank and the end of its records, if that hasn't been
+    /// done yet.
+    async fn mount(&mut self) -> Result<(), StorageError> {
+        if self.active.is_some() {
+            return Ok(());
+        }
+
+        let mut newest = None;
+
+        for bank in 0..2 {
+            let mut header = [0; BANK_HEADER_SIZE];
+            self.flash
+                .read(bank * self.bank_size, &mut header)
+                .await
+                .map_err(flash_error)?;
+
+            if header[..4] != BANK_MAGIC {
+                continue;
+            }
+
+            let generation = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+
+            if newest.is_none_or(|(_, newest)| generation > newest) {
+                newest = Some((bank, generation));
+            }
+        }
+
+        let Some(active) = newest else {
+            info!("Flash storage is empty, so formatting it");
+
+            self.erase(0).await?;
+            self.write_bank_header(0, 1).await?;
+            self.active = Some((0, 1));
+            self.end = Self::data_start();
+
+            return Ok(());
+        };
+
+        self.active = Some(active);
+        self.find_end().await
+    }
+
+    /// Checks every record in the active bank, to find where they end. If one
+    /// is damaged, the records end before it, and the bank is compacted before
+    /// more are added.
+    async fn find_end(&mut self) -> Result<(), StorageError> {
+        let mut offset = Self::data_start();
+
+        while offset as usize + RECORD_HEADER_SIZE <= self.bank_size as usize {
+            let mut bytes = [0; RECORD_HEADER_SIZE];
+            self.flash
+                .read(self.bank() + offset, &mut bytes)
+                .await
+                .map_err(flash_error)?;
+
+            if bytes == [0xff; RECORD_HEADER_SIZE] {
+                break;
+            }
+
+            let Some(header) = Header::decode(&bytes)
+                .filter(|header| offset + Self::record_size(header) <= self.bank_size)
+            else {
+                warn!("Flash storage has a damaged record header at {offset}");
+                self.damaged = true;
+                break;
+            };
+
+            if self.record_crc(offset, &header).await? != header.crc {
+                warn!("Flash storage has a damaged record at {offset}");
+                self.damaged = true;
+                break;
+            }
+
+            offset += Self::record_size(&header);
+        }
+
+        self.end = offset;
+
+        Ok(())
+    }
+
+    async fn record_crc(&mut self, offset: u32, header: &Header) -> Result<u32, StorageError> {
+        let mut digest = CRC.digest();
+        digest.update(&header.encode()[..8]);
+
+        let mut chunk = [0; CHUNK_SIZE];
+        let mut at = offset + RECORD_HEADER_SIZE as u32;
+        let mut remaining = usize::from(header.key_len) + header.value_len as usize;
+
+        while remaining > 0 {
+            let len = remaining.min(CHUNK_SIZE);
+            self.flash
+                .read(self.bank() + at, &mut chunk[..len])
+                .await
+                .map_err(flash_error)?;
+
+            digest.update(&chunk[..len]);
+            at += len as u32;
+            remaining -= len;
+        }
+
+        Ok(digest.finalize())
+    }
+
+    /// The header of the record at `offset` in the active bank, which must be
+    /// before [`Self::end`], and whether its key is `key`.
+    async fn header_at(&mut self, offset: u32, key: &[u8]) -> Result<(Header, bool), StorageError> {
+        let mut bytes = [0; RECORD_HEADER_SIZE];
+        self.flash
+            .read(self.bank() + offset, &mut bytes)
+            .await
+            .map_err(flash_error)?;
+
+        let header = Header::decode(&bytes).ok_or(StorageError::Corrupt)?;
+
+        if usize::from(header.key_len) != key.len() {
+            return Ok((header, false));
+        }
+
+        let mut record_key = [0; MAX_KEY_LENGTH];
+        let record_key = &mut record_key[..key.len()];
+        self.flash
+            .read(self.bank() + offset + RECORD_HEADER_SIZE as u32, record_key)
+            .await
+            .map_err(flash_error)?;
+
+        Ok((header, record_key == key))
+    }
+
+    /// Whether a record after `offset` in the active bank writes or removes
+    /// `key`, so nothing for it before then is in use.
+    async fn replaced_after(&mut self, mut offset: u32, key: &[u8]) -> Result<bool, StorageError> {
+        while offset < self.end {
+            let (header, matches) = self.header_at(offset, key).await?;
+
+            if matches && header.kind != Kind::Append {
+                return Ok(true);
+            }
+
+            offset += Self::record_size(&header);
+        }
+
+        Ok(false)
+    }
+
+    async fn add_record(
+        &mut self,
+        kind: Kind,
+        key: &str,
+        value: &[u8],
+    ) -> Result<(), StorageError> {
+        if !is_valid_key(key) || key.len() > MAX_KEY_LENGTH {
+            return Err(StorageError::InvalidKey);
+        }
+
+        self.mount().await?;
+
+        let header = Header::new(kind, key.as_bytes(), value);
+        let size = Self::record_size(&header);
+
+        if self.damaged || self.end + size > self.bank_size {
+            self.compact().await?;
+
+            if self.end + size > self.bank_size {
+                return Err(StorageError::TooLarge);
+            }
+        }
+
+        self.write_parts(
+            self.bank() + self.end,
+            &[&header.encode(), key.as_bytes(), value],
+        )
+        .await?;
+        self.end += size;
+
+        Ok(())
+    }
+
+    /// Copies the records still in use to the other bank, and switches to it.
+    async fn compact(&mut self) -> Result<(), StorageError> {
+        let Some((bank, generation)) = self.active else {
+            return Ok(());
+        };
+
+        let target = 1 - bank;
+        self.erase(target).await?;
+
+        let mut offset = Self::data_start();
+        let mut copied = Self::data_start();
+
+        while offset < self.end {
+            let (header, _) = self.header_at(offset, &[]).await?;
+            let size = Self::record_size(&header);
+
+            let mut key = [0; MAX_KEY_LENGTH];
+            let key = &mut key[..usize::from(header.key_len)];
+            self.flash
+                .read(self.bank() + offset + RECORD_HEADER_SIZE as u32, key)
+                .await
+                .map_err(flash_error)?;
+
+            if header.kind != Kind::Remove && !self.replaced_after(offset + size, key).await? {
+                self.copy(self.bank() + offset, target * self.bank_size + copied, size)
+                    .await?;
+                copied += size;
+            }
+
+            offset += size;
+        }
+
+        let generation = generation.wrapping_add(1);
+        self.write_bank_header(target, generation).await?;
+
+        debug!(
+            "Compacted flash storage from {} to {copied} bytes",
+            self.end
+        );
+
+        self.active = Some((target, generation));
+        self.end = copied;
+        self.damaged = false;
+
+        Ok(())
+    }
+
+    async fn erase(&mut self, bank: u32) -> Result<(), StorageError> {
+        let start = bank * self.bank_size;
+
+        self.flash
+            .erase(start, start + self.bank_size)
+            .await
+            .map_err(flash_error)
+    }
+
+    async fn write_bank_header(&mut self, bank: u32, generation: u32) -> Result<(), StorageError> {
+        self.write_parts(
+            bank * self.bank_size,
+            &[&BANK_MAGIC, &generation.to_le_bytes()],
+        )
+        .await
+    }
+
+    /// Writes `parts` one after the other from `at`, padded to a whole number
+    /// of writes.
+    async fn write_parts(&mut self, at: u32, parts: &[&[u8]]) -> Result<(), StorageError> {
+        let mut chunk = [0xff; CHUNK_SIZE];
+        let mut filled = 0;
+        let mut written = 0;
+
+        for mut part in parts.iter().copied() {
+            while !part.is_empty() {
+                let len = part.len().min(CHUNK_SIZE - filled);
+                chunk[filled..filled + len].copy_from_slice(&part[..len]);
+                filled += len;
+                part = &part[len..];
+
+                if filled == CHUNK_SIZE {
+                    self.flash
+                        .write(at + written, &chunk)
+                        .await
+                        .map_err(flash_error)?;
+                    written += CHUNK_SIZE as u32;
+                    filled = 0;
+                }
+            }
+        }
+
+        if filled > 0 {
+            let padded = filled.next_multiple_of(F::WRITE_SIZE);
+            chunk[filled..padded].fill(0xff);
+
+            self.flash
+                .write(at + written, &chunk[..padded])
+                .await
+                .map_err(flash_error)?;
+        }
+
+        Ok(())
+    }
+
+    /// Copies `len` bytes, a whole number of writes, from `from` to `to`.
+    async fn copy(&mut self, from: u32, to: u32, len: u32) -> Result<(), StorageError> {
+        let mut chunk = [0; CHUNK_SIZE];
+        let mut copied = 0;
+
+        while copied < len {
+            let chunk = &mut chunk[..(len - copied).min(CHUNK_SIZE as u32) as usize];
+
+            self.flash
+                .read(from + copied, chunk)
+                .await
+                .map_err(flash_error)?;
+            self.flash
+                .write(to + copied, chunk)
+                .await
+                .map_err(flash_error)?;
+
+            copied += chunk.len() as u32;
+        }
+
+        Ok(())
+    }
+}
+
+impl<F: NorFlash> Storage for FlashStorage<F> {
+    async fn read(&mut self, key: &str, buf: &mut [u8]) -> Result<Option<usize>, StorageError> {
+        if !is_valid_key(key) || key.len() > MAX_KEY_LENGTH {
+            return Err(StorageError::InvalidKey);
+        }
+
+        self.mount().await?;
+
+        let mut len = None;
+        let mut too_large = false;
+        let mut offset = Self::data_start();
+
+        while offset < self.end {
+            let (header, matches) = self.header_at(offset, key.as_bytes()).await?;
+
+            if matches {
+                let start = match header.kind {
+                    Kind::Write => 0,
+                    Kind::Append => len.unwrap_or(0),
+                    Kind::Remove => {
+                        len = None;
+                        too_large = false;
+                        offset += Self::record_size(&header);
+                        continue;
+                    }
+                };
+
+                if header.kind == Kind::Write {
+                    too_large = false;
+                }
+
+                let end = start + header.value_len as usize;
+
+                match buf.get_mut(start..end) {
+                    Some(value) => self
+                        .flash
+                        .read(self.bank() + offset + header.value_offset(), value)
+                        .await
+                        .map_err(flash_error)?,
+                    None => too_large = true,
+                }
+
+                len = Some(end);
+            }
+
+            offset += Self::record_size(&header);
+        }
+
+        if too_large {
+            return Err(StorageError::TooLarge);
+        }
+
+        Ok(len)
+    }
+
+    async fn write(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        self.add_record(Kind::Write, key, value).await
+    }
+
+    async fn append(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        self.add_record(Kind::Append, key, value).await
+    }
+
+    async fn remove(&mut self, key: &str) -> Result<(), StorageError> {
+        self.add_record(Kind::Remove, key, &[]).await
+    }
+}
+
+fn flash_error(e: impl NorFlashError) -> StorageError {
+    warn!("Flash storage failed: {:?}", e.kind());
+
+    StorageError::Io(embedded_io::ErrorKind::Other)
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_storage_async::nor_flash::{ErrorType, NorFlashErrorKind, ReadNorFlash};
+
+    use super::*;
+
+    /// Two 512 byte banks of flash, which like real NOR flash can only clear
+    /// bits until erased.
+    struct RamFlash([u8; 1024]);
+
+    impl ErrorType for RamFlash {
+        type Error = NorFlashErrorKind;
+    }
+
+    impl ReadNorFlash for RamFlash {
+        const READ_SIZE: usize = 1;
+
+        async fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.0[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    impl NorFlash for RamFlash {
+        const WRITE_SIZE: usize = 4;
+        const ERASE_SIZE: usize = 256;
+
+        async fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+            self.0[from as usize..to as usize].fill(0xff);
+            Ok(())
+        }
+
+        async fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            assert!(
+                (offset as usize).is_multiple_of(Self::WRITE_SIZE)
+                    && bytes.len().is_multiple_of(Self::WRITE_SIZE)
+            );
+
+            for (stored, byte) in self.0[offset as usize..].iter_mut().zip(bytes) {
+                *stored &= byte;
+            }
+
+            Ok(())
+        }
+    }
+
+    async fn read(storage: &mut FlashStorage<RamFlash>, key: &str) -> Option<std::vec::Vec<u8>> {
+        let mut buf = [0; 128];
+        let len = storage
+            .read(key, &mut buf)
+            .await
+            .expect("flash is readable")?;
+
+        Some(buf[..len].to_vec())
+    }
+
+    #[tokio::test]
+    async fn values_survive_compaction_remounting_and_torn_writes() {
+        let mut storage = FlashStorage::new(RamFlash([0; 1024]));
+
+        storage.write("a", b"hello").await.expect("room");
+        storage.append("a", b" world").await.expect("room");
+        storage.append("journal", b"123").await.expect("room");
+        storage.write("b", b"gone").await.expect("room");
+        storage.remove("b").await.expect("room");
+
+        // Far more than fits in a bank, so it is compacted many times.
+        for i in 0..64u8 {
+            storage
+                .write("c", &[i; 40])
+                .await
+                .expect("room once compacted");
+        }
+
+        assert_eq!(
+            read(&mut storage, "a").await.as_deref(),
+            Some(&b"hello world"[..])
+        );
+        assert_eq!(
+            read(&mut storage, "journal").await.as_deref(),
+            Some(&b"123"[..])
+        );
+        assert_eq!(read(&mut storage, "b").await, None);
+        assert_eq!(read(&mut storage, "c").await, Some([63; 40].to_vec()));
+
+        // The last record loses some of its bits to a power cut.
+        let end = storage.bank() + storage.end;
+        let mut flash = storage.into_inner();
+        flash.0[end as usize - 8] = 0;
+
+        let mut storage = FlashStorage::new(flash);
+
+        assert_eq!(
+            read(&mut storage, "a").await.as_deref(),
+            Some(&b"hello world"[..])
+        );
+        assert_eq!(read(&mut storage, "c").await, Some([62; 40].to_vec()));
+
+        storage
+            .append("journal", b"4")
+            .await
+            .expect("room once compacted");
+        assert_eq!(
+            read(&mut storage, "journal").await.as_deref(),
+            Some(&b"1234"[..])
+        );
+        assert_eq!(read(&mut storage, "c").await, Some([62; 40].to_vec()));
+
+        assert!(matches!(
+            storage.write("d", &[0; 512]).await,
+            Err(StorageError::TooLarge)
+        ));
+        assert!(matches!(
+            storage.write("Not A Key", b"").await,
+            Err(StorageError::InvalidKey)
+        ));
+    }
+}
@@ -0,0 +1,42 @@
+//! Values kept in memory, for tests.
+
+use std::collections::BTreeMap;
+use std::string::{String, ToString};
+
+use super::Storage;
+use crate::prelude::*;
+
+#[derive(Debug, Default)]
+pub(crate) struct MemoryStorage(pub BTreeMap<String, std::vec::Vec<u8>>);
+
+impl Storage for MemoryStorage {
+    async fn read(&mut self, key: &str, buf: &mut [u8]) -> Result<Option<usize>, StorageError> {
+        let Some(value) = self.0.get(key) else {
+            return Ok(None);
+        };
+
+        buf.get_mut(..value.len())
+            .ok_or(StorageError::TooLarge)?
+            .copy_from_slice(value);
+
+        Ok(Some(value.len()))
+    }
+
+    async fn write(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        self.0.insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+
+    async fn append(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        self.0
+            .entry(key.to_string())
+            .or_default()
+            .extend_from_slice(value);
+        Ok(())
+    }
+
+    async fn remove(&mut self, key: &str) -> Result<(), StorageError> {
+        self.0.remove(key);
+        Ok(())
+    }
+}
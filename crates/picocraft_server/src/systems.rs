@@ -3,15 +3,42 @@ pub mod debug;
 
 use picocraft_ecs::components::*;
 use picocraft_ecs::entity::EntityId;
-use picocraft_ecs::events::WorldEvent;
-use picocraft_ecs::pools::PlayerBundle;
+use picocraft_ecs::events::{KickReason, MAX_EVENT_PLUGIN_MESSAGE_SIZE, WorldEvent};
+use picocraft_ecs::pools::{PlayerBundle, PlayerSaveData};
 use picocraft_ecs::storage::{ComponentStore, GetComponent};
 use picocraft_ecs::traits::Pool;
+use picocraft_ecs::world::{LoginId, PendingJoin, SavedPlayer};
 use picocraft_ecs::{ComponentStorageError, World};
+use picocraft_proto::serverbound::Hand;
+
+use picocraft_terrain::Terrain;
+use picocraft_terrain::terrain::blocks::Block;
+use picocraft_terrain::terrain::coordinates::Coordinates;
+use picocraft_terrain::terrain::modifications::ModificationError;
 
 use crate::channels::EVENTS;
+use crate::journal;
+use crate::player_data;
 use crate::prelude::*;
 
+/// How long a login waits for the player's old session to leave, before it is
+/// turned away instead. Ten seconds, at 20 ticks a second. Kicked sessions are
+/// closed within [`KICK_TIMEOUT`](crate::client::KICK_TIMEOUT) even if their
+/// connection is dead, so they needn't be timed out by keep-alives first.
+pub const DUPLICATE_LOGIN_TIMEOUT_TICKS: u64 = 200;
+
+/// How far from their eyes players can reach blocks: creative's block
+/// interaction range of 5, plus the block of leeway vanilla gives for latency.
+pub const BLOCK_REACH: f32 = 6.0;
+
+/// How far above a standing player's feet their eyes are.
+const EYE_HEIGHT: f32 = 1.62;
+
+/// The size of a standing player's bounding box, which blocks can't be
+/// placed inside.
+const PLAYER_WIDTH: f32 = 0.6;
+const PLAYER_HEIGHT: f32 = 1.8;
+
 pub enum MovementUpdate {
     Nearby(DeltaPosition),
     Teleport(Position),
@@ -99,7 +126,98 @@ pub fn system_player_moved(
     EVENTS.immediate_publisher().publish_immediate(event);
 }
 
-pub fn system_player_joined(world: &mut World, username: String<16>, uuid: UUID) {
+pub fn system_player_joined(world: &mut World, login: LoginId, username: String<16>, uuid: UUID) {
+    // Someone already in the world with this UUID logged in again, so kick the
+    // old session. This login is only spawned once it has left, so their save
+    // data includes everything the old session did.
+    if let Some((index, _)) = world.players.uuid.iter().find(|(_, u)| u.0 == uuid) {
+        info!("{username} [{uuid}] logged in from another location, kicking their old session.");
+
+        EVENTS
+            .immediate_publisher()
+            .publish_immediate(WorldEvent::PlayerKicked {
+                recipient: EntityId::player(index),
+                reason: KickReason::DuplicateLogin,
+            });
+
+        let pending = PendingJoin {
+            login,
+            username,
+            uuid,
+            since: world.tick_count(),
+            loading: false,
+        };
+
+        if world.pending_joins.push(pending).is_err() {
+            warn!("Too many logins waiting for their old session to leave, turning {uuid} away.");
+
+            EVENTS
+                .immediate_publisher()
+                .publish_immediate(WorldEvent::JoinRejected { login });
+        }
+
+        return;
+    }
+
+    let save = world.saved_player(uuid).map(|saved| saved.save.clone());
+
+    // Players the world doesn't remember may still have save data in storage,
+    // so they are spawned once it has been loaded.
+    if save.is_none() && player_data::is_stored() {
+        let pending = PendingJoin {
+            login,
+            username,
+            uuid,
+            since: world.tick_count(),
+            loading: true,
+        };
+
+        // If it was asked for but there's no room to wait, the loaded save
+        // data is just ignored.
+        if !player_data::load(uuid) || world.pending_joins.push(pending).is_err() {
+            warn!("Too many logins waiting for their save data, turning {uuid} away.");
+
+            EVENTS
+                .immediate_publisher()
+                .publish_immediate(WorldEvent::JoinRejected { login });
+        }
+
+        return;
+    }
+
+    spawn_player(world, login, username, uuid, save);
+}
+
+/// The save data of a login waiting on storage has been loaded, so it can be
+/// spawned.
+pub fn system_player_data_loaded(world: &mut World, uuid: UUID, save: Option<PlayerSaveData>) {
+    let Some(i) = world
+        .pending_joins
+        .iter()
+        .position(|p| p.uuid == uuid && p.loading)
+    else {
+        return;
+    };
+
+    let pending = world.pending_joins.remove(i);
+
+    // Another login with this UUID was spawned while this one was loading, so
+    // this one has to wait for it to leave like any other duplicate.
+    if world.players.uuid.iter().any(|(_, u)| u.0 == uuid) {
+        system_player_joined(world, pending.login, pending.username, uuid);
+        return;
+    }
+
+    spawn_player(world, pending.login, pending.username, uuid, save);
+}
+
+fn spawn_player(
+    world: &mut World,
+    login: LoginId,
+    username: String<16>,
+    uuid: UUID,
+    save: Option<PlayerSaveData>,
+) {
     let existing_players: Vec<_, MAX_PLAYERS> = world
         .players
         .uuid
@@ -131,11 +249,6 @@ pub fn system_player_joined(world: &mut World, username: String<16>, uuid: UUID)
         })
         .collect();
 
-    let save = world
-        .player_save_data
-        .iter()
-        .find_map(|slot| slot.as_ref().filter(|s| s.uuid == Uuid(uuid)).cloned());
-
     let (position, rotation) = match &save {
         Some(save) => (save.position, save.rotation),
         None => (Position::new(0.0, 96.0, 0.0), Rotation::default()),
@@ -150,6 +263,7 @@ pub fn system_player_joined(world: &mut World, username: String<16>, uuid: UUID)
             //TODO new player spawn pos should come from terrain and random number gen
             position,
             rotation,
+            inventory: Inventory::new(),
         }),
     };
 
@@ -158,6 +272,7 @@ pub fn system_player_joined(world: &mut World, username: String<16>, uuid: UUID)
             EVENTS
                 .immediate_publisher()
                 .publish_immediate(WorldEvent::PlayerJoined {
+                    login,
                     player_id: entity_ref.entity_id,
                     username,
                     uuid,
@@ -181,6 +296,8 @@ pub fn system_player_joined(world: &mut World, username: String<16>, uuid: UUID)
         .insert(Dimension::Overworld)
         .expect("EntityId should be valid");
 
+    let player_id = player.entity_id;
+
     for (player_id, username, uuid, pos, rot) in existing_players {
         EVENTS
             .immediate_publisher()
@@ -194,13 +311,71 @@ pub fn system_player_joined(world: &mut World, username: String<16>, uuid: UUID)
             });
     }
 
+    let inventory = world
+        .players
+        .inventory
+        .get(player_id.index())
+        .cloned()
+        .expect("inventory should be a required field");
+
+    EVENTS
+        .immediate_publisher()
+        .publish_immediate(WorldEvent::InventoryContent {
+            recipient: player_id,
+            inventory,
+        });
+
     EVENTS
         .immediate_publisher()
         .publish_immediate(WorldEvent::WorldReady {
-            recipient: player.entity_id,
+            recipient: player_id,
         });
 }
 
+pub fn system_resync_player(world: &mut World, player_id: EntityId) {
+    if !world.players.canonical().contains(player_id.index()) {
+        error!(
+            "\"{:?}\" does not correspond to an active player.",
+            player_id
+        );
+        return;
+    }
+
+    for (index, uuid) in world.players.uuid.iter() {
+        if index == player_id.index() {
+            continue;
+        }
+
+        let username = world
+            .players
+            .username
+            .get(index)
+            .expect("username should be a required field");
+        let position = world
+            .players
+            .position
+            .get(index)
+            .expect("position should be a required field");
+        let rotation = world
+            .players
+            .rotation
+            .get(index)
+            .expect("rotation should be a required field");
+
+        // Spawning an entity the client already has just replaces it.
+        EVENTS
+            .immediate_publisher()
+            .publish_immediate(WorldEvent::ExistingPlayer {
+                recipient: player_id,
+                player_id: EntityId::player(index),
+                username: username.0.clone(),
+                uuid: uuid.0,
+                position: *position,
+                rotation: *rotation,
+            });
+    }
+}
+
 pub fn system_player_left(world: &mut World, player_id: EntityId) {
     let index = player_id.index();
 
@@ -220,15 +395,10 @@ pub fn system_player_left(world: &mut World, player_id: EntityId) {
         .0;
 
     if let Some(save) = world.players.snapshot(player_id) {
-        // find the slot by UUID and store it
-        if let Some(slot) = world
-            .player_save_data
-            .iter_mut()
-            .find(|s| s.as_ref().map(|s| s.uuid == save.uuid).unwrap_or(false))
-        {
-            *slot = Some(save);
-        } else if let Some(slot) = world.player_save_data.iter_mut().find(|s| s.is_none()) {
-            *slot = Some(save);
+        let stored = player_data::save(save.clone());
+
+        if let Some(forgotten) = world.remember_player(save, stored) {
+            forget_player(forgotten);
         }
     }
 
@@ -240,4 +410,564 @@ pub fn system_player_left(world: &mut World, player_id: EntityId) {
     EVENTS
         .immediate_publisher()
         .publish_immediate(WorldEvent::PlayerLeft { player_id, uuid });
+
+    let Some(i) = world
+        .pending_joins
+        .iter()
+        .position(|p| p.uuid == uuid && !p.loading)
+    else {
+        return;
+    };
+
+    let pending = world.pending_joins.remove(i);
+    system_player_joined(world, pending.login, pending.username, pending.uuid);
+
+    // Someone logged in yet again while the first login was waiting, so that
+    // one is already out of date.
+    if world
+        .pending_joins
+        .iter()
+        .any(|p| p.uuid == uuid && !p.loading)
+        && let Some((index, _)) = world.players.uuid.iter().find(|(_, u)| u.0 == uuid)
+    {
+        EVENTS
+            .immediate_publisher()
+            .publish_immediate(WorldEvent::PlayerKicked {
+                recipient: EntityId::player(index),
+                reason: KickReason::DuplicateLogin,
+            });
+    }
+}
+
+/// Turns away logins whose old session hasn't left, or whose save data
+/// hasn't loaded, after [`DUPLICATE_LOGIN_TIMEOUT_TICKS`]. They can't be
+/// spawned alongside the old session, and despawning it from under its
+/// connection would leave that connection sending commands for an entity
+/// which may be reused. Spawning them without their save data would replace
+/// it when they leave.
+pub fn system_expire_pending_joins(world: &mut World) {
+    let now = world.tick_count();
+
+    world.pending_joins.retain(|pending| {
+        if now.saturating_sub(pending.since) < DUPLICATE_LOGIN_TIMEOUT_TICKS {
+            return true;
+        }
+
+        if pending.loading {
+            warn!(
+                "The save data of {} [{}] didn't load in time, turning them away.",
+                pending.username, pending.uuid
+            );
+        } else {
+            warn!(
+                "The old session of {} [{}] didn't leave in time, turning the new login away.",
+                pending.username, pending.uuid
+            );
+        }
+
+        EVENTS
+            .immediate_publisher()
+            .publish_immediate(WorldEvent::JoinRejected {
+                login: pending.login,
+            });
+
+        false
+    });
+}
+
+/// Puts `item` in `slot` of the player's inventory window.
+pub fn system_set_inventory_slot(
+    world: &mut World,
+    player_id: EntityId,
+    slot: u8,
+    item: ItemStack,
+) {
+    let Some(inventory) = world.players.inventory.get_mut(player_id.index()) else {
+        error!(
+            "\"{:?}\" does not correspond to an active player.",
+            player_id
+        );
+        return;
+    };
+
+    if !inventory.set(usize::from(slot), item) {
+        debug!("{player_id:?} tried to put an item in slot {slot}, which doesn't exist");
+    }
+}
+
+pub fn system_select_hotbar_slot(world: &mut World, player_id: EntityId, slot: u8) {
+    let Some(inventory) = world.players.inventory.get_mut(player_id.index()) else {
+        error!(
+            "\"{:?}\" does not correspond to an active player.",
+            player_id
+        );
+        return;
+    };
+
+    if !inventory.select(slot) {
+        debug!("{player_id:?} tried to select hotbar slot {slot}, which doesn't exist");
+    }
+}
+
+/// Hands the save data of every player in the world over to be stored, along
+/// with any which couldn't be before.
+pub fn system_save_players(world: &mut World) {
+    if !player_data::is_stored() {
+        return;
+    }
+
+    for (index, _) in world.players.uuid.iter() {
+        if let Some(save) = world.players.snapshot(EntityId::player(index))
+            && !player_data::save(save)
+        {
+            warn!("Too many saves are waiting for storage, so a player wasn't saved.");
+        }
+    }
+
+    for saved in world.player_save_data.iter_mut().flatten() {
+        if !saved.stored {
+            saved.stored = player_data::save(saved.save.clone());
+        }
+    }
+}
+
+/// Warns when a player's save data is forgotten without having been stored,
+/// as it is lost.
+fn forget_player(forgotten: SavedPlayer) {
+    let SavedPlayer { save, stored, .. } = forgotten;
+
+    if stored || player_data::save(save.clone()) {
+        return;
+    }
+
+    warn!(
+        "Forgetting the save data of {} [{}], as too many players have left since they did.",
+        save.username.0, save.uuid.0
+    );
+}
+
+pub fn system_block_broken<const MAX_CHANGES: usize>(
+    world: &mut World,
+    terrain: &Terrain<MAX_CHANGES>,
+    player_id: EntityId,
+    position: BlockPosition,
+    sequence: VarInt,
+) {
+    let Some(player_position) = world.players.position.get(player_id.index()) else {
+        error!(
+            "\"{:?}\" does not correspond to an active player.",
+            player_id
+        );
+        return;
+    };
+
+    if !within_reach(player_position, position) {
+        warn!("{player_id:?} tried to break a block out of reach at {position:?}");
+    } else if let Some(coordinates) = coordinates(position)
+        && terrain
+            .block_state_at(coordinates)
+            .is_some_and(|state| *state != Block::Air as i32)
+    {
+        match terrain.set_block_state(coordinates, VarInt(Block::Air as i32)) {
+            Ok(()) => {
+                journal::record(coordinates, VarInt(Block::Air as i32));
+
+                EVENTS
+                    .immediate_publisher()
+                    .publish_immediate(WorldEvent::BlockBroken {
+                        player_id,
+                        position,
+                    });
+            }
+            Err(ModificationError::Full) => {
+                warn!("Too many blocks have been changed to break another at {position:?}");
+                revert_block(terrain, player_id, position, coordinates);
+            }
+            Err(ModificationError::OutOfBounds | ModificationError::PaletteFull) => {}
+        }
+    }
+
+    // Anything which wasn't broken is put back by the player's client.
+    EVENTS
+        .immediate_publisher()
+        .publish_immediate(WorldEvent::BlockChangesAcknowledged {
+            recipient: player_id,
+            sequence,
+        });
+}
+
+pub fn system_block_placed<const MAX_CHANGES: usize>(
+    world: &mut World,
+    terrain: &Terrain<MAX_CHANGES>,
+    player_id: EntityId,
+    hand: Hand,
+    against: BlockPosition,
+    position: BlockPosition,
+    sequence: VarInt,
+) {
+    let Some(player_position) = world.players.position.get(player_id.index()) else {
+        error!(
+            "\"{:?}\" does not correspond to an active player.",
+            player_id
+        );
+        return;
+    };
+
+    let block_state = world
+        .players
+        .inventory
+        .get(player_id.index())
+        .map(|inventory| inventory.held_item(hand))
+        .filter(|item| !item.is_empty())
+        .and_then(|item| Block::from_item(VarInt(i32::from(item.item_id))))
+        .map(|block| VarInt(block as i32));
+
+    if block_state.is_none() {
+        // Only blocks can be placed, and nothing else is done with items yet.
+    } else if !within_reach(player_position, against) {
+        warn!("{player_id:?} tried to place a block out of reach at {position:?}");
+    } else if world
+        .players
+        .position
+        .iter()
+        .any(|(_, player)| intersects_player(player, position))
+    {
+        debug!("{player_id:?} tried to place a block inside a player at {position:?}");
+    } else if let Some(block_state) = block_state
+        && let Some(coordinates) = coordinates(position)
+        && terrain
+            .block_state_at(coordinates)
+            .is_some_and(Block::is_replaceable)
+    {
+        match terrain.set_block_state(coordinates, block_state) {
+            Ok(()) => {
+                journal::record(coordinates, block_state);
+
+                EVENTS
+                    .immediate_publisher()
+                    .publish_immediate(WorldEvent::BlockPlaced {
+                        player_id,
+                        position,
+                        block_state,
+                    });
+            }
+            Err(ModificationError::Full) => {
+                warn!("Too many blocks have been changed to place another at {position:?}");
+                revert_block(terrain, player_id, position, coordinates);
+            }
+            Err(ModificationError::PaletteFull) => {
+                warn!("No room in the chunk section's palette for a block at {position:?}");
+                revert_block(terrain, player_id, position, coordinates);
+            }
+            Err(ModificationError::OutOfBounds) => {}
+        }
+    }
+
+    // Anything which wasn't placed is taken away by the player's client.
+    EVENTS
+        .immediate_publisher()
+        .publish_immediate(WorldEvent::BlockChangesAcknowledged {
+            recipient: player_id,
+            sequence,
+        });
+}
+
+/// Puts the block at `position` back on `player_id`'s client, after their
+/// change to it was refused. Their client would do so once acknowledged, but
+/// only to what it last heard the block was.
+fn revert_block<const MAX_CHANGES: usize>(
+    terrain: &Terrain<MAX_CHANGES>,
+    player_id: EntityId,
+    position: BlockPosition,
+    coordinates: Coordinates,
+) {
+    let Some(block_state) = terrain.block_state_at(coordinates) else {
+        return;
+    };
+
+    EVENTS
+        .immediate_publisher()
+        .publish_immediate(WorldEvent::BlockReverted {
+            recipient: player_id,
+            position,
+            block_state,
+        });
+}
+
+/// Whether the block at `block` overlaps the bounding box of a player standing
+/// at `position`.
+fn intersects_player(position: &Position, block: BlockPosition) -> bool {
+    let overlaps = |low: f32, high: f32, block: i32| {
+        let block = block as f32;
+        low < block + 1.0 && high > block
+    };
+
+    overlaps(
+        position.x - PLAYER_WIDTH / 2.0,
+        position.x + PLAYER_WIDTH / 2.0,
+        block.x(),
+    ) && overlaps(position.y, position.y + PLAYER_HEIGHT, block.y())
+        && overlaps(
+            position.z - PLAYER_WIDTH / 2.0,
+            position.z + PLAYER_WIDTH / 2.0,
+            block.z(),
+        )
+}
+
+/// Whether the block at `block` is close enough to a player at `position` for
+/// them to interact with it.
+fn within_reach(position: &Position, block: BlockPosition) -> bool {
+    // The distance to the closest point of the block, from the player's eyes.
+    let distance = |eye: f32, block: i32| {
+        let block = block as f32;
+        eye.clamp(block, block + 1.0) - eye
+    };
+
+    let dx = distance(position.x, block.x());
+    let dy = distance(position.y + EYE_HEIGHT, block.y());
+    let dz = distance(position.z, block.z());
+
+    dx * dx + dy * dy + dz * dz < BLOCK_REACH * BLOCK_REACH
+}
+
+/// Where `position` is in the terrain, if it's within the world's height.
+fn coordinates(position: BlockPosition) -> Option<Coordinates> {
+    Some(Coordinates::new(
+        i16::try_from(position.x()).ok()?,
+        u8::try_from(position.y()).ok()?,
+        i16::try_from(position.z()).ok()?,
+    ))
+}
+
+pub fn system_transfer_player(world: &mut World, player_id: EntityId, host: String<64>, port: u16) {
+    if !world.players.canonical().contains(player_id.index()) {
+        error!(
+            "\"{:?}\" does not correspond to an active player.",
+            player_id
+        );
+        return;
+    }
+
+    EVENTS
+        .immediate_publisher()
+        .publish_immediate(WorldEvent::TransferPlayer {
+            recipient: player_id,
+            host,
+            port,
+        });
+}
+
+pub fn system_send_plugin_message(
+    world: &mut World,
+    player_id: Option<EntityId>,
+    channel: PluginChannel,
+    data: Vec<u8, MAX_EVENT_PLUGIN_MESSAGE_SIZE>,
+) {
+    if let Some(player_id) = player_id
+        && !world.players.canonical().contains(player_id.index())
+    {
+        error!(
+            "\"{:?}\" does not correspond to an active player.",
+            player_id
+        );
+        return;
+    }
+
+    EVENTS
+        .immediate_publisher()
+        .publish_immediate(WorldEvent::PluginMessage {
+            recipient: player_id,
+            channel,
+            data,
+        });
+}
+
+pub fn system_update_latency(world: &mut World, player_id: EntityId, latency: i32) {
+    let Some(uuid) = world.players.uuid.get(player_id.index()) else {
+        error!(
+            "\"{:?}\" does not correspond to an active player.",
+            player_id
+        );
+        return;
+    };
+
+    EVENTS
+        .immediate_publisher()
+        .publish_immediate(WorldEvent::PlayerLatencyUpdated {
+            player_id,
+            uuid: uuid.0,
+            latency,
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_second_login_waits_for_the_first_to_leave() {
+        let mut world = World::new();
+        let username = String::try_from("Steve").expect("short enough");
+        let uuid = UUID::from_u128(0x1234);
+
+        system_player_joined(&mut world, LoginId(0), username.clone(), uuid);
+        let first = EntityId::player(world.players.uuid.iter().next().expect("spawned").0);
+
+        let moved_to = Position::new(10.0, 70.0, -4.0);
+        system_player_moved(&mut world, first, Some(moved_to), None, true, false);
+
+        system_player_joined(&mut world, LoginId(1), username, uuid);
+        assert_eq!(world.players.count(), 1);
+        assert_eq!(world.pending_joins.len(), 1);
+
+        system_player_left(&mut world, first);
+        assert!(world.pending_joins.is_empty());
+
+        let (index, _) = world.players.uuid.iter().next().expect("rejoined");
+        let position = world.players.position.get(index).expect("required");
+        assert_eq!(
+            (position.x, position.y, position.z),
+            (moved_to.x, moved_to.y, moved_to.z)
+        );
+    }
+
+    #[test]
+    fn a_login_is_turned_away_if_the_old_session_never_leaves() {
+        let mut world = World::new();
+        let username = String::try_from("Alex").expect("short enough");
+        let uuid = UUID::from_u128(0x5678);
+
+        system_player_joined(&mut world, LoginId(0), username.clone(), uuid);
+        system_player_joined(&mut world, LoginId(1), username, uuid);
+
+        for _ in 0..DUPLICATE_LOGIN_TIMEOUT_TICKS {
+            world.increment_tick();
+        }
+        system_expire_pending_joins(&mut world);
+
+        assert!(world.pending_joins.is_empty());
+        assert_eq!(world.players.count(), 1);
+    }
+
+    #[test]
+    fn concurrent_logins_each_hear_about_their_own_join() {
+        let mut world = World::new();
+        let username = String::try_from("Steve").expect("short enough");
+        let uuid = UUID::from_u128(0x9abc);
+        let mut events = EVENTS.subscriber().expect("a subscriber is free");
+
+        system_player_joined(&mut world, LoginId(100), username.clone(), uuid);
+        let old = EntityId::player(world.players.uuid.iter().next().expect("spawned").0);
+
+        // Both are in configuration at once, waiting to join.
+        system_player_joined(&mut world, LoginId(101), username.clone(), uuid);
+        system_player_joined(&mut world, LoginId(102), username, uuid);
+        assert_eq!(world.pending_joins.len(), 2);
+
+        // The first spawns once the old session leaves, and the second kicks it.
+        system_player_left(&mut world, old);
+        assert_eq!(world.pending_joins.len(), 1);
+
+        for _ in 0..DUPLICATE_LOGIN_TIMEOUT_TICKS {
+            world.increment_tick();
+        }
+        system_expire_pending_joins(&mut world);
+
+        let mut joined = std::vec::Vec::new();
+        let mut rejected = std::vec::Vec::new();
+        while let Some(event) = events.try_next_message_pure() {
+            match event {
+                WorldEvent::PlayerJoined { login, .. } if login.0 >= 100 => joined.push(login),
+                WorldEvent::JoinRejected { login } if login.0 >= 100 => rejected.push(login),
+                _ => {}
+            }
+        }
+
+        assert_eq!(joined, [LoginId(100), LoginId(101)]);
+        assert_eq!(rejected, [LoginId(102)]);
+    }
+
+    #[test]
+    fn blocks_are_put_back_once_the_terrain_is_full() {
+        let mut world = World::new();
+        let terrain = picocraft_terrain::TerrainBuilder::new(0).build_with_max_changes::<1>();
+        let mut events = EVENTS.subscriber().expect("a subscriber is free");
+
+        let air = VarInt(Block::Air as i32);
+        terrain
+            .set_block_state(Coordinates::new(40, 0, 40), air)
+            .expect("room for a change");
+        assert_eq!(
+            terrain.set_block_state(Coordinates::new(41, 0, 40), air),
+            Err(ModificationError::Full)
+        );
+
+        let username = String::try_from("Steve").expect("short enough");
+        system_player_joined(&mut world, LoginId(0), username, UUID::from_u128(0xdef0));
+        let player_id = EntityId::player(world.players.uuid.iter().next().expect("spawned").0);
+        let standing_at = Position::new(-39.5, 1.0, 21.5);
+        system_player_moved(&mut world, player_id, Some(standing_at), None, true, false);
+
+        let position = BlockPosition::new(-40, 21, 0);
+        let bedrock = terrain
+            .block_state_at(Coordinates::new(-40, 0, 21))
+            .expect("inside the terrain");
+        system_block_broken(&mut world, &terrain, player_id, position, VarInt(0x7e57));
+
+        let mut reverted = None;
+        let mut acknowledged = false;
+        while let Some(event) = events.try_next_message_pure() {
+            match event {
+                WorldEvent::BlockBroken {
+                    position: broken, ..
+                } => {
+                    assert_ne!(broken, position, "the block shouldn't be broken");
+                }
+                WorldEvent::BlockReverted {
+                    position: at,
+                    block_state,
+                    ..
+                } if at == position => reverted = Some(block_state),
+                WorldEvent::BlockChangesAcknowledged { sequence, .. } if sequence.0 == 0x7e57 => {
+                    assert!(reverted.is_some(), "put back before acknowledging");
+                    acknowledged = true;
+                }
+                _ => {}
+            }
+        }
+
+        assert_eq!(reverted, Some(bedrock));
+        assert!(acknowledged);
+        assert_eq!(
+            terrain.block_state_at(Coordinates::new(-40, 0, 21)),
+            Some(bedrock)
+        );
+    }
+
+    #[test]
+    fn the_player_seen_longest_ago_is_forgotten_first() {
+        let mut world = World::new();
+
+        let join_and_leave = |world: &mut World, uuid: u128| {
+            let username = String::try_from("Steve").expect("short enough");
+            system_player_joined(world, LoginId(0), username, UUID::from_u128(uuid));
+
+            let (index, _) = world.players.uuid.iter().next().expect("spawned");
+            world.increment_tick();
+            system_player_left(world, EntityId::player(index));
+        };
+
+        join_and_leave(&mut world, 0);
+        join_and_leave(&mut world, 1);
+        // Now the first player was seen more recently than the second.
+        join_and_leave(&mut world, 0);
+
+        for uuid in 2..=world.player_save_data.len() as u128 {
+            join_and_leave(&mut world, uuid);
+        }
+
+        assert!(world.saved_player(UUID::from_u128(0)).is_some());
+        assert!(world.saved_player(UUID::from_u128(1)).is_none());
+        assert!(world.saved_player(UUID::from_u128(2)).is_some());
+    }
 }
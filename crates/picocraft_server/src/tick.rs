@@ -1,18 +1,33 @@
+use core::sync::atomic::Ordering;
+
+use log::warn;
 use picocraft_ecs::commands::WorldCommand;
+use picocraft_ecs::storage::GetComponent;
 use picocraft_ecs::world::World;
+use picocraft_proto::clientbound::PlayerSample;
 use picocraft_terrain::Terrain;
 
-use crate::channels::COMMANDS;
+use crate::channels::{COMMANDS, ONLINE_PLAYERS, PLAYER_SAMPLE};
+use crate::player_data::{self, AUTOSAVE_INTERVAL_TICKS};
 use crate::systems::*;
 
-pub fn tick(world: &mut World, _terrain: &Terrain) {
+pub fn tick(world: &mut World, terrain: &Terrain) {
     world.increment_tick();
 
     // drain commands first to mutate world state before any systems run
     while let Ok(cmd) = COMMANDS.try_receive() {
-        handle_command(world, cmd);
+        handle_command(world, terrain, cmd);
     }
 
+    system_expire_pending_joins(world);
+
+    if world.tick_count().is_multiple_of(AUTOSAVE_INTERVAL_TICKS) {
+        system_save_players(world);
+    }
+
+    ONLINE_PLAYERS.store(world.players.count(), Ordering::Relaxed);
+    update_player_sample(world);
+
     // debug::print_players_every_second(world);
 
     // // systems
@@ -31,8 +46,35 @@ pub fn tick(world: &mut World, _terrain: &Terrain) {
     // combat::apply_damage(&mut world.players);
 }
 
+/// Lists the first players in the world for the server list.
+fn update_player_sample(world: &World) {
+    PLAYER_SAMPLE.lock(|sample| {
+        let mut sample = sample.borrow_mut();
+        sample.clear();
+
+        let players = world
+            .players
+            .username
+            .iter()
+            .filter_map(|(index, username)| {
+                let uuid = world.players.uuid.get(index)?;
+
+                Some(PlayerSample {
+                    name: username.0.clone(),
+                    id: uuid.0,
+                })
+            });
+
+        for player in players {
+            if sample.push(player).is_err() {
+                break;
+            }
+        }
+    });
+}
+
 #[allow(unreachable_patterns)]
-fn handle_command(world: &mut World, cmd: WorldCommand) {
+fn handle_command(world: &mut World, terrain: &Terrain, cmd: WorldCommand) {
     match cmd {
         //TODO too verbose
         WorldCommand::PlayerMoved {
@@ -81,12 +123,72 @@ fn handle_command(world: &mut World, cmd: WorldCommand) {
                 against_wall,
             );
         }
-        WorldCommand::PlayerJoined { username, uuid } => {
-            system_player_joined(world, username, uuid);
+        WorldCommand::PlayerJoined {
+            login,
+            username,
+            uuid,
+        } => {
+            system_player_joined(world, login, username, uuid);
         }
         WorldCommand::PlayerLeft { player_id } => {
             system_player_left(world, player_id);
         }
+        WorldCommand::PlayerDataLoaded { uuid, save } => {
+            system_player_data_loaded(world, uuid, save);
+        }
+        WorldCommand::SavePlayers => {
+            system_save_players(world);
+
+            if !player_data::flush() {
+                warn!("Too many saves are waiting for storage to finish saving players.");
+            }
+        }
+        WorldCommand::TransferPlayer {
+            player_id,
+            host,
+            port,
+        } => {
+            system_transfer_player(world, player_id, host, port);
+        }
+        WorldCommand::ResyncPlayer { player_id } => {
+            system_resync_player(world, player_id);
+        }
+        WorldCommand::UpdateLatency { player_id, latency } => {
+            system_update_latency(world, player_id, latency);
+        }
+        WorldCommand::BlockBroken {
+            player_id,
+            position,
+            sequence,
+        } => {
+            system_block_broken(world, terrain, player_id, position, sequence);
+        }
+        WorldCommand::BlockPlaced {
+            player_id,
+            hand,
+            against,
+            position,
+            sequence,
+        } => {
+            system_block_placed(world, terrain, player_id, hand, against, position, sequence);
+        }
+        WorldCommand::SetInventorySlot {
+            player_id,
+            slot,
+            item,
+        } => {
+            system_set_inventory_slot(world, player_id, slot, item);
+        }
+        WorldCommand::SelectHotbarSlot { player_id, slot } => {
+            system_select_hotbar_slot(world, player_id, slot);
+        }
+        WorldCommand::SendPluginMessage {
+            player_id,
+            channel,
+            data,
+        } => {
+            system_send_plugin_message(world, player_id, channel, data);
+        }
         _ => {}
     }
 }
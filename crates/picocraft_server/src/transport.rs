@@ -0,0 +1,39 @@
+//! The byte streams clients connect over.
+//!
+//! A [`Listener`] accepts connections, each of which is a [`Transport`]. The
+//! rest of the server only reads and writes through `embedded_io_async`, so
+//! it runs the same over tokio's TCP, embassy-net, or the in-memory
+//! [`memory`] pipes used by tests.
+
+#[cfg(feature = "embassy")]
+pub mod embassy;
+pub mod memory;
+#[cfg(feature = "tokio")]
+pub mod tcp;
+
+use core::net::SocketAddr;
+
+use crate::prelude::*;
+
+/// A connection to a single client.
+#[allow(async_fn_in_trait)]
+pub trait Transport: Read + Write + embedded_io::ErrorType<Error = SocketError> {
+    /// Where the client connected from.
+    fn remote_endpoint(&self) -> SocketAddr;
+
+    /// Waits for data to arrive, and copies as much of it as fits into `buf`
+    /// without consuming it.
+    async fn peek(&mut self, buf: &mut [u8]) -> Result<usize, SocketError>;
+
+    /// Sends anything buffered and closes the connection for writing. The
+    /// client sees the end of the stream once it has read everything.
+    async fn shutdown(&mut self) -> Result<(), SocketError>;
+}
+
+/// Accepts new connections for the server.
+#[allow(async_fn_in_trait)]
+pub trait Listener {
+    type Transport: Transport;
+
+    async fn accept(&mut self) -> Result<Self::Transport, SocketError>;
+}
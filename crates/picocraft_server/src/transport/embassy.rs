@@ -0,0 +1,176 @@
+use core::cell::{Cell, UnsafeCell};
+use core::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use embassy_net::Stack;
+use embassy_net::tcp::TcpSocket;
+use embassy_sync::blocking_mutex::Mutex as BlockingMutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+
+use super::{Listener, Transport};
+use crate::prelude::*;
+
+/// How long to wait before looking for a free [`SocketSlot`] again, when
+/// every one is in use.
+const SLOT_RETRY: embassy_time::Duration = embassy_time::Duration::from_millis(250);
+
+/// The receive and transmit buffers for one embassy-net socket. A listener
+/// needs one for each connection it can have open at once.
+pub struct SocketSlot<const RX: usize = 2048, const TX: usize = 2048> {
+    buffers: UnsafeCell<([u8; RX], [u8; TX])>,
+    in_use: BlockingMutex<CriticalSectionRawMutex, Cell<bool>>,
+}
+
+// SAFETY: The buffers are only ever handed out by `SocketSlot::claim`, which
+// checks and sets `in_use` under a critical section, so only one socket can
+// have them at a time.
+unsafe impl<const RX: usize, const TX: usize> Sync for SocketSlot<RX, TX> {}
+
+impl<const RX: usize, const TX: usize> SocketSlot<RX, TX> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            buffers: UnsafeCell::new(([0; RX], [0; TX])),
+            in_use: BlockingMutex::new(Cell::new(false)),
+        }
+    }
+
+    /// Takes the buffers, if no socket is using them.
+    #[allow(clippy::mut_from_ref)]
+    fn claim(&self) -> Option<(&mut [u8], &mut [u8], SlotGuard<'_>)> {
+        if self.in_use.lock(|in_use| in_use.replace(true)) {
+            return None;
+        }
+
+        // SAFETY: `in_use` was false and is now true, so nothing else has the
+        // buffers until the returned guard is dropped.
+        let (rx, tx) = unsafe { &mut *self.buffers.get() };
+
+        Some((rx, tx, SlotGuard(&self.in_use)))
+    }
+}
+
+impl<const RX: usize, const TX: usize> Default for SocketSlot<RX, TX> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Frees a [`SocketSlot`] when dropped.
+struct SlotGuard<'a>(&'a BlockingMutex<CriticalSectionRawMutex, Cell<bool>>);
+
+impl Drop for SlotGuard<'_> {
+    fn drop(&mut self) {
+        self.0.lock(|in_use| in_use.set(false));
+    }
+}
+
+/// Accepts TCP connections on `port`, with a socket for each of `slots`.
+pub struct EmbassyListener<'a, const RX: usize = 2048, const TX: usize = 2048> {
+    stack: Stack<'a>,
+    port: u16,
+    slots: &'a [SocketSlot<RX, TX>],
+}
+
+impl<'a, const RX: usize, const TX: usize> EmbassyListener<'a, RX, TX> {
+    pub fn new(stack: Stack<'a>, port: u16, slots: &'a [SocketSlot<RX, TX>]) -> Self {
+        Self { stack, port, slots }
+    }
+}
+
+impl<'a, const RX: usize, const TX: usize> Listener for EmbassyListener<'a, RX, TX> {
+    type Transport = EmbassyTransport<'a>;
+
+    async fn accept(&mut self) -> Result<EmbassyTransport<'a>, SocketError> {
+        let (rx, tx, slot) = loop {
+            if let Some(claimed) = self.slots.iter().find_map(SocketSlot::claim) {
+                break claimed;
+            }
+
+            embassy_time::Timer::after(SLOT_RETRY).await;
+        };
+
+        let mut socket = TcpSocket::new(self.stack, rx, tx);
+
+        if let Err(e) = socket.accept(self.port).await {
+            warn!("failed to accept a connection: {e:?}");
+            return Err(SocketError::Io(embedded_io::ErrorKind::ConnectionAborted));
+        }
+
+        let remote_endpoint = socket.remote_endpoint().map_or(
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+            |endpoint| SocketAddr::new(endpoint.addr.into(), endpoint.port),
+        );
+
+        Ok(EmbassyTransport {
+            socket,
+            remote_endpoint,
+            _slot: slot,
+        })
+    }
+}
+
+/// An embassy-net TCP connection.
+pub struct EmbassyTransport<'a> {
+    socket: TcpSocket<'a>,
+    remote_endpoint: SocketAddr,
+    /// Declared after `socket`, so the buffers are only freed once the socket
+    /// is done with them.
+    _slot: SlotGuard<'a>,
+}
+
+impl Transport for EmbassyTransport<'_> {
+    fn remote_endpoint(&self) -> SocketAddr {
+        self.remote_endpoint
+    }
+
+    async fn peek(&mut self, buf: &mut [u8]) -> Result<usize, SocketError> {
+        self.socket
+            .read_with(|data| {
+                let len = data.len().min(buf.len());
+                buf[..len].copy_from_slice(&data[..len]);
+
+                // Nothing is consumed.
+                (0, len)
+            })
+            .await
+            .map_err(|e| SocketError::Io(embedded_io::Error::kind(&e)))
+    }
+
+    async fn shutdown(&mut self) -> Result<(), SocketError> {
+        self.socket.close();
+
+        self.socket
+            .flush()
+            .await
+            .map_err(|e| SocketError::Io(embedded_io::Error::kind(&e)))
+    }
+}
+
+impl embedded_io::ErrorType for EmbassyTransport<'_> {
+    type Error = SocketError;
+}
+
+impl Read for EmbassyTransport<'_> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.socket
+            .read(buf)
+            .await
+            .map_err(|e| SocketError::Io(embedded_io::Error::kind(&e)))
+    }
+}
+
+impl Write for EmbassyTransport<'_> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.socket
+            .write(buf)
+            .await
+            .map_err(|e| SocketError::Io(embedded_io::Error::kind(&e)))
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.socket
+            .flush()
+            .await
+            .map_err(|e| SocketError::Io(embedded_io::Error::kind(&e)))
+    }
+}
@@ -0,0 +1,304 @@
+//! In-memory connections, so tests can run a whole server in one process
+//! without binding real ports.
+//!
+//! ```ignore
+//! static LISTENER: MemoryListener = MemoryListener::new();
+//! static PIPE: MemoryPipe = MemoryPipe::new();
+//!
+//! let mut server = Server::new(config, &LISTENER, system_rng);
+//! let mut client = LISTENER.connect(&PIPE).await;
+//! ```
+
+use core::cell::{Cell, RefCell};
+use core::future::poll_fn;
+use core::net::{IpAddr, Ipv4Addr, SocketAddr};
+use core::task::{Context, Poll};
+
+use embassy_sync::blocking_mutex::Mutex as BlockingMutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::Channel;
+use embassy_sync::waitqueue::WakerRegistration;
+use heapless::Deque;
+
+use super::{Listener, Transport};
+use crate::prelude::*;
+
+/// Bytes buffered in each direction of a pipe by default.
+pub const DEFAULT_CAPACITY: usize = 4096;
+
+/// Connections which can wait to be accepted before
+/// [`MemoryListener::connect`] waits too.
+const BACKLOG: usize = 4;
+
+/// The first port handed out to connecting clients, so each gets its own
+/// address like they would over TCP.
+const FIRST_PORT: u16 = 49152;
+
+/// What the server appears as to clients.
+const SERVER_ENDPOINT: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 25565);
+
+/// Accepts connections made with [`MemoryListener::connect`].
+pub struct MemoryListener<'a, const N: usize = DEFAULT_CAPACITY> {
+    incoming: Channel<CriticalSectionRawMutex, MemoryTransport<'a, N>, BACKLOG>,
+    connections: BlockingMutex<CriticalSectionRawMutex, Cell<u16>>,
+}
+
+impl<'a, const N: usize> MemoryListener<'a, N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            incoming: Channel::new(),
+            connections: BlockingMutex::new(Cell::new(0)),
+        }
+    }
+
+    /// Connects over `pipe`, returning the client's end once the server's
+    /// end is waiting to be accepted. A pipe can only be used for one
+    /// connection.
+    pub async fn connect(&self, pipe: &'a MemoryPipe<N>) -> MemoryTransport<'a, N> {
+        let port = self.connections.lock(|connections| {
+            let count = connections.get();
+            connections.set(count.wrapping_add(1));
+            FIRST_PORT.wrapping_add(count)
+        });
+
+        let client_endpoint = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port);
+
+        let client = MemoryTransport {
+            rx: &pipe.to_client,
+            tx: &pipe.to_server,
+            remote_endpoint: SERVER_ENDPOINT,
+        };
+        let server = MemoryTransport {
+            rx: &pipe.to_server,
+            tx: &pipe.to_client,
+            remote_endpoint: client_endpoint,
+        };
+
+        self.incoming.send(server).await;
+
+        client
+    }
+}
+
+impl<const N: usize> Default for MemoryListener<'_, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, const N: usize> Listener for &'a MemoryListener<'a, N> {
+    type Transport = MemoryTransport<'a, N>;
+
+    async fn accept(&mut self) -> Result<Self::Transport, SocketError> {
+        Ok(self.incoming.receive().await)
+    }
+}
+
+/// The buffers behind one in-memory connection, with room for `N` bytes in
+/// each direction.
+pub struct MemoryPipe<const N: usize = DEFAULT_CAPACITY> {
+    to_server: Direction<N>,
+    to_client: Direction<N>,
+}
+
+impl<const N: usize> MemoryPipe<N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            to_server: Direction::new(),
+            to_client: Direction::new(),
+        }
+    }
+}
+
+impl<const N: usize> Default for MemoryPipe<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One end of a [`MemoryPipe`]. Dropping it closes the connection in both
+/// directions, so the other end reads the end of the stream and can't write.
+pub struct MemoryTransport<'a, const N: usize = DEFAULT_CAPACITY> {
+    rx: &'a Direction<N>,
+    tx: &'a Direction<N>,
+    remote_endpoint: SocketAddr,
+}
+
+impl<const N: usize> Transport for MemoryTransport<'_, N> {
+    fn remote_endpoint(&self) -> SocketAddr {
+        self.remote_endpoint
+    }
+
+    async fn peek(&mut self, buf: &mut [u8]) -> Result<usize, SocketError> {
+        Ok(poll_fn(|cx| self.rx.poll_read(cx, buf, false)).await)
+    }
+
+    async fn shutdown(&mut self) -> Result<(), SocketError> {
+        self.tx.close();
+
+        Ok(())
+    }
+}
+
+impl<const N: usize> Drop for MemoryTransport<'_, N> {
+    fn drop(&mut self) {
+        self.rx.close();
+        self.tx.close();
+    }
+}
+
+impl<const N: usize> embedded_io::ErrorType for MemoryTransport<'_, N> {
+    type Error = SocketError;
+}
+
+impl<const N: usize> Read for MemoryTransport<'_, N> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        Ok(poll_fn(|cx| self.rx.poll_read(cx, buf, true)).await)
+    }
+}
+
+impl<const N: usize> Write for MemoryTransport<'_, N> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        poll_fn(|cx| self.tx.poll_write(cx, buf)).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// The bytes travelling one way through a pipe.
+struct Direction<const N: usize> {
+    state: BlockingMutex<CriticalSectionRawMutex, RefCell<DirectionState<N>>>,
+}
+
+struct DirectionState<const N: usize> {
+    buffer: Deque<u8, N>,
+    closed: bool,
+    reader: WakerRegistration,
+    writer: WakerRegistration,
+}
+
+impl<const N: usize> Direction<N> {
+    const fn new() -> Self {
+        Self {
+            state: BlockingMutex::new(RefCell::new(DirectionState {
+                buffer: Deque::new(),
+                closed: false,
+                reader: WakerRegistration::new(),
+                writer: WakerRegistration::new(),
+            })),
+        }
+    }
+
+    /// Copies buffered bytes into `buf`, removing them if `consume` is set.
+    /// Gives 0 once the direction is closed and everything has been read.
+    fn poll_read(&self, cx: &mut Context<'_>, buf: &mut [u8], consume: bool) -> Poll<usize> {
+        self.state.lock(|state| {
+            let mut state = state.borrow_mut();
+
+            if buf.is_empty() || (state.buffer.is_empty() && state.closed) {
+                return Poll::Ready(0);
+            }
+
+            if state.buffer.is_empty() {
+                state.reader.register(cx.waker());
+                return Poll::Pending;
+            }
+
+            let len = buf.len().min(state.buffer.len());
+
+            for (byte, buffered) in buf.iter_mut().zip(state.buffer.iter()) {
+                *byte = *buffered;
+            }
+
+            if consume {
+                for _ in 0..len {
+                    state.buffer.pop_front();
+                }
+
+                state.writer.wake();
+            }
+
+            Poll::Ready(len)
+        })
+    }
+
+    fn poll_write(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize, SocketError>> {
+        self.state.lock(|state| {
+            let mut state = state.borrow_mut();
+
+            if state.closed {
+                return Poll::Ready(Err(SocketError::Io(embedded_io::ErrorKind::BrokenPipe)));
+            }
+
+            if buf.is_empty() {
+                return Poll::Ready(Ok(0));
+            }
+
+            if state.buffer.is_full() {
+                state.writer.register(cx.waker());
+                return Poll::Pending;
+            }
+
+            let len = buf.len().min(N - state.buffer.len());
+
+            for &byte in &buf[..len] {
+                let _ = state.buffer.push_back(byte);
+            }
+
+            state.reader.wake();
+
+            Poll::Ready(Ok(len))
+        })
+    }
+
+    fn close(&self) {
+        self.state.lock(|state| {
+            let mut state = state.borrow_mut();
+
+            state.closed = true;
+            state.reader.wake();
+            state.writer.wake();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pipes_carry_bytes_both_ways() {
+        static LISTENER: MemoryListener<'static, 8> = MemoryListener::new();
+        static PIPE: MemoryPipe<8> = MemoryPipe::new();
+
+        embassy_futures::block_on(async {
+            let mut client = LISTENER.connect(&PIPE).await;
+            let mut server = (&LISTENER).accept().await.expect("a client is waiting");
+
+            assert_eq!(server.remote_endpoint().port(), FIRST_PORT);
+
+            // Writes stop once the buffer is full.
+            assert_eq!(client.write(b"hello, server").await.expect("open"), 8);
+
+            let mut buf = [0u8; 16];
+            assert_eq!(server.peek(&mut buf).await.expect("open"), 8);
+            assert_eq!(server.read(&mut buf).await.expect("open"), 8);
+            assert_eq!(&buf[..8], b"hello, s");
+
+            server.write_all(b"hi").await.expect("open");
+            server.shutdown().await.expect("open");
+
+            assert_eq!(client.read(&mut buf).await.expect("open"), 2);
+            assert_eq!(&buf[..2], b"hi");
+            assert_eq!(client.read(&mut buf).await.expect("open"), 0);
+
+            drop(client);
+            assert!(server.write(b"?").await.is_err());
+            assert_eq!(server.read(&mut buf).await.expect("open"), 0);
+        });
+    }
+}
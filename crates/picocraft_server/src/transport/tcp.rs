@@ -0,0 +1,81 @@
+use core::net::SocketAddr;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt, BufStream};
+use tokio::net::{TcpListener, TcpStream};
+
+use super::{Listener, Transport};
+use crate::prelude::*;
+
+/// A tokio TCP connection, with buffered reads and writes so that encoding a
+/// packet field by field doesn't make a syscall for each one.
+#[derive(Debug)]
+pub struct TcpTransport {
+    inner: BufStream<TcpStream>,
+    remote_endpoint: SocketAddr,
+}
+
+impl TcpTransport {
+    pub fn new(stream: TcpStream) -> Result<Self, SocketError> {
+        let remote_endpoint = stream.peer_addr()?;
+
+        Ok(Self {
+            inner: BufStream::with_capacity(1024, 1024, stream),
+            remote_endpoint,
+        })
+    }
+}
+
+impl Transport for TcpTransport {
+    fn remote_endpoint(&self) -> SocketAddr {
+        self.remote_endpoint
+    }
+
+    async fn peek(&mut self, buf: &mut [u8]) -> Result<usize, SocketError> {
+        // This skips over anything already in the read buffer, so is only
+        // accurate before the first read.
+        Ok(self
+            .inner
+            .get_mut()
+            .peek(buf)
+            .await
+            .inspect_err(|e| warn!("failed to peek at upcoming data: {e}"))?)
+    }
+
+    async fn shutdown(&mut self) -> Result<(), SocketError> {
+        Ok(self
+            .inner
+            .shutdown()
+            .await
+            .inspect_err(|e| warn!("failed to send shutdown command: {e}"))?)
+    }
+}
+
+impl embedded_io::ErrorType for TcpTransport {
+    type Error = SocketError;
+}
+
+impl Read for TcpTransport {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        Ok(self.inner.read(buf).await?)
+    }
+}
+
+impl Write for TcpTransport {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(self.inner.write(buf).await?)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(self.inner.flush().await?)
+    }
+}
+
+impl Listener for TcpListener {
+    type Transport = TcpTransport;
+
+    async fn accept(&mut self) -> Result<TcpTransport, SocketError> {
+        let (stream, _) = TcpListener::accept(self).await?;
+
+        TcpTransport::new(stream)
+    }
+}
@@ -4,27 +4,39 @@ pub mod chunks;
 pub mod coordinates;
 pub mod heightmaps;
 pub mod light;
+pub mod modifications;
 pub mod palettes;
 pub mod spiral_iterator;
 
+use core::cell::RefCell;
+
 use blocks::IndexedBlock;
 use coordinates::*;
+use embassy_sync::blocking_mutex::Mutex as BlockingMutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use modifications::{BlockChange, MAX_BLOCK_CHANGES, ModificationError, Modifications};
 
 use crate::noise::*;
 use crate::prelude::*;
 use crate::terrain::chunks::ChunkSection;
 use crate::terrain::heightmaps::ChunkHeightmaps;
+use crate::terrain::palettes::{BlockPalette, MAX_PALETTE_LEN};
 
+/// The generated terrain, and up to `MAX_CHANGES` blocks players have changed
+/// in it.
 #[non_exhaustive]
-pub struct Terrain {
+pub struct Terrain<const MAX_CHANGES: usize = MAX_BLOCK_CHANGES> {
     pub(crate) seed: u64,
     pub(crate) terrain_map: NoiseMap256,
     /// The player y level considered to be "sea level", for which air blocks
     /// below this level are filled with water, before caves are applied.
     pub(crate) sea_level: u8,
+    /// Blocks players have changed since the terrain was generated.
+    pub(crate) modifications:
+        BlockingMutex<CriticalSectionRawMutex, RefCell<Modifications<MAX_CHANGES>>>,
 }
 
-impl Terrain {
+impl<const MAX_CHANGES: usize> Terrain<MAX_CHANGES> {
     pub fn sea_level(&self) -> u8 {
         self.sea_level
     }
@@ -33,6 +45,73 @@ impl Terrain {
         self.seed
     }
 
+    /// The block state ID at `coordinates`, including any changes made to it,
+    /// or [`None`] if it is outside the generated terrain.
+    pub fn block_state_at(&self, coordinates: Coordinates) -> Option<VarInt> {
+        let generated = self.generated_block_state_at(coordinates)?;
+
+        let changed = self
+            .modifications
+            .lock(|modifications| modifications.borrow().get(coordinates));
+
+        Some(changed.unwrap_or(generated))
+    }
+
+    /// Changes the block at `coordinates` to `state`, so it is sent that way
+    /// from now on.
+    pub fn set_block_state(
+        &self,
+        coordinates: Coordinates,
+        state: VarInt,
+    ) -> Result<(), ModificationError> {
+        let generated = self
+            .generated_block_state_at(coordinates)
+            .ok_or(ModificationError::OutOfBounds)?;
+
+        let section = coordinates.chunk();
+        let palette = self.get_palette(section);
+
+        self.modifications.lock(|modifications| {
+            let mut modifications = modifications.borrow_mut();
+
+            // Changing a block back to how it was generated frees its change.
+            if state == generated {
+                modifications.remove(coordinates);
+                return Ok(());
+            }
+
+            if palette.index_of(state).is_none() {
+                let extra = extra_states(&palette, modifications.in_section(section));
+
+                if !extra.contains(&state) && extra.is_full() {
+                    return Err(ModificationError::PaletteFull);
+                }
+            }
+
+            modifications.set(coordinates, state)
+        })
+    }
+
+    /// Runs `f` with the changes made to the terrain.
+    pub fn with_modifications<R>(&self, f: impl FnOnce(&Modifications<MAX_CHANGES>) -> R) -> R {
+        self.modifications
+            .lock(|modifications| f(&modifications.borrow()))
+    }
+
+    fn generated_block_state_at(&self, coordinates: Coordinates) -> Option<VarInt> {
+        let Coordinates { x, y, z } = coordinates;
+
+        // The terrain map is 256 blocks across, centred on 0, 0.
+        self.terrain_map
+            .get(x.checked_add(128)?, z.checked_add(128)?)?;
+
+        let block = self
+            .get_palette(coordinates.chunk())
+            .to_block(self.get_indexed_block_at(x, y, z));
+
+        Some(VarInt(block as i32))
+    }
+
     pub fn get_chunk_packet(&self, chunk_x: i8, chunk_z: i8) -> chunks::ChunkAndLightPacket {
         chunks::ChunkAndLightPacket {
             chunk_x: Int::from(chunk_x),
@@ -67,6 +146,7 @@ impl Terrain {
         chunk_coords: ChunkCoordinates,
         heightmap: &mut ChunkHeightmaps,
     ) -> chunks::ChunkSection {
+        let palette = self.get_palette(chunk_coords);
         let mut packed_blocks: Array<u64, 256> = Array::new();
 
         let mut block_count: Short = 0;
@@ -76,39 +156,73 @@ impl Terrain {
         let mut accumulator: u64 = 0;
         let mut shift: u32 = 0;
 
-        for coords in bounds.iter() {
-            let indexed_block = self.get_indexed_block_at(coords.x, coords.y, coords.z);
+        let extra = self.modifications.lock(|modifications| {
+            let modifications = modifications.borrow();
+            let changes = modifications.in_section(chunk_coords);
+            let extra = extra_states(&palette, changes);
 
-            let local_x = coords.x.rem_euclid(16) as u8;
-            let local_z = coords.z.rem_euclid(16) as u8;
+            // Blocks are generated in the order changes are sorted in, so each
+            // change is reached in turn.
+            let mut changes = changes.iter().peekable();
 
-            if indexed_block != IndexedBlock::Air {
-                heightmap
-                    .world_surface
-                    .set(local_x, local_z, Some(coords.y));
+            for (index, coords) in bounds.iter().enumerate() {
+                let generated = self
+                    .get_indexed_block_at(coords.x, coords.y, coords.z)
+                    .get_id();
 
-                block_count += 1;
-            }
+                let palette_index = match changes
+                    .next_if(|change| usize::from(change.index()) == index)
+                {
+                    Some(change) => changed_block(&palette, &extra, change).unwrap_or(generated),
+                    None => generated,
+                };
+
+                let local_x = coords.x.rem_euclid(16) as u8;
+                let local_z = coords.z.rem_euclid(16) as u8;
 
-            let value = indexed_block as u64;
+                if palette_index != IndexedBlock::Air.get_id() {
+                    heightmap
+                        .world_surface
+                        .set(local_x, local_z, Some(coords.y));
 
-            accumulator |= (value << shift);
-            shift += 4;
+                    block_count += 1;
+                }
 
-            if shift >= 64 {
-                packed_blocks
-                    .push(accumulator)
-                    .expect("should have space for 256 Longs in chunk section");
-                accumulator = 0;
-                shift = 0;
+                let value = u64::from(palette_index);
+
+                accumulator |= (value << shift);
+                shift += 4;
+
+                if shift >= 64 {
+                    packed_blocks
+                        .push(accumulator)
+                        .expect("should have space for 256 Longs in chunk section");
+                    accumulator = 0;
+                    shift = 0;
+                }
             }
-        }
+
+            extra
+        });
+
+        // Blocks which aren't in the terrain's palette go after it.
+        let palette = if extra.is_empty() {
+            palette.into()
+        } else {
+            let mut states = Vec::<VarInt, MAX_PALETTE_LEN>::from_iter(
+                IndexedBlock::ALL
+                    .map(|indexed_block| VarInt(palette.to_block(indexed_block) as i32)),
+            );
+            states.extend(extra);
+
+            BlockPalette::States(states.into())
+        };
 
         chunks::ChunkSection {
             block_count,
             blocks: chunks::BlockContainer {
                 bits_per_entry: 4,
-                palette: self.get_palette(chunk_coords),
+                palette,
                 packed_blocks,
             },
             biomes: chunks::BiomeContainer::default(),
@@ -193,3 +307,52 @@ impl Terrain {
         }
     }
 }
+
+/// Most block states a section can have besides those in its terrain palette.
+const MAX_EXTRA_STATES: usize = MAX_PALETTE_LEN - IndexedBlock::ALL.len();
+
+/// The states blocks in a section were changed to which aren't in its terrain
+/// palette, in the order they are added to the palette after it.
+fn extra_states(
+    palette: &palettes::Palette,
+    changes: &[BlockChange],
+) -> Vec<VarInt, MAX_EXTRA_STATES> {
+    let mut extra = Vec::new();
+
+    for change in changes {
+        if palette.index_of(change.state).is_none() && !extra.contains(&change.state) {
+            // `set_block_state` refuses any more than fit.
+            let _ = extra.push(change.state);
+        }
+    }
+
+    extra
+}
+
+/// Where `change` is in the section's palette, which is its terrain palette
+/// followed by `extra`.
+fn changed_block(
+    palette: &palettes::Palette,
+    extra: &[VarInt],
+    change: &BlockChange,
+) -> Option<u8> {
+    let found = palette
+        .index_of(change.state)
+        .map(|block| block.get_id())
+        .or_else(|| {
+            extra
+                .iter()
+                .position(|state| *state == change.state)
+                .map(|i| (IndexedBlock::ALL.len() + i) as u8)
+        });
+
+    if found.is_none() {
+        log::warn!(
+            "Block state {} at {:?} doesn't fit in the chunk section's palette",
+            *change.state,
+            change.coordinates()
+        );
+    }
+
+    found
+}
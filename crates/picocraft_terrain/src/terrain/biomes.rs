@@ -1,33 +1,79 @@
+use picocraft_proto::registry::{self, RegistryEntry};
+
 use crate::prelude::*;
 
-// All biomes except plains currently don't work, since the shared registries
-// aren't implemented well yet, and onlu plains is defined.
-#[derive(Debug, picocraft_derive::Encode, Clone, Copy)]
+/// The biomes terrain is generated with. These are also the entries of the
+/// `minecraft:worldgen/biome` registry sent to clients, in the order given by
+/// [`Biome::ALL`], so each biome is sent in chunk data as its discriminant.
+#[derive(Debug, picocraft_derive::Encode, Clone, Copy, PartialEq, Eq)]
 #[protocol(value = VarInt)]
 pub enum Biome {
     Plains = 0,
-    Ocean = -1,
-    Mountains = -2,
-    Desert = -3,
-    Taiga = -4,
-    Savanna = -5,
-    Forest = -6,
-    River = -7,
+    Ocean = 1,
+    Mountains = 2,
+    Desert = 3,
+    Taiga = 4,
+    Savanna = 5,
+    Forest = 6,
+    River = 7,
 }
 
 impl Biome {
-    pub const ALL: [Biome; 1] = [
+    pub const ALL: [Biome; 8] = [
         Biome::Plains,
-        // Biome::Ocean,
-        // Biome::Mountains,
-        // Biome::Desert,
-        // Biome::Taiga,
-        // Biome::Savanna,
-        // Biome::Forest,
-        // Biome::River,
+        Biome::Ocean,
+        Biome::Mountains,
+        Biome::Desert,
+        Biome::Taiga,
+        Biome::Savanna,
+        Biome::Forest,
+        Biome::River,
     ];
 
     pub fn index(&self) -> u8 {
         (*self) as u8
     }
+
+    /// The vanilla biome this looks like to the client.
+    pub const fn id(self) -> &'static str {
+        match self {
+            Self::Plains => "plains",
+            Self::Ocean => "ocean",
+            Self::Mountains => "windswept_hills",
+            Self::Desert => "desert",
+            Self::Taiga => "taiga",
+            Self::Savanna => "savanna",
+            Self::Forest => "forest",
+            Self::River => "river",
+        }
+    }
+
+    pub const fn data(self) -> registry::Biome {
+        let plains = registry::Biome::PLAINS;
+
+        let (temperature, downfall, sky_color) = match self {
+            Self::Plains => return plains,
+            Self::Ocean | Self::River => (0.5, 0.5, 0x007b_a4ff),
+            Self::Mountains => (0.2, 0.3, 0x007d_a2ff),
+            Self::Desert | Self::Savanna => (2.0, 0.0, 0x006e_b1ff),
+            Self::Taiga => (0.25, 0.8, 0x007d_a3ff),
+            Self::Forest => (0.7, 0.8, 0x0079_a6ff),
+        };
+
+        registry::Biome {
+            has_precipitation: downfall > 0.0,
+            temperature,
+            downfall,
+            effects: registry::BiomeEffects {
+                sky_color,
+                ..plains.effects
+            },
+        }
+    }
+
+    /// The `minecraft:worldgen/biome` registry, in the order chunk data
+    /// refers to it.
+    pub fn registry_entries() -> [RegistryEntry<'static, registry::Biome>; 8] {
+        Self::ALL.map(|biome| RegistryEntry::new(biome.id(), biome.data()))
+    }
 }
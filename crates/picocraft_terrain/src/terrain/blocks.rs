@@ -64,6 +64,7 @@ pub enum Block {
     Bedrock = 85,
 
     Stone = 1,
+    Cobblestone = 14,
     Deepslate = 27722,
     Granite = 2,
     Diorite = 4,
@@ -134,3 +135,28 @@ pub enum Block {
     SnowBlock = -43,
     Clay = -44,
 }
+
+impl Block {
+    /// The block placed by the item with ID `item_id`, for the few items whose
+    /// protocol 773 IDs are known. Like the block state IDs, these should
+    /// really come from the built-in registries.
+    pub fn from_item(item_id: VarInt) -> Option<Self> {
+        Some(match item_id.0 {
+            1 => Self::Stone,
+            2 => Self::Granite,
+            4 => Self::Diorite,
+            6 => Self::Andesite,
+            27 => Self::GrassBlock,
+            28 => Self::Dirt,
+            35 => Self::Cobblestone,
+            _ => return None,
+        })
+    }
+
+    /// Whether placing a block here replaces it, rather than being refused.
+    pub fn is_replaceable(state: VarInt) -> bool {
+        [Self::Air, Self::Water, Self::Lava, Self::ShortGrass]
+            .into_iter()
+            .any(|block| block as i32 == state.0)
+    }
+}
@@ -3,9 +3,12 @@ pub mod empty_chunk;
 use picocraft_derive::{Decode, Encode, Packet};
 
 use super::heightmaps::ChunkHeightmaps;
-use super::palettes::Palette;
+use super::palettes::{BlockPalette, Palette};
 use crate::prelude::*;
 
+/// Chunk sections in a chunk column, from the bottom of the world up.
+pub const SECTIONS: usize = WORLD_HEIGHT / 16;
+
 #[derive(Debug, Packet)]
 #[packet(id = 0x2c)]
 pub struct ChunkAndLightPacket {
@@ -19,7 +22,7 @@ pub struct ChunkAndLightPacket {
 pub struct ChunkData {
     pub heightmaps: ChunkHeightmaps,
 
-    pub data: Array<ChunkSection, 16>,
+    pub data: Array<ChunkSection, SECTIONS>,
     pub block_entities: PrefixedArray<BlockEntity, 0>,
 }
 
@@ -42,12 +45,37 @@ impl Encode for ChunkData {
 }
 
 impl Decode for ChunkData {
-    async fn decode<R: embedded_io_async::Read>(mut _buffer: R) -> Result<Self, DecodeError> {
-        todo!()
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        let heightmaps = ChunkHeightmaps::decode(&mut buffer).await?;
+
+        // The size of the sections is only needed to skip over them.
+        VarInt::decode(&mut buffer).await?;
+
+        let mut data = Array::new();
+
+        for _ in 0..SECTIONS {
+            let _ = data.push(ChunkSection::decode(&mut buffer).await?);
+        }
+
+        Ok(Self {
+            heightmaps,
+            data,
+            block_entities: PrefixedArray::decode(&mut buffer).await?,
+        })
     }
 }
 
-#[derive(Debug, Encode)]
+impl ChunkData {
+    /// The block state ID at `x`, `y`, `z` within the chunk column.
+    pub fn block_state(&self, x: u8, y: u8, z: u8) -> Option<VarInt> {
+        self.data
+            .get(usize::from(y / 16))?
+            .blocks
+            .block_state(x, y % 16, z)
+    }
+}
+
+#[derive(Debug, Encode, Decode)]
 pub struct ChunkSection {
     pub block_count: Short,
     pub blocks: BlockContainer,
@@ -57,18 +85,49 @@ pub struct ChunkSection {
 #[derive(Debug)]
 pub struct BlockContainer {
     pub bits_per_entry: UnsignedByte,
-    pub palette: Palette,
+    pub palette: BlockPalette,
     pub packed_blocks: Array<u64, 256>,
 }
 
+/// Longs needed to pack `entries` entries of `bits_per_entry` bits, which
+/// don't span across longs.
+fn packed_len(entries: usize, bits_per_entry: u8) -> usize {
+    entries.div_ceil(64 / usize::from(bits_per_entry))
+}
+
+impl BlockContainer {
+    /// Only indirect palettes of up to 4 bits per entry are used, which is
+    /// what fits in `packed_blocks`.
+    pub const MAX_BITS_PER_ENTRY: u8 = 4;
+
+    /// The block state ID at `x`, `y`, `z` within the section.
+    pub fn block_state(&self, x: u8, y: u8, z: u8) -> Option<VarInt> {
+        if self.bits_per_entry == 0 {
+            return self.palette.state(0);
+        }
+
+        let bits = usize::from(self.bits_per_entry);
+        let entries_per_long = 64 / bits;
+        let index = (usize::from(y) * 16 + usize::from(z)) * 16 + usize::from(x);
+
+        let long = self.packed_blocks.get(index / entries_per_long)?;
+        let entry = (long >> ((index % entries_per_long) * bits)) & ((1 << bits) - 1);
+
+        self.palette.state(entry as usize)
+    }
+}
+
 impl Encode for BlockContainer {
     async fn encode<W: embedded_io_async::Write>(&self, mut buffer: W) -> Result<(), EncodeError> {
         if self.bits_per_entry == 0 {
-            // Special case for all air chunks, which have no palette and 0 bits per entry.
-            // In this case, we still need to write a palette (with a single entry of air)
-            // and 1 bit per entry to avoid confusion on the client side.
-            0u8.encode(&mut buffer).await?;
+            // Special case for sections of a single block (e.g. all air), which have no
+            // packed data, and a palette of just that block without a length prefix.
             0u8.encode(&mut buffer).await?;
+            self.palette
+                .state(0)
+                .unwrap_or_default()
+                .encode(&mut buffer)
+                .await?;
             return Ok(());
         }
 
@@ -80,11 +139,44 @@ impl Encode for BlockContainer {
     }
 }
 
+impl Decode for BlockContainer {
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        let bits_per_entry = u8::decode(&mut buffer).await?;
+
+        if bits_per_entry == 0 {
+            let state = VarInt::decode(&mut buffer).await?;
+
+            return Ok(Self {
+                bits_per_entry,
+                palette: BlockPalette::States(PrefixedArray::from_array([state])),
+                packed_blocks: Array::new(),
+            });
+        }
+
+        if bits_per_entry > Self::MAX_BITS_PER_ENTRY {
+            return Err(DecodeError::InvalidBPE(bits_per_entry));
+        }
+
+        let palette = BlockPalette::decode(&mut buffer).await?;
+        let mut packed_blocks = Array::new();
+
+        for _ in 0..packed_len(4096, bits_per_entry) {
+            let _ = packed_blocks.push(u64::decode(&mut buffer).await?);
+        }
+
+        Ok(Self {
+            bits_per_entry,
+            palette,
+            packed_blocks,
+        })
+    }
+}
+
 impl Default for BlockContainer {
     fn default() -> Self {
         Self {
             bits_per_entry: 0,
-            palette: Palette::Plains,
+            palette: Palette::Plains.into(),
             packed_blocks: Array::new(),
         }
     }
@@ -115,6 +207,33 @@ impl Encode for BiomeContainer {
     }
 }
 
+impl Decode for BiomeContainer {
+    /// Biomes are only kept if they use an indirect palette, and then without
+    /// the palette itself.
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        let bits_per_entry = u8::decode(&mut buffer).await?;
+
+        if bits_per_entry == 0 {
+            VarInt::decode(&mut buffer).await?;
+            return Ok(Self::default());
+        }
+
+        if bits_per_entry > 3 {
+            return Err(DecodeError::InvalidBPE(bits_per_entry));
+        }
+
+        PrefixedArray::<VarInt, 8>::decode(&mut buffer).await?;
+
+        let mut data = Array::new();
+
+        for _ in 0..packed_len(64, bits_per_entry) {
+            let _ = data.push(u64::decode(&mut buffer).await?);
+        }
+
+        Ok(Self { data })
+    }
+}
+
 #[derive(Debug, Clone, Encode, Decode)]
 pub struct BlockEntity {
     packed_xz: UnsignedByte,
@@ -30,7 +30,7 @@ impl From<(i8, i8)> for ChunkColumnCoordinates {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ChunkCoordinates {
     pub x: i8,
     pub y: u8,
@@ -47,6 +47,15 @@ impl ChunkCoordinates {
         Self { x, y, z }
     }
 
+    /// The block at `index` within the section, as its blocks are packed.
+    pub fn block(&self, index: u16) -> Coordinates {
+        Coordinates::new(
+            i16::from(self.x) * 16 + (index & 0x0f) as i16,
+            self.y * 16 + (index >> 8) as u8,
+            i16::from(self.z) * 16 + ((index >> 4) & 0x0f) as i16,
+        )
+    }
+
     pub fn to_bounds(&self) -> CoordinateBounds {
         let start_x = i16::from(self.x) * 16;
 
@@ -97,7 +106,7 @@ impl CoordinateBounds {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Coordinates {
     pub x: i16,
     pub y: u8,
@@ -108,6 +117,17 @@ impl Coordinates {
     pub fn new(x: i16, y: u8, z: i16) -> Self {
         Self { x, y, z }
     }
+
+    /// The chunk section the block is in. Only meaningful within the 256
+    /// blocks either side of 0, 0 that chunk coordinates can reach.
+    pub fn chunk(&self) -> ChunkCoordinates {
+        ChunkCoordinates::new((self.x >> 4) as i8, self.y >> 4, (self.z >> 4) as i8)
+    }
+
+    /// The block's index within its chunk section, as blocks are packed.
+    pub fn section_index(&self) -> u16 {
+        (u16::from(self.y & 0x0f) << 8) | ((self.z & 0x0f) as u16) << 4 | (self.x & 0x0f) as u16
+    }
 }
 
 impl Add for Coordinates {
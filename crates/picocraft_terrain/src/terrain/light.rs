@@ -39,8 +39,32 @@ impl Encode for LightData {
 }
 
 impl Decode for LightData {
-    async fn decode<R: embedded_io_async::Read>(_buffer: R) -> Result<Self, DecodeError> {
-        unimplemented!()
+    /// Everything is always fully lit, so the light arrays are skipped.
+    async fn decode<R: embedded_io_async::Read>(mut buffer: R) -> Result<Self, DecodeError> {
+        // sky light, block light, empty sky light and empty block light masks
+        for _ in 0..4 {
+            BitSet::<1>::decode(&mut buffer).await?;
+        }
+
+        // sky light arrays, then block light arrays
+        for _ in 0..2 {
+            let arrays = VarInt::decode(&mut buffer).await?;
+
+            for _ in 0..*arrays {
+                let length = VarInt::decode(&mut buffer).await?;
+                let mut remaining =
+                    usize::try_from(*length).map_err(|_| DecodeError::VarIntTooSmall(length))?;
+                let mut chunk = [0u8; 256];
+
+                while remaining > 0 {
+                    let read = remaining.min(chunk.len());
+                    buffer.read_exact(&mut chunk[..read]).await?;
+                    remaining -= read;
+                }
+            }
+        }
+
+        Ok(Self)
     }
 }
 
@@ -0,0 +1,207 @@
+//! Blocks players have changed, laid over the generated terrain. Terrain is
+//! generated again whenever a chunk is sent, so anything changed has to be
+//! kept here to be seen in it.
+
+use super::coordinates::{ChunkCoordinates, Coordinates};
+use crate::prelude::*;
+
+/// Most blocks which can differ from the generated terrain at once, across the
+/// whole world, unless a [`Terrain`](crate::Terrain) is built with another
+/// limit. Each change takes 8 bytes.
+///
+/// A block changed back to how it was generated frees its change, but
+/// otherwise changes are never forgotten, and they are saved and loaded again
+/// with the world. Once this many are in use, breaking or placing blocks
+/// anywhere else is refused, and players see the block put back.
+pub const MAX_BLOCK_CHANGES: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModificationError {
+    /// The block is outside the generated terrain.
+    OutOfBounds,
+    /// Every change is in use.
+    Full,
+    /// The block's chunk section can't show another kind of block.
+    PaletteFull,
+}
+
+/// A block which differs from the generated terrain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockChange {
+    /// The chunk section, then the block's index within it, so changes in the
+    /// same section sort together and in the order its blocks are sent.
+    key: u32,
+    pub state: VarInt,
+}
+
+impl BlockChange {
+    pub fn coordinates(&self) -> Coordinates {
+        self.section().block(self.index())
+    }
+
+    /// The chunk section the block is in.
+    pub fn section(&self) -> ChunkCoordinates {
+        ChunkCoordinates::new(
+            ((self.key >> 24) as u8 ^ 0x80) as i8,
+            (self.key >> 12) as u8 & 0x0f,
+            ((self.key >> 16) as u8 ^ 0x80) as i8,
+        )
+    }
+
+    /// The block's index within its chunk section, as blocks are packed.
+    pub fn index(&self) -> u16 {
+        (self.key & 0x0fff) as u16
+    }
+}
+
+/// The key of the section `chunk` with block index 0.
+fn section_key(chunk: ChunkCoordinates) -> u32 {
+    (u32::from(chunk.x as u8 ^ 0x80) << 24)
+        | (u32::from(chunk.z as u8 ^ 0x80) << 16)
+        | (u32::from(chunk.y) << 12)
+}
+
+fn key(coordinates: Coordinates) -> Option<u32> {
+    let chunk = ChunkCoordinates::new(
+        i8::try_from(coordinates.x >> 4).ok()?,
+        coordinates.y >> 4,
+        i8::try_from(coordinates.z >> 4).ok()?,
+    );
+
+    Some(section_key(chunk) | u32::from(coordinates.section_index()))
+}
+
+/// The changed blocks, kept sorted so a chunk section's changes can be found
+/// without searching all of them.
+#[derive(Debug, Default)]
+pub struct Modifications<const N: usize = MAX_BLOCK_CHANGES> {
+    changes: Vec<BlockChange, N>,
+}
+
+impl<const N: usize> Modifications<N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            changes: Vec::new(),
+        }
+    }
+
+    pub fn get(&self, coordinates: Coordinates) -> Option<VarInt> {
+        let key = key(coordinates)?;
+
+        self.changes
+            .binary_search_by_key(&key, |change| change.key)
+            .ok()
+            .map(|i| self.changes[i].state)
+    }
+
+    /// Records the block at `coordinates` as `state`, replacing any earlier
+    /// change to it.
+    pub fn set(
+        &mut self,
+        coordinates: Coordinates,
+        state: VarInt,
+    ) -> Result<(), ModificationError> {
+        let key = key(coordinates).ok_or(ModificationError::OutOfBounds)?;
+
+        match self.changes.binary_search_by_key(&key, |change| change.key) {
+            Ok(i) => {
+                self.changes[i].state = state;
+                Ok(())
+            }
+            Err(i) => self
+                .changes
+                .insert(i, BlockChange { key, state })
+                .map_err(|_| ModificationError::Full),
+        }
+    }
+
+    /// Forgets any change to the block at `coordinates`, returning whether
+    /// there was one.
+    pub fn remove(&mut self, coordinates: Coordinates) -> bool {
+        let Some(key) = key(coordinates) else {
+            return false;
+        };
+
+        match self.changes.binary_search_by_key(&key, |change| change.key) {
+            Ok(i) => {
+                self.changes.remove(i);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// The changes within `section`, in the order its blocks are packed.
+    pub fn in_section(&self, section: ChunkCoordinates) -> &[BlockChange] {
+        let start = section_key(section);
+        let end = start + 0x1000;
+
+        let from = self.changes.partition_point(|change| change.key < start);
+        let to = self.changes.partition_point(|change| change.key < end);
+
+        &self.changes[from..to]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &BlockChange> {
+        self.changes.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_are_found_by_block_and_by_section() {
+        let mut modifications = Modifications::<4>::new();
+
+        let a = Coordinates::new(-1, 70, 17);
+        let b = Coordinates::new(-16, 64, 31);
+        let c = Coordinates::new(0, 70, 17);
+
+        modifications.set(a, VarInt(0)).expect("room for a change");
+        modifications.set(b, VarInt(1)).expect("room for a change");
+        modifications.set(c, VarInt(9)).expect("room for a change");
+        modifications
+            .set(a, VarInt(10))
+            .expect("replacing takes no room");
+
+        assert_eq!(modifications.len(), 3);
+        assert_eq!(modifications.get(a), Some(VarInt(10)));
+        assert_eq!(modifications.get(Coordinates::new(1, 70, 17)), None);
+
+        // `a` and `b` share a section, and `a` is packed after `b`.
+        let section = modifications.in_section(ChunkCoordinates::new(-1, 4, 1));
+        assert_eq!(section.len(), 2);
+        assert_eq!(section[1].coordinates().x, a.x);
+        assert_eq!(section[1].coordinates().y, a.y);
+        assert_eq!(section[1].coordinates().z, a.z);
+        assert_eq!(section[0].coordinates().x, b.x);
+
+        assert!(modifications.remove(a));
+        assert!(!modifications.remove(a));
+        assert_eq!(modifications.get(a), None);
+
+        modifications.set(a, VarInt(0)).expect("room for a change");
+        modifications
+            .set(Coordinates::new(5, 5, 5), VarInt(0))
+            .expect("room for a change");
+        assert_eq!(
+            modifications.set(Coordinates::new(6, 6, 6), VarInt(0)),
+            Err(ModificationError::Full)
+        );
+        assert_eq!(
+            modifications.set(Coordinates::new(4096, 0, 0), VarInt(0)),
+            Err(ModificationError::OutOfBounds)
+        );
+    }
+}
@@ -42,6 +42,13 @@ impl From<Biome> for Palette {
 }
 
 impl Palette {
+    /// The indexed block which is `state` in this palette, if any is.
+    pub fn index_of(&self, state: VarInt) -> Option<IndexedBlock> {
+        IndexedBlock::ALL
+            .into_iter()
+            .find(|indexed_block| self.to_block(*indexed_block) as i32 == *state)
+    }
+
     #[inline]
     pub fn to_block(&self, indexed_block: IndexedBlock) -> Block {
         match self {
@@ -202,3 +209,49 @@ impl Palette {
         }
     }
 }
+
+/// The most block states an indirect palette can hold, at 4 bits per entry.
+pub const MAX_PALETTE_LEN: usize = 16;
+
+/// The block states a [`BlockContainer`](super::chunks::BlockContainer)'s
+/// entries are indices into.
+#[derive(Debug, Clone)]
+pub enum BlockPalette {
+    /// Generated terrain, indexed by [`IndexedBlock`].
+    Terrain(Palette),
+    /// Block state IDs, as decoded from chunk data.
+    States(PrefixedArray<VarInt, MAX_PALETTE_LEN>),
+}
+
+impl BlockPalette {
+    /// The block state ID at `index`, if the palette is that long.
+    pub fn state(&self, index: usize) -> Option<VarInt> {
+        match self {
+            Self::Terrain(palette) => IndexedBlock::ALL
+                .get(index)
+                .map(|indexed_block| VarInt(palette.to_block(*indexed_block) as i32)),
+            Self::States(states) => states.get(index).copied(),
+        }
+    }
+}
+
+impl From<Palette> for BlockPalette {
+    fn from(value: Palette) -> Self {
+        Self::Terrain(value)
+    }
+}
+
+impl Encode for BlockPalette {
+    async fn encode<W: embedded_io_async::Write>(&self, buffer: W) -> Result<(), EncodeError> {
+        match self {
+            Self::Terrain(palette) => palette.encode(buffer).await,
+            Self::States(states) => states.encode(buffer).await,
+        }
+    }
+}
+
+impl Decode for BlockPalette {
+    async fn decode<R: embedded_io_async::Read>(buffer: R) -> Result<Self, DecodeError> {
+        Ok(Self::States(PrefixedArray::decode(buffer).await?))
+    }
+}
@@ -1,5 +1,10 @@
+use core::cell::RefCell;
+
+use embassy_sync::blocking_mutex::Mutex as BlockingMutex;
+
 use crate::Terrain;
 use crate::noise::*;
+use crate::terrain::modifications::Modifications;
 
 #[non_exhaustive]
 pub struct TerrainBuilder {
@@ -17,10 +22,17 @@ impl TerrainBuilder {
     }
 
     pub fn build(self) -> Terrain {
+        self.build_with_max_changes()
+    }
+
+    /// Builds the terrain with room for `MAX_CHANGES` changed blocks, rather
+    /// than [`MAX_BLOCK_CHANGES`](crate::terrain::modifications::MAX_BLOCK_CHANGES).
+    pub fn build_with_max_changes<const MAX_CHANGES: usize>(self) -> Terrain<MAX_CHANGES> {
         Terrain {
             seed: self.seed,
             terrain_map: self.generate_terrain_map(),
             sea_level: self.sea_level,
+            modifications: BlockingMutex::new(RefCell::new(Modifications::new())),
         }
     }
 